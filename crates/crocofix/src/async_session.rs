@@ -0,0 +1,220 @@
+use crate::codec::MessageCodec;
+use crate::error::Error;
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+use crate::session::{Session, SessionState};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Framed;
+
+// How long to wait for incoming data before checking whether a Heartbeat is due.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Drives a Session over an async stream. Administrative messages are answered automatically and
+// Heartbeats are sent when the session is idle, application messages are returned to the caller.
+// The runtime must have the time driver enabled.
+pub struct AsyncSession<T> {
+    framed: Framed<T, MessageCodec>,
+    session: Session,
+    // Application messages received while waiting for the logon or logout to complete.
+    pending: VecDeque<Message>,
+    closed: bool
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncSession<T> {
+
+    pub fn new(stream: T, session: Session) -> Self
+    {
+        Self {
+            framed: Framed::new(stream, MessageCodec::default()),
+            session,
+            pending: VecDeque::new(),
+            closed: false
+        }
+    }
+
+    pub fn session(&self) -> &Session
+    {
+        &self.session
+    }
+
+    pub fn into_inner(self) -> T
+    {
+        self.framed.into_inner()
+    }
+
+    // Send a Logon and wait for the counterparty to acknowledge it. An acceptor doesn't need to
+    // call this, the Logon is answered when it is received.
+    pub async fn logon(&mut self) -> Result<(), Error>
+    {
+        let logon = self.session.logon();
+        self.framed.send(&logon).await?;
+        self.wait_for(SessionState::LoggedOn).await
+    }
+
+    // Send a Logout and wait for the counterparty to acknowledge it.
+    pub async fn logout(&mut self, text: Option<&str>) -> Result<(), Error>
+    {
+        let logout = self.session.logout(text);
+        self.framed.send(&logout).await?;
+        self.wait_for(SessionState::LoggedOut).await
+    }
+
+    // Create and send a message with this sessions header and the supplied body.
+    pub async fn send(&mut self, msg_type: &str, body: &FieldCollection) -> Result<(), Error>
+    {
+        let message = self.session.create(msg_type, body);
+        self.framed.send(&message).await
+    }
+
+    // Send a copy of an existing message with this sessions header.
+    pub async fn send_message(&mut self, message: &Message) -> Result<(), Error>
+    {
+        let message = self.session.prepare(message)?;
+        self.framed.send(&message).await
+    }
+
+    // Wait for the next application message, returns None when the session is logged out or the stream is closed.
+    pub async fn receive(&mut self) -> Result<Option<Message>, Error>
+    {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+
+        while self.session.state() != SessionState::LoggedOut {
+            if let Some(message) = self.read().await? {
+                return Ok(Some(message));
+            }
+            if self.closed {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn wait_for(&mut self, state: SessionState) -> Result<(), Error>
+    {
+        while self.session.state() != state {
+            if let Some(message) = self.read().await? {
+                self.pending.push_back(message);
+            }
+            if self.closed {
+                return Err(Error::IoError);
+            }
+        }
+        Ok(())
+    }
+
+    // Wait up to the poll interval for a message, process it, and return it if it is an application message.
+    async fn read(&mut self) -> Result<Option<Message>, Error>
+    {
+        let message = match tokio::time::timeout(POLL_INTERVAL, self.framed.next()).await {
+            Ok(Some(message)) => Some(message?),
+            Ok(None) => {
+                self.closed = true;
+                None
+            }
+            Err(_elapsed) => None
+        };
+
+        if let Some(heartbeat) = self.session.poll(Instant::now()) {
+            self.framed.send(&heartbeat).await?;
+        }
+
+        let Some(message) = message else {
+            return Ok(None);
+        };
+
+        for response in self.session.receive(&message)? {
+            self.framed.send(&response).await?;
+        }
+
+        if message.is_admin() {
+            return Ok(None);
+        }
+
+        Ok(Some(message))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::field::Field;
+    use crate::session::SessionOptions;
+
+    fn session(sender_comp_id: &str, target_comp_id: &str) -> Session
+    {
+        Session::new(SessionOptions {
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn order_single() -> FieldCollection
+    {
+        let mut body = FieldCollection::default();
+        body.push(Field::from_str(crate::FIX_5_0SP2::ClOrdID::TAG, "1"));
+        body.push(Field::from_str(crate::FIX_5_0SP2::Symbol::TAG, "WTF.AX"));
+        body.push(Field::from_str(crate::FIX_5_0SP2::Side::TAG, "1"));
+        body.push(Field::from_str(crate::FIX_5_0SP2::OrderQty::TAG, "100"));
+        body
+    }
+
+    #[tokio::test]
+    async fn initiator_and_acceptor_exchange_messages() -> Result<(), crate::error::Error>
+    {
+        let (initiator_stream, acceptor_stream) = tokio::io::duplex(1024);
+        let mut initiator = AsyncSession::new(initiator_stream, session("INITIATOR", "ACCEPTOR"));
+        let mut acceptor = AsyncSession::new(acceptor_stream, session("ACCEPTOR", ""));
+
+        let acceptor = tokio::spawn(async move {
+            let mut received = Vec::new();
+            while let Some(message) = acceptor.receive().await? {
+                let mut body = FieldCollection::default();
+                body.push(message.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.clone());
+                body.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, "0"));
+                acceptor.send(crate::FIX_5_0SP2::message::ExecutionReport::MSG_TYPE, &body).await?;
+                received.push(message);
+            }
+            Ok::<_, crate::error::Error>((received, acceptor.session().options.target_comp_id.clone()))
+        });
+
+        initiator.logon().await?;
+        assert_eq!(initiator.session().state(), SessionState::LoggedOn);
+
+        initiator.send(crate::FIX_5_0SP2::message::NewOrderSingle::MSG_TYPE, &order_single()).await?;
+        let report = initiator.receive().await?.unwrap();
+        assert_eq!(report.msg_type(), Some("8"));
+        assert_eq!(report.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value, "1");
+        assert_eq!(report.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value, "ACCEPTOR");
+        assert_eq!(report.fields.get(crate::FIX_5_0SP2::MsgSeqNum::TAG)?.value, "2");
+
+        initiator.logout(None).await?;
+        assert_eq!(initiator.session().state(), SessionState::LoggedOut);
+        assert!(initiator.receive().await?.is_none());
+
+        let (received, target_comp_id) = acceptor.await.unwrap()?;
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].msg_type(), Some("D"));
+        assert_eq!(target_comp_id, "INITIATOR");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn logon_fails_when_the_stream_is_closed() -> Result<(), crate::error::Error>
+    {
+        let (initiator_stream, acceptor_stream) = tokio::io::duplex(1024);
+        drop(acceptor_stream);
+        let mut initiator = AsyncSession::new(initiator_stream, session("INITIATOR", "ACCEPTOR"));
+        assert!(initiator.logon().await.is_err());
+        Ok(())
+    }
+
+}
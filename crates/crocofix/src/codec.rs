@@ -0,0 +1,183 @@
+use crate::message::{EncodeOptions, Message};
+use crate::frame_decoder::{resynchronise, FrameDecoder};
+use crate::error::Error;
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+// A tokio_util codec that frames a byte stream into messages and encodes messages with the
+// supplied options. The BodyLength and CheckSum are calculated by default. A message that fails to
+// decode is skipped rather than returned as an error because Framed ends the stream after an error.
+pub struct MessageCodec {
+    decoder: FrameDecoder,
+    encode_options: EncodeOptions,
+    // Set when a message fails to decode, the bytes up to the next BeginString are discarded.
+    resynchronising: bool
+}
+
+impl Default for MessageCodec {
+    fn default() -> Self {
+        MessageCodec::with_encode_options(EncodeOptions::Standard)
+    }
+}
+
+impl MessageCodec {
+
+    pub fn with_encode_options(encode_options: EncodeOptions) -> Self
+    {
+        Self {
+            decoder: FrameDecoder::default(),
+            encode_options,
+            resynchronising: false
+        }
+    }
+
+}
+
+impl Decoder for MessageCodec {
+
+    type Item = Message;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error>
+    {
+        while !src.is_empty() {
+            if self.resynchronising {
+                let (discard, found) = resynchronise(src);
+                src.advance(discard);
+                self.resynchronising = !found;
+                if !found {
+                    break;
+                }
+            }
+            let start = self.decoder.message_start(src);
+            let (consumed, message) = match self.decoder.decode(src) {
+                Ok(result) => result,
+                Err(_) => {
+                    src.advance((start + 1).min(src.len()));
+                    self.resynchronising = true;
+                    continue;
+                }
+            };
+            src.advance(consumed);
+            if message.is_some() {
+                return Ok(message);
+            }
+            if consumed == 0 {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    // A partial message or garbage at the end of the stream is discarded rather than reported as an error.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error>
+    {
+        let message = self.decode(src)?;
+        if message.is_none() {
+            src.clear();
+            self.decoder.reset();
+            self.resynchronising = false;
+        }
+        Ok(message)
+    }
+
+}
+
+impl Encoder<&Message> for MessageCodec {
+
+    type Error = Error;
+
+    fn encode(&mut self, item: &Message, dst: &mut BytesMut) -> Result<(), Self::Error>
+    {
+        let mut buffer = Vec::new();
+        item.encode(&mut buffer, self.encode_options)?;
+        dst.extend_from_slice(&buffer);
+        Ok(())
+    }
+
+}
+
+impl Encoder<Message> for MessageCodec {
+
+    type Error = Error;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error>
+    {
+        self.encode(&item, dst)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    const LOGON: &str = "8=FIX.4.4\u{0001}9=72\u{0001}35=A\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=1\u{0001}52=20190816-10:34:27.752\u{0001}98=0\u{0001}108=30\u{0001}10=013\u{0001}";
+    const HEARTBEAT: &str = "8=FIX.4.4\u{0001}9=58\u{0001}35=0\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20190816-10:34:57.752\u{0001}10=168\u{0001}";
+
+    #[tokio::test]
+    async fn decode_messages_split_across_writes() -> Result<(), crate::error::Error>
+    {
+        let (mut writer, reader) = tokio::io::duplex(16);
+        let mut framed = FramedRead::new(reader, MessageCodec::default());
+
+        let input = format!("garbage{}{}\n", LOGON, HEARTBEAT);
+        tokio::spawn(async move {
+            for chunk in input.as_bytes().chunks(7) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+
+        let logon = framed.next().await.unwrap()?;
+        assert_eq!(logon.msg_type(), Some("A"));
+        let heartbeat = framed.next().await.unwrap()?;
+        assert_eq!(heartbeat.fields.get(crate::FIX_5_0SP2::SendingTime::TAG)?.value, "20190816-10:34:57.752");
+        assert!(framed.next().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decode_continues_after_a_corrupt_message() -> Result<(), crate::error::Error>
+    {
+        let (mut writer, reader) = tokio::io::duplex(16);
+        let mut framed = FramedRead::new(reader, MessageCodec::default());
+
+        let corrupt = "8=FIX.4.4\u{0001}9=20\u{0001}35=D\u{0001}A=B\u{0001}38=100\u{0001}10=000\u{0001}";
+        let input = format!("{}{}{}", LOGON, corrupt, HEARTBEAT);
+        tokio::spawn(async move {
+            for chunk in input.as_bytes().chunks(7) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+
+        assert_eq!(framed.next().await.unwrap()?.msg_type(), Some("A"));
+        assert_eq!(framed.next().await.unwrap()?.msg_type(), Some("0"));
+        assert!(framed.next().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encode_sets_body_length_and_checksum() -> Result<(), crate::error::Error>
+    {
+        let mut message = Message::default();
+        message.decode(LOGON.replace("9=72", "9=0").replace("10=013", "10=000").as_bytes())?;
+
+        let mut buffer = Vec::new();
+        {
+            let mut framed = FramedWrite::new(&mut buffer, MessageCodec::default());
+            framed.send(&message).await?;
+        }
+
+        let mut expected = Vec::new();
+        message.encode(&mut expected, EncodeOptions::Standard)?;
+        assert_eq!(buffer, expected);
+        assert!(String::from_utf8_lossy(&buffer).contains("\u{0001}9=72\u{0001}"));
+        Ok(())
+    }
+
+}
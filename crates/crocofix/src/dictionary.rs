@@ -139,6 +139,17 @@ impl OrchestrationFieldCollection {
         }
     }
 
+    // The definition of a field or None if the tag isn't in the dictionary, unlike indexing this
+    // doesn't panic for user defined tags.
+    pub fn try_get(&self, tag: usize) -> Option<&dyn OrchestrationField>
+    {
+        self.offsets
+            .get(tag)
+            .and_then(|&offset| self.fields.get(offset))
+            .map(|field| field.as_ref())
+            .filter(|field| field.is_valid())
+    }
+
     pub fn name_of_field(&self, tag: usize) -> Option<&'static str> 
     {
         self.offsets
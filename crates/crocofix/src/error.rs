@@ -16,8 +16,23 @@ pub enum Error {
     OrderIndexOutOfRange(usize),
     OrderBookAlreadyContainsOrderWithKey(String),
     OrderBookDoesNotContainOrderWithKey(String),
+    OrderBookDoesNotContainExecution(String),
+    OrderBookDoesNotContainRejectedMessage(String),
     InvalidOrderBookFields(String),
-    OsStringConversionFailed(OsString)
+    OsStringConversionFailed(OsString),
+    ChecksumOutOfRange(u32),
+    FieldValueParseFailed(String),
+    InvalidSnapshot(String),
+    InvalidOrderQuery(String),
+    InvalidNumberFormat(String),
+    QuoteBookAlreadyContainsQuoteRequestWithKey(String),
+    QuoteBookDoesNotContainQuoteRequestWithKey(String),
+    QuoteBookDoesNotContainQuoteWithKey(String),
+    MarketDataBookDoesNotContainEntry(String),
+    UnsupportedMDUpdateAction(String),
+    PostTradeBookAlreadyContainsAllocationWithKey(String),
+    PostTradeBookDoesNotContainAllocationWithID(String),
+    Timeout
 }
 
 impl From<io::Error> for Error {
@@ -0,0 +1,157 @@
+use crate::message::Message;
+use crate::error::Error;
+
+// FIX 4.2 and earlier report fills with these ExecTypes, they were replaced by Trade in FIX 4.3.
+const LEGACY_PARTIAL_FILL: &str = "1";
+const LEGACY_FILL: &str = "2";
+
+// A single fill, bust, or correction reported for an order.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Execution {
+    pub exec_id: String,
+    pub exec_type: String,
+    pub last_qty: f64,
+    pub last_px: f64,
+    pub last_mkt: Option<String>,
+    pub transact_time: Option<String>,
+    // For a TradeCancel or TradeCorrect this is the ExecID of the execution being adjusted.
+    pub exec_ref_id: Option<String>,
+    // The ExecID of the TradeCancel or TradeCorrect that replaced this execution.
+    pub superseded_by: Option<String>,
+    // Set when we have sent a DontKnowTrade for this execution.
+    pub disputed: bool
+}
+
+impl Execution {
+
+    // Returns None if the ExecutionReport does not report a fill, bust, or correction.
+    pub fn from_execution_report(execution_report: &Message) -> Result<Option<Self>, Error>
+    {
+        let exec_type = execution_report.fields.try_get(crate::FIX_5_0SP2::ExecType::TAG).map(|field| field.value.as_str());
+
+        let last_qty = match execution_report.fields.try_get(crate::FIX_5_0SP2::LastQty::TAG) {
+            Some(field) => field.decimal_value()?,
+            None => 0.0
+        };
+
+        let is_execution = match exec_type {
+            Some(exec_type) => {
+                exec_type == crate::FIX_5_0SP2::ExecType::Trade().value ||
+                exec_type == crate::FIX_5_0SP2::ExecType::TradeCorrect().value ||
+                exec_type == crate::FIX_5_0SP2::ExecType::TradeCancel().value ||
+                exec_type == LEGACY_PARTIAL_FILL ||
+                exec_type == LEGACY_FILL
+            }
+            None => last_qty > 0.0
+        };
+
+        if !is_execution {
+            return Ok(None);
+        }
+
+        let last_px = match execution_report.fields.try_get(crate::FIX_5_0SP2::LastPx::TAG) {
+            Some(field) => field.decimal_value()?,
+            None => 0.0
+        };
+
+        let optional_value = |tag: u32| execution_report.fields.try_get(tag).map(|field| field.value.clone());
+
+        Ok(Some(Execution {
+            exec_id: execution_report.fields.get(crate::FIX_5_0SP2::ExecID::TAG)?.value.clone(),
+            exec_type: exec_type.unwrap_or_default().to_string(),
+            last_qty,
+            last_px,
+            last_mkt: optional_value(crate::FIX_5_0SP2::LastMkt::TAG),
+            transact_time: optional_value(crate::FIX_5_0SP2::TransactTime::TAG),
+            exec_ref_id: optional_value(crate::FIX_5_0SP2::ExecRefID::TAG),
+            superseded_by: None,
+            disputed: false
+        }))
+    }
+
+    pub fn is_trade_cancel(&self) -> bool
+    {
+        self.exec_type == crate::FIX_5_0SP2::ExecType::TradeCancel().value
+    }
+
+    pub fn is_trade_correct(&self) -> bool
+    {
+        self.exec_type == crate::FIX_5_0SP2::ExecType::TradeCorrect().value
+    }
+
+    // A fill or correction that has not been busted or corrected, these are the executions that make up CumQty.
+    pub fn is_active(&self) -> bool
+    {
+        !self.is_trade_cancel() && self.superseded_by.is_none()
+    }
+
+    pub fn notional(&self) -> f64
+    {
+        self.last_qty * self.last_px
+    }
+
+    // A bust or correction references the execution it adjusts, or the most recent correction to it.
+    pub fn is_adjusted_by(&self, exec_ref_id: &str) -> bool
+    {
+        self.is_active() && (self.exec_id == exec_ref_id || self.exec_ref_id.as_deref() == Some(exec_ref_id))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn trade() -> Result<(), crate::error::Error>
+    {
+        let report = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}32=4000\u{0001}31=20.5\u{0001}30=XASX\u{0001}60=20200114-08:13:21.072\u{0001}10=000\u{0001}")?;
+        let execution = Execution::from_execution_report(&report)?.unwrap();
+        assert_eq!(execution.exec_id, "2");
+        assert_eq!(execution.last_qty, 4000.0);
+        assert_eq!(execution.last_px, 20.5);
+        assert_eq!(execution.last_mkt.as_deref(), Some("XASX"));
+        assert_eq!(execution.transact_time.as_deref(), Some("20200114-08:13:21.072"));
+        assert_eq!(execution.notional(), 82000.0);
+        assert!(execution.is_active());
+        Ok(())
+    }
+
+    #[test]
+    pub fn legacy_fill() -> Result<(), crate::error::Error>
+    {
+        let report = decode_message("8=FIX.4.2\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=2\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=2\u{0001}32=100\u{0001}31=20\u{0001}10=000\u{0001}")?;
+        assert!(Execution::from_execution_report(&report)?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    pub fn acknowledgement_is_not_an_execution() -> Result<(), crate::error::Error>
+    {
+        let report = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}32=0\u{0001}31=0\u{0001}10=000\u{0001}")?;
+        assert_eq!(Execution::from_execution_report(&report)?, None);
+        Ok(())
+    }
+
+    #[test]
+    pub fn trade_cancel_references_original() -> Result<(), crate::error::Error>
+    {
+        let report = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=4\u{0001}19=2\u{0001}150=H\u{0001}10=000\u{0001}")?;
+        let execution = Execution::from_execution_report(&report)?.unwrap();
+        assert!(execution.is_trade_cancel());
+        assert!(!execution.is_active());
+        assert_eq!(execution.exec_ref_id.as_deref(), Some("2"));
+        Ok(())
+    }
+
+}
@@ -1,8 +1,24 @@
 use core::fmt;
 use crate::dictionary::FieldValue;
+use crate::error::Error;
+
+// The number of decimal places used when formatting computed values such as average prices.
+const DECIMAL_PRECISION: usize = 8;
+
+// Quantities are parsed as decimals so allow for representation error when comparing them.
+pub const QUANTITY_TOLERANCE: f64 = 0.00000001;
+// AvgPx is often rounded by the counterparty so it only needs to be this close to the VWAP of the fills.
+pub const AVG_PX_TOLERANCE: f64 = 0.0001;
+
+// Format a computed value for a report or description, see Field::from_decimal.
+pub fn format_decimal(value: f64) -> String
+{
+    Field::from_decimal(0, value).value
+}
 
 // TODO - consider encoding this with an enum
 #[derive(fmt::Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
     pub tag: u32,
     pub value: String
@@ -26,6 +42,22 @@ impl Field {
         }    
     }
 
+    // Format a numeric value without trailing zeros or an exponent e.g. 10000 not 10000.0
+    pub fn from_decimal(tag: u32, value: f64) -> Self
+    {
+        let text = format!("{:.*}", DECIMAL_PRECISION, value);
+        let text = text.trim_end_matches('0').trim_end_matches('.');
+        Self {
+            tag,
+            value: if text == "-0" { "0".to_string() } else { text.to_string() }
+        }
+    }
+
+    pub fn decimal_value(&self) -> Result<f64, Error>
+    {
+        self.value.trim().parse::<f64>().map_err(|_error| Error::FieldValueParseFailed(format!("{}", self)))
+    }
+
 }
 
 impl fmt::Display for Field {
@@ -62,4 +94,21 @@ mod tests {
 
     }
 
+    #[test]
+    pub fn from_decimal()
+    {
+        assert_eq!(Field::from_decimal(crate::FIX_5_0SP2::OrderQty::TAG, 10000.0).value, "10000");
+        assert_eq!(Field::from_decimal(crate::FIX_5_0SP2::Price::TAG, 11.58).value, "11.58");
+        assert_eq!(Field::from_decimal(crate::FIX_5_0SP2::AvgPx::TAG, 0.1 + 0.2).value, "0.3");
+        assert_eq!(Field::from_decimal(crate::FIX_5_0SP2::AvgPx::TAG, -0.000000001).value, "0");
+    }
+
+    #[test]
+    pub fn decimal_value() -> Result<(), crate::error::Error>
+    {
+        assert_eq!(Field::from_str(crate::FIX_5_0SP2::Price::TAG, "11.565").decimal_value()?, 11.565);
+        assert_eq!(Field::from_str(crate::FIX_5_0SP2::Price::TAG, "abc").decimal_value(), Err(Error::FieldValueParseFailed("44=abc".to_string())));
+        Ok(())
+    }
+
 }
\ No newline at end of file
@@ -28,6 +28,8 @@ pub enum RemoveOperation
 }
 
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct FieldCollection {
 
     fields: Vec<Field>
@@ -115,6 +117,63 @@ impl FieldCollection {
         }
     }
 
+    // Split a repeating group into its instances. Each instance starts with the first field after
+    // the NumInGroup field and the group ends at the first field that isn't one of the member
+    // tags or once NumInGroup instances have been collected. If no member tags are given the group
+    // extends to the standard trailer, this suits groups like NoOrders that are the last thing in
+    // the message body. Nested groups are included in the instance that contains them. A group
+    // with a count of zero, or a count that isn't a number, has no instances.
+    pub fn group(&self, count_tag: u32, member_tags: &[u32]) -> Vec<FieldCollection>
+    {
+        const TRAILER_TAGS: [u32; 3] = [
+            crate::FIX_5_0SP2::SignatureLength::TAG,
+            crate::FIX_5_0SP2::Signature::TAG,
+            crate::FIX_5_0SP2::CheckSum::TAG
+        ];
+
+        let mut instances: Vec<FieldCollection> = Vec::new();
+
+        let Some(start) = self.fields.iter().position(|field| field.tag == count_tag) else {
+            return instances;
+        };
+
+        let count = self.fields[start].value.parse::<usize>().unwrap_or(0);
+        if count == 0 {
+            return instances;
+        }
+
+        let Some(delimiter) = self.fields.get(start + 1).map(|field| field.tag) else {
+            return instances;
+        };
+
+        if !member_tags.is_empty() && !member_tags.contains(&delimiter) {
+            return instances;
+        }
+
+        for field in &self.fields[start + 1..] {
+            let is_member = if member_tags.is_empty() {
+                !TRAILER_TAGS.contains(&field.tag)
+            }
+            else {
+                field.tag == delimiter || member_tags.contains(&field.tag)
+            };
+            if !is_member {
+                break;
+            }
+            if field.tag == delimiter {
+                if instances.len() == count {
+                    break;
+                }
+                instances.push(FieldCollection::default());
+            }
+            if let Some(instance) = instances.last_mut() {
+                instance.push(field.clone());
+            }
+        }
+
+        instances
+    }
+
 }
 
 // TODO - study these traits.
@@ -324,6 +383,49 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn repeating_group_instances()
+    {
+        let mut collection = FieldCollection::default();
+        for (tag, value) in [(55, "SPREAD"), (555, "2"), (600, "BHP.AX"), (624, "1"), (600, "RIO.AX"), (623, "2"), (624, "2"), (38, "10"), (10, "000")] {
+            collection.push(Field::from_str(tag, value));
+        }
+
+        let legs = collection.group(555, &[600, 623, 624]);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].len(), 2);
+        assert_eq!(legs[1].len(), 3);
+        assert_eq!(legs[1].get(623).unwrap().value, "2");
+
+        let to_trailer = collection.group(555, &[]);
+        assert_eq!(to_trailer.len(), 2);
+        assert_eq!(to_trailer[1].get(38).unwrap().value, "10");
+        assert_eq!(to_trailer[1].try_get(10), None);
+
+        assert!(collection.group(73, &[]).is_empty());
+    }
+
+    #[test]
+    pub fn repeating_group_instances_are_limited_by_the_count()
+    {
+        let mut empty = FieldCollection::default();
+        for (tag, value) in [(55, "SPREAD"), (555, "0"), (38, "10"), (10, "000")] {
+            empty.push(Field::from_str(tag, value));
+        }
+        assert!(empty.group(555, &[600, 623, 624]).is_empty());
+        assert!(empty.group(555, &[]).is_empty());
+
+        let mut collection = FieldCollection::default();
+        for (tag, value) in [(555, "1"), (600, "BHP.AX"), (624, "1"), (600, "RIO.AX"), (624, "2"), (10, "000")] {
+            collection.push(Field::from_str(tag, value));
+        }
+        let legs = collection.group(555, &[600, 624]);
+        assert_eq!(legs.len(), 1);
+        assert_eq!(legs[0].get(600).unwrap().value, "BHP.AX");
+
+        assert!(collection.group(555, &[624]).is_empty());
+    }
+
     #[test]
     pub fn try_get_existent_field_returns_first_instance_of_multiply_defined_field()
     {
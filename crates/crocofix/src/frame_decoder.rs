@@ -0,0 +1,263 @@
+use crate::message::Message;
+use crate::error::Error;
+use std::io::{ErrorKind, Read};
+
+const BEGIN_STRING_PREFIX: &[u8] = b"8=";
+// A more specific prefix used to find the next message after one fails to decode, 8= alone can
+// match the end of another tag such as OrderQty.
+const BEGIN_STRING: &[u8] = b"8=FIX";
+const READ_BUFFER_SIZE: usize = 4096;
+
+// Split a byte stream into complete FIX messages. Bytes are supplied in whatever pieces
+// they arrive in and any bytes that cannot be decoded yet, typically a partial field at the end
+// of a read, are left for the caller to supply again along with the next piece of the stream.
+// Anything preceding a BeginString field is discarded so the decoder will resynchronise after
+// receiving garbage.
+#[derive(Default)]
+pub struct FrameDecoder {
+    message: Message,
+    started: bool
+}
+
+impl FrameDecoder {
+
+    // Decode as much of the buffer as possible. Returns the number of bytes consumed and the
+    // message if one was completed. Bytes following a completed message are not consumed.
+    pub fn decode(&mut self, buffer: &[u8]) -> Result<(usize, Option<Message>), Error>
+    {
+        let mut skipped = 0;
+
+        if !self.started {
+            match position(buffer, BEGIN_STRING_PREFIX) {
+                Some(position) => {
+                    skipped = position;
+                    self.started = true;
+                }
+                None => {
+                    // Keep the last byte in case it is the start of a BeginString.
+                    return Ok((buffer.len().saturating_sub(BEGIN_STRING_PREFIX.len() - 1), None));
+                }
+            }
+        }
+
+        let result = match self.message.decode(&buffer[skipped..]) {
+            Ok(result) => result,
+            Err(error) => {
+                self.reset();
+                return Err(error);
+            }
+        };
+
+        if !result.complete {
+            return Ok((skipped + result.consumed, None));
+        }
+
+        self.started = false;
+
+        Ok((skipped + result.consumed, Some(std::mem::take(&mut self.message))))
+    }
+
+    // Where the message being decoded starts in the buffer, a message started in a previous buffer
+    // starts at the beginning of this one.
+    pub(crate) fn message_start(&self, buffer: &[u8]) -> usize
+    {
+        if self.started { 0 } else { position(buffer, BEGIN_STRING_PREFIX).unwrap_or(0) }
+    }
+
+    // Discard any partially decoded message.
+    pub fn reset(&mut self)
+    {
+        self.message = Message::default();
+        self.started = false;
+    }
+
+}
+
+fn position(buffer: &[u8], pattern: &[u8]) -> Option<usize>
+{
+    buffer.windows(pattern.len()).position(|window| window == pattern)
+}
+
+// After a message fails to decode everything up to the next BeginString is discarded. Returns the
+// number of bytes to discard and whether the BeginString was found, if it wasn't the end of the
+// buffer is kept in case it is the start of one.
+pub(crate) fn resynchronise(buffer: &[u8]) -> (usize, bool)
+{
+    match position(buffer, BEGIN_STRING) {
+        Some(position) => (position, true),
+        None => (buffer.len().saturating_sub(BEGIN_STRING.len() - 1), false)
+    }
+}
+
+// Read complete FIX messages from a blocking byte stream such as a file or a TcpStream.
+pub struct FrameReader<R: Read> {
+    reader: R,
+    decoder: FrameDecoder,
+    buffer: Vec<u8>,
+    // Set when a message fails to decode, the bytes up to the next BeginString are discarded.
+    resynchronising: bool
+}
+
+impl<R: Read> FrameReader<R> {
+
+    pub fn new(reader: R) -> Self
+    {
+        Self {
+            reader,
+            decoder: FrameDecoder::default(),
+            buffer: Vec::new(),
+            resynchronising: false
+        }
+    }
+
+    pub fn get_ref(&self) -> &R
+    {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R
+    {
+        &mut self.reader
+    }
+
+    // Returns Ok(None) at the end of the stream. If the underlying reader has a timeout configured
+    // and it expires Error::Timeout is returned, the read can be retried and no data will be lost.
+    // If a message fails to decode the error is returned and the next read continues with the
+    // following message.
+    pub fn read_message(&mut self) -> Result<Option<Message>, Error>
+    {
+        loop {
+            if self.resynchronising {
+                let (discard, found) = resynchronise(&self.buffer);
+                self.buffer.drain(..discard);
+                self.resynchronising = !found;
+            }
+
+            if !self.resynchronising && !self.buffer.is_empty() {
+                let start = self.decoder.message_start(&self.buffer);
+                let (consumed, message) = match self.decoder.decode(&self.buffer) {
+                    Ok(result) => result,
+                    Err(error) => {
+                        self.buffer.drain(..(start + 1).min(self.buffer.len()));
+                        self.resynchronising = true;
+                        return Err(error);
+                    }
+                };
+                self.buffer.drain(..consumed);
+                if message.is_some() {
+                    return Ok(message);
+                }
+            }
+
+            let mut chunk = [0u8; READ_BUFFER_SIZE];
+
+            let read = match self.reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut => return Err(Error::Timeout),
+                Err(error) => return Err(error.into())
+            };
+
+            if read == 0 {
+                return Ok(None);
+            }
+
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+
+    type Item = Result<Message, Error>;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        self.read_message().transpose()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const LOGON: &str = "8=FIX.4.4\u{0001}9=72\u{0001}35=A\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=1\u{0001}52=20190816-10:34:27.752\u{0001}98=0\u{0001}108=30\u{0001}10=013\u{0001}";
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
+
+    #[test]
+    pub fn decode_a_complete_message() -> Result<(), crate::error::Error>
+    {
+        let mut decoder = FrameDecoder::default();
+        let (consumed, message) = decoder.decode(LOGON.as_bytes())?;
+        assert_eq!(consumed, LOGON.len());
+        assert_eq!(message.ok_or(Error::MessageDoesNotContainMsgType)?.msg_type(), Some("A"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn decode_leaves_trailing_message_unconsumed() -> Result<(), crate::error::Error>
+    {
+        let text = format!("{}{}", LOGON, ORDER_SINGLE);
+        let mut decoder = FrameDecoder::default();
+        let (consumed, message) = decoder.decode(text.as_bytes())?;
+        assert_eq!(consumed, LOGON.len());
+        assert!(message.is_some());
+        let (consumed, message) = decoder.decode(&text.as_bytes()[LOGON.len()..])?;
+        assert_eq!(consumed, ORDER_SINGLE.len());
+        assert_eq!(message.ok_or(Error::MessageDoesNotContainMsgType)?.msg_type(), Some("D"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn decode_skips_leading_garbage() -> Result<(), crate::error::Error>
+    {
+        let text = format!("2019-08-17 13:00:00.000 {}", LOGON);
+        let mut decoder = FrameDecoder::default();
+        let (consumed, message) = decoder.decode(text.as_bytes())?;
+        assert_eq!(consumed, text.len());
+        assert!(message.is_some());
+        Ok(())
+    }
+
+    #[test]
+    pub fn frame_reader_reads_messages_split_across_reads() -> Result<(), crate::error::Error>
+    {
+        // A reader that returns a few bytes at a time.
+        struct Trickle<'a> { data: &'a [u8] }
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let count = self.data.len().min(buf.len()).min(7);
+                buf[..count].copy_from_slice(&self.data[..count]);
+                self.data = &self.data[count..];
+                Ok(count)
+            }
+        }
+
+        let text = format!("{}{}{}", LOGON, ORDER_SINGLE, LOGON);
+        let reader = FrameReader::new(Trickle { data: text.as_bytes() });
+        let msg_types: Vec<String> = reader
+            .map(|message| message.map(|message| message.msg_type().unwrap_or_default().to_string()))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(msg_types, vec!["A", "D", "A"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn frame_reader_continues_after_a_corrupt_message()
+    {
+        let corrupt = "8=FIX.4.4\u{0001}9=20\u{0001}35=D\u{0001}A=B\u{0001}38=100\u{0001}10=000\u{0001}";
+        let text = format!("{}{}{}", LOGON, corrupt, ORDER_SINGLE);
+        let results: Vec<Result<String, Error>> = FrameReader::new(text.as_bytes())
+            .map(|message| message.map(|message| message.msg_type().unwrap_or_default().to_string()))
+            .collect();
+        assert_eq!(results, vec![
+            Ok("A".to_string()),
+            Err(Error::TagParseFailed("A".to_string())),
+            Ok("D".to_string())
+        ]);
+    }
+
+}
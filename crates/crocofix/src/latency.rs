@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+use std::fmt;
+use crate::execution::Execution;
+use crate::message::Message;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::timestamp::parse_utc_timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyKind {
+    // NewOrderSingle to the first ExecutionReport.
+    Acknowledged,
+    // NewOrderSingle to the first fill.
+    FirstFill,
+    // OrderCancelRequest to the Canceled ExecutionReport.
+    Canceled,
+    // OrderCancelReplaceRequest to the Replaced ExecutionReport.
+    Replaced
+}
+
+impl LatencyKind {
+
+    pub fn request_msg_type(&self) -> &'static str
+    {
+        match self {
+            LatencyKind::Acknowledged | LatencyKind::FirstFill => crate::FIX_5_0SP2::MsgType::NewOrderSingle().value,
+            LatencyKind::Canceled => crate::FIX_5_0SP2::MsgType::OrderCancelRequest().value,
+            LatencyKind::Replaced => crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value
+        }
+    }
+
+}
+
+impl fmt::Display for LatencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LatencyKind::Acknowledged => "Acknowledged",
+            LatencyKind::FirstFill => "FirstFill",
+            LatencyKind::Canceled => "Canceled",
+            LatencyKind::Replaced => "Replaced"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The time the message was logged if we have it, otherwise the SendingTime or TransactTime.
+pub fn timestamp(message: &Message) -> Option<DateTime<Utc>>
+{
+    if message.logged_time.is_some() {
+        return message.logged_time;
+    }
+
+    [crate::FIX_5_0SP2::SendingTime::TAG, crate::FIX_5_0SP2::TransactTime::TAG]
+        .iter()
+        .find_map(|tag| message.fields.try_get(*tag).and_then(|field| parse_utc_timestamp(&field.value)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySample {
+    pub counterparty: String,
+    pub kind: LatencyKind,
+    pub cl_ord_id: String,
+    pub milliseconds: f64
+}
+
+fn is_execution_report(message: &Message) -> bool
+{
+    message.msg_type() == Some(crate::FIX_5_0SP2::MsgType::ExecutionReport().value)
+}
+
+fn has_exec_type(message: &Message, exec_type: &str) -> bool
+{
+    is_execution_report(message) && message.fields.try_get(crate::FIX_5_0SP2::ExecType::TAG).is_some_and(|field| field.value == exec_type)
+}
+
+fn is_fill(message: &Message) -> bool
+{
+    is_execution_report(message) && Execution::from_execution_report(message)
+        .ok()
+        .flatten()
+        .is_some_and(|execution| !execution.is_trade_cancel() && !execution.is_trade_correct())
+}
+
+// Measure the time from each request in the history of an order to the first response matching the predicate.
+fn measure(order: &Order, kind: LatencyKind, is_response: impl Fn(&Message) -> bool) -> Vec<(&Message, LatencySample)>
+{
+    let mut samples = Vec::new();
+
+    for (index, request) in order.messages.iter().enumerate() {
+        if request.msg_type() != Some(kind.request_msg_type()) {
+            continue;
+        }
+        let Some(response) = order.messages[index + 1..].iter().find(|message| is_response(message)) else {
+            continue;
+        };
+        let (Some(sent), Some(received)) = (timestamp(request), timestamp(response)) else {
+            continue;
+        };
+        let value = |tag: u32| request.fields.try_get(tag).map(|field| field.value.clone()).unwrap_or_default();
+        samples.push((request, LatencySample {
+            counterparty: value(crate::FIX_5_0SP2::TargetCompID::TAG),
+            kind,
+            cl_ord_id: value(crate::FIX_5_0SP2::ClOrdID::TAG),
+            milliseconds: (received - sent).num_microseconds().unwrap_or_default() as f64 / 1000.0
+        }));
+    }
+
+    samples
+}
+
+fn order_samples(order: &Order) -> Vec<(&Message, LatencySample)>
+{
+    let mut samples = measure(order, LatencyKind::Acknowledged, is_execution_report);
+    samples.extend(measure(order, LatencyKind::FirstFill, is_fill));
+    samples.extend(measure(order, LatencyKind::Canceled, |message| has_exec_type(message, crate::FIX_5_0SP2::ExecType::Canceled().value)));
+    samples.extend(measure(order, LatencyKind::Replaced, |message| has_exec_type(message, crate::FIX_5_0SP2::ExecType::Replaced().value)));
+    samples
+}
+
+// Measure the latencies for every order in the book. An order created by a replace starts with
+// a copy of the history of the order it replaced so requests are only measured once.
+pub fn measure_order_book(order_book: &OrderBook) -> Vec<LatencySample>
+{
+    let mut seen = HashSet::new();
+    let mut samples = Vec::new();
+
+    for order in order_book.orders.values() {
+        for (request, sample) in order_samples(order) {
+            let identity: Vec<Option<&str>> = [
+                crate::FIX_5_0SP2::SenderCompID::TAG,
+                crate::FIX_5_0SP2::TargetCompID::TAG,
+                crate::FIX_5_0SP2::MsgSeqNum::TAG,
+                crate::FIX_5_0SP2::ClOrdID::TAG
+            ].iter().map(|tag| request.fields.try_get(*tag).map(|field| field.value.as_str())).collect();
+            if seen.insert((identity, request.logged_time, sample.kind)) {
+                samples.push(sample);
+            }
+        }
+    }
+
+    samples
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyStatistics {
+    pub count: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64
+}
+
+impl LatencyStatistics {
+
+    // Nearest rank percentiles, returns None if there are no samples.
+    pub fn from_milliseconds(mut milliseconds: Vec<f64>) -> Option<Self>
+    {
+        if milliseconds.is_empty() {
+            return None;
+        }
+
+        milliseconds.sort_by(|a, b| a.total_cmp(b));
+
+        let percentile = |percentile: f64| {
+            let rank = (percentile / 100.0 * milliseconds.len() as f64).ceil() as usize;
+            milliseconds[rank.max(1) - 1]
+        };
+
+        Some(LatencyStatistics {
+            count: milliseconds.len(),
+            min: milliseconds[0],
+            mean: milliseconds.iter().sum::<f64>() / milliseconds.len() as f64,
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: milliseconds[milliseconds.len() - 1]
+        })
+    }
+
+}
+
+// Percentiles for each counterparty and kind of latency in the order they were first seen.
+pub fn latency_statistics(samples: &[LatencySample]) -> IndexMap<(String, LatencyKind), LatencyStatistics>
+{
+    let mut groups: IndexMap<(String, LatencyKind), Vec<f64>> = IndexMap::new();
+    for sample in samples {
+        groups.entry((sample.counterparty.clone(), sample.kind)).or_default().push(sample.milliseconds);
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(key, milliseconds)| LatencyStatistics::from_milliseconds(milliseconds).map(|statistics| (key, statistics)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200114-08:13:20.000\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+    const REPORT_NEW: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20200114-08:13:20.005\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}10=000\u{0001}";
+    const REPORT_PARTIAL: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}52=20200114-08:13:20.250\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}32=100\u{0001}31=21\u{0001}10=000\u{0001}";
+    const REPLACE_REQUEST: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:13:21.000\u{0001}11=62\u{0001}41=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+    const REPLACED: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}52=20200114-08:13:21.012\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=3\u{0001}150=5\u{0001}38=15000\u{0001}10=000\u{0001}";
+    const CANCEL_REQUEST: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=F\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}52=20200114-08:13:22.000\u{0001}11=63\u{0001}41=62\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}10=000\u{0001}";
+    const CANCELED: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}52=20200114-08:13:22.020\u{0001}39=4\u{0001}11=63\u{0001}41=62\u{0001}37=1\u{0001}17=4\u{0001}150=4\u{0001}10=000\u{0001}";
+
+    fn logged(text: &str, prefix: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = decode_message(text)?;
+        message.logged_time = crate::timestamp::parse_log_prefix(prefix);
+        Ok(message)
+    }
+
+    fn order_book() -> Result<OrderBook, crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        for text in [ORDER_SINGLE, REPORT_NEW, REPORT_PARTIAL, REPLACE_REQUEST, REPLACED, CANCEL_REQUEST, CANCELED] {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    #[test]
+    pub fn order_lifecycle_latencies() -> Result<(), crate::error::Error>
+    {
+        let samples = measure_order_book(&order_book()?);
+        let latencies: Vec<(LatencyKind, &str, f64)> = samples.iter().map(|sample| (sample.kind, sample.cl_ord_id.as_str(), sample.milliseconds)).collect();
+        assert_eq!(latencies, vec![
+            (LatencyKind::Acknowledged, "61", 5.0),
+            (LatencyKind::FirstFill, "61", 250.0),
+            (LatencyKind::Replaced, "62", 12.0),
+            (LatencyKind::Canceled, "63", 20.0)
+        ]);
+        assert!(samples.iter().all(|sample| sample.counterparty == "ACCEPTOR"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn log_timestamps_are_preferred() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        book.process(&logged(ORDER_SINGLE, "20200114-08:13:20.100 ->")?)?;
+        book.process(&logged(REPORT_NEW, "20200114-08:13:20.103 <-")?)?;
+        let samples = measure_order_book(&book);
+        assert_eq!(samples[0].kind, LatencyKind::Acknowledged);
+        assert_eq!(samples[0].milliseconds, 3.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn log_timestamps_span_sequence_resets() -> Result<(), crate::error::Error>
+    {
+        // The second day of the log reuses the MsgSeqNums of the first.
+        let mut book = OrderBook::default();
+        book.process(&logged(ORDER_SINGLE, "20200114-08:13:20.100 ->")?)?;
+        book.process(&logged(REPORT_NEW, "20200114-08:13:20.103 <-")?)?;
+        book.process(&logged(&ORDER_SINGLE.replace("11=61", "11=71"), "20200115-09:00:00.000 ->")?)?;
+        book.process(&logged(&REPORT_NEW.replace("11=61", "11=71"), "20200115-09:00:00.007 <-")?)?;
+        let samples = measure_order_book(&book);
+        let latencies: Vec<(&str, f64)> = samples.iter().map(|sample| (sample.cl_ord_id.as_str(), sample.milliseconds)).collect();
+        assert_eq!(latencies, vec![("61", 3.0), ("71", 7.0)]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn percentiles()
+    {
+        let statistics = LatencyStatistics::from_milliseconds((1..=100).rev().map(|value| value as f64).collect()).unwrap();
+        assert_eq!(statistics.count, 100);
+        assert_eq!(statistics.min, 1.0);
+        assert_eq!(statistics.mean, 50.5);
+        assert_eq!(statistics.p50, 50.0);
+        assert_eq!(statistics.p90, 90.0);
+        assert_eq!(statistics.p99, 99.0);
+        assert_eq!(statistics.max, 100.0);
+        assert_eq!(LatencyStatistics::from_milliseconds(vec![]), None);
+    }
+
+}
@@ -0,0 +1,90 @@
+use crate::latency::{latency_statistics, LatencySample};
+use crate::field::format_decimal;
+use std::io::Write;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+const HEADERS: [&str; 10] = ["Counterparty", "Request", "Response", "Count", "Min", "Mean", "P50", "P90", "P99", "Max"];
+const NUMERIC_COLUMNS: [usize; 7] = [3, 4, 5, 6, 7, 8, 9];
+
+// Prints latency percentiles in milliseconds for each counterparty and request type.
+#[derive(Default)]
+pub struct LatencyReport {
+}
+
+impl LatencyReport
+{
+    fn format_milliseconds(value: f64) -> String
+    {
+        format_decimal((value * 1000.0).round() / 1000.0)
+    }
+
+    pub fn print<W: Write>(&self, writer: &mut W, samples: &[LatencySample]) -> std::io::Result<()>
+    {
+        let mut table = Table::default();
+
+        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+            .set_style(TableComponent::HeaderLines, '-')
+            .set_style(TableComponent::MiddleHeaderIntersections, '-')
+        ;
+
+        table.set_header(HEADERS.iter().enumerate().map(|(index, header)| {
+            Cell::new(header).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+        }));
+
+        let messages = crate::FIX_5_0SP2::messages();
+
+        for ((counterparty, kind), statistics) in latency_statistics(samples) {
+            let row = [
+                counterparty,
+                messages.name_of_message(kind.request_msg_type()).unwrap_or_default().to_string(),
+                kind.to_string(),
+                statistics.count.to_string(),
+                LatencyReport::format_milliseconds(statistics.min),
+                LatencyReport::format_milliseconds(statistics.mean),
+                LatencyReport::format_milliseconds(statistics.p50),
+                LatencyReport::format_milliseconds(statistics.p90),
+                LatencyReport::format_milliseconds(statistics.p99),
+                LatencyReport::format_milliseconds(statistics.max)
+            ];
+            table.add_row(row.into_iter().enumerate().map(|(index, value)| {
+                Cell::new(value).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+            }));
+        }
+
+        writer.write_all(format!("{}\n\n", table).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::latency::LatencyKind;
+
+    #[test]
+    pub fn statistics_are_printed_per_counterparty_and_request()
+    {
+        let sample = |counterparty: &str, kind: LatencyKind, milliseconds: f64| LatencySample {
+            counterparty: counterparty.to_string(),
+            kind,
+            cl_ord_id: String::new(),
+            milliseconds
+        };
+        let samples = [
+            sample("ACCEPTOR", LatencyKind::Acknowledged, 1.5),
+            sample("ACCEPTOR", LatencyKind::Acknowledged, 2.5),
+            sample("OTHER", LatencyKind::Canceled, 10.0)
+        ];
+
+        let mut output = Vec::new();
+        LatencyReport::default().print(&mut output, &samples).unwrap();
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[1], "| Counterparty   Request              Response       Count   Min   Mean   P50   P90   P99   Max |");
+        assert_eq!(lines[3], "| ACCEPTOR       NewOrderSingle       Acknowledged       2   1.5      2   1.5   2.5   2.5   2.5 |");
+        assert_eq!(lines[4], "| OTHER          OrderCancelRequest   Canceled           1    10     10    10    10    10    10 |");
+    }
+
+}
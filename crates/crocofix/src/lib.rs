@@ -2,10 +2,42 @@ pub mod dictionary;
 pub mod field;
 pub mod message;
 pub mod error;
+pub mod execution;
+pub mod latency;
+pub mod latency_report;
+pub mod market_data;
+pub mod market_data_book;
+pub mod market_data_report;
+pub mod mass_action;
 pub mod order;
 pub mod order_book;
+pub mod order_chain;
+pub mod order_chain_report;
+pub mod order_column;
+pub mod order_event;
+pub mod order_list;
+pub mod order_query;
 pub mod order_report;
+pub mod order_validation;
+pub mod position;
+pub mod position_report;
+pub mod post_trade;
+pub mod post_trade_book;
+pub mod post_trade_report;
+pub mod quote;
+pub mod quote_book;
+pub mod quote_report;
+pub mod report_writer;
 pub mod field_collection;
+pub mod frame_decoder;
+pub mod matching_engine;
+pub mod multileg;
+pub mod session;
+pub mod timestamp;
+#[cfg(feature = "tokio")]
+pub mod codec;
+#[cfg(feature = "tokio")]
+pub mod async_session;
 
 include!(concat!(env!("OUT_DIR"), "/FIX_4_2.rs"));
 include!(concat!(env!("OUT_DIR"), "/FIX_4_4.rs"));
@@ -97,6 +129,14 @@ mod tests {
         assert_eq!(FIX_5_0SP2::fields().name_of_value(999999, "54"), None);
     }
 
+    #[test]
+    fn try_get_field_definition() {
+        assert_eq!(FIX_4_4::fields().try_get(54).map(|field| field.name()), Some("Side"));
+        assert!(FIX_4_4::fields().try_get(0).is_none());
+        assert!(FIX_4_4::fields().try_get(1000).is_none());
+        assert!(FIX_5_0SP2::fields().try_get(999999).is_none());
+    }
+
     #[test]
     #[should_panic]
     fn tag_too_high_fails() {
@@ -0,0 +1,273 @@
+use crate::field_collection::FieldCollection;
+use crate::error::Error;
+
+fn decimal(fields: &FieldCollection, tag: u32) -> Option<f64>
+{
+    fields.try_get(tag).and_then(|field| field.decimal_value().ok())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarketDataSide {
+    Bid,
+    Offer
+}
+
+impl MarketDataSide {
+
+    // The side of the book an MDEntryType belongs to, None for trades, statistics etc.
+    pub fn for_entry_type(entry_type: &str) -> Option<Self>
+    {
+        if entry_type == crate::FIX_5_0SP2::MDEntryType::Bid().value {
+            return Some(MarketDataSide::Bid);
+        }
+        if entry_type == crate::FIX_5_0SP2::MDEntryType::Offer().value {
+            return Some(MarketDataSide::Offer);
+        }
+        None
+    }
+
+    // Bids are ordered highest price first, offers lowest price first. An entry without a price
+    // e.g. a market order, is ordered behind every priced entry.
+    fn is_better(&self, price: Option<f64>, than: Option<f64>) -> bool
+    {
+        match (price, than) {
+            (Some(price), Some(than)) => match self {
+                MarketDataSide::Bid => price > than,
+                MarketDataSide::Offer => price < than
+            },
+            (Some(_), None) => true,
+            _ => false
+        }
+    }
+
+}
+
+// An instance of the NoMDEntries group of a MarketDataSnapshotFullRefresh or
+// MarketDataIncrementalRefresh. An entry with an MDEntryID is an order in an order by order feed,
+// an entry without one is a price level.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketDataEntry {
+    pub entry_type: Option<String>,
+    pub id: Option<String>,
+    pub price: Option<f64>,
+    pub size: Option<f64>,
+    pub number_of_orders: Option<u32>,
+    // The 1 based MDEntryPositionNo, the position of the entry on its side of the book.
+    pub position: Option<usize>,
+    pub time: Option<String>
+}
+
+impl MarketDataEntry {
+
+    pub fn from_fields(fields: &FieldCollection) -> Self
+    {
+        Self {
+            entry_type: fields.try_get(crate::FIX_5_0SP2::MDEntryType::TAG).map(|field| field.value.clone()),
+            id: fields.try_get(crate::FIX_5_0SP2::MDEntryID::TAG).map(|field| field.value.clone()),
+            price: decimal(fields, crate::FIX_5_0SP2::MDEntryPx::TAG),
+            size: decimal(fields, crate::FIX_5_0SP2::MDEntrySize::TAG),
+            number_of_orders: fields.try_get(crate::FIX_5_0SP2::NumberOfOrders::TAG).and_then(|field| field.value.parse().ok()),
+            position: fields.try_get(crate::FIX_5_0SP2::MDEntryPositionNo::TAG).and_then(|field| field.value.parse().ok()),
+            time: fields.try_get(crate::FIX_5_0SP2::MDEntryTime::TAG).map(|field| field.value.clone())
+        }
+    }
+
+    fn describe(&self) -> String
+    {
+        match (&self.id, self.position, self.price) {
+            (Some(id), _, _) => format!("MDEntryID={}", id),
+            (None, Some(position), _) => format!("MDEntryPositionNo={}", position),
+            (None, None, Some(price)) => format!("MDEntryPx={}", price),
+            (None, None, None) => "MDEntryPx=".to_string()
+        }
+    }
+
+}
+
+// The entries at one price aggregated, the number of orders is the sum of NumberOfOrders or a
+// count of the entries for an order by order feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDataLevel {
+    pub price: Option<f64>,
+    pub size: f64,
+    pub orders: u32
+}
+
+// The book for a single symbol.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketDataInstrument {
+    pub symbol: String,
+    pub bids: Vec<MarketDataEntry>,
+    pub offers: Vec<MarketDataEntry>,
+    pub last_trade: Option<MarketDataEntry>,
+    // The number of snapshots and incremental entries applied.
+    pub updates: usize
+}
+
+impl MarketDataInstrument {
+
+    pub fn new(symbol: &str) -> Self
+    {
+        Self {
+            symbol: symbol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn entries(&self, side: MarketDataSide) -> &Vec<MarketDataEntry>
+    {
+        match side {
+            MarketDataSide::Bid => &self.bids,
+            MarketDataSide::Offer => &self.offers
+        }
+    }
+
+    fn entries_mut(&mut self, side: MarketDataSide) -> &mut Vec<MarketDataEntry>
+    {
+        match side {
+            MarketDataSide::Bid => &mut self.bids,
+            MarketDataSide::Offer => &mut self.offers
+        }
+    }
+
+    // The side of the book containing an entry, for a Change or Delete that omits MDEntryType.
+    fn side_of(&self, id: &str) -> Option<MarketDataSide>
+    {
+        [MarketDataSide::Bid, MarketDataSide::Offer]
+            .into_iter()
+            .find(|side| self.entries(*side).iter().any(|entry| entry.id.as_deref() == Some(id)))
+    }
+
+    // Up to the best count price levels on one side of the book.
+    pub fn depth(&self, side: MarketDataSide, count: usize) -> Vec<MarketDataLevel>
+    {
+        let mut levels: Vec<MarketDataLevel> = Vec::new();
+
+        for entry in self.entries(side) {
+            let orders = if entry.id.is_some() { 1 } else { entry.number_of_orders.unwrap_or(0) };
+            match levels.last_mut() {
+                Some(level) if level.price == entry.price => {
+                    level.size += entry.size.unwrap_or(0.0);
+                    level.orders += orders;
+                }
+                _ => {
+                    if levels.len() == count {
+                        break;
+                    }
+                    levels.push(MarketDataLevel { price: entry.price, size: entry.size.unwrap_or(0.0), orders });
+                }
+            }
+        }
+
+        levels
+    }
+
+    pub fn best_bid(&self) -> Option<MarketDataLevel>
+    {
+        self.depth(MarketDataSide::Bid, 1).pop()
+    }
+
+    pub fn best_offer(&self) -> Option<MarketDataLevel>
+    {
+        self.depth(MarketDataSide::Offer, 1).pop()
+    }
+
+    pub(crate) fn clear(&mut self)
+    {
+        self.bids.clear();
+        self.offers.clear();
+    }
+
+    pub(crate) fn apply(&mut self, action: &str, entry: MarketDataEntry) -> Result<(), Error>
+    {
+        self.updates += 1;
+
+        let side = match entry.entry_type.as_deref() {
+            Some(entry_type) => {
+                if entry_type == crate::FIX_5_0SP2::MDEntryType::Trade().value {
+                    // A trade can't be changed or deleted in the book, a bust is reported elsewhere.
+                    if action == crate::FIX_5_0SP2::MDUpdateAction::New().value {
+                        self.last_trade = Some(entry);
+                    }
+                    return Ok(());
+                }
+                match MarketDataSide::for_entry_type(entry_type) {
+                    Some(side) => side,
+                    None => return Ok(())
+                }
+            }
+            None => {
+                let side = entry.id.as_deref().and_then(|id| self.side_of(id));
+                match side {
+                    Some(side) => side,
+                    None => return Err(Error::MessageDoesNotContainFieldWithTag(crate::FIX_5_0SP2::MDEntryType::TAG))
+                }
+            }
+        };
+
+        if action == crate::FIX_5_0SP2::MDUpdateAction::New().value {
+            self.insert(side, entry);
+            return Ok(());
+        }
+
+        let Some(index) = self.find(side, &entry) else {
+            return Err(Error::MarketDataBookDoesNotContainEntry(format!("{} {}", self.symbol, entry.describe())));
+        };
+
+        if action == crate::FIX_5_0SP2::MDUpdateAction::Change().value {
+            let entries = self.entries_mut(side);
+            let existing = entries.remove(index);
+            let entry = MarketDataEntry {
+                entry_type: entry.entry_type.or(existing.entry_type),
+                price: entry.price.or(existing.price),
+                number_of_orders: entry.number_of_orders.or(existing.number_of_orders),
+                ..entry
+            };
+            // A price level changed by position keeps its position, an order that changed price
+            // loses its priority.
+            if entry.position.is_some() || entry.price == existing.price {
+                entries.insert(index, entry);
+            }
+            else {
+                self.insert(side, entry);
+            }
+            return Ok(());
+        }
+
+        if action == crate::FIX_5_0SP2::MDUpdateAction::Delete().value {
+            self.entries_mut(side).remove(index);
+            return Ok(());
+        }
+
+        Err(Error::UnsupportedMDUpdateAction(action.to_string()))
+    }
+
+    // Insert at MDEntryPositionNo if present, otherwise behind every entry at the same or a better
+    // price.
+    fn insert(&mut self, side: MarketDataSide, entry: MarketDataEntry)
+    {
+        let entries = self.entries_mut(side);
+        let index = match entry.position {
+            Some(position) => position.saturating_sub(1).min(entries.len()),
+            None => entries.iter().position(|existing| side.is_better(entry.price, existing.price)).unwrap_or(entries.len())
+        };
+        entries.insert(index, entry);
+    }
+
+    // Find an entry by MDEntryID, then MDEntryPositionNo, then MDEntryPx.
+    fn find(&self, side: MarketDataSide, entry: &MarketDataEntry) -> Option<usize>
+    {
+        let entries = self.entries(side);
+        if let Some(id) = &entry.id {
+            return entries.iter().position(|existing| existing.id.as_ref() == Some(id));
+        }
+        if let Some(position) = entry.position {
+            return if position >= 1 && position <= entries.len() { Some(position - 1) } else { None };
+        }
+        entries.iter().position(|existing| existing.price == entry.price)
+    }
+
+}
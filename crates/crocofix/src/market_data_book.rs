@@ -0,0 +1,189 @@
+use indexmap::IndexMap;
+use crate::market_data::{MarketDataEntry, MarketDataInstrument};
+use crate::message::Message;
+use crate::error::Error;
+
+// Reconstructs a book per symbol from MarketDataSnapshotFullRefresh and
+// MarketDataIncrementalRefresh messages. This is independent of the OrderBook, the entries are the
+// market rather than orders sent on the session.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketDataBook {
+    pub instruments: IndexMap<String, MarketDataInstrument>
+}
+
+impl MarketDataBook {
+
+    // Returns the symbols updated by the message.
+    pub fn process(&mut self, message: &Message) -> Result<Vec<String>, Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::MarketDataSnapshotFullRefresh().value {
+            return self.process_snapshot(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::MarketDataIncrementalRefresh().value {
+            return self.process_incremental(message);
+        }
+
+        Err(Error::UnsupportedMsgType(msg_type.to_string()))
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.instruments.clear();
+    }
+
+    pub fn instrument(&self, symbol: &str) -> Option<&MarketDataInstrument>
+    {
+        self.instruments.get(symbol)
+    }
+
+    // Messages are applied to a copy of each instrument they update so a message with an entry that
+    // fails leaves the book as it was.
+    fn copy_of_instrument(&self, symbol: &str) -> MarketDataInstrument
+    {
+        self.instruments.get(symbol).cloned().unwrap_or_else(|| MarketDataInstrument::new(symbol))
+    }
+
+    // A snapshot replaces both sides of the book, the last trade is kept unless the snapshot has one.
+    fn process_snapshot(&mut self, message: &Message) -> Result<Vec<String>, Error>
+    {
+        let symbol = message.fields.get(crate::FIX_5_0SP2::Symbol::TAG)?.value.clone();
+        let mut instrument = self.copy_of_instrument(&symbol);
+        instrument.clear();
+
+        for fields in message.fields.group(crate::FIX_5_0SP2::NoMDEntries::TAG, &[]) {
+            instrument.apply(crate::FIX_5_0SP2::MDUpdateAction::New().value, MarketDataEntry::from_fields(&fields))?;
+        }
+
+        self.instruments.insert(symbol.clone(), instrument);
+
+        Ok(vec![symbol])
+    }
+
+    // Each entry has its own Symbol, an entry without one is for the same symbol as the entry
+    // before it.
+    fn process_incremental(&mut self, message: &Message) -> Result<Vec<String>, Error>
+    {
+        let mut updated: IndexMap<String, MarketDataInstrument> = IndexMap::new();
+        let mut symbol = message.fields.try_get(crate::FIX_5_0SP2::Symbol::TAG).map(|field| field.value.clone());
+
+        for fields in message.fields.group(crate::FIX_5_0SP2::NoMDEntries::TAG, &[]) {
+            let action = fields.get(crate::FIX_5_0SP2::MDUpdateAction::TAG)?.value.clone();
+            if let Some(field) = fields.try_get(crate::FIX_5_0SP2::Symbol::TAG) {
+                symbol = Some(field.value.clone());
+            }
+            let Some(symbol) = &symbol else {
+                return Err(Error::MessageDoesNotContainFieldWithTag(crate::FIX_5_0SP2::Symbol::TAG));
+            };
+            updated
+                .entry(symbol.clone())
+                .or_insert_with(|| self.copy_of_instrument(symbol))
+                .apply(&action, MarketDataEntry::from_fields(&fields))?;
+        }
+
+        let symbols = updated.keys().cloned().collect();
+        self.instruments.extend(updated);
+
+        Ok(symbols)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::market_data::{MarketDataLevel, MarketDataSide};
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    fn level(price: f64, size: f64, orders: u32) -> MarketDataLevel
+    {
+        MarketDataLevel { price: Some(price), size, orders }
+    }
+
+    #[test]
+    pub fn snapshot_and_incremental_price_levels() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=W\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=2\u{0001}262=1\u{0001}55=BHP.AX\u{0001}268=4\u{0001}269=0\u{0001}270=21.00\u{0001}271=500\u{0001}346=2\u{0001}269=0\u{0001}270=20.99\u{0001}271=300\u{0001}346=1\u{0001}269=1\u{0001}270=21.01\u{0001}271=200\u{0001}346=1\u{0001}269=1\u{0001}270=21.02\u{0001}271=700\u{0001}346=3\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=3\u{0001}262=1\u{0001}268=4\u{0001}279=0\u{0001}269=0\u{0001}55=BHP.AX\u{0001}270=21.005\u{0001}271=100\u{0001}346=1\u{0001}279=1\u{0001}269=1\u{0001}270=21.01\u{0001}271=150\u{0001}279=2\u{0001}269=0\u{0001}270=20.99\u{0001}279=0\u{0001}269=2\u{0001}270=21.01\u{0001}271=50\u{0001}10=000\u{0001}"
+        ];
+        let mut book = MarketDataBook::default();
+
+        assert_eq!(book.process(&decode_message(messages[0])?)?, vec!["BHP.AX".to_string()]);
+        let instrument = book.instrument("BHP.AX").unwrap();
+        assert_eq!(instrument.best_bid(), Some(level(21.0, 500.0, 2)));
+        assert_eq!(instrument.best_offer(), Some(level(21.01, 200.0, 1)));
+
+        assert_eq!(book.process(&decode_message(messages[1])?)?, vec!["BHP.AX".to_string()]);
+        let instrument = book.instrument("BHP.AX").unwrap();
+        assert_eq!(instrument.depth(MarketDataSide::Bid, 5), vec![level(21.005, 100.0, 1), level(21.0, 500.0, 2)]);
+        assert_eq!(instrument.depth(MarketDataSide::Offer, 1), vec![level(21.01, 150.0, 1)]);
+        let last_trade = instrument.last_trade.as_ref().unwrap();
+        assert_eq!(last_trade.price, Some(21.01));
+        assert_eq!(last_trade.size, Some(50.0));
+        Ok(())
+    }
+
+    #[test]
+    pub fn order_by_order_entries_are_aggregated_into_levels() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=2\u{0001}268=3\u{0001}279=0\u{0001}269=0\u{0001}278=A\u{0001}55=BHP.AX\u{0001}270=21\u{0001}271=100\u{0001}279=0\u{0001}269=0\u{0001}278=B\u{0001}270=21\u{0001}271=200\u{0001}279=0\u{0001}269=0\u{0001}278=C\u{0001}270=20.5\u{0001}271=300\u{0001}10=000\u{0001}",
+            // Change without an MDEntryType, the price change moves C behind A and B.
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=3\u{0001}268=2\u{0001}279=1\u{0001}278=C\u{0001}55=BHP.AX\u{0001}270=21\u{0001}271=300\u{0001}279=2\u{0001}278=A\u{0001}10=000\u{0001}"
+        ];
+        let mut book = MarketDataBook::default();
+
+        book.process(&decode_message(messages[0])?)?;
+        let instrument = book.instrument("BHP.AX").unwrap();
+        assert_eq!(instrument.depth(MarketDataSide::Bid, 5), vec![level(21.0, 300.0, 2), level(20.5, 300.0, 1)]);
+
+        book.process(&decode_message(messages[1])?)?;
+        let instrument = book.instrument("BHP.AX").unwrap();
+        assert_eq!(instrument.depth(MarketDataSide::Bid, 5), vec![level(21.0, 500.0, 2)]);
+        let ids: Vec<&str> = instrument.bids.iter().filter_map(|entry| entry.id.as_deref()).collect();
+        assert_eq!(ids, vec!["B", "C"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn delete_of_an_unknown_entry_fails() -> Result<(), crate::error::Error>
+    {
+        let message = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=2\u{0001}268=1\u{0001}279=2\u{0001}269=1\u{0001}278=Z\u{0001}55=BHP.AX\u{0001}10=000\u{0001}")?;
+        let mut book = MarketDataBook::default();
+        assert_eq!(book.process(&message), Err(Error::MarketDataBookDoesNotContainEntry("BHP.AX MDEntryID=Z".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    pub fn incremental_refresh_with_an_entry_that_fails_is_not_applied() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=W\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=2\u{0001}55=BHP.AX\u{0001}268=1\u{0001}269=0\u{0001}270=21.00\u{0001}271=500\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=3\u{0001}268=3\u{0001}279=0\u{0001}269=0\u{0001}55=BHP.AX\u{0001}270=21.005\u{0001}271=100\u{0001}279=0\u{0001}269=1\u{0001}55=RIO.AX\u{0001}270=120\u{0001}271=10\u{0001}279=2\u{0001}269=1\u{0001}278=Z\u{0001}10=000\u{0001}"
+        ];
+        let mut book = MarketDataBook::default();
+        book.process(&decode_message(messages[0])?)?;
+
+        assert_eq!(book.process(&decode_message(messages[1])?), Err(Error::MarketDataBookDoesNotContainEntry("RIO.AX MDEntryID=Z".to_string())));
+        let instrument = book.instrument("BHP.AX").unwrap();
+        assert_eq!(instrument.depth(MarketDataSide::Bid, 5), vec![level(21.0, 500.0, 0)]);
+        assert_eq!(instrument.updates, 1);
+        assert!(book.instrument("RIO.AX").is_none());
+        Ok(())
+    }
+
+}
@@ -0,0 +1,153 @@
+use crate::market_data::{MarketDataInstrument, MarketDataLevel, MarketDataSide};
+use crate::market_data_book::MarketDataBook;
+use crate::field::format_decimal;
+use std::io::Write;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+const HEADERS: [&str; 9] = ["Symbol", "BidOrders", "BidSize", "BidPx", "OfferPx", "OfferSize", "OfferOrders", "LastPx", "LastSize"];
+const NUMERIC_COLUMNS: [usize; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+// Prints the top levels of each side of the book with the last trade, one row per level.
+pub struct MarketDataReport {
+    depth: usize
+}
+
+impl Default for MarketDataReport {
+    fn default() -> Self {
+        Self { depth: 5 }
+    }
+}
+
+impl MarketDataReport
+{
+    pub fn with_depth(depth: usize) -> Self
+    {
+        Self {
+            depth
+        }
+    }
+
+    fn table() -> Table
+    {
+        let mut table = Table::default();
+
+        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+            .set_style(TableComponent::HeaderLines, '-')
+            .set_style(TableComponent::MiddleHeaderIntersections, '-')
+        ;
+
+        table.set_header(HEADERS.iter().enumerate().map(|(index, header)| {
+            Cell::new(header).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+        }));
+
+        table
+    }
+
+    fn row(values: Vec<String>) -> Vec<Cell>
+    {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                Cell::new(value).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+            })
+            .collect()
+    }
+
+    // Orders, size, and price of a level, or empty cells once one side has run out of levels.
+    fn level_values(level: Option<&MarketDataLevel>) -> [String; 3]
+    {
+        match level {
+            Some(level) => [
+                if level.orders > 0 { level.orders.to_string() } else { String::new() },
+                format_decimal(level.size),
+                level.price.map(format_decimal).unwrap_or_default()
+            ],
+            None => Default::default()
+        }
+    }
+
+    fn add_instrument(&self, table: &mut Table, instrument: &MarketDataInstrument)
+    {
+        let bids = instrument.depth(MarketDataSide::Bid, self.depth);
+        let offers = instrument.depth(MarketDataSide::Offer, self.depth);
+
+        for index in 0..bids.len().max(offers.len()).max(1) {
+            let [bid_orders, bid_size, bid_px] = MarketDataReport::level_values(bids.get(index));
+            let [offer_orders, offer_size, offer_px] = MarketDataReport::level_values(offers.get(index));
+            let last_trade = if index == 0 { instrument.last_trade.as_ref() } else { None };
+            let row = vec![
+                if index == 0 { instrument.symbol.clone() } else { String::new() },
+                bid_orders,
+                bid_size,
+                bid_px,
+                offer_px,
+                offer_size,
+                offer_orders,
+                last_trade.and_then(|trade| trade.price).map(format_decimal).unwrap_or_default(),
+                last_trade.and_then(|trade| trade.size).map(format_decimal).unwrap_or_default()
+            ];
+            table.add_row(MarketDataReport::row(row));
+        }
+    }
+
+    // Print the given symbols, typically those updated by the most recent message.
+    pub fn print_symbols<W: Write>(&self, writer: &mut W, market_data_book: &MarketDataBook, symbols: &[String]) -> std::io::Result<()>
+    {
+        let mut table = MarketDataReport::table();
+
+        for symbol in symbols {
+            if let Some(instrument) = market_data_book.instrument(symbol) {
+                self.add_instrument(&mut table, instrument);
+            }
+        }
+
+        writer.write_all(format!("{}\n\n", table).as_bytes())
+    }
+
+    pub fn print<W: Write>(&self, writer: &mut W, market_data_book: &MarketDataBook) -> std::io::Result<()>
+    {
+        let symbols: Vec<String> = market_data_book.instruments.keys().cloned().collect();
+        self.print_symbols(writer, market_data_book, &symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn top_levels_are_printed() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=W\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=2\u{0001}55=BHP.AX\u{0001}268=4\u{0001}269=0\u{0001}270=21\u{0001}271=500\u{0001}346=2\u{0001}269=0\u{0001}270=20.99\u{0001}271=300\u{0001}346=1\u{0001}269=0\u{0001}270=20.98\u{0001}271=100\u{0001}346=1\u{0001}269=1\u{0001}270=21.01\u{0001}271=200\u{0001}346=1\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=X\u{0001}49=MD\u{0001}56=CLIENT\u{0001}34=3\u{0001}268=1\u{0001}279=0\u{0001}269=2\u{0001}55=BHP.AX\u{0001}270=21.01\u{0001}271=50\u{0001}10=000\u{0001}"
+        ];
+        let mut book = MarketDataBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        MarketDataReport::with_depth(2).print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[1], "| Symbol   BidOrders   BidSize   BidPx   OfferPx   OfferSize   OfferOrders   LastPx   LastSize |");
+        assert_eq!(lines[3], "| BHP.AX           2       500      21     21.01         200             1    21.01         50 |");
+        assert_eq!(lines[4], "|                  1       300   20.99                                                         |");
+        Ok(())
+    }
+
+}
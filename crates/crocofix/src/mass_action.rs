@@ -0,0 +1,431 @@
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::order_event::OrderEvent;
+use crate::order_query::OrderFilter;
+use crate::error::Error;
+
+// The members of the NoAffectedOrders group in an OrderMassCancelReport.
+const AFFECTED_ORDER_FIELDS: [u32; 3] = [
+    crate::FIX_5_0SP2::OrigClOrdID::TAG,
+    crate::FIX_5_0SP2::AffectedOrderID::TAG,
+    crate::FIX_5_0SP2::AffectedSecondaryOrderID::TAG
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MassActionKind {
+    Cancel,
+    Status
+}
+
+// The orders a mass cancel or mass status request applies to.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MassActionScope {
+    AllOrders,
+    Symbol(String),
+    SecurityType(String),
+    // A request type we can't predict the orders for e.g. by trading session or party, this is
+    // the MassCancelRequestType or MassStatusReqType.
+    Unsupported(String)
+}
+
+impl MassActionScope {
+
+    // MassCancelRequestType and MassStatusReqType share values for the scopes we support.
+    fn for_request(request_type: &str, request: &Message) -> Self
+    {
+        let value = |tag: u32| request.fields.try_get(tag).map(|field| field.value.clone()).unwrap_or_default();
+
+        if request_type == crate::FIX_5_0SP2::MassCancelRequestType::CancelAllOrders().value ||
+           request_type == crate::FIX_5_0SP2::MassStatusReqType::StatusForAllOrders().value {
+            return MassActionScope::AllOrders;
+        }
+
+        if request_type == crate::FIX_5_0SP2::MassCancelRequestType::CancelOrdersForASecurity().value ||
+           request_type == crate::FIX_5_0SP2::MassStatusReqType::StatusForOrdersForASecurity().value {
+            return MassActionScope::Symbol(value(crate::FIX_5_0SP2::Symbol::TAG));
+        }
+
+        if request_type == crate::FIX_5_0SP2::MassCancelRequestType::CancelOrdersForASecurityType().value ||
+           request_type == crate::FIX_5_0SP2::MassStatusReqType::StatusForOrdersForASecurityType().value {
+            return MassActionScope::SecurityType(value(crate::FIX_5_0SP2::SecurityType::TAG));
+        }
+
+        MassActionScope::Unsupported(request_type.to_string())
+    }
+
+}
+
+impl std::fmt::Display for MassActionScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MassActionScope::AllOrders => write!(f, "AllOrders"),
+            MassActionScope::Symbol(symbol) => write!(f, "Symbol={}", symbol),
+            MassActionScope::SecurityType(security_type) => write!(f, "SecurityType={}", security_type),
+            MassActionScope::Unsupported(request_type) => write!(f, "RequestType={}", request_type)
+        }
+    }
+}
+
+// An OrderMassCancelRequest or OrderMassStatusRequest and the responses to it. Mass actions are
+// identified by SenderCompID, TargetCompID, and the ClOrdID or MassStatusReqID of the request.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MassAction {
+    pub key: String,
+    pub kind: MassActionKind,
+    // The ClOrdID of an OrderMassCancelRequest or the MassStatusReqID of an OrderMassStatusRequest.
+    pub id: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub scope: MassActionScope,
+    // An OrderMassCancelRequest can be restricted to one side of the market.
+    pub side: Option<String>,
+    // The request, OrderMassCancelReport, and ExecutionReports carrying the MassStatusReqID.
+    pub messages: Vec<Message>,
+    // The keys of the orders that were open and in scope when the request was sent.
+    pub expected: Vec<String>,
+    // The keys of the orders the counterparty says it acted on, the NoAffectedOrders of an
+    // OrderMassCancelReport or the orders it sent an ExecutionReport for in response to a status request.
+    pub affected: Vec<String>,
+    pub mass_cancel_response: Option<String>,
+    pub total_affected_orders: Option<usize>
+}
+
+impl MassAction {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, id)
+    }
+
+    fn new(kind: MassActionKind, request: &Message) -> Result<Self, Error>
+    {
+        let (id_tag, request_type_tag) = match kind {
+            MassActionKind::Cancel => (crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::MassCancelRequestType::TAG),
+            MassActionKind::Status => (crate::FIX_5_0SP2::MassStatusReqID::TAG, crate::FIX_5_0SP2::MassStatusReqType::TAG)
+        };
+
+        let id = request.fields.get(id_tag)?.value.clone();
+        let sender_comp_id = request.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone();
+        let target_comp_id = request.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.clone();
+        let request_type = request.fields.get(request_type_tag)?.value.as_str();
+
+        Ok(MassAction {
+            key: MassAction::create_key(&sender_comp_id, &target_comp_id, &id),
+            kind,
+            id,
+            sender_comp_id,
+            target_comp_id,
+            scope: MassActionScope::for_request(request_type, request),
+            side: request.fields.try_get(crate::FIX_5_0SP2::Side::TAG).map(|field| field.value.clone()),
+            messages: vec![request.clone()],
+            expected: Vec::new(),
+            affected: Vec::new(),
+            mass_cancel_response: None,
+            total_affected_orders: None
+        })
+    }
+
+    // The orders this request should apply to, None if we can't tell from the scope.
+    pub fn filter(&self) -> Option<OrderFilter>
+    {
+        let filter = OrderFilter::default().open().counterparty(&self.sender_comp_id, &self.target_comp_id);
+        let filter = match &self.scope {
+            MassActionScope::AllOrders => filter,
+            MassActionScope::Symbol(symbol) => filter.symbol(symbol),
+            MassActionScope::SecurityType(security_type) => filter.field(crate::FIX_5_0SP2::SecurityType::TAG, security_type),
+            MassActionScope::Unsupported(_) => return None
+        };
+        match &self.side {
+            Some(side) => Some(filter.side(side)),
+            None => Some(filter)
+        }
+    }
+
+    pub fn is_rejected(&self) -> bool
+    {
+        self.mass_cancel_response.as_deref() == Some(crate::FIX_5_0SP2::MassCancelResponse::CancelRequestRejected().value)
+    }
+
+}
+
+impl OrderBook {
+
+    // The current version of each order the request was expected to apply to.
+    pub fn mass_action_orders(&self, key: &str) -> Vec<&Order>
+    {
+        let Some(mass_action) = self.mass_actions.get(key) else {
+            return Vec::new();
+        };
+        mass_action.expected.iter().filter_map(|key| self.latest(key)).collect()
+    }
+
+    // Orders in the scope of a mass cancel that are still open, whether or not the counterparty
+    // listed them as affected.
+    pub fn uncanceled_orders(&self, key: &str) -> Vec<&Order>
+    {
+        self.mass_action_orders(key).into_iter().filter(|order| order.is_open()).collect()
+    }
+
+    // Orders in the scope of a mass status request the counterparty hasn't sent a status for.
+    pub fn unreported_orders(&self, key: &str) -> Vec<&Order>
+    {
+        let Some(mass_action) = self.mass_actions.get(key) else {
+            return Vec::new();
+        };
+        self.mass_action_orders(key)
+            .into_iter()
+            .filter(|order| !mass_action.affected.contains(&order.key))
+            .collect()
+    }
+
+    // The orders that haven't been acted on, uncanceled for a mass cancel and unreported for a mass status request.
+    pub fn outstanding_orders(&self, key: &str) -> Vec<&Order>
+    {
+        match self.mass_actions.get(key).map(|mass_action| mass_action.kind) {
+            Some(MassActionKind::Cancel) => self.uncanceled_orders(key),
+            Some(MassActionKind::Status) => self.unreported_orders(key),
+            None => Vec::new()
+        }
+    }
+
+    // Orders the counterparty acted on that we didn't expect the request to apply to.
+    pub fn unexpected_orders(&self, key: &str) -> Vec<&Order>
+    {
+        let Some(mass_action) = self.mass_actions.get(key) else {
+            return Vec::new();
+        };
+        let expected: Vec<&str> = self.mass_action_orders(key).iter().map(|order| order.key.as_str()).collect();
+        mass_action.affected
+            .iter()
+            .filter(|key| !expected.contains(&key.as_str()))
+            .filter_map(|key| self.orders.get(key))
+            .collect()
+    }
+
+    fn insert_mass_action(&mut self, mut mass_action: MassAction) -> Result<Vec<OrderEvent>, Error>
+    {
+        if self.mass_actions.contains_key(&mass_action.key) {
+            return Err(Error::OrderBookAlreadyContainsOrderWithKey(mass_action.key));
+        }
+
+        if let Some(filter) = mass_action.filter() {
+            mass_action.expected = self.query(&filter).map(|order| order.key.clone()).collect();
+        }
+
+        self.mass_actions.insert(mass_action.key.clone(), mass_action);
+        Ok(vec![])
+    }
+
+    pub(crate) fn process_order_mass_cancel_request(&mut self, request: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        self.insert_mass_action(MassAction::new(MassActionKind::Cancel, request)?)
+    }
+
+    pub(crate) fn process_order_mass_status_request(&mut self, request: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        self.insert_mass_action(MassAction::new(MassActionKind::Status, request)?)
+    }
+
+    // Find the current version of an order in the NoAffectedOrders group by OrigClOrdID or OrderID.
+    fn affected_order_key(&self, mass_action: &MassAction, affected: &FieldCollection) -> Option<String>
+    {
+        let orig_cl_ord_id = affected.try_get(crate::FIX_5_0SP2::OrigClOrdID::TAG).map(|field| field.value.as_str());
+        let order_id = affected.try_get(crate::FIX_5_0SP2::AffectedOrderID::TAG).map(|field| field.value.as_str());
+
+        self.orders
+            .values()
+            .filter(|order| order.sender_comp_id == mass_action.sender_comp_id && order.target_comp_id == mass_action.target_comp_id)
+            .find(|order| {
+                orig_cl_ord_id == Some(order.cl_ord_id.value.as_str()) ||
+                (order_id.is_some() && order.fields.try_get(crate::FIX_5_0SP2::OrderID::TAG).map(|field| field.value.as_str()) == order_id)
+            })
+            .and_then(|order| self.latest(&order.key))
+            .map(|order| order.key.clone())
+    }
+
+    pub(crate) fn process_order_mass_cancel_report(&mut self, report: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let cl_ord_id = report.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value.as_str();
+        let sender_comp_id = report.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+        let target_comp_id = report.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+        let key = MassAction::create_key(target_comp_id, sender_comp_id, cl_ord_id);
+
+        let Some(mass_action) = self.mass_actions.get(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        let affected: Vec<String> = report.fields
+            .group(crate::FIX_5_0SP2::NoAffectedOrders::TAG, &AFFECTED_ORDER_FIELDS)
+            .iter()
+            .filter_map(|affected| self.affected_order_key(mass_action, affected))
+            .collect();
+
+        let Some(mass_action) = self.mass_actions.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        mass_action.messages.push(report.clone());
+        mass_action.mass_cancel_response = report.fields.try_get(crate::FIX_5_0SP2::MassCancelResponse::TAG).map(|field| field.value.clone());
+        mass_action.total_affected_orders = report.fields.try_get(crate::FIX_5_0SP2::TotalAffectedOrders::TAG).and_then(|field| field.value.parse().ok());
+        for key in affected {
+            if !mass_action.affected.contains(&key) {
+                mass_action.affected.push(key);
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    // Record an ExecutionReport sent in response to an OrderMassStatusRequest against the request,
+    // the report has already been applied to the order with order_key.
+    pub(crate) fn process_mass_status_report(&mut self, execution_report: &Message, order_key: &str) -> Result<(), Error>
+    {
+        let Some(mass_status_req_id) = execution_report.fields.try_get(crate::FIX_5_0SP2::MassStatusReqID::TAG) else {
+            return Ok(());
+        };
+        let sender_comp_id = execution_report.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+        let target_comp_id = execution_report.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+        let key = MassAction::create_key(target_comp_id, sender_comp_id, &mass_status_req_id.value);
+
+        let order_key = self.latest(order_key).map(|order| order.key.clone());
+
+        // Unsolicited or the request was before the start of the log.
+        let Some(mass_action) = self.mass_actions.get_mut(&key) else {
+            return Ok(());
+        };
+
+        mass_action.messages.push(execution_report.clone());
+        if let Some(order_key) = order_key {
+            if !mass_action.affected.contains(&order_key) {
+                mass_action.affected.push(order_key);
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const ORDERS: [&str; 8] = [
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}167=CS\u{0001}54=1\u{0001}38=100\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}37=O1\u{0001}17=1\u{0001}150=0\u{0001}39=0\u{0001}151=100\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=BHP.AX\u{0001}167=CS\u{0001}54=2\u{0001}38=200\u{0001}40=2\u{0001}44=22\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=2\u{0001}37=O2\u{0001}17=2\u{0001}150=0\u{0001}39=0\u{0001}151=200\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=3\u{0001}55=RIO.AX\u{0001}167=CS\u{0001}54=1\u{0001}38=300\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}11=3\u{0001}37=O3\u{0001}17=3\u{0001}150=0\u{0001}39=0\u{0001}151=300\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=5\u{0001}11=4\u{0001}55=BHP.AX\u{0001}167=CS\u{0001}54=1\u{0001}38=400\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}11=4\u{0001}37=O4\u{0001}17=4\u{0001}150=F\u{0001}39=2\u{0001}32=400\u{0001}31=21\u{0001}151=0\u{0001}14=400\u{0001}6=21\u{0001}10=000\u{0001}"
+    ];
+
+    fn process(texts: &[&str]) -> Result<OrderBook, Error>
+    {
+        let mut book = OrderBook::default();
+        for text in ORDERS.iter().chain(texts) {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    fn keys(orders: Vec<&Order>) -> Vec<String>
+    {
+        orders.iter().map(|order| order.key.clone()).collect()
+    }
+
+    const MASS_CANCEL_SYMBOL: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=q\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=6\u{0001}11=M1\u{0001}530=1\u{0001}55=BHP.AX\u{0001}60=20240115-10:00:00\u{0001}10=000\u{0001}";
+
+    #[test]
+    pub fn mass_cancel_predicts_the_orders_in_scope() -> Result<(), Error>
+    {
+        let book = process(&[MASS_CANCEL_SYMBOL])?;
+        let mass_action = &book.mass_actions["INITIATOR-ACCEPTOR-M1"];
+        assert_eq!(mass_action.kind, MassActionKind::Cancel);
+        assert_eq!(mass_action.scope, MassActionScope::Symbol("BHP.AX".to_string()));
+        // The RIO.AX order is out of scope and the filled BHP.AX order is closed.
+        assert_eq!(mass_action.expected, vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-2"]);
+        assert_eq!(keys(book.uncanceled_orders("INITIATOR-ACCEPTOR-M1")), vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-2"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_cancel_reports_orders_that_were_not_canceled() -> Result<(), Error>
+    {
+        let report = "8=FIX.4.4\u{0001}9=0\u{0001}35=r\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}11=M1\u{0001}37=MC1\u{0001}530=1\u{0001}531=1\u{0001}533=2\u{0001}534=2\u{0001}41=1\u{0001}535=O1\u{0001}41=3\u{0001}535=O3\u{0001}10=000\u{0001}";
+        let canceled = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}11=1\u{0001}37=O1\u{0001}17=5\u{0001}150=4\u{0001}39=4\u{0001}151=0\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+
+        let book = process(&[MASS_CANCEL_SYMBOL, report, canceled])?;
+        let mass_action = &book.mass_actions["INITIATOR-ACCEPTOR-M1"];
+        assert!(!mass_action.is_rejected());
+        assert_eq!(mass_action.total_affected_orders, Some(2));
+        assert_eq!(mass_action.affected, vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-3"]);
+
+        assert_eq!(keys(book.uncanceled_orders("INITIATOR-ACCEPTOR-M1")), vec!["INITIATOR-ACCEPTOR-2"]);
+        assert_eq!(keys(book.outstanding_orders("INITIATOR-ACCEPTOR-M1")), vec!["INITIATOR-ACCEPTOR-2"]);
+        assert_eq!(keys(book.unexpected_orders("INITIATOR-ACCEPTOR-M1")), vec!["INITIATOR-ACCEPTOR-3"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_cancel_report_without_affected_orders() -> Result<(), Error>
+    {
+        let report = "8=FIX.4.4\u{0001}9=0\u{0001}35=r\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}11=M1\u{0001}37=MC1\u{0001}530=1\u{0001}531=1\u{0001}533=0\u{0001}534=0\u{0001}58=none\u{0001}10=000\u{0001}";
+
+        let book = process(&[MASS_CANCEL_SYMBOL, report])?;
+        let mass_action = &book.mass_actions["INITIATOR-ACCEPTOR-M1"];
+        assert!(mass_action.affected.is_empty());
+        assert_eq!(keys(book.uncanceled_orders("INITIATOR-ACCEPTOR-M1")), vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-2"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_cancel_by_side_and_rejected() -> Result<(), Error>
+    {
+        let request = "8=FIX.4.4\u{0001}9=0\u{0001}35=q\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=6\u{0001}11=M2\u{0001}530=7\u{0001}54=1\u{0001}60=20240115-10:00:00\u{0001}10=000\u{0001}";
+        let report = "8=FIX.4.4\u{0001}9=0\u{0001}35=r\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}11=M2\u{0001}37=MC2\u{0001}530=7\u{0001}531=0\u{0001}532=99\u{0001}10=000\u{0001}";
+
+        let book = process(&[request, report])?;
+        let mass_action = &book.mass_actions["INITIATOR-ACCEPTOR-M2"];
+        assert_eq!(mass_action.scope, MassActionScope::AllOrders);
+        assert!(mass_action.is_rejected());
+        assert_eq!(mass_action.expected, vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-3"]);
+        assert_eq!(book.uncanceled_orders("INITIATOR-ACCEPTOR-M2").len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_status_reports_orders_without_a_status() -> Result<(), Error>
+    {
+        let request = "8=FIX.4.4\u{0001}9=0\u{0001}35=AF\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=6\u{0001}584=S1\u{0001}585=5\u{0001}167=CS\u{0001}10=000\u{0001}";
+        let status = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}584=S1\u{0001}911=3\u{0001}11=3\u{0001}37=O3\u{0001}17=6\u{0001}150=I\u{0001}39=0\u{0001}151=300\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let unknown = "8=FIX.4.4\u{0001}9=0\u{0001}35=r\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}11=M9\u{0001}530=7\u{0001}531=7\u{0001}10=000\u{0001}";
+
+        let mut book = process(&[request, status])?;
+        assert!(book.process(&decode_message(unknown)?).is_err());
+
+        let mass_action = &book.mass_actions["INITIATOR-ACCEPTOR-S1"];
+        assert_eq!(mass_action.kind, MassActionKind::Status);
+        assert_eq!(mass_action.scope.to_string(), "SecurityType=CS");
+        assert_eq!(mass_action.messages.len(), 2);
+        assert_eq!(mass_action.affected, vec!["INITIATOR-ACCEPTOR-3"]);
+        assert_eq!(keys(book.unreported_orders("INITIATOR-ACCEPTOR-S1")), vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-2"]);
+        Ok(())
+    }
+
+}
@@ -0,0 +1,1035 @@
+use crate::message::Message;
+use crate::field::Field;
+use crate::field_collection::FieldCollection;
+use crate::error::Error;
+use crate::order::Order;
+use crate::timestamp::format_utc_timestamp;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+// CxlRejResponseTo values
+const RESPONSE_TO_CANCEL: &str = "1";
+const RESPONSE_TO_REPLACE: &str = "2";
+// CxlRejReason values
+const TOO_LATE_TO_CANCEL: &str = "0";
+const UNKNOWN_ORDER: &str = "1";
+// OrdRejReason values
+const DUPLICATE_ORDER: &str = "6";
+const OTHER: &str = "99";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Buy,
+    Sell
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimeInForce {
+    Day,
+    GoodTillCancel,
+    ImmediateOrCancel,
+    FillOrKill
+}
+
+// The aggregate quantity resting at a single price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Level {
+    pub price: f64,
+    pub quantity: f64,
+    pub orders: usize
+}
+
+struct EngineOrder {
+    order_id: String,
+    // The comp ids are from the point of view of the engine, reports are sent from sender to target.
+    begin_string: String,
+    sender_comp_id: String,
+    target_comp_id: String,
+    cl_ord_id: String,
+    orig_cl_ord_id: Option<String>,
+    symbol: String,
+    side: Side,
+    // These are echoed back in execution reports exactly as the client sent them.
+    side_field: Field,
+    ord_type: Field,
+    time_in_force: Option<Field>,
+    tif: TimeInForce,
+    price: Option<f64>,
+    order_qty: f64,
+    cum_qty: f64,
+    // The sum of LastQty * LastPx, AvgPx is derived from this so it doesn't accumulate rounding errors.
+    notional: f64,
+    // Orders at the same price are matched in ascending sequence order.
+    sequence: u64,
+    done: bool
+}
+
+impl EngineOrder {
+
+    fn leaves_qty(&self) -> f64
+    {
+        if self.done { 0.0 } else { (self.order_qty - self.cum_qty).max(0.0) }
+    }
+
+    fn avg_px(&self) -> f64
+    {
+        if self.cum_qty > 0.0 { self.notional / self.cum_qty } else { 0.0 }
+    }
+
+    // Does this order have priority over the other order on the same side of the book.
+    fn has_priority_over(&self, other: &EngineOrder) -> bool
+    {
+        let (price, other_price) = (self.price.unwrap_or_default(), other.price.unwrap_or_default());
+        if price != other_price {
+            return match self.side {
+                Side::Buy => price > other_price,
+                Side::Sell => price < other_price
+            };
+        }
+        self.sequence < other.sequence
+    }
+
+    // Can this order trade with a resting order at the specified price.
+    fn crosses(&self, price: f64) -> bool
+    {
+        match (self.price, self.side) {
+            (None, _) => true,
+            (Some(limit), Side::Buy) => price <= limit,
+            (Some(limit), Side::Sell) => price >= limit
+        }
+    }
+
+}
+
+// The resting orders for a single symbol, stored as OrderIDs in priority order.
+#[derive(Default)]
+struct Book {
+    bids: Vec<String>,
+    asks: Vec<String>
+}
+
+impl Book {
+
+    fn side(&self, side: Side) -> &Vec<String>
+    {
+        match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut Vec<String>
+    {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks
+        }
+    }
+
+}
+
+// A price-time priority matching engine. Clients send NewOrderSingle, OrderCancelRequest, and
+// OrderCancelReplaceRequest messages and the engine returns the ExecutionReport and OrderCancelReject
+// messages for every order affected, including the resting orders of other clients. The messages
+// returned contain BeginString, MsgType, SenderCompID, and TargetCompID but no MsgSeqNum or SendingTime,
+// they are intended to be passed to Session::prepare for the client identified by the TargetCompID.
+//
+// Limit orders rest on the book until they are filled, canceled, or expire. Market orders and the
+// remainder of IOC orders are canceled once they have traded with whatever is available and FOK orders
+// are canceled without trading unless they can be filled immediately. A cancel/replace that only
+// reduces the OrderQty keeps its time priority, any other change moves the order to the back of the
+// queue at its price and it may trade immediately if its new price crosses the book.
+//
+// The engine is deterministic, the same input messages and transact times always produce the same output.
+#[derive(Default)]
+pub struct MatchingEngine {
+    books: BTreeMap<String, Book>,
+    orders: HashMap<String, EngineOrder>,
+    // Maps the key of the ClOrdID the client knows the order by to the OrderID.
+    keys: HashMap<String, String>,
+    next_order_id: u64,
+    next_exec_id: u64,
+    next_sequence: u64
+}
+
+impl MatchingEngine {
+
+    pub fn process(&mut self, message: &Message, transact_time: &DateTime<Utc>) -> Result<Vec<Message>, Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        let transact_time = format_utc_timestamp(transact_time);
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderSingle().value {
+            return self.process_order_single(message, &transact_time);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelRequest().value {
+            return self.process_order_cancel_request(message, &transact_time);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value {
+            return self.process_order_cancel_replace_request(message, &transact_time);
+        }
+
+        Err(Error::UnsupportedMsgType(msg_type.to_string()))
+    }
+
+    // Expire all resting Day orders, GTC orders remain on the book.
+    pub fn end_of_day(&mut self, transact_time: &DateTime<Utc>) -> Vec<Message>
+    {
+        let transact_time = format_utc_timestamp(transact_time);
+        let mut reports = Vec::new();
+
+        let expiring: Vec<String> = self.books
+            .values()
+            .flat_map(|book| book.bids.iter().chain(book.asks.iter()))
+            .filter(|order_id| self.orders[*order_id].tif == TimeInForce::Day)
+            .cloned()
+            .collect();
+
+        for order_id in expiring {
+            self.remove_from_book(&order_id);
+            let order = self.orders.get_mut(&order_id).expect("resting orders are always known");
+            order.done = true;
+            reports.push(self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::Expired().value, crate::FIX_5_0SP2::OrdStatus::Expired().value, None, None, &transact_time));
+        }
+
+        reports
+    }
+
+    pub fn best_bid(&self, symbol: &str) -> Option<f64>
+    {
+        self.depth(symbol, Side::Buy).first().map(|level| level.price)
+    }
+
+    pub fn best_ask(&self, symbol: &str) -> Option<f64>
+    {
+        self.depth(symbol, Side::Sell).first().map(|level| level.price)
+    }
+
+    // The price levels on one side of the book for a symbol, best price first.
+    pub fn depth(&self, symbol: &str, side: Side) -> Vec<Level>
+    {
+        let mut levels: Vec<Level> = Vec::new();
+
+        let Some(book) = self.books.get(symbol) else {
+            return levels;
+        };
+
+        for order_id in book.side(side) {
+            let order = &self.orders[order_id];
+            let price = order.price.unwrap_or_default();
+            match levels.last_mut() {
+                Some(level) if level.price == price => {
+                    level.quantity += order.leaves_qty();
+                    level.orders += 1;
+                }
+                _ => levels.push(Level { price, quantity: order.leaves_qty(), orders: 1 })
+            }
+        }
+
+        levels
+    }
+
+    fn process_order_single(&mut self, order_single: &Message, transact_time: &str) -> Result<Vec<Message>, Error>
+    {
+        let key = Order::key_for_message(order_single, false)?;
+        let order_id = self.next_order_id();
+        let sequence = self.next_sequence();
+
+        let fields = &order_single.fields;
+        let side_field = fields.get(crate::FIX_5_0SP2::Side::TAG)?.clone();
+        let ord_type = fields.get(crate::FIX_5_0SP2::OrdType::TAG)?.clone();
+        let time_in_force = fields.try_get(crate::FIX_5_0SP2::TimeInForce::TAG).cloned();
+
+        let mut order = EngineOrder {
+            order_id: order_id.clone(),
+            begin_string: fields.get(crate::FIX_5_0SP2::BeginString::TAG)?.value.clone(),
+            sender_comp_id: fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.clone(),
+            target_comp_id: fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone(),
+            cl_ord_id: fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value.clone(),
+            orig_cl_ord_id: None,
+            symbol: fields.get(crate::FIX_5_0SP2::Symbol::TAG)?.value.clone(),
+            side: Side::Buy,
+            side_field,
+            ord_type,
+            time_in_force,
+            tif: TimeInForce::Day,
+            price: None,
+            order_qty: fields.try_get(crate::FIX_5_0SP2::OrderQty::TAG).map(|field| field.decimal_value()).transpose()?.unwrap_or_default(),
+            cum_qty: 0.0,
+            notional: 0.0,
+            sequence,
+            done: true
+        };
+
+        let rejection = if self.keys.contains_key(&key) {
+            Some((DUPLICATE_ORDER, "Duplicate ClOrdID".to_string()))
+        }
+        else {
+            MatchingEngine::validate(&mut order, fields).err().map(|text| (OTHER, text))
+        };
+
+        self.orders.insert(order_id.clone(), order);
+
+        if let Some((reason, text)) = rejection {
+            let mut report = self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::Rejected().value, crate::FIX_5_0SP2::OrdStatus::Rejected().value, None, Some(&text), transact_time);
+            report.fields.push(Field::from_str(crate::FIX_5_0SP2::OrdRejReason::TAG, reason));
+            return Ok(vec![report]);
+        }
+
+        self.keys.insert(key, order_id.clone());
+        self.orders.get_mut(&order_id).expect("order was just inserted").done = false;
+
+        let mut reports = vec![self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::New().value, crate::FIX_5_0SP2::OrdStatus::New().value, None, None, transact_time)];
+
+        let order = &self.orders[&order_id];
+        if order.tif == TimeInForce::FillOrKill && self.available_quantity(order) < order.order_qty {
+            self.orders.get_mut(&order_id).expect("order was just inserted").done = true;
+            reports.push(self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::Canceled().value, crate::FIX_5_0SP2::OrdStatus::Canceled().value, None, Some("Unable to fill or kill"), transact_time));
+            return Ok(reports);
+        }
+
+        reports.extend(self.match_and_rest(&order_id, transact_time));
+
+        Ok(reports)
+    }
+
+    // Check the order can be handled and populate the fields we parse out of the message.
+    fn validate(order: &mut EngineOrder, fields: &FieldCollection) -> Result<(), String>
+    {
+        order.side = if &order.side_field == crate::FIX_5_0SP2::Side::Buy() {
+            Side::Buy
+        }
+        else if &order.side_field == crate::FIX_5_0SP2::Side::Sell() {
+            Side::Sell
+        }
+        else {
+            return Err(format!("Unsupported Side {}", order.side_field.value));
+        };
+
+        order.tif = MatchingEngine::parse_time_in_force(order.time_in_force.as_ref())?;
+
+        if order.order_qty <= 0.0 {
+            return Err("OrderQty must be greater than zero".to_string());
+        }
+
+        MatchingEngine::parse_price(order, fields)
+    }
+
+    fn parse_time_in_force(time_in_force: Option<&Field>) -> Result<TimeInForce, String>
+    {
+        // TimeInForce defaults to Day when it is not specified.
+        let Some(time_in_force) = time_in_force else {
+            return Ok(TimeInForce::Day);
+        };
+
+        if time_in_force == crate::FIX_5_0SP2::TimeInForce::Day() {
+            Ok(TimeInForce::Day)
+        }
+        else if time_in_force == crate::FIX_5_0SP2::TimeInForce::GoodTillCancel() {
+            Ok(TimeInForce::GoodTillCancel)
+        }
+        else if time_in_force == crate::FIX_5_0SP2::TimeInForce::ImmediateOrCancel() {
+            Ok(TimeInForce::ImmediateOrCancel)
+        }
+        else if time_in_force == crate::FIX_5_0SP2::TimeInForce::FillOrKill() {
+            Ok(TimeInForce::FillOrKill)
+        }
+        else {
+            Err(format!("Unsupported TimeInForce {}", time_in_force.value))
+        }
+    }
+
+    fn parse_price(order: &mut EngineOrder, fields: &FieldCollection) -> Result<(), String>
+    {
+        if &order.ord_type == crate::FIX_5_0SP2::OrdType::Market() {
+            order.price = None;
+            return Ok(());
+        }
+
+        if &order.ord_type != crate::FIX_5_0SP2::OrdType::Limit() {
+            return Err(format!("Unsupported OrdType {}", order.ord_type.value));
+        }
+
+        let Some(price) = fields.try_get(crate::FIX_5_0SP2::Price::TAG) else {
+            return Err("Limit orders require a Price".to_string());
+        };
+
+        match price.decimal_value() {
+            Ok(price) if price > 0.0 => {
+                order.price = Some(price);
+                Ok(())
+            }
+            _ => Err(format!("Invalid Price {}", price.value))
+        }
+    }
+
+    // The quantity on the opposite side of the book the order could trade with right now.
+    fn available_quantity(&self, order: &EngineOrder) -> f64
+    {
+        let Some(book) = self.books.get(&order.symbol) else {
+            return 0.0;
+        };
+
+        book.side(MatchingEngine::opposite(order.side))
+            .iter()
+            .map(|order_id| &self.orders[order_id])
+            .take_while(|resting| order.crosses(resting.price.unwrap_or_default()))
+            .map(|resting| resting.leaves_qty())
+            .sum()
+    }
+
+    fn opposite(side: Side) -> Side
+    {
+        match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy
+        }
+    }
+
+    // Trade the order against the book and then either rest or cancel what remains.
+    fn match_and_rest(&mut self, order_id: &str, transact_time: &str) -> Vec<Message>
+    {
+        let mut reports = Vec::new();
+        let (symbol, side) = {
+            let order = &self.orders[order_id];
+            (order.symbol.clone(), order.side)
+        };
+
+        loop {
+            let aggressor = &self.orders[order_id];
+            let leaves_qty = aggressor.leaves_qty();
+            if leaves_qty <= 0.0 {
+                break;
+            }
+
+            let Some(resting_id) = self.books.get(&symbol).and_then(|book| book.side(MatchingEngine::opposite(side)).first()).cloned() else {
+                break;
+            };
+
+            let resting = &self.orders[&resting_id];
+            // Resting orders are always limit orders so they always have a price.
+            let last_px = resting.price.unwrap_or_default();
+            if !aggressor.crosses(last_px) {
+                break;
+            }
+
+            let last_qty = leaves_qty.min(resting.leaves_qty());
+
+            for id in [order_id, resting_id.as_str()] {
+                let order = self.orders.get_mut(id).expect("matched orders are always known");
+                order.cum_qty += last_qty;
+                order.notional += last_qty * last_px;
+            }
+
+            // The aggressor is reported first then the resting order.
+            reports.push(self.trade_report(order_id, last_qty, last_px, transact_time));
+            reports.push(self.trade_report(&resting_id, last_qty, last_px, transact_time));
+
+            if self.orders[&resting_id].leaves_qty() <= 0.0 {
+                self.remove_from_book(&resting_id);
+            }
+        }
+
+        let order = &self.orders[order_id];
+        if order.leaves_qty() <= 0.0 {
+            return reports;
+        }
+
+        if order.price.is_none() || order.tif == TimeInForce::ImmediateOrCancel || order.tif == TimeInForce::FillOrKill {
+            let text = if order.price.is_none() { "Market order remainder canceled" } else { "Immediate or cancel order remainder canceled" };
+            self.orders.get_mut(order_id).expect("order is known").done = true;
+            reports.push(self.execution_report(order_id, crate::FIX_5_0SP2::ExecType::Canceled().value, crate::FIX_5_0SP2::OrdStatus::Canceled().value, None, Some(text), transact_time));
+            return reports;
+        }
+
+        self.insert_into_book(order_id);
+
+        reports
+    }
+
+    fn insert_into_book(&mut self, order_id: &str)
+    {
+        let order = &self.orders[order_id];
+        let book = self.books.entry(order.symbol.clone()).or_default();
+        let queue = book.side_mut(order.side);
+        let position = queue.iter().position(|other| order.has_priority_over(&self.orders[other])).unwrap_or(queue.len());
+        queue.insert(position, order_id.to_string());
+    }
+
+    fn remove_from_book(&mut self, order_id: &str)
+    {
+        let order = &self.orders[order_id];
+        if let Some(book) = self.books.get_mut(&order.symbol) {
+            book.side_mut(order.side).retain(|other| other != order_id);
+        }
+    }
+
+    fn trade_report(&mut self, order_id: &str, last_qty: f64, last_px: f64, transact_time: &str) -> Message
+    {
+        let ord_status = if self.orders[order_id].leaves_qty() <= 0.0 {
+            crate::FIX_5_0SP2::OrdStatus::Filled().value
+        }
+        else {
+            crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value
+        };
+
+        self.execution_report(order_id, crate::FIX_5_0SP2::ExecType::Trade().value, ord_status, Some((last_qty, last_px)), None, transact_time)
+    }
+
+    // Find a live order from the OrigClOrdID of a cancel or cancel/replace request or return the
+    // reject to send if there isn't one.
+    fn live_order(&mut self, request: &Message, response_to: &str) -> Result<Result<String, Message>, Error>
+    {
+        let key = Order::key_for_message(request, false)?;
+
+        let Some(order_id) = self.keys.get(&key).cloned() else {
+            return Ok(Err(self.order_cancel_reject(request, None, response_to, UNKNOWN_ORDER, "Unknown order")?));
+        };
+
+        let order = &self.orders[&order_id];
+        if order.done || order.leaves_qty() <= 0.0 {
+            let ord_status = MatchingEngine::ord_status(order).to_string();
+            return Ok(Err(self.order_cancel_reject(request, Some((&order_id, &ord_status)), response_to, TOO_LATE_TO_CANCEL, "Too late to cancel")?));
+        }
+
+        Ok(Ok(order_id))
+    }
+
+    fn process_order_cancel_request(&mut self, order_cancel_request: &Message, transact_time: &str) -> Result<Vec<Message>, Error>
+    {
+        let order_id = match self.live_order(order_cancel_request, RESPONSE_TO_CANCEL)? {
+            Ok(order_id) => order_id,
+            Err(reject) => return Ok(vec![reject])
+        };
+
+        self.remove_from_book(&order_id);
+        self.rekey(&order_id, order_cancel_request)?;
+        self.orders.get_mut(&order_id).expect("live orders are always known").done = true;
+
+        Ok(vec![self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::Canceled().value, crate::FIX_5_0SP2::OrdStatus::Canceled().value, None, None, transact_time)])
+    }
+
+    fn process_order_cancel_replace_request(&mut self, order_cancel_replace_request: &Message, transact_time: &str) -> Result<Vec<Message>, Error>
+    {
+        let order_id = match self.live_order(order_cancel_replace_request, RESPONSE_TO_REPLACE)? {
+            Ok(order_id) => order_id,
+            Err(reject) => return Ok(vec![reject])
+        };
+
+        let fields = &order_cancel_replace_request.fields;
+        let order = &self.orders[&order_id];
+
+        // Build the replacement from the current order so we can validate it before changing anything.
+        let mut replacement = EngineOrder {
+            order_id: order.order_id.clone(),
+            begin_string: order.begin_string.clone(),
+            sender_comp_id: order.sender_comp_id.clone(),
+            target_comp_id: order.target_comp_id.clone(),
+            cl_ord_id: order.cl_ord_id.clone(),
+            orig_cl_ord_id: order.orig_cl_ord_id.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side,
+            side_field: fields.try_get(crate::FIX_5_0SP2::Side::TAG).cloned().unwrap_or_else(|| order.side_field.clone()),
+            ord_type: fields.try_get(crate::FIX_5_0SP2::OrdType::TAG).cloned().unwrap_or_else(|| order.ord_type.clone()),
+            time_in_force: fields.try_get(crate::FIX_5_0SP2::TimeInForce::TAG).cloned().or_else(|| order.time_in_force.clone()),
+            tif: order.tif,
+            price: order.price,
+            order_qty: match fields.try_get(crate::FIX_5_0SP2::OrderQty::TAG) {
+                Some(order_qty) => order_qty.decimal_value()?,
+                None => order.order_qty
+            },
+            cum_qty: order.cum_qty,
+            notional: order.notional,
+            sequence: order.sequence,
+            done: false
+        };
+
+        let symbol_changed = fields.try_get(crate::FIX_5_0SP2::Symbol::TAG).is_some_and(|symbol| symbol.value != order.symbol);
+
+        let validation = if symbol_changed || replacement.side_field.value != order.side_field.value {
+            Err("Symbol and Side cannot be changed".to_string())
+        }
+        else if replacement.ord_type.value != crate::FIX_5_0SP2::OrdType::Limit().value {
+            Err(format!("Unsupported OrdType {}", replacement.ord_type.value))
+        }
+        else {
+            MatchingEngine::validate(&mut replacement, fields).and_then(|_| {
+                match replacement.tif {
+                    TimeInForce::Day | TimeInForce::GoodTillCancel => Ok(()),
+                    _ => Err("Only Day and GTC orders can be replaced".to_string())
+                }
+            })
+        };
+
+        if let Err(text) = validation {
+            let ord_status = MatchingEngine::ord_status(order).to_string();
+            return Ok(vec![self.order_cancel_reject(order_cancel_replace_request, Some((&order_id, &ord_status)), RESPONSE_TO_REPLACE, OTHER, &text)?]);
+        }
+
+        // Reducing the quantity is the only change that keeps time priority.
+        let keeps_priority = replacement.price == order.price && replacement.order_qty <= order.order_qty;
+
+        self.remove_from_book(&order_id);
+        if !keeps_priority {
+            replacement.sequence = self.next_sequence();
+        }
+        if replacement.leaves_qty() <= 0.0 {
+            // The order was reduced to the quantity already filled.
+            replacement.done = true;
+        }
+        self.orders.insert(order_id.clone(), replacement);
+        self.rekey(&order_id, order_cancel_replace_request)?;
+
+        let order = &self.orders[&order_id];
+        let ord_status = MatchingEngine::ord_status(order);
+        let done = order.done;
+        let mut reports = vec![self.execution_report(&order_id, crate::FIX_5_0SP2::ExecType::Replaced().value, ord_status, None, None, transact_time)];
+
+        if done {
+            return Ok(reports);
+        }
+
+        if keeps_priority {
+            self.insert_into_book(&order_id);
+        }
+        else {
+            reports.extend(self.match_and_rest(&order_id, transact_time));
+        }
+
+        Ok(reports)
+    }
+
+    // A successful cancel or cancel/replace changes the ClOrdID the client uses to refer to the order.
+    fn rekey(&mut self, order_id: &str, request: &Message) -> Result<(), Error>
+    {
+        let cl_ord_id = request.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value.clone();
+        let key = Order::key_for_message(request, false)?;
+        self.keys.remove(&key);
+
+        let order = self.orders.get_mut(order_id).expect("live orders are always known");
+        order.orig_cl_ord_id = Some(std::mem::replace(&mut order.cl_ord_id, cl_ord_id));
+        self.keys.insert(Order::create_key(&order.target_comp_id, &order.sender_comp_id, &order.cl_ord_id), order_id.to_string());
+
+        Ok(())
+    }
+
+    fn ord_status(order: &EngineOrder) -> &'static str
+    {
+        if order.leaves_qty() <= 0.0 && order.cum_qty >= order.order_qty {
+            crate::FIX_5_0SP2::OrdStatus::Filled().value
+        }
+        else if order.done {
+            crate::FIX_5_0SP2::OrdStatus::Canceled().value
+        }
+        else if order.cum_qty > 0.0 {
+            crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value
+        }
+        else {
+            crate::FIX_5_0SP2::OrdStatus::New().value
+        }
+    }
+
+    fn header(msg_type: &str, begin_string: &str, sender_comp_id: &str, target_comp_id: &str) -> Message
+    {
+        let mut message = Message::default();
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::BeginString::TAG, begin_string));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::MsgType::TAG, msg_type));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::SenderCompID::TAG, sender_comp_id));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::TargetCompID::TAG, target_comp_id));
+        message
+    }
+
+    fn execution_report(&mut self,
+                        order_id: &str,
+                        exec_type: &str,
+                        ord_status: &str,
+                        fill: Option<(f64, f64)>,
+                        text: Option<&str>,
+                        transact_time: &str) -> Message
+    {
+        let exec_id = self.next_exec_id();
+        let order = &self.orders[order_id];
+
+        let mut report = MatchingEngine::header(crate::FIX_5_0SP2::MsgType::ExecutionReport().value, &order.begin_string, &order.sender_comp_id, &order.target_comp_id);
+        let fields = &mut report.fields;
+        fields.push(Field::from_str(crate::FIX_5_0SP2::OrderID::TAG, &order.order_id));
+        fields.push(Field::from_str(crate::FIX_5_0SP2::ClOrdID::TAG, &order.cl_ord_id));
+        // OrigClOrdID is only sent in response to a cancel or cancel/replace, subsequent reports refer to the order by ClOrdID alone.
+        let responds_to_request = exec_type == crate::FIX_5_0SP2::ExecType::Canceled().value || exec_type == crate::FIX_5_0SP2::ExecType::Replaced().value;
+        if let (Some(orig_cl_ord_id), true) = (&order.orig_cl_ord_id, responds_to_request) {
+            fields.push(Field::from_str(crate::FIX_5_0SP2::OrigClOrdID::TAG, orig_cl_ord_id));
+        }
+        fields.push(Field::from_str(crate::FIX_5_0SP2::ExecID::TAG, &exec_id));
+        fields.push(Field::from_str(crate::FIX_5_0SP2::ExecType::TAG, exec_type));
+        fields.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, ord_status));
+        fields.push(Field::from_str(crate::FIX_5_0SP2::Symbol::TAG, &order.symbol));
+        fields.push(order.side_field.clone());
+        fields.push(Field::from_decimal(crate::FIX_5_0SP2::OrderQty::TAG, order.order_qty));
+        fields.push(order.ord_type.clone());
+        if let Some(price) = order.price {
+            fields.push(Field::from_decimal(crate::FIX_5_0SP2::Price::TAG, price));
+        }
+        if let Some(time_in_force) = &order.time_in_force {
+            fields.push(time_in_force.clone());
+        }
+        if let Some((last_qty, last_px)) = fill {
+            fields.push(Field::from_decimal(crate::FIX_5_0SP2::LastQty::TAG, last_qty));
+            fields.push(Field::from_decimal(crate::FIX_5_0SP2::LastPx::TAG, last_px));
+        }
+        fields.push(Field::from_decimal(crate::FIX_5_0SP2::LeavesQty::TAG, order.leaves_qty()));
+        fields.push(Field::from_decimal(crate::FIX_5_0SP2::CumQty::TAG, order.cum_qty));
+        fields.push(Field::from_decimal(crate::FIX_5_0SP2::AvgPx::TAG, order.avg_px()));
+        fields.push(Field::from_str(crate::FIX_5_0SP2::TransactTime::TAG, transact_time));
+        if let Some(text) = text {
+            fields.push(Field::from_str(crate::FIX_5_0SP2::Text::TAG, text));
+        }
+
+        report
+    }
+
+    fn order_cancel_reject(&self,
+                           request: &Message,
+                           order: Option<(&str, &str)>,
+                           response_to: &str,
+                           reason: &str,
+                           text: &str) -> Result<Message, Error>
+    {
+        let fields = &request.fields;
+        let mut reject = MatchingEngine::header(
+            crate::FIX_5_0SP2::MsgType::OrderCancelReject().value,
+            &fields.get(crate::FIX_5_0SP2::BeginString::TAG)?.value,
+            &fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value,
+            &fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value
+        );
+        let (order_id, ord_status) = order.unwrap_or(("NONE", crate::FIX_5_0SP2::OrdStatus::Rejected().value));
+        reject.fields.push(Field::from_str(crate::FIX_5_0SP2::OrderID::TAG, order_id));
+        reject.fields.push(fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.clone());
+        reject.fields.push(fields.get(crate::FIX_5_0SP2::OrigClOrdID::TAG)?.clone());
+        reject.fields.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, ord_status));
+        reject.fields.push(Field::from_str(crate::FIX_5_0SP2::CxlRejResponseTo::TAG, response_to));
+        reject.fields.push(Field::from_str(crate::FIX_5_0SP2::CxlRejReason::TAG, reason));
+        reject.fields.push(Field::from_str(crate::FIX_5_0SP2::Text::TAG, text));
+        Ok(reject)
+    }
+
+    fn next_order_id(&mut self) -> String
+    {
+        self.next_order_id += 1;
+        self.next_order_id.to_string()
+    }
+
+    fn next_exec_id(&mut self) -> String
+    {
+        self.next_exec_id += 1;
+        self.next_exec_id.to_string()
+    }
+
+    fn next_sequence(&mut self) -> u64
+    {
+        self.next_sequence += 1;
+        self.next_sequence
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::EncodeOptions;
+    use chrono::TimeZone;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.replace('|', "\u{0001}").as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    fn transact_time() -> DateTime<Utc>
+    {
+        Utc.with_ymd_and_hms(2020, 1, 19, 2, 35, 9).unwrap()
+    }
+
+    // Render messages one per line with | as the field separator so golden output is readable.
+    fn render(messages: &[Message]) -> Result<Vec<String>, crate::error::Error>
+    {
+        let mut lines = Vec::new();
+        for message in messages {
+            let mut buffer = Vec::new();
+            message.encode(&mut buffer, EncodeOptions::empty())?;
+            lines.push(String::from_utf8_lossy(&buffer).replace('\u{0001}', "|"));
+        }
+        Ok(lines)
+    }
+
+    fn process(engine: &mut MatchingEngine, text: &str) -> Result<Vec<String>, crate::error::Error>
+    {
+        render(&engine.process(&decode_message(text)?, &transact_time())?)
+    }
+
+    fn order_single(sender_comp_id: &str, cl_ord_id: &str, side: &str, order_qty: &str, price: Option<&str>, time_in_force: &str) -> String
+    {
+        let ord_type = match price {
+            Some(price) => format!("40=2|44={}|", price),
+            None => "40=1|".to_string()
+        };
+        format!("8=FIX.4.4|9=0|35=D|49={}|56=ENGINE|34=1|52=20200119-02:35:09.000|11={}|55=WTF.AX|54={}|38={}|{}59={}|60=20200119-02:35:09.000|10=000|",
+            sender_comp_id, cl_ord_id, side, order_qty, ord_type, time_in_force)
+    }
+
+    #[test]
+    pub fn limit_order_rests() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        assert_eq!(
+            process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=1|17=1|150=0|39=0|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=0|151=100|14=0|6=0|60=20200119-02:35:09.000|"]
+        );
+        assert_eq!(engine.best_bid("WTF.AX"), Some(11.56));
+        assert_eq!(engine.best_ask("WTF.AX"), None);
+        Ok(())
+    }
+
+    #[test]
+    pub fn aggressive_order_trades_at_resting_prices_in_price_time_priority() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("SELLER", "1", "2", "100", Some("11.57"), "1"))?;
+        process(&mut engine, &order_single("SELLER", "2", "2", "100", Some("11.56"), "1"))?;
+        process(&mut engine, &order_single("SELLER", "3", "2", "100", Some("11.56"), "1"))?;
+        assert_eq!(
+            process(&mut engine, &order_single("BUYER", "1", "1", "250", Some("11.57"), "0"))?,
+            vec![
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=4|11=1|17=4|150=0|39=0|55=WTF.AX|54=1|38=250|40=2|44=11.57|59=0|151=250|14=0|6=0|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=4|11=1|17=5|150=F|39=1|55=WTF.AX|54=1|38=250|40=2|44=11.57|59=0|32=100|31=11.56|151=150|14=100|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=SELLER|37=2|11=2|17=6|150=F|39=2|55=WTF.AX|54=2|38=100|40=2|44=11.56|59=1|32=100|31=11.56|151=0|14=100|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=4|11=1|17=7|150=F|39=1|55=WTF.AX|54=1|38=250|40=2|44=11.57|59=0|32=100|31=11.56|151=50|14=200|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=SELLER|37=3|11=3|17=8|150=F|39=2|55=WTF.AX|54=2|38=100|40=2|44=11.56|59=1|32=100|31=11.56|151=0|14=100|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=4|11=1|17=9|150=F|39=2|55=WTF.AX|54=1|38=250|40=2|44=11.57|59=0|32=50|31=11.57|151=0|14=250|6=11.562|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=SELLER|37=1|11=1|17=10|150=F|39=1|55=WTF.AX|54=2|38=100|40=2|44=11.57|59=1|32=50|31=11.57|151=50|14=50|6=11.57|60=20200119-02:35:09.000|"
+            ]
+        );
+        assert_eq!(engine.depth("WTF.AX", Side::Sell), vec![Level { price: 11.57, quantity: 50.0, orders: 1 }]);
+        assert!(engine.depth("WTF.AX", Side::Buy).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn market_order_remainder_is_canceled() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("SELLER", "1", "2", "100", Some("11.56"), "0"))?;
+        assert_eq!(
+            process(&mut engine, &order_single("BUYER", "1", "1", "150", None, "0"))?,
+            vec![
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=2|150=0|39=0|55=WTF.AX|54=1|38=150|40=1|59=0|151=150|14=0|6=0|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=3|150=F|39=1|55=WTF.AX|54=1|38=150|40=1|59=0|32=100|31=11.56|151=50|14=100|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=SELLER|37=1|11=1|17=4|150=F|39=2|55=WTF.AX|54=2|38=100|40=2|44=11.56|59=0|32=100|31=11.56|151=0|14=100|6=11.56|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=5|150=4|39=4|55=WTF.AX|54=1|38=150|40=1|59=0|151=0|14=100|6=11.56|60=20200119-02:35:09.000|58=Market order remainder canceled|"
+            ]
+        );
+        assert!(engine.depth("WTF.AX", Side::Buy).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn immediate_or_cancel_remainder_is_canceled() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("SELLER", "1", "2", "40", Some("11.56"), "0"))?;
+        let reports = process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "3"))?;
+        assert_eq!(reports.len(), 4);
+        assert_eq!(reports[3], "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=5|150=4|39=4|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=3|151=0|14=40|6=11.56|60=20200119-02:35:09.000|58=Immediate or cancel order remainder canceled|");
+        assert!(engine.depth("WTF.AX", Side::Buy).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn fill_or_kill_does_not_trade_unless_it_can_be_filled() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("SELLER", "1", "2", "40", Some("11.56"), "0"))?;
+        assert_eq!(
+            process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "4"))?,
+            vec![
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=2|150=0|39=0|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=4|151=100|14=0|6=0|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=1|17=3|150=4|39=4|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=4|151=0|14=0|6=0|60=20200119-02:35:09.000|58=Unable to fill or kill|"
+            ]
+        );
+        assert_eq!(engine.depth("WTF.AX", Side::Sell), vec![Level { price: 11.56, quantity: 40.0, orders: 1 }]);
+        let reports = process(&mut engine, &order_single("BUYER", "2", "1", "40", Some("11.56"), "4"))?;
+        assert_eq!(reports.len(), 3);
+        assert!(reports[1].contains("|150=F|39=2|"));
+        assert!(engine.depth("WTF.AX", Side::Sell).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn invalid_orders_are_rejected() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        assert_eq!(
+            process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "6"))?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=1|17=1|150=8|39=8|55=WTF.AX|54=1|38=100|40=2|59=6|151=0|14=0|6=0|60=20200119-02:35:09.000|58=Unsupported TimeInForce 6|103=99|"]
+        );
+        process(&mut engine, &order_single("BUYER", "2", "1", "100", Some("11.56"), "0"))?;
+        let reports = process(&mut engine, &order_single("BUYER", "2", "1", "100", Some("11.56"), "0"))?;
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].ends_with("|58=Duplicate ClOrdID|103=6|"));
+        let reports = process(&mut engine, &order_single("BUYER", "3", "1", "0", Some("11.56"), "0"))?;
+        assert!(reports[0].contains("|39=8|"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn cancel() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        let cancel = "8=FIX.4.4|9=0|35=F|49=BUYER|56=ENGINE|34=2|52=20200119-02:35:09.000|41=1|11=2|55=WTF.AX|54=1|38=100|60=20200119-02:35:09.000|10=000|";
+        assert_eq!(
+            process(&mut engine, cancel)?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=2|41=1|17=2|150=4|39=4|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=0|151=0|14=0|6=0|60=20200119-02:35:09.000|"]
+        );
+        assert_eq!(engine.best_bid("WTF.AX"), None);
+        assert_eq!(
+            process(&mut engine, cancel)?,
+            vec!["8=FIX.4.4|35=9|49=ENGINE|56=BUYER|37=NONE|11=2|41=1|39=8|434=1|102=1|58=Unknown order|"]
+        );
+        let cancel = "8=FIX.4.4|9=0|35=F|49=BUYER|56=ENGINE|34=2|52=20200119-02:35:09.000|41=2|11=3|55=WTF.AX|54=1|38=100|60=20200119-02:35:09.000|10=000|";
+        assert_eq!(
+            process(&mut engine, cancel)?,
+            vec!["8=FIX.4.4|35=9|49=ENGINE|56=BUYER|37=1|11=3|41=2|39=4|434=1|102=0|58=Too late to cancel|"]
+        );
+        Ok(())
+    }
+
+    fn replace(orig_cl_ord_id: &str, cl_ord_id: &str, order_qty: &str, price: &str) -> String
+    {
+        format!("8=FIX.4.4|9=0|35=G|49=BUYER|56=ENGINE|34=2|52=20200119-02:35:09.000|41={}|11={}|55=WTF.AX|54=1|38={}|40=2|44={}|59=0|60=20200119-02:35:09.000|10=000|",
+            orig_cl_ord_id, cl_ord_id, order_qty, price)
+    }
+
+    #[test]
+    pub fn replace_reducing_quantity_keeps_priority() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        process(&mut engine, &order_single("BUYER", "2", "1", "100", Some("11.56"), "0"))?;
+        assert_eq!(
+            process(&mut engine, &replace("1", "3", "60", "11.56"))?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=3|41=1|17=3|150=5|39=0|55=WTF.AX|54=1|38=60|40=2|44=11.56|59=0|151=60|14=0|6=0|60=20200119-02:35:09.000|"]
+        );
+        let reports = process(&mut engine, &order_single("SELLER", "1", "2", "60", Some("11.56"), "0"))?;
+        assert!(reports[2].contains("|56=BUYER|37=1|11=3|17="));
+        assert!(reports[2].contains("|150=F|39=2|"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn replace_increasing_quantity_loses_priority() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        process(&mut engine, &order_single("BUYER", "2", "1", "100", Some("11.56"), "0"))?;
+        process(&mut engine, &replace("1", "3", "200", "11.56"))?;
+        let reports = process(&mut engine, &order_single("SELLER", "1", "2", "100", Some("11.56"), "0"))?;
+        assert!(reports[2].contains("|56=BUYER|37=2|11=2|"));
+        assert_eq!(engine.depth("WTF.AX", Side::Buy), vec![Level { price: 11.56, quantity: 200.0, orders: 1 }]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn replace_to_crossing_price_trades() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("SELLER", "1", "2", "100", Some("11.57"), "0"))?;
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        assert_eq!(
+            process(&mut engine, &replace("1", "2", "100", "11.57"))?,
+            vec![
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=2|41=1|17=3|150=5|39=0|55=WTF.AX|54=1|38=100|40=2|44=11.57|59=0|151=100|14=0|6=0|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=2|11=2|17=4|150=F|39=2|55=WTF.AX|54=1|38=100|40=2|44=11.57|59=0|32=100|31=11.57|151=0|14=100|6=11.57|60=20200119-02:35:09.000|",
+                "8=FIX.4.4|35=8|49=ENGINE|56=SELLER|37=1|11=1|17=5|150=F|39=2|55=WTF.AX|54=2|38=100|40=2|44=11.57|59=0|32=100|31=11.57|151=0|14=100|6=11.57|60=20200119-02:35:09.000|"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn replace_to_filled_quantity_completes_the_order() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        process(&mut engine, &order_single("SELLER", "1", "2", "40", Some("11.56"), "0"))?;
+        assert_eq!(
+            process(&mut engine, &replace("1", "2", "40", "11.56"))?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=2|41=1|17=5|150=5|39=2|55=WTF.AX|54=1|38=40|40=2|44=11.56|59=0|151=0|14=40|6=11.56|60=20200119-02:35:09.000|"]
+        );
+        assert!(engine.depth("WTF.AX", Side::Buy).is_empty());
+        let reports = process(&mut engine, &replace("2", "3", "100", "11.56"))?;
+        assert!(reports[0].contains("|35=9|"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn replace_cannot_change_side() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        let request = replace("1", "2", "100", "11.56").replace("|54=1|", "|54=2|");
+        assert_eq!(
+            process(&mut engine, &request)?,
+            vec!["8=FIX.4.4|35=9|49=ENGINE|56=BUYER|37=1|11=2|41=1|39=0|434=2|102=99|58=Symbol and Side cannot be changed|"]
+        );
+        assert_eq!(engine.best_bid("WTF.AX"), Some(11.56));
+        Ok(())
+    }
+
+    #[test]
+    pub fn end_of_day_expires_day_orders() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        process(&mut engine, &order_single("BUYER", "1", "1", "100", Some("11.56"), "0"))?;
+        process(&mut engine, &order_single("BUYER", "2", "1", "100", Some("11.55"), "1"))?;
+        assert_eq!(
+            render(&engine.end_of_day(&transact_time()))?,
+            vec!["8=FIX.4.4|35=8|49=ENGINE|56=BUYER|37=1|11=1|17=3|150=C|39=C|55=WTF.AX|54=1|38=100|40=2|44=11.56|59=0|151=0|14=0|6=0|60=20200119-02:35:09.000|"]
+        );
+        assert_eq!(engine.best_bid("WTF.AX"), Some(11.55));
+        Ok(())
+    }
+
+    #[test]
+    pub fn reports_are_accepted_by_the_order_book() -> Result<(), crate::error::Error>
+    {
+        let mut engine = MatchingEngine::default();
+        let mut order_book = crate::order_book::OrderBook::default();
+        for text in [
+            order_single("SELLER", "1", "2", "100", Some("11.56"), "0"),
+            order_single("BUYER", "1", "1", "60", Some("11.55"), "0"),
+            replace("1", "2", "150", "11.56"),
+        ] {
+            let message = decode_message(&text)?;
+            order_book.process(&message)?;
+            for report in engine.process(&message, &transact_time())? {
+                order_book.process(&report)?;
+            }
+        }
+        let seller = &order_book.orders["SELLER-ENGINE-1"];
+        assert_eq!(seller.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "100");
+        assert_eq!(seller.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::Filled());
+        let buyer = &order_book.orders["BUYER-ENGINE-2"];
+        assert_eq!(buyer.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "100");
+        assert_eq!(buyer.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::PartiallyFilled());
+        Ok(())
+    }
+
+}
@@ -3,6 +3,7 @@ use crate::error::Error;
 use crate::field_collection::{FieldCollection, SetOperation};
 use std::fmt;
 use bitflags::bitflags;
+use chrono::{DateTime, Utc};
 
 const VALUE_SEPARATOR: u8 = b'=';
 const FIELD_SEPARATOR: u8 = 0x01;
@@ -27,9 +28,14 @@ bitflags! {
 }
 
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message {
 
     pub fields: FieldCollection,
+    // The time the message was logged, from the timestamp that prefixes each line in logs written
+    // by fixproxy, fixsim, and fixreplay. This is not part of the encoded message.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub logged_time: Option<DateTime<Utc>>,
     decode_checksum: u32,
     decode_checksum_valid: bool
 
@@ -134,16 +140,71 @@ impl Message {
     // Encode this FIX message into the supplied buffer. This method calculates 
     // and rewrites the BodyLength and CheckSum by default, these fields must already be present, they
     // will not be added. It does no validation of the message content/structure. 
-    // Returns 0 if the buffer is not big enough.
-    pub fn encode(&self, _buffer: &mut Vec<u8>, _options: EncodeOptions) -> Result<usize, Error>
+    // The encoded bytes are appended to the buffer and the number of bytes written is returned.
+    // BeginString and MsgSeqNum are the responsibility of the session, they are encoded as is.
+    pub fn encode(&self, buffer: &mut Vec<u8>, options: EncodeOptions) -> Result<usize, Error>
     {
-        Ok(0)
+        let start_index = buffer.len();
+        let mut body_length_index = None;
+        let mut checksum_index = None;
+        
+        for field in &self.fields {
+
+            if field.tag == crate::FIX_5_0SP2::CheckSum::TAG {
+                checksum_index = Some(buffer.len());
+                if options.contains(EncodeOptions::SetChecksum) {
+                    // The checksum is calculated and written once the rest of the message is encoded.
+                    break;
+                }
+            }
+
+            if field.tag == crate::FIX_5_0SP2::BodyLength::TAG && options.contains(EncodeOptions::SetBodyLength) {
+                // The body length is inserted here once we know where the body ends.
+                body_length_index = Some(buffer.len());
+                continue;
+            }
+
+            buffer.extend_from_slice(field.tag.to_string().as_bytes());
+            buffer.push(VALUE_SEPARATOR);
+            buffer.extend_from_slice(field.value.as_bytes());
+            buffer.push(FIELD_SEPARATOR);
+        }
+
+        if let Some(body_length_index) = body_length_index {
+            let body_end_index = checksum_index.unwrap_or(buffer.len());
+            let body_length = body_end_index - body_length_index;
+            let body_length_field = format!("{}={}\u{0001}", crate::FIX_5_0SP2::BodyLength::TAG, body_length);
+            buffer.splice(body_length_index..body_length_index, body_length_field.bytes());
+            if let Some(index) = checksum_index.as_mut() {
+                *index += body_length_field.len();
+            }
+        }
+
+        if options.contains(EncodeOptions::SetChecksum) {
+            if let Some(checksum_index) = checksum_index {
+                let checksum = buffer[start_index..checksum_index].iter().map(|&byte| byte as u32).sum::<u32>() % 256;
+                buffer.extend_from_slice(format!("{}={}\u{0001}", crate::FIX_5_0SP2::CheckSum::TAG, Message::format_checksum(checksum)?).as_bytes());
+            }
+        }
+
+        Ok(buffer.len() - start_index)
+    }
+
+    // CheckSum is always transmitted as exactly 3 digits.
+    pub fn format_checksum(checksum: u32) -> Result<String, Error>
+    {
+        if checksum > 999 {
+            return Err(Error::ChecksumOutOfRange(checksum));
+        }
+
+        Ok(format!("{:03}", checksum))
     }
 
     pub fn is_data_field(tag: u32) -> bool
     {
-        // TODO - think about what we do for unknown tags here. Is it better to continue and assume non data or error? The C++ version should be aligned.
-        crate::FIX_5_0SP2::fields()[tag as usize].is_data()
+        // Tags that are not in the dictionary, typically user defined fields, are assumed not to be data fields.
+        // TODO - The C++ version should be aligned.
+        crate::FIX_5_0SP2::fields().is_tag_valid(tag as usize) && crate::FIX_5_0SP2::fields()[tag as usize].is_data()
     }
 
     pub fn is_admin(&self) -> bool 
@@ -343,14 +404,14 @@ mod tests {
     #[test]
     fn encode_a_message() -> Result<(), crate::error::Error>
     {
-        // let expected = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
-        // let mut message = Message::default();
-        // message.decode(expected.as_bytes())?;
-
-        // let mut actual = Vec::new();
-        // let result = message.encode(&mut actual, EncodeOptions::Standard)?;
-        // assert!(result > 0);
-        // assert_eq!(actual, expected.as_bytes());
+        let expected = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
+        let mut message = Message::default();
+        message.decode(expected.as_bytes())?;
+
+        let mut actual = Vec::new();
+        let result = message.encode(&mut actual, EncodeOptions::Standard)?;
+        assert!(result > 0);
+        assert_eq!(actual, expected.as_bytes());
         
         Ok(())
     }
@@ -358,14 +419,14 @@ mod tests {
     #[test]
     fn encode_does_not_add_checksum_if_it_is_not_present() -> Result<(), crate::error::Error>
     {
-        // let expected = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}";
-        // let mut message = Message::default();
-        // message.decode(expected.as_bytes())?;
-
-        // let mut actual = Vec::new();
-        // let result = message.encode(&mut actual, EncodeOptions::Standard)?;
-        // assert!(result > 0);
-        // assert_eq!(actual, expected.as_bytes());
+        let expected = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}";
+        let mut message = Message::default();
+        message.decode(expected.as_bytes())?;
+
+        let mut actual = Vec::new();
+        let result = message.encode(&mut actual, EncodeOptions::Standard)?;
+        assert!(result > 0);
+        assert_eq!(actual, expected.as_bytes());
        
         Ok(())
     }
@@ -373,14 +434,14 @@ mod tests {
     #[test]
     fn encode_does_not_add_body_length_if_it_is_not_present() -> Result<(), crate::error::Error>
     {
-        // let expected = "8=FIX.4.4\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
-        // let mut message = Message::default();
-        // message.decode(expected.as_bytes())?;
+        let expected = "8=FIX.4.4\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
+        let mut message = Message::default();
+        message.decode(expected.as_bytes())?;
 
-        // let mut actual = Vec::new();
-        // let result = message.encode(&mut actual, EncodeOptions::Standard - EncodeOptions::SetChecksum)?;
-        // assert!(result > 0);
-        // assert_eq!(actual, expected.as_bytes());
+        let mut actual = Vec::new();
+        let result = message.encode(&mut actual, EncodeOptions::Standard - EncodeOptions::SetChecksum)?;
+        assert!(result > 0);
+        assert_eq!(actual, expected.as_bytes());
 
         Ok(())
     }
@@ -388,21 +449,19 @@ mod tests {
     #[test]
     fn format_checksum_greater_than_3_digits_fails() -> Result<(), crate::error::Error>
     {
-        // REQUIRE_THROWS(message::format_checksum(9999));
+        assert_eq!(Message::format_checksum(9999), Err(crate::error::Error::ChecksumOutOfRange(9999)));
         Ok(())
     }
 
     #[test]
     fn format_checksum_pads_values_with_less_than_3_digits() -> Result<(), crate::error::Error>
     {
-        /*
-        REQUIRE(message::format_checksum(999) == "999");
-        REQUIRE(message::format_checksum(99) == "099");
-        REQUIRE(message::format_checksum(9) == "009");
-        REQUIRE(message::format_checksum(0) == "000");
-        REQUIRE(message::format_checksum(90) == "090");
-        REQUIRE(message::format_checksum(900) == "900");
-        */
+        assert_eq!(Message::format_checksum(999)?, "999");
+        assert_eq!(Message::format_checksum(99)?, "099");
+        assert_eq!(Message::format_checksum(9)?, "009");
+        assert_eq!(Message::format_checksum(0)?, "000");
+        assert_eq!(Message::format_checksum(90)?, "090");
+        assert_eq!(Message::format_checksum(900)?, "900");
         Ok(())
     }
 
@@ -469,20 +528,20 @@ mod tests {
     #[test]
     fn encode_a_message_containing_a_data_field() -> Result<(), crate::error::Error>
     {
-        // let expected = "8=FIX.4.4\u{0001}9=30\u{0001}93=20\u{0001}89=ABCDEF\u{0001}ABCDEFABC\u{0001}DEF\u{0001}10=119\u{0001}";
+        let expected = "8=FIX.4.4\u{0001}9=30\u{0001}93=20\u{0001}89=ABCDEF\u{0001}ABCDEFABC\u{0001}DEF\u{0001}10=119\u{0001}";
 
-        // let mut message = Message::default();
+        let mut message = Message::default();
         
-        // message.fields().emplace_back(8, "FIX.4.4");
-        // message.fields().emplace_back(9, "40");
-        // message.fields().emplace_back(93, "20");
-        // message.fields().emplace_back(89, "ABCDEF\u{0001}ABCDEFABC\u{0001}DEF");
-        // message.fields().emplace_back(10, "220");
-
-        // let mut actual = Vec::new();
-        // let result = message.encode(&mut actual, EncodeOptions::Standard)?;
-        // assert!(result > 0);
-        // assert_eq!(actual, expected.as_bytes());
+        message.fields.push(Field::from_str(8, "FIX.4.4"));
+        message.fields.push(Field::from_str(9, "40"));
+        message.fields.push(Field::from_str(93, "20"));
+        message.fields.push(Field::from_str(89, "ABCDEF\u{0001}ABCDEFABC\u{0001}DEF"));
+        message.fields.push(Field::from_str(10, "220"));
+
+        let mut actual = Vec::new();
+        let result = message.encode(&mut actual, EncodeOptions::Standard)?;
+        assert!(result > 0);
+        assert_eq!(actual, expected.as_bytes());
         
         Ok(())
     }
@@ -0,0 +1,305 @@
+use crate::field::QUANTITY_TOLERANCE;
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+
+// The fields of the NoLegs group we track, the InstrumentLeg component has many more but only
+// the fields listed here are recognised as part of the group.
+pub const LEG_FIELDS: &[u32] = &[
+    crate::FIX_5_0SP2::LegSymbol::TAG,
+    crate::FIX_5_0SP2::LegSymbolSfx::TAG,
+    crate::FIX_5_0SP2::LegSecurityID::TAG,
+    crate::FIX_5_0SP2::LegSecurityIDSource::TAG,
+    crate::FIX_5_0SP2::LegProduct::TAG,
+    crate::FIX_5_0SP2::LegCFICode::TAG,
+    crate::FIX_5_0SP2::LegSecurityType::TAG,
+    crate::FIX_5_0SP2::LegMaturityMonthYear::TAG,
+    crate::FIX_5_0SP2::LegMaturityDate::TAG,
+    crate::FIX_5_0SP2::LegStrikePrice::TAG,
+    crate::FIX_5_0SP2::LegRatioQty::TAG,
+    crate::FIX_5_0SP2::LegSide::TAG,
+    crate::FIX_5_0SP2::LegCurrency::TAG,
+    crate::FIX_5_0SP2::LegQty::TAG,
+    crate::FIX_5_0SP2::LegPositionEffect::TAG,
+    crate::FIX_5_0SP2::LegRefID::TAG,
+    crate::FIX_5_0SP2::LegPrice::TAG,
+    crate::FIX_5_0SP2::LegSettlType::TAG,
+    crate::FIX_5_0SP2::LegSettlDate::TAG,
+    crate::FIX_5_0SP2::LegLastPx::TAG
+];
+
+// A leg of a multileg order. The quantity is LegQty if it was specified, otherwise the
+// OrderQty of the order multiplied by the LegRatioQty.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Leg {
+    pub leg_ref_id: Option<String>,
+    pub symbol: Option<String>,
+    pub side: Option<String>,
+    pub ratio_qty: f64,
+    pub quantity: f64,
+    pub price: Option<f64>,
+    pub cum_qty: f64,
+    pub notional: f64,
+    pub ord_status: Option<String>
+}
+
+fn value(fields: &FieldCollection, tag: u32) -> Option<String>
+{
+    fields.try_get(tag).map(|field| field.value.clone())
+}
+
+fn decimal(fields: &FieldCollection, tag: u32) -> Option<f64>
+{
+    fields.try_get(tag).and_then(|field| field.decimal_value().ok())
+}
+
+impl Leg {
+
+    fn from_fields(fields: &FieldCollection, order_qty: f64) -> Self
+    {
+        let ratio_qty = decimal(fields, crate::FIX_5_0SP2::LegRatioQty::TAG).unwrap_or(1.0);
+        Leg {
+            leg_ref_id: value(fields, crate::FIX_5_0SP2::LegRefID::TAG),
+            symbol: value(fields, crate::FIX_5_0SP2::LegSymbol::TAG),
+            side: value(fields, crate::FIX_5_0SP2::LegSide::TAG),
+            ratio_qty,
+            quantity: decimal(fields, crate::FIX_5_0SP2::LegQty::TAG).unwrap_or(order_qty * ratio_qty),
+            price: decimal(fields, crate::FIX_5_0SP2::LegPrice::TAG),
+            ..Default::default()
+        }
+    }
+
+    // Legs are identified by LegRefID if both sides have one, otherwise by LegSymbol.
+    fn matches(&self, leg_ref_id: Option<&str>, symbol: Option<&str>) -> bool
+    {
+        match (self.leg_ref_id.as_deref(), leg_ref_id) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => symbol.is_some() && self.symbol.as_deref() == symbol
+        }
+    }
+
+    fn matches_fields(&self, fields: &FieldCollection) -> bool
+    {
+        self.matches(
+            fields.try_get(crate::FIX_5_0SP2::LegRefID::TAG).map(|field| field.value.as_str()),
+            fields.try_get(crate::FIX_5_0SP2::LegSymbol::TAG).map(|field| field.value.as_str())
+        )
+    }
+
+    pub fn leaves_qty(&self) -> f64
+    {
+        (self.quantity - self.cum_qty).max(0.0)
+    }
+
+    pub fn avg_px(&self) -> Option<f64>
+    {
+        if self.cum_qty > 0.0 {
+            Some(self.notional / self.cum_qty)
+        }
+        else {
+            None
+        }
+    }
+
+    fn fill(&mut self, quantity: f64, price: f64)
+    {
+        self.cum_qty += quantity;
+        self.notional += quantity * price;
+    }
+
+    // A leg is Filled once its quantity has been executed, until then it follows the status of
+    // the order unless it has been partially executed.
+    fn update_status(&mut self, ord_status: Option<&str>)
+    {
+        if self.quantity > 0.0 && self.cum_qty >= self.quantity - QUANTITY_TOLERANCE {
+            self.ord_status = Some(crate::FIX_5_0SP2::OrdStatus::Filled().value.to_string());
+            return;
+        }
+
+        if let Some(ord_status) = ord_status {
+            if crate::order_validation::is_closed(ord_status) || self.cum_qty == 0.0 {
+                self.ord_status = Some(ord_status.to_string());
+                return;
+            }
+        }
+
+        if self.cum_qty > 0.0 {
+            self.ord_status = Some(crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value.to_string());
+        }
+    }
+
+}
+
+// The legs specified in the NoLegs group of a NewOrderMultileg or MultilegOrderCancelReplace.
+pub fn legs_for_message(message: &Message) -> Vec<Leg>
+{
+    let order_qty = decimal(&message.fields, crate::FIX_5_0SP2::OrderQty::TAG).unwrap_or_default();
+    message.fields
+        .group(crate::FIX_5_0SP2::NoLegs::TAG, LEG_FIELDS)
+        .iter()
+        .map(|fields| Leg::from_fields(fields, order_qty))
+        .collect()
+}
+
+// The legs specified by a MultilegOrderCancelReplace, legs that are carried over from the
+// previous version of the order keep their executions.
+pub fn amend_legs(legs: &[Leg], request: &Message) -> Vec<Leg>
+{
+    legs_for_message(request)
+        .into_iter()
+        .map(|mut amended| {
+            if let Some(existing) = legs.iter().find(|leg| leg.matches(amended.leg_ref_id.as_deref(), amended.symbol.as_deref())) {
+                amended.cum_qty = existing.cum_qty;
+                amended.notional = existing.notional;
+                amended.ord_status = existing.ord_status.clone();
+            }
+            amended
+        })
+        .collect()
+}
+
+// A report for one leg of a multileg order, its quantities and status are those of the leg rather
+// than the order.
+pub fn is_individual_leg_report(execution_report: &Message) -> bool
+{
+    execution_report.fields.try_get(crate::FIX_5_0SP2::MultiLegReportingType::TAG)
+        .is_some_and(|field| field.value == crate::FIX_5_0SP2::MultiLegReportingType::IndividualLegOfAMultiLegSecurity().value)
+}
+
+// Apply an ExecutionReport for a multileg order to its legs. A report with MultiLegReportingType
+// IndividualLegOfAMultiLegSecurity applies to the leg identified in its NoLegs group or by its
+// Symbol, any other report applies to every leg. Fills come from the NoLegs group where LegQty
+// and LegLastPx are the quantity and price of each leg, without them the LastQty is allocated to
+// the legs by ratio at the LastPx.
+pub fn apply_execution_report(legs: &mut [Leg], execution_report: &Message)
+{
+    let fields = &execution_report.fields;
+
+    // The legs of a replacement come from the MultilegOrderCancelReplace, see amend_legs.
+    if fields.try_get(crate::FIX_5_0SP2::ExecType::TAG).is_some_and(|field| field.value == crate::FIX_5_0SP2::ExecType::Replaced().value) {
+        return;
+    }
+
+    let ord_status = fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).map(|field| field.value.as_str());
+    let last_qty = decimal(fields, crate::FIX_5_0SP2::LastQty::TAG).unwrap_or_default();
+    let last_px = decimal(fields, crate::FIX_5_0SP2::LastPx::TAG).unwrap_or_default();
+    let is_trade = fields.try_get(crate::FIX_5_0SP2::ExecType::TAG).is_none_or(|field| field.value == crate::FIX_5_0SP2::ExecType::Trade().value) && last_qty > 0.0;
+    let individual_leg = is_individual_leg_report(execution_report);
+    let reported_legs = fields.group(crate::FIX_5_0SP2::NoLegs::TAG, LEG_FIELDS);
+
+    for leg in legs.iter_mut() {
+        let reported = reported_legs.iter().find(|reported| leg.matches_fields(reported));
+
+        if individual_leg {
+            let symbol = fields.try_get(crate::FIX_5_0SP2::Symbol::TAG).map(|field| field.value.as_str());
+            if reported.is_none() && !leg.matches(None, symbol) {
+                continue;
+            }
+            if is_trade {
+                leg.fill(last_qty, last_px);
+            }
+        }
+        else if is_trade {
+            let quantity = reported.and_then(|reported| decimal(reported, crate::FIX_5_0SP2::LegQty::TAG)).unwrap_or(last_qty * leg.ratio_qty);
+            let price = reported.and_then(|reported| decimal(reported, crate::FIX_5_0SP2::LegLastPx::TAG)).unwrap_or(last_px);
+            leg.fill(quantity, price);
+        }
+
+        leg.update_status(ord_status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::order_book::OrderBook;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const ORDER_MULTILEG: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=AB\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=S1\u{0001}54=1\u{0001}555=2\u{0001}600=BHP.AX\u{0001}623=1\u{0001}624=1\u{0001}654=L1\u{0001}600=RIO.AX\u{0001}623=2\u{0001}624=2\u{0001}654=L2\u{0001}38=100\u{0001}40=2\u{0001}44=1.5\u{0001}10=000\u{0001}";
+
+    #[test]
+    pub fn legs_are_created_from_the_no_legs_group() -> Result<(), crate::error::Error>
+    {
+        let legs = legs_for_message(&decode_message(ORDER_MULTILEG)?);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].symbol.as_deref(), Some("BHP.AX"));
+        assert_eq!(legs[0].quantity, 100.0);
+        assert_eq!(legs[1].leg_ref_id.as_deref(), Some("L2"));
+        assert_eq!(legs[1].side.as_deref(), Some("2"));
+        assert_eq!(legs[1].quantity, 200.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn an_empty_no_legs_group_has_no_legs() -> Result<(), crate::error::Error>
+    {
+        let text = "8=FIX.4.4\u{0001}9=0\u{0001}35=AB\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=S1\u{0001}54=1\u{0001}555=0\u{0001}38=100\u{0001}40=2\u{0001}44=1.5\u{0001}10=000\u{0001}";
+        assert!(legs_for_message(&decode_message(text)?).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn leg_executions_set_each_legs_status() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            ORDER_MULTILEG,
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=S1\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}39=0\u{0001}442=3\u{0001}151=100\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=S1\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}39=1\u{0001}442=2\u{0001}55=BHP.AX\u{0001}555=1\u{0001}654=L1\u{0001}32=100\u{0001}31=21\u{0001}151=100\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}11=S1\u{0001}37=1\u{0001}17=3\u{0001}150=F\u{0001}39=1\u{0001}442=2\u{0001}55=RIO.AX\u{0001}32=50\u{0001}31=120\u{0001}151=100\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default().with_validation();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let order = &book.orders["INITIATOR-ACCEPTOR-S1"];
+        // The leg fills are not fills of the order.
+        assert!(order.executions.is_empty());
+        assert_eq!(order.filled_quantity(), 0.0);
+        assert_eq!(order.ord_status(), Some(crate::FIX_5_0SP2::OrdStatus::New().value));
+        assert!(book.violations().is_empty());
+        assert_eq!(order.legs[0].cum_qty, 100.0);
+        assert_eq!(order.legs[0].ord_status.as_deref(), Some(crate::FIX_5_0SP2::OrdStatus::Filled().value));
+        assert_eq!(order.legs[0].avg_px(), Some(21.0));
+        assert_eq!(order.legs[1].cum_qty, 50.0);
+        assert_eq!(order.legs[1].leaves_qty(), 150.0);
+        assert_eq!(order.legs[1].ord_status.as_deref(), Some(crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value));
+        Ok(())
+    }
+
+    #[test]
+    pub fn multileg_replace_keeps_leg_executions() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            ORDER_MULTILEG,
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=S1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=1\u{0001}442=3\u{0001}555=2\u{0001}654=L1\u{0001}687=10\u{0001}637=21\u{0001}654=L2\u{0001}687=20\u{0001}637=119\u{0001}32=10\u{0001}31=1.5\u{0001}151=90\u{0001}14=10\u{0001}6=1.5\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AC\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=S2\u{0001}41=S1\u{0001}54=1\u{0001}555=2\u{0001}600=BHP.AX\u{0001}623=1\u{0001}624=1\u{0001}654=L1\u{0001}600=RIO.AX\u{0001}623=3\u{0001}624=2\u{0001}654=L2\u{0001}38=100\u{0001}40=2\u{0001}44=1.6\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=S2\u{0001}41=S1\u{0001}37=1\u{0001}17=2\u{0001}150=5\u{0001}39=5\u{0001}442=3\u{0001}151=90\u{0001}14=10\u{0001}6=1.5\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let original = &book.orders["INITIATOR-ACCEPTOR-S1"];
+        assert_eq!(original.legs[1].quantity, 200.0);
+        assert_eq!(original.legs[1].cum_qty, 20.0);
+        assert_eq!(original.legs[1].avg_px(), Some(119.0));
+
+        let replacement = &book.orders["INITIATOR-ACCEPTOR-S2"];
+        assert_eq!(replacement.legs[0].cum_qty, 10.0);
+        assert_eq!(replacement.legs[1].quantity, 300.0);
+        assert_eq!(replacement.legs[1].cum_qty, 20.0);
+        assert_eq!(replacement.legs[1].ord_status.as_deref(), Some(crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value));
+        Ok(())
+    }
+
+}
@@ -1,9 +1,14 @@
 use crate::message::Message;
-use crate::field::Field;
+use crate::field::{format_decimal, Field, AVG_PX_TOLERANCE, QUANTITY_TOLERANCE};
 use crate::field_collection::{FieldCollection, SetOperation};
 use crate::error::Error;
+use crate::execution::Execution;
+use crate::order_validation::Violation;
+use crate::order_chain::Amendment;
+use crate::multileg::Leg;
 
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Order {
     pub messages: Vec<Message>,
     pub fields: FieldCollection,
@@ -29,43 +34,72 @@ pub struct Order {
     //    and give the new order ClOrdID=2. We walk back through the order list to find this
     //    ClOrdId in mNewClOrdId.
     // TODO - can we make this Option<&Field>? we need lifetimes
-    new_cl_ord_id: Option<Field>
+    new_cl_ord_id: Option<Field>,
+    // The fills, busts, and corrections reported for this order in the order they were received.
+    pub executions: Vec<Execution>,
+    // Breaches of the order state change matrices and quantity invariants, only populated when the OrderBook is validating.
+    pub violations: Vec<Violation>,
+    // The keys of the versions of this order either side of a cancel replace.
+    pub predecessor: Option<String>,
+    pub successor: Option<String>,
+    // The amendments that led to this version of the order, oldest first.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub amendments: Vec<Amendment>,
+    // The legs of a multileg order, empty for single orders.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub legs: Vec<Leg>
 }
 
 impl Order {
     
     pub fn new(message: &Message) -> Result<Self, Error> 
+    {
+        Order::with_key(message, Order::key_for_message(message, false)?)
+    }
+
+    // Create an order with a key from an OrderKeyStrategy. This is usually a NewOrderSingle but
+    // orders can be created from an ExecutionReport when tracking drop copies.
+    pub fn with_key(message: &Message, key: String) -> Result<Self, Error>
     {
         let mut order = Order { 
-            key: Order::key_for_message(message, false)?, 
+            key,
             fields: message.fields.clone(),
             begin_string: message.fields.get(crate::FIX_5_0SP2::BeginString::TAG)?.value.clone(),
             sender_comp_id: message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone(),
             target_comp_id: message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.clone(),
             cl_ord_id: message.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.clone(),
             orig_cl_ord_id: message.fields.try_get(crate::FIX_5_0SP2::OrigClOrdID::TAG).and_then(|field| Some(field.clone())),
+            legs: crate::multileg::legs_for_message(message),
             ..Default::default() 
         };
         order.messages.push(message.clone());
+        if message.msg_type() == Some(crate::FIX_5_0SP2::MsgType::ExecutionReport().value) {
+            if let Some(execution) = Execution::from_execution_report(message)? {
+                order.executions.push(execution);
+            }
+        }
         Ok(order)
     }
 
-    fn create_key(sender_comp_id: &str, target_comp_id: &str, cl_ord_id: &str) -> String
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, cl_ord_id: &str) -> String
     {
         format!("{}-{}-{}", sender_comp_id, target_comp_id, cl_ord_id)
     }
 
+    // The ClOrdID of the order a message refers to, for cancel and replace requests this is the OrigClOrdID.
+    pub fn cl_ord_id_for_message(message: &Message) -> Result<&str, Error>
+    {
+        match message.fields.try_get(crate::FIX_5_0SP2::OrigClOrdID::TAG) {
+            Some(field) => Ok(field.value.as_str()),
+            None => Ok(message.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value.as_str())
+        }
+    }
+
     pub fn key_for_message(message: &Message, reverse_comp_ids: bool) -> Result<String, Error>
     {
         let sender_comp_id = message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
         let target_comp_id = message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
-
-        let cl_ord_id = match message.fields.try_get(crate::FIX_5_0SP2::OrigClOrdID::TAG) {
-            Some(field) => field.value.as_str(),
-            None => {
-                message.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value.as_str()
-            }
-        };
+        let cl_ord_id = Order::cl_ord_id_for_message(message)?;
 
         if reverse_comp_ids {
             Ok(Order::create_key(target_comp_id, sender_comp_id, cl_ord_id))
@@ -114,7 +148,8 @@ impl Order {
             return Err(crate::error::Error::MessageDoesNotContainMsgType);    
         };
 
-        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value {
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value ||
+           msg_type == crate::FIX_5_0SP2::MsgType::MultilegOrderCancelReplace().value {
             self.previous_ord_status = self.fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).and_then(|field| Some(field.clone()));
             self.new_cl_ord_id = Some(message.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.clone());
             self.update_pending_fields(&message.fields);
@@ -122,6 +157,23 @@ impl Order {
             return Ok(());
         }
 
+        if msg_type == crate::FIX_5_0SP2::MsgType::ExecutionReport().value {
+            // A report for an individual leg is recorded on that leg, its fill is not a fill of the order.
+            if !self.legs.is_empty() && crate::multileg::is_individual_leg_report(message) {
+                crate::multileg::apply_execution_report(&mut self.legs, message);
+                return Ok(());
+            }
+            if let Some(execution) = Execution::from_execution_report(message)? {
+                if execution.is_trade_cancel() || execution.is_trade_correct() {
+                    return self.adjust_execution(message, execution);
+                }
+                self.executions.push(execution);
+            }
+            if !self.legs.is_empty() {
+                crate::multileg::apply_execution_report(&mut self.legs, message);
+            }
+        }
+
         if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelRequest().value {
             self.previous_ord_status = self.fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).and_then(|field| Some(field.clone()));
             self.update_pending_fields(&message.fields);
@@ -134,6 +186,190 @@ impl Order {
         Ok(())
     }
 
+    // Mark the execution referenced by a bust or correction as superseded and return its LastQty and LastPx.
+    pub(crate) fn supersede_execution(&mut self, exec_ref_id: &str, exec_id: &str) -> Option<(f64, f64)>
+    {
+        let adjusted = self.executions
+            .iter_mut()
+            .rev()
+            .find(|execution| execution.is_adjusted_by(exec_ref_id))?;
+
+        adjusted.superseded_by = Some(exec_id.to_string());
+        Some((adjusted.last_qty, adjusted.last_px))
+    }
+
+    fn decimal_field(fields: &FieldCollection, tag: u32) -> f64
+    {
+        fields.try_get(tag).and_then(|field| field.decimal_value().ok()).unwrap_or_default()
+    }
+
+    // A TradeCancel reverses the execution referenced by ExecRefID and a TradeCorrect replaces its
+    // LastQty and LastPx. Counterparties usually send the corrected CumQty and AvgPx, if they don't
+    // we derive them from the referenced execution or the most recent correction to it.
+    fn adjust_execution(&mut self, execution_report: &Message, adjustment: Execution) -> Result<(), Error>
+    {
+        let exec_ref_id = execution_report.fields.get(crate::FIX_5_0SP2::ExecRefID::TAG)?.value.as_str();
+
+        let Some((previous_qty, previous_px)) = self.supersede_execution(exec_ref_id, &adjustment.exec_id) else {
+            self.executions.push(adjustment);
+            self.update_fields(&execution_report.fields);
+            return Ok(());
+        };
+
+        let cum_qty = Order::decimal_field(&self.fields, crate::FIX_5_0SP2::CumQty::TAG);
+        let avg_px = Order::decimal_field(&self.fields, crate::FIX_5_0SP2::AvgPx::TAG);
+
+        let (last_qty, last_px) = if adjustment.is_trade_correct() {
+            (adjustment.last_qty, adjustment.last_px)
+        }
+        else {
+            (0.0, 0.0)
+        };
+
+        let adjusted_cum_qty = cum_qty - previous_qty + last_qty;
+        let adjusted_avg_px = if adjusted_cum_qty > 0.0 {
+            (avg_px * cum_qty - previous_qty * previous_px + last_qty * last_px) / adjusted_cum_qty
+        }
+        else {
+            0.0
+        };
+
+        self.executions.push(adjustment);
+        self.update_fields(&execution_report.fields);
+
+        if execution_report.fields.try_get(crate::FIX_5_0SP2::CumQty::TAG).is_none() {
+            self.fields.set(&Field::from_decimal(crate::FIX_5_0SP2::CumQty::TAG, adjusted_cum_qty), SetOperation::ReplaceFirstOrAppend);
+        }
+
+        if execution_report.fields.try_get(crate::FIX_5_0SP2::AvgPx::TAG).is_none() {
+            self.fields.set(&Field::from_decimal(crate::FIX_5_0SP2::AvgPx::TAG, adjusted_avg_px), SetOperation::ReplaceFirstOrAppend);
+        }
+
+        Ok(())
+    }
+
+    pub fn ord_status(&self) -> Option<&str>
+    {
+        self.fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).map(|field| field.value.as_str())
+    }
+
+    pub fn is_replaced(&self) -> bool
+    {
+        self.ord_status() == Some(crate::FIX_5_0SP2::OrdStatus::Replaced().value)
+    }
+
+    // An order that can still trade, this includes orders that haven't been acknowledged and orders
+    // with a pending cancel or replace.
+    pub fn is_open(&self) -> bool
+    {
+        self.ord_status().is_none_or(|ord_status| !crate::order_validation::is_closed(ord_status)) && !self.is_replaced()
+    }
+
+    // The LeavesQty reported by the counterparty or the OrderQty less the CumQty if it hasn't reported one.
+    pub fn open_quantity(&self) -> f64
+    {
+        if !self.is_open() {
+            return 0.0;
+        }
+
+        match self.fields.try_get(crate::FIX_5_0SP2::LeavesQty::TAG).and_then(|field| field.decimal_value().ok()) {
+            Some(leaves_qty) => leaves_qty,
+            None => {
+                Order::decimal_field(&self.fields, crate::FIX_5_0SP2::OrderQty::TAG) - Order::decimal_field(&self.fields, crate::FIX_5_0SP2::CumQty::TAG)
+            }
+        }
+    }
+
+    // The total quantity of the fills reported against this version of the order that have not
+    // been busted or corrected, OrderBook::chain_filled_quantity includes the versions it replaced.
+    pub fn filled_quantity(&self) -> f64
+    {
+        self.executions.iter().filter(|execution| execution.is_active()).map(|execution| execution.last_qty).sum()
+    }
+
+    pub fn notional(&self) -> f64
+    {
+        self.executions.iter().filter(|execution| execution.is_active()).map(|execution| execution.notional()).sum()
+    }
+
+    pub fn vwap(&self) -> Option<f64>
+    {
+        let filled_quantity = self.filled_quantity();
+        if filled_quantity > 0.0 {
+            Some(self.notional() / filled_quantity)
+        }
+        else {
+            None
+        }
+    }
+
+    // Compare the CumQty and AvgPx reported by the counterparty with the fills and return a
+    // description of each disagreement. The CumQty of a replacement includes the fills of the
+    // versions it replaced so OrderBook::check_fills passes the totals for the whole chain.
+    pub(crate) fn check_fills(&self, filled_quantity: f64, notional: f64) -> Vec<String>
+    {
+        let mut discrepancies = Vec::new();
+
+        let vwap = (filled_quantity > 0.0).then(|| notional / filled_quantity);
+
+        if let Some(cum_qty) = self.fields.try_get(crate::FIX_5_0SP2::CumQty::TAG).and_then(|field| field.decimal_value().ok()) {
+            if (cum_qty - filled_quantity).abs() > QUANTITY_TOLERANCE {
+                discrepancies.push(format!("CumQty {} does not match filled quantity {}", cum_qty, format_decimal(filled_quantity)));
+            }
+        }
+
+        if let (Some(avg_px), Some(vwap)) = (self.fields.try_get(crate::FIX_5_0SP2::AvgPx::TAG).and_then(|field| field.decimal_value().ok()), vwap) {
+            if (avg_px - vwap).abs() > AVG_PX_TOLERANCE {
+                discrepancies.push(format!("AvgPx {} does not match VWAP {}", avg_px, format_decimal(vwap)));
+            }
+        }
+
+        discrepancies
+    }
+
+    // Record a message that is part of the orders history but doesn't change its state e.g. an OrderStatusRequest.
+    pub fn record(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+    }
+
+    pub fn dont_know_trade(&mut self, dont_know_trade: &Message) -> Result<(), Error>
+    {
+        let exec_id = dont_know_trade.fields.get(crate::FIX_5_0SP2::ExecID::TAG)?.value.as_str();
+        self.messages.push(dont_know_trade.clone());
+        for execution in self.executions.iter_mut().filter(|execution| execution.exec_id == exec_id) {
+            execution.disputed = true;
+        }
+        Ok(())
+    }
+
+    // Process a BusinessMessageReject or session Reject for a request sent for this order. If the
+    // order itself was rejected it is marked as Rejected, if a cancel or replace request was
+    // rejected the pending changes are rolled back.
+    pub fn reject(&mut self, reject: &Message, request: &Message) -> Result<(), Error>
+    {
+        self.messages.push(reject.clone());
+
+        let Some(msg_type) = request.msg_type() else {
+            return Err(crate::error::Error::MessageDoesNotContainMsgType);
+        };
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderSingle().value ||
+           msg_type == crate::FIX_5_0SP2::MsgType::NewOrderMultileg().value {
+            self.fields.set(&Field::from_field_value(crate::FIX_5_0SP2::OrdStatus::Rejected()), SetOperation::ReplaceFirstOrAppend);
+            if let Some(text) = reject.fields.try_get(crate::FIX_5_0SP2::Text::TAG) {
+                self.fields.set(text, SetOperation::ReplaceFirstOrAppend);
+            }
+        }
+        else if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelRequest().value ||
+                msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value ||
+                msg_type == crate::FIX_5_0SP2::MsgType::MultilegOrderCancelReplace().value {
+            self.rollback();
+        }
+
+        Ok(())
+    }
+
     pub fn rollback(&mut self)
     {
         self.pending_fields.clear();
@@ -155,6 +391,9 @@ impl Order {
     pub fn replace(&mut self, execution_report: &Message) -> Result<Order, Error>
     {
         let mut replacement = self.clone(); 
+        // Each fill and violation belongs to the version of the order it was reported against.
+        replacement.executions.clear();
+        replacement.violations.clear();
         replacement.update(&execution_report)?;
         replacement.commit();
         self.rollback();
@@ -171,11 +410,21 @@ impl Order {
                 replacement.fields.set(cl_ord_id, SetOperation::ReplaceFirstOrAppend);                
                 replacement.fields.set(&Field::from_str(crate::FIX_5_0SP2::OrigClOrdID::TAG, replacement.cl_ord_id.value.as_str()), SetOperation::ReplaceFirstOrAppend);
                 replacement.orig_cl_ord_id = Some(replacement.cl_ord_id.clone());
-                replacement.cl_ord_id = self.cl_ord_id.clone();
+                replacement.cl_ord_id = cl_ord_id.clone();
             }
         }
 
         replacement.key = Order::create_key(replacement.sender_comp_id.as_str(), replacement.target_comp_id.as_str(), replacement.cl_ord_id.value.as_str());
+        // The replacement starts with no request outstanding.
+        replacement.new_cl_ord_id = None;
+
+        let multileg_replace = self.messages.iter().rev().find(|message| {
+            message.msg_type() == Some(crate::FIX_5_0SP2::MsgType::MultilegOrderCancelReplace().value) &&
+            message.fields.try_get(crate::FIX_5_0SP2::ClOrdID::TAG) == Some(&replacement.cl_ord_id)
+        });
+        if let Some(request) = multileg_replace {
+            replacement.legs = crate::multileg::amend_legs(&replacement.legs, request);
+        }
         replacement.fields.set(&Field::from_field_value(crate::FIX_5_0SP2::OrdStatus::New()), SetOperation::ReplaceFirstOrAppend);
         self.fields.set(&Field::from_field_value(crate::FIX_5_0SP2::OrdStatus::Replaced()), SetOperation::ReplaceFirstOrAppend);
 
@@ -186,6 +435,147 @@ impl Order {
 
 }
 
+// Determines which order a message belongs to. Requests such as NewOrderSingle are sent by the
+// owner of the order and responses such as ExecutionReport are sent by the counterparty.
+pub trait OrderKeyStrategy {
+
+    fn key_for_message(&self, message: &Message, is_response: bool) -> Result<String, Error>;
+
+    // The key of the order created by a Replaced ExecutionReport. If this is the key of the
+    // original order the replacement takes its place in the OrderBook.
+    fn key_for_replacement(&self, execution_report: &Message, replacement: &Order) -> Result<String, Error>;
+
+    // Return true to create orders from ExecutionReports for unknown orders e.g. for drop copies
+    // where we never see the NewOrderSingle.
+    fn creates_orders_from_execution_reports(&self) -> bool
+    {
+        false
+    }
+
+}
+
+fn comp_ids(message: &Message, is_response: bool) -> Result<(&str, &str), Error>
+{
+    let sender_comp_id = message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+    let target_comp_id = message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+    if is_response {
+        Ok((target_comp_id, sender_comp_id))
+    }
+    else {
+        Ok((sender_comp_id, target_comp_id))
+    }
+}
+
+// SenderCompID-TargetCompID-ClOrdID for orders sent directly to the counterparty.
+#[derive(Default)]
+pub struct DirectKeyStrategy;
+
+impl OrderKeyStrategy for DirectKeyStrategy {
+
+    fn key_for_message(&self, message: &Message, is_response: bool) -> Result<String, Error>
+    {
+        Order::key_for_message(message, is_response)
+    }
+
+    fn key_for_replacement(&self, _execution_report: &Message, replacement: &Order) -> Result<String, Error>
+    {
+        Ok(Order::create_key(&replacement.sender_comp_id, &replacement.target_comp_id, &replacement.cl_ord_id.value))
+    }
+
+}
+
+// For sessions routed through a hub the parties are identified by OnBehalfOfCompID and
+// DeliverToCompID, the SenderCompID and TargetCompID are used when they are not present.
+#[derive(Default)]
+pub struct HubKeyStrategy;
+
+impl HubKeyStrategy {
+
+    fn parties(message: &Message, is_response: bool) -> Result<(&str, &str), Error>
+    {
+        let (sender_comp_id, target_comp_id) = comp_ids(message, false)?;
+        let originator = message.fields.try_get(crate::FIX_5_0SP2::OnBehalfOfCompID::TAG).map(|field| field.value.as_str()).unwrap_or(sender_comp_id);
+        let destination = message.fields.try_get(crate::FIX_5_0SP2::DeliverToCompID::TAG).map(|field| field.value.as_str()).unwrap_or(target_comp_id);
+        if is_response {
+            Ok((destination, originator))
+        }
+        else {
+            Ok((originator, destination))
+        }
+    }
+
+}
+
+impl OrderKeyStrategy for HubKeyStrategy {
+
+    fn key_for_message(&self, message: &Message, is_response: bool) -> Result<String, Error>
+    {
+        let (owner, counterparty) = HubKeyStrategy::parties(message, is_response)?;
+        Ok(Order::create_key(owner, counterparty, Order::cl_ord_id_for_message(message)?))
+    }
+
+    fn key_for_replacement(&self, execution_report: &Message, replacement: &Order) -> Result<String, Error>
+    {
+        let (owner, counterparty) = HubKeyStrategy::parties(execution_report, true)?;
+        Ok(Order::create_key(owner, counterparty, &replacement.cl_ord_id.value))
+    }
+
+}
+
+// Orders are identified by the OrderID assigned by the counterparty which doesn't change when an
+// order is replaced. This suits drop copy sessions where only ExecutionReports are seen.
+#[derive(Default)]
+pub struct OrderIdKeyStrategy;
+
+impl OrderKeyStrategy for OrderIdKeyStrategy {
+
+    fn key_for_message(&self, message: &Message, is_response: bool) -> Result<String, Error>
+    {
+        let (owner, counterparty) = comp_ids(message, is_response)?;
+        Ok(Order::create_key(owner, counterparty, &message.fields.get(crate::FIX_5_0SP2::OrderID::TAG)?.value))
+    }
+
+    fn key_for_replacement(&self, execution_report: &Message, _replacement: &Order) -> Result<String, Error>
+    {
+        self.key_for_message(execution_report, true)
+    }
+
+    fn creates_orders_from_execution_reports(&self) -> bool
+    {
+        true
+    }
+
+}
+
+// Orders are identified by SecondaryClOrdID which some firms keep constant across a cancel replace
+// chain, messages without one fall back to the ClOrdID.
+#[derive(Default)]
+pub struct SecondaryClOrdIdKeyStrategy;
+
+impl OrderKeyStrategy for SecondaryClOrdIdKeyStrategy {
+
+    fn key_for_message(&self, message: &Message, is_response: bool) -> Result<String, Error>
+    {
+        let (owner, counterparty) = comp_ids(message, is_response)?;
+        let id = match message.fields.try_get(crate::FIX_5_0SP2::SecondaryClOrdID::TAG) {
+            Some(field) => field.value.as_str(),
+            None => Order::cl_ord_id_for_message(message)?
+        };
+        Ok(Order::create_key(owner, counterparty, id))
+    }
+
+    fn key_for_replacement(&self, execution_report: &Message, replacement: &Order) -> Result<String, Error>
+    {
+        let (owner, counterparty) = comp_ids(execution_report, true)?;
+        let id = match execution_report.fields.try_get(crate::FIX_5_0SP2::SecondaryClOrdID::TAG) {
+            Some(field) => field.value.as_str(),
+            None => replacement.cl_ord_id.value.as_str()
+        };
+        Ok(Order::create_key(owner, counterparty, id))
+    }
+
+}
+
 #[cfg(test)]
 mod tests {
 
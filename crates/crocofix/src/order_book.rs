@@ -1,17 +1,79 @@
 use indexmap::IndexMap;
 use crate::message::Message;
-use crate::order::Order;
+use crate::order::{Order, OrderKeyStrategy, DirectKeyStrategy};
+use crate::order_chain::Amendment;
+use crate::order_list::OrderList;
+use crate::mass_action::MassAction;
+use crate::order_event::{OrderEvent, OrderEventKind};
+use crate::order_validation::{validate_execution_report, Violation};
 use crate::error::Error;
 
-#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderBook {
     // TODO - perhaps remove Clone from Order and Box
-    pub orders: IndexMap<String, Order>
+    pub orders: IndexMap<String, Order>,
+    // Lists created by a NewOrderList, the orders in a list are also in orders.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lists: IndexMap<String, OrderList>,
+    // OrderMassCancelRequests and OrderMassStatusRequests and the responses to them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mass_actions: IndexMap<String, MassAction>,
+    validate: bool,
+    // Replaced and PendingReplace reports for versions of an order we haven't seen yet, keyed by
+    // the key of that version. They are processed as soon as it is created.
+    #[cfg_attr(feature = "serde", serde(default))]
+    deferred: IndexMap<String, Vec<Message>>,
+    // Strategies are code not data so they aren't saved in a snapshot, set it again after restoring.
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_key_strategy"))]
+    key_strategy: Box<dyn OrderKeyStrategy>
+}
+
+fn default_key_strategy() -> Box<dyn OrderKeyStrategy>
+{
+    Box::new(DirectKeyStrategy)
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self {
+            orders: IndexMap::new(),
+            lists: IndexMap::new(),
+            mass_actions: IndexMap::new(),
+            validate: false,
+            deferred: IndexMap::new(),
+            key_strategy: default_key_strategy()
+        }
+    }
 }
 
 impl OrderBook {
 
-    pub fn process(&mut self, message: &Message) -> Result<(), Error>
+    // Check each ExecutionReport against the FIX order state change matrices and quantity
+    // invariants before it is applied, any violations are recorded on the order.
+    pub fn with_validation(mut self) -> Self
+    {
+        self.validate = true;
+        self
+    }
+
+    // Change how messages are matched to orders, this should be done before any messages are processed.
+    pub fn with_key_strategy(mut self, key_strategy: Box<dyn OrderKeyStrategy>) -> Self
+    {
+        self.key_strategy = key_strategy;
+        self
+    }
+
+    pub fn violations(&self) -> Vec<(&Order, &Violation)>
+    {
+        self.orders
+            .values()
+            .flat_map(|order| order.violations.iter().map(move |violation| (order, violation)))
+            .collect()
+    }
+
+    // Apply a message to the order it belongs to and return the resulting changes. Messages that
+    // are recorded without changing the order, such as an OrderStatusRequest, return no events.
+    pub fn process(&mut self, message: &Message) -> Result<Vec<OrderEvent>, Error>
     {
         let Some(msg_type) = message.msg_type() else {
             return Err(Error::MessageDoesNotContainMsgType);
@@ -19,52 +81,161 @@ impl OrderBook {
 
         // TODO - make these comparisons more natural
         // TODO - can we change the dictionary so these are constants which will allow a match expression?
-        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderSingle().value { 
+        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderSingle().value ||
+           msg_type == crate::FIX_5_0SP2::MsgType::NewOrderMultileg().value { 
             return self.process_order_single(message);
         }
 
         if msg_type == crate::FIX_5_0SP2::MsgType::ExecutionReport().value {
-            return self.process_execution_report(message);
+            let events = self.process_execution_report(message)?;
+            if message.fields.try_get(crate::FIX_5_0SP2::MassStatusReqID::TAG).is_some() {
+                let key = self.key_strategy.key_for_message(message, true)?;
+                self.process_mass_status_report(message, &key)?;
+            }
+            return Ok(events);
         }
 
         if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelRequest().value {
             return self.process_order_cancel_request(message);
         }
 
-        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value {
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value ||
+           msg_type == crate::FIX_5_0SP2::MsgType::MultilegOrderCancelReplace().value {
             return self.process_order_cancel_replace_request(message);
         }
 
+        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderList().value {
+            return self.process_new_order_list(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::ListStatus().value {
+            return self.process_list_status(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::ListCancelRequest().value {
+            return self.process_list_cancel_request(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderMassCancelRequest().value {
+            return self.process_order_mass_cancel_request(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderMassCancelReport().value {
+            return self.process_order_mass_cancel_report(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderMassStatusRequest().value {
+            return self.process_order_mass_status_request(message);
+        }
+
         if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReject().value {
             return self.process_order_cancel_reject(message);
         }
 
+        if msg_type == crate::FIX_5_0SP2::MsgType::OrderStatusRequest().value {
+            return self.process_order_status_request(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::DontKnowTrade().value {
+            return self.process_dont_know_trade(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::BusinessMessageReject().value ||
+           msg_type == crate::FIX_5_0SP2::MsgType::Reject().value {
+            return self.process_reject(message);
+        }
+
         Err(Error::UnsupportedMsgType(msg_type.to_string()))
     }
 
     pub fn clear(&mut self)
     {
         self.orders.clear();
+        self.lists.clear();
+        self.mass_actions.clear();
+        self.deferred.clear();
+    }
+
+    // Process any reports that arrived before the order they refer to was created.
+    fn process_deferred(&mut self, key: &str) -> Result<Vec<OrderEvent>, Error>
+    {
+        let mut events = Vec::new();
+        if let Some(execution_reports) = self.deferred.shift_remove(key) {
+            for execution_report in execution_reports {
+                events.extend(self.process_execution_report(&execution_report)?);
+            }
+        }
+        Ok(events)
+    }
+
+    // A replace request or report for a cancel replace that has already completed, e.g. a
+    // PendingReplace that was delayed. These refer to the original order by OrigClOrdID and the
+    // new order by ClOrdID.
+    fn is_superseded(&self, key: &str, message: &Message) -> bool
+    {
+        let Some(successor) = self.successor(key) else {
+            return false;
+        };
+
+        let is_replace = match message.msg_type() {
+            Some(msg_type) if msg_type == crate::FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value => true,
+            Some(msg_type) if msg_type == crate::FIX_5_0SP2::MsgType::ExecutionReport().value => {
+                field_value(message, crate::FIX_5_0SP2::ExecType::TAG).is_some_and(|exec_type| {
+                    exec_type == crate::FIX_5_0SP2::ExecType::PendingReplace().value ||
+                    exec_type == crate::FIX_5_0SP2::ExecType::Replaced().value
+                })
+            },
+            _ => false
+        };
+
+        is_replace && field_value(message, crate::FIX_5_0SP2::ClOrdID::TAG) == Some(successor.cl_ord_id.value.as_str())
     }
 
-    fn process_order_single(&mut self, order_single: &Message) -> Result<(), Error>
+    pub(crate) fn process_order_single(&mut self, order_single: &Message) -> Result<Vec<OrderEvent>, Error>
     {
-        let order = Order::new(order_single)?;
+        let order = Order::with_key(order_single, self.key_strategy.key_for_message(order_single, false)?)?;
         // TODO - understand and improve this
         let key = (&order).key.clone();
         if self.orders.contains_key(&key) {
             return Err(Error::OrderBookAlreadyContainsOrderWithKey(key));
         }
+        let event = OrderEvent::new(&key, OrderEventKind::OrderCreated, &Default::default(), &order.fields);
         self.orders.insert(key.clone(), order.clone());
-        Ok(())
+        let mut events = vec![event];
+        events.extend(self.process_deferred(&key)?);
+        Ok(events)
     }
 
-    fn process_execution_report(&mut self, execution_report: &Message) -> Result<(), Error>
+    fn process_execution_report(&mut self, execution_report: &Message) -> Result<Vec<OrderEvent>, Error>
     {
-        let key = Order::key_for_message(&execution_report, true)?;
+        let key = self.key_strategy.key_for_message(execution_report, true)?;
+
+        if !self.orders.contains_key(&key) && self.key_strategy.creates_orders_from_execution_reports() {
+            let order = Order::with_key(execution_report, key.clone())?;
+            let event = OrderEvent::new(&key, OrderEventKind::OrderCreated, &Default::default(), &order.fields);
+            self.orders.insert(key, order);
+            return Ok(vec![event]);
+        }
         
-        if !self.orders.contains_key(&key) {
+        if !self.orders.contains_key(&key) && OrderBook::is_replace_report(execution_report) {
+            self.deferred.entry(key).or_default().push(execution_report.clone());
+            return Ok(vec![]);
+        }
+
+        if self.is_superseded(&key, execution_report) {
+            if let Some(order) = self.orders.get_mut(&key) {
+                order.record(execution_report);
+            }
+            return Ok(vec![]);
+        }
+
+        let Some(order) = self.orders.get_mut(&key) else {
             return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        if self.validate {
+            let violations = validate_execution_report(order, execution_report);
+            order.violations.extend(violations);
         }
 
         if let Some(exec_type) = execution_report.fields.try_get(crate::FIX_5_0SP2::ExecType::TAG) {
@@ -72,61 +243,267 @@ impl OrderBook {
                 let Some(order) = self.orders.get_mut(&key) else {
                     return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
                 };
-                let replacement = order.replace(execution_report)?;
+                let before = order.fields.clone();
+                let mut replacement = order.replace(execution_report)?;
+                replacement.key = self.key_strategy.key_for_replacement(execution_report, &replacement)?;
                 // TODO - understand and improve this
                 let replacement_key = (&replacement).key.clone();
+                let kind = OrderEventKind::Replaced { old_key: key.clone(), new_key: replacement_key.clone() };
+                let event = OrderEvent::new(&replacement_key, kind, &before, &replacement.fields);
+                replacement.amendments.push(Amendment::new(&key, &replacement_key, &before, &replacement.fields));
+                // Some strategies keep the same key across a replace so the replacement takes the place of the original.
+                if replacement_key == key {
+                    if let Some(order) = self.orders.get_mut(&key) {
+                        replacement.executions = std::mem::take(&mut order.executions);
+                        replacement.violations = std::mem::take(&mut order.violations);
+                    }
+                    self.orders.insert(replacement_key, replacement);
+                    return Ok(vec![event]);
+                }
                 if self.orders.contains_key(&replacement_key) {
                     return Err(Error::OrderBookAlreadyContainsOrderWithKey(replacement_key));
                 }
-                self.orders.insert(replacement_key.clone(), replacement.clone());
-                return Ok(());
+                if let Some(order) = self.orders.get_mut(&key) {
+                    order.successor = Some(replacement_key.clone());
+                }
+                replacement.predecessor = Some(key.clone());
+                replacement.successor = None;
+                self.orders.insert(replacement_key.clone(), replacement);
+                let mut events = vec![event];
+                events.extend(self.process_deferred(&replacement_key)?);
+                return Ok(events);
             }
         }
 
+        self.supersede_predecessor_execution(&key, execution_report)?;
+
         let Some(order) = self.orders.get_mut(&key) else {
             return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
         };
 
-        order.update(&execution_report)
+        let before = order.fields.clone();
+        order.update(execution_report)?;
+        let kind = OrderEventKind::for_execution_report(execution_report, &before, &order.fields);
+        Ok(vec![OrderEvent::new(&key, kind, &before, &order.fields)])
     }
 
-    fn process_order_cancel_request(&mut self, order_cancel_request: &Message) -> Result<(), Error>
+    fn process_order_cancel_request(&mut self, order_cancel_request: &Message) -> Result<Vec<OrderEvent>, Error>
     {
-        let key = Order::key_for_message(&order_cancel_request, false)?;
+        let key = self.key_strategy.key_for_message(order_cancel_request, false)?;
         
         let Some(order) = self.orders.get_mut(&key) else {
             return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
         };
         
-        order.update(&order_cancel_request)
+        let before = order.fields.clone();
+        order.update(order_cancel_request)?;
+        Ok(vec![OrderEvent::new(&key, OrderEventKind::PendingChange, &before, &order.fields)])
     }
 
-    fn process_order_cancel_replace_request(&mut self, order_cancel_replace_request: &Message) -> Result<(), Error>
+    fn process_order_cancel_replace_request(&mut self, order_cancel_replace_request: &Message) -> Result<Vec<OrderEvent>, Error>
     {
-        let key = Order::key_for_message(&order_cancel_replace_request, false)?;
+        let key = self.key_strategy.key_for_message(order_cancel_replace_request, false)?;
+
+        if self.is_superseded(&key, order_cancel_replace_request) {
+            if let Some(order) = self.orders.get_mut(&key) {
+                order.record(order_cancel_replace_request);
+            }
+            return Ok(vec![]);
+        }
 
         let Some(order) = self.orders.get_mut(&key) else {
             return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
         };
 
-        order.update(&order_cancel_replace_request)
+        let before = order.fields.clone();
+        order.update(order_cancel_replace_request)?;
+        Ok(vec![OrderEvent::new(&key, OrderEventKind::PendingChange, &before, &order.fields)])
     }
 
-    fn process_order_cancel_reject(&mut self, order_cancel_reject: &Message) -> Result<(), Error>
+    fn process_order_cancel_reject(&mut self, order_cancel_reject: &Message) -> Result<Vec<OrderEvent>, Error>
     {
-        let key = Order::key_for_message(&order_cancel_reject, true)?;
+        let key = self.key_strategy.key_for_message(order_cancel_reject, true)?;
         
         let Some(order) = self.orders.get_mut(&key) else {
             return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
         };
 
+        let before = order.fields.clone();
         order.rollback();
 
-        Ok(())
+        Ok(vec![OrderEvent::new(&key, OrderEventKind::CancelRejected, &before, &order.fields)])
+    }
+
+    fn process_order_status_request(&mut self, order_status_request: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let key = self.key_strategy.key_for_message(order_status_request, false)?;
+
+        let Some(order) = self.orders.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        order.record(order_status_request);
+
+        Ok(vec![])
+    }
+
+    fn is_replace_report(execution_report: &Message) -> bool
+    {
+        execution_report.fields.try_get(crate::FIX_5_0SP2::OrigClOrdID::TAG).is_some() &&
+        field_value(execution_report, crate::FIX_5_0SP2::ExecType::TAG).is_some_and(|exec_type| {
+            exec_type == crate::FIX_5_0SP2::ExecType::PendingReplace().value ||
+            exec_type == crate::FIX_5_0SP2::ExecType::Replaced().value
+        })
+    }
+
+    // Search the orders, most recent first, for a message sent from sender_comp_id to target_comp_id
+    // that satisfies the predicate and return the key of the order it belongs to along with the message.
+    fn find_message(&self, sender_comp_id: &str, target_comp_id: &str, predicate: impl Fn(&Message) -> bool) -> Option<(String, Message)>
+    {
+        self.orders
+            .values()
+            .rev()
+            .filter(|order| {
+                (order.sender_comp_id == sender_comp_id && order.target_comp_id == target_comp_id) ||
+                (order.sender_comp_id == target_comp_id && order.target_comp_id == sender_comp_id)
+            })
+            .find_map(|order| {
+                order.messages
+                    .iter()
+                    .rev()
+                    .filter(|message| field_value(message, crate::FIX_5_0SP2::SenderCompID::TAG) == Some(sender_comp_id))
+                    .find(|message| predicate(message))
+                    .map(|message| (order.key.clone(), message.clone()))
+            })
+    }
+
+    // A DontKnowTrade doesn't necessarily carry a ClOrdID so we find the order via the ExecID of the execution being disputed.
+    fn process_dont_know_trade(&mut self, dont_know_trade: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let exec_id = dont_know_trade.fields.get(crate::FIX_5_0SP2::ExecID::TAG)?.value.as_str();
+        let sender_comp_id = dont_know_trade.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+        let target_comp_id = dont_know_trade.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+
+        let execution = self.find_message(target_comp_id, sender_comp_id, |message| {
+            message.msg_type() == Some(crate::FIX_5_0SP2::MsgType::ExecutionReport().value) &&
+            field_value(message, crate::FIX_5_0SP2::ExecID::TAG) == Some(exec_id)
+        });
+
+        let Some((key, _)) = execution else {
+            return Err(Error::OrderBookDoesNotContainExecution(exec_id.to_string()));
+        };
+
+        let Some(order) = self.orders.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        order.dont_know_trade(dont_know_trade)?;
+
+        Ok(vec![])
+    }
+
+    // A BusinessMessageReject references the rejected message by BusinessRejectRefID, which is
+    // typically the ClOrdID, and RefSeqNum. A session Reject only has RefSeqNum. We prefer the ID
+    // because sequence numbers are reset each session.
+    fn process_reject(&mut self, reject: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let sender_comp_id = reject.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+        let target_comp_id = reject.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+        let ref_msg_type = field_value(reject, crate::FIX_5_0SP2::RefMsgType::TAG);
+        let ref_id = field_value(reject, crate::FIX_5_0SP2::BusinessRejectRefID::TAG);
+        let ref_seq_num = field_value(reject, crate::FIX_5_0SP2::RefSeqNum::TAG);
+
+        let is_referenced_type = |message: &Message| ref_msg_type.is_none_or(|msg_type| message.msg_type() == Some(msg_type));
+
+        let by_id = ref_id.and_then(|ref_id| {
+            self.find_message(target_comp_id, sender_comp_id, |message| {
+                is_referenced_type(message) && field_value(message, crate::FIX_5_0SP2::ClOrdID::TAG) == Some(ref_id)
+            })
+        });
+
+        let rejected = by_id.or_else(|| {
+            ref_seq_num.and_then(|ref_seq_num| {
+                self.find_message(target_comp_id, sender_comp_id, |message| {
+                    is_referenced_type(message) && field_value(message, crate::FIX_5_0SP2::MsgSeqNum::TAG) == Some(ref_seq_num)
+                })
+            })
+        });
+
+        let Some((key, request)) = rejected else {
+            let reference = match (ref_id, ref_seq_num) {
+                (Some(ref_id), _) => format!("BusinessRejectRefID={}", ref_id),
+                (None, Some(ref_seq_num)) => format!("RefSeqNum={}", ref_seq_num),
+                (None, None) => String::new()
+            };
+            return Err(Error::OrderBookDoesNotContainRejectedMessage(reference));
+        };
+
+        let Some(order) = self.orders.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        let before = order.fields.clone();
+        order.reject(reject, &request)?;
+
+        let kind = if request.msg_type() == Some(crate::FIX_5_0SP2::MsgType::NewOrderSingle().value) ||
+                      request.msg_type() == Some(crate::FIX_5_0SP2::MsgType::NewOrderMultileg().value) {
+            OrderEventKind::Rejected
+        }
+        else {
+            OrderEventKind::CancelRejected
+        };
+
+        Ok(vec![OrderEvent::new(&key, kind, &before, &order.fields)])
+    }
+
+}
+
+// Increment this when a change to Order or OrderBook means older snapshots can't be restored.
+#[cfg(feature = "serde")]
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    order_book: &'a OrderBook
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Snapshot {
+    version: u32,
+    order_book: OrderBook
+}
+
+#[cfg(feature = "serde")]
+impl OrderBook {
+
+    // Write the complete state of the book, including the message history of each order, as JSON.
+    pub fn save<W: std::io::Write>(&self, writer: W) -> Result<(), Error>
+    {
+        let snapshot = SnapshotRef { version: SNAPSHOT_VERSION, order_book: self };
+        serde_json::to_writer(writer, &snapshot).map_err(|error| Error::InvalidSnapshot(error.to_string()))
+    }
+
+    // Restore a book written by save. The key strategy is not part of the snapshot so the book
+    // will use the DirectKeyStrategy unless another is set with with_key_strategy.
+    pub fn restore<R: std::io::Read>(reader: R) -> Result<Self, Error>
+    {
+        let snapshot: Snapshot = serde_json::from_reader(reader).map_err(|error| Error::InvalidSnapshot(error.to_string()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::InvalidSnapshot(format!("unsupported snapshot version {}", snapshot.version)));
+        }
+        Ok(snapshot.order_book)
     }
 
 }
 
+fn field_value(message: &Message, tag: u32) -> Option<&str>
+{
+    message.fields.try_get(tag).map(|field| field.value.as_str())
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -429,7 +806,7 @@ mod tests {
         let message = decode_message(text)?;
         let mut book = OrderBook::default();
         match book.process(&message) {
-            Ok(()) => panic!(""),
+            Ok(_) => panic!(""),
             Err(error) => {
                 // REQUIRE(reason == "unsupported MsgType = S");
                 // REQUIRE_FALSE(processed);
@@ -485,4 +862,385 @@ mod tests {
         Ok(())
     }
     
-}
\ No newline at end of file
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}59=0\u{0001}10=021\u{0001}";
+    const REPORT_NEW: &str = "8=FIX.4.4\u{0001}9=173\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20200114-08:13:20.072\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}151=10000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=0\u{0001}31=0\u{0001}14=0\u{0001}6=0\u{0001}10=021\u{0001}";
+    const REPORT_PARTIAL: &str = "8=FIX.4.4\u{0001}9=187\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}52=20200114-08:13:21.072\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}151=6000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=4000\u{0001}31=20\u{0001}14=4000\u{0001}6=20\u{0001}10=081\u{0001}";
+    const REPORT_FILLED: &str = "8=FIX.4.4\u{0001}9=185\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}52=20200114-08:13:22.072\u{0001}39=2\u{0001}11=61\u{0001}37=1\u{0001}17=3\u{0001}150=F\u{0001}151=0\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=6000\u{0001}31=21\u{0001}14=10000\u{0001}6=20.6\u{0001}10=201\u{0001}";
+
+    fn filled_order_book() -> Result<OrderBook, crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        for text in [ORDER_SINGLE, REPORT_NEW, REPORT_PARTIAL, REPORT_FILLED] {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    fn first_order(book: &OrderBook) -> Result<&Order, crate::error::Error>
+    {
+        book.orders.values().next().ok_or(Error::OrderIndexOutOfRange(0))
+    }
+
+    #[test]
+    pub fn order_status_request_recorded() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=96\u{0001}35=H\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:14:00.000\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}10=000\u{0001}")?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=185\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}52=20200114-08:14:00.010\u{0001}39=2\u{0001}11=61\u{0001}37=1\u{0001}17=4\u{0001}150=I\u{0001}151=0\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}14=10000\u{0001}6=20.6\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.messages.len(), 6);
+        assert_eq!(order.messages[4].msg_type(), Some("H"));
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::Filled());
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "10000");
+        Ok(())
+    }
+
+    #[test]
+    pub fn order_status_request_for_unknown_order() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        let message = decode_message("8=FIX.4.4\u{0001}9=96\u{0001}35=H\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:14:00.000\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}10=000\u{0001}")?;
+        assert_eq!(book.process(&message), Err(crate::error::Error::OrderBookDoesNotContainOrderWithKey("INITIATOR-ACCEPTOR-61".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    pub fn dont_know_trade_flags_execution() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=90\u{0001}35=Q\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:14:00.000\u{0001}37=1\u{0001}17=3\u{0001}127=B\u{0001}55=BHP.AX\u{0001}54=1\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        let disputed: Vec<&str> = order.executions.iter().filter(|execution| execution.disputed).map(|execution| execution.exec_id.as_str()).collect();
+        assert_eq!(disputed, vec!["3"]);
+        assert_eq!(order.messages.last().and_then(|message| message.msg_type()), Some("Q"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn dont_know_trade_for_unknown_execution() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        let message = decode_message("8=FIX.4.4\u{0001}9=90\u{0001}35=Q\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:14:00.000\u{0001}37=1\u{0001}17=99\u{0001}127=A\u{0001}55=BHP.AX\u{0001}54=1\u{0001}10=000\u{0001}")?;
+        assert_eq!(book.process(&message), Err(crate::error::Error::OrderBookDoesNotContainExecution("99".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    pub fn business_message_reject_rejects_order() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        book.process(&decode_message(ORDER_SINGLE)?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=112\u{0001}35=j\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20200114-08:13:20.072\u{0001}45=2\u{0001}372=D\u{0001}379=61\u{0001}380=4\u{0001}58=Application not available\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::Rejected());
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::Text::TAG)?.value, "Application not available");
+        Ok(())
+    }
+
+    #[test]
+    pub fn session_reject_rolls_back_order_cancel_request() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        book.process(&decode_message(ORDER_SINGLE)?)?;
+        book.process(&decode_message(REPORT_NEW)?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=120\u{0001}35=F\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200114-08:13:30.000\u{0001}41=61\u{0001}11=62\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}10=000\u{0001}")?)?;
+        assert_eq!(first_order(&book)?.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::PendingCancel());
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=90\u{0001}35=3\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}52=20200114-08:13:30.010\u{0001}45=3\u{0001}371=41\u{0001}372=F\u{0001}373=5\u{0001}10=000\u{0001}")?)?;
+        assert_eq!(first_order(&book)?.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::New());
+        Ok(())
+    }
+
+    #[test]
+    pub fn session_reject_for_unknown_message() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        let message = decode_message("8=FIX.4.4\u{0001}9=80\u{0001}35=3\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}52=20200114-08:13:30.010\u{0001}45=999\u{0001}373=1\u{0001}10=000\u{0001}")?;
+        assert_eq!(book.process(&message), Err(crate::error::Error::OrderBookDoesNotContainRejectedMessage("RefSeqNum=999".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    pub fn restatement_updates_order() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        book.process(&decode_message(ORDER_SINGLE)?)?;
+        book.process(&decode_message(REPORT_NEW)?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=173\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}52=20200114-08:13:25.000\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=D\u{0001}378=1\u{0001}151=8000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=8000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::New());
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrderQty::TAG)?.value, "8000");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::LeavesQty::TAG)?.value, "8000");
+        Ok(())
+    }
+
+    #[test]
+    pub fn trade_cancel_and_correct_adjust_fills() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        assert_eq!(first_order(&book)?.fields.get(crate::FIX_5_0SP2::AvgPx::TAG)?.value, "20.6");
+
+        // Neither report carries CumQty or AvgPx so they are derived from the referenced executions.
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=150\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}52=20200114-08:14:00.000\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=4\u{0001}19=2\u{0001}150=H\u{0001}151=4000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::PartiallyFilled());
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "6000");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::AvgPx::TAG)?.value, "21");
+
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=150\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}52=20200114-08:14:10.000\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=5\u{0001}19=3\u{0001}150=G\u{0001}151=5000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=5000\u{0001}31=22\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "5000");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::AvgPx::TAG)?.value, "22");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::LeavesQty::TAG)?.value, "5000");
+
+        // A second correction applies to the first correction not the original execution.
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=150\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}52=20200114-08:14:20.000\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=6\u{0001}19=3\u{0001}150=G\u{0001}151=4000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=6000\u{0001}31=22\u{0001}14=6000\u{0001}6=22\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "6000");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::AvgPx::TAG)?.value, "22");
+        Ok(())
+    }
+
+    #[test]
+    pub fn executions_are_tracked_with_fill_metrics() -> Result<(), crate::error::Error>
+    {
+        let mut book = filled_order_book()?;
+        let order = first_order(&book)?;
+        assert_eq!(order.executions.iter().map(|execution| execution.exec_id.as_str()).collect::<Vec<_>>(), vec!["2", "3"]);
+        assert_eq!(order.filled_quantity(), 10000.0);
+        assert_eq!(order.notional(), 206000.0);
+        assert_eq!(order.vwap(), Some(20.6));
+        assert!(book.check_fills(&order.key).is_empty());
+
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=150\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}52=20200114-08:14:00.000\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=4\u{0001}19=2\u{0001}150=H\u{0001}151=4000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}10=000\u{0001}")?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=150\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}52=20200114-08:14:10.000\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=5\u{0001}19=3\u{0001}150=G\u{0001}151=5000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=5000\u{0001}31=22\u{0001}10=000\u{0001}")?)?;
+        let order = first_order(&book)?;
+        assert_eq!(order.executions.len(), 4);
+        assert_eq!(order.executions[0].superseded_by.as_deref(), Some("4"));
+        assert_eq!(order.executions[1].superseded_by.as_deref(), Some("5"));
+        assert_eq!(order.executions[3].exec_ref_id.as_deref(), Some("3"));
+        assert_eq!(order.filled_quantity(), 5000.0);
+        assert_eq!(order.vwap(), Some(22.0));
+        assert!(book.check_fills(&order.key).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn check_fills_reports_discrepancies() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        book.process(&decode_message(ORDER_SINGLE)?)?;
+        book.process(&decode_message(REPORT_NEW)?)?;
+        book.process(&decode_message(&REPORT_PARTIAL.replace("14=4000", "14=5000").replace("6=20", "6=20.5"))?)?;
+        assert_eq!(book.check_fills(&first_order(&book)?.key), vec![
+            "CumQty 5000 does not match filled quantity 4000",
+            "AvgPx 20.5 does not match VWAP 20"
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn violations_are_only_recorded_when_validating() -> Result<(), crate::error::Error>
+    {
+        let canceled = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=4\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=4\u{0001}151=0\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let late_fill = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=2\u{0001}11=61\u{0001}37=1\u{0001}17=3\u{0001}150=F\u{0001}151=0\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}32=10000\u{0001}31=21\u{0001}14=10000\u{0001}6=21\u{0001}10=000\u{0001}";
+
+        let mut book = OrderBook::default();
+        let mut validating_book = OrderBook::default().with_validation();
+        for text in [ORDER_SINGLE, REPORT_NEW, canceled, late_fill] {
+            let message = decode_message(text)?;
+            book.process(&message)?;
+            validating_book.process(&message)?;
+        }
+
+        assert!(book.violations().is_empty());
+        let violations = validating_book.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0.key, "INITIATOR-ACCEPTOR-61");
+        assert_eq!(violations[0].1.to_string(), "MsgSeqNum=4 fill received for an order with OrdStatus Canceled");
+        Ok(())
+    }
+
+    #[test]
+    pub fn violations_are_not_repeated_on_the_replacement() -> Result<(), crate::error::Error>
+    {
+        let replace_request = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=62\u{0001}41=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=12000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let replaced = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=1\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=3\u{0001}150=5\u{0001}151=8000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=12000\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}";
+
+        let mut book = OrderBook::default().with_validation();
+        for text in [ORDER_SINGLE, REPORT_NEW, &REPORT_PARTIAL.replace("151=6000", "151=5000"), replace_request, replaced] {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let violations = book.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0.key, "INITIATOR-ACCEPTOR-61");
+        assert_eq!(violations[0].1.to_string(), "MsgSeqNum=3 CumQty 4000 + LeavesQty 5000 does not equal OrderQty 10000");
+        assert!(book.orders["INITIATOR-ACCEPTOR-62"].violations.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn hub_key_strategy_uses_on_behalf_of_and_deliver_to() -> Result<(), crate::error::Error>
+    {
+        let order_single = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=HUB\u{0001}56=ACCEPTOR\u{0001}115=CLIENT\u{0001}34=2\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let report = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=HUB\u{0001}128=CLIENT\u{0001}34=2\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}151=10000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let mut book = OrderBook::default().with_key_strategy(Box::new(crate::order::HubKeyStrategy));
+        book.process(&decode_message(order_single)?)?;
+        book.process(&decode_message(report)?)?;
+        assert_eq!(book.orders.len(), 1);
+        let order = first_order(&book)?;
+        assert_eq!(order.key, "CLIENT-ACCEPTOR-61");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::New());
+        Ok(())
+    }
+
+    #[test]
+    pub fn order_id_key_strategy_tracks_drop_copies() -> Result<(), crate::error::Error>
+    {
+        let replaced = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=4\u{0001}150=5\u{0001}151=11000\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}";
+        let mut book = OrderBook::default().with_key_strategy(Box::new(crate::order::OrderIdKeyStrategy));
+        for text in [REPORT_NEW, REPORT_PARTIAL, replaced] {
+            book.process(&decode_message(text)?)?;
+        }
+        assert_eq!(book.orders.len(), 1);
+        let order = first_order(&book)?;
+        assert_eq!(order.key, "INITIATOR-ACCEPTOR-1");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrderQty::TAG)?.value, "15000");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "4000");
+        assert_eq!(order.filled_quantity(), 4000.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn secondary_cl_ord_id_key_strategy_keeps_key_across_replace() -> Result<(), crate::error::Error>
+    {
+        let order_single = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=61\u{0001}526=A\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let report_new = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}39=0\u{0001}11=61\u{0001}526=A\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}151=10000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let replace_request = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=62\u{0001}41=61\u{0001}526=A\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let replaced = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}526=A\u{0001}37=1\u{0001}17=2\u{0001}150=5\u{0001}151=15000\u{0001}38=15000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let mut book = OrderBook::default().with_key_strategy(Box::new(crate::order::SecondaryClOrdIdKeyStrategy));
+        for text in [order_single, report_new, replace_request, replaced] {
+            book.process(&decode_message(text)?)?;
+        }
+        assert_eq!(book.orders.len(), 1);
+        let order = first_order(&book)?;
+        assert_eq!(order.key, "INITIATOR-ACCEPTOR-A");
+        assert_eq!(order.cl_ord_id.value, "62");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrderQty::TAG)?.value, "15000");
+        Ok(())
+    }
+
+    #[test]
+    pub fn events_describe_the_order_lifecycle() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        let mut events = Vec::new();
+        for text in [ORDER_SINGLE, REPORT_NEW, REPORT_PARTIAL, REPORT_FILLED] {
+            events.extend(book.process(&decode_message(text)?)?);
+        }
+        let kinds: Vec<OrderEventKind> = events.iter().map(|event| event.kind.clone()).collect();
+        assert_eq!(kinds, vec![
+            OrderEventKind::OrderCreated,
+            OrderEventKind::Acknowledged,
+            OrderEventKind::PartiallyFilled,
+            OrderEventKind::Filled
+        ]);
+        assert!(events.iter().all(|event| event.key == "INITIATOR-ACCEPTOR-61"));
+        let cum_qty = events[3].change(crate::FIX_5_0SP2::CumQty::TAG).unwrap();
+        assert_eq!(cum_qty.before.as_deref(), Some("4000"));
+        assert_eq!(cum_qty.after.as_deref(), Some("10000"));
+        assert_eq!(events[3].change(crate::FIX_5_0SP2::Symbol::TAG), None);
+        Ok(())
+    }
+
+    #[test]
+    pub fn events_for_replace_and_cancel_reject() -> Result<(), crate::error::Error>
+    {
+        let replace_request = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=62\u{0001}41=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let replaced = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=2\u{0001}150=5\u{0001}151=15000\u{0001}38=15000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let cancel_request = "8=FIX.4.4\u{0001}9=0\u{0001}35=F\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=63\u{0001}41=62\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}10=000\u{0001}";
+        let cancel_reject = "8=FIX.4.4\u{0001}9=0\u{0001}35=9\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}37=1\u{0001}39=0\u{0001}11=63\u{0001}41=62\u{0001}434=1\u{0001}58=Too late\u{0001}10=000\u{0001}";
+
+        let mut book = OrderBook::default();
+        book.process(&decode_message(ORDER_SINGLE)?)?;
+        book.process(&decode_message(REPORT_NEW)?)?;
+
+        let events = book.process(&decode_message(replace_request)?)?;
+        assert_eq!(events[0].kind, OrderEventKind::PendingChange);
+        assert_eq!(events[0].change(crate::FIX_5_0SP2::OrdStatus::TAG).unwrap().to_string(), "OrdStatus 0 -> E");
+
+        let events = book.process(&decode_message(replaced)?)?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].key, "INITIATOR-ACCEPTOR-62");
+        assert_eq!(events[0].kind, OrderEventKind::Replaced { old_key: "INITIATOR-ACCEPTOR-61".to_string(), new_key: "INITIATOR-ACCEPTOR-62".to_string() });
+        assert_eq!(events[0].change(crate::FIX_5_0SP2::OrderQty::TAG).unwrap().to_string(), "OrderQty 10000 -> 15000");
+
+        book.process(&decode_message(cancel_request)?)?;
+        let events = book.process(&decode_message(cancel_reject)?)?;
+        assert_eq!(events[0].kind, OrderEventKind::CancelRejected);
+        assert_eq!(events[0].change(crate::FIX_5_0SP2::OrdStatus::TAG).unwrap().to_string(), "OrdStatus 6 -> 0");
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn restored_book_continues_where_the_snapshot_left_off() -> Result<(), crate::error::Error>
+    {
+        let replace_request = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=62\u{0001}41=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+        let replaced = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=2\u{0001}150=5\u{0001}151=15000\u{0001}38=15000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let filled = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=2\u{0001}11=62\u{0001}37=1\u{0001}17=3\u{0001}150=F\u{0001}151=0\u{0001}38=15000\u{0001}32=15000\u{0001}31=21\u{0001}14=15000\u{0001}6=21\u{0001}10=000\u{0001}";
+        let log = [ORDER_SINGLE, REPORT_NEW, replace_request, replaced, filled];
+
+        let mut whole = OrderBook::default().with_validation();
+        for text in log {
+            whole.process(&decode_message(text)?)?;
+        }
+
+        // Snapshot while the replace is pending so the private pending state has to survive.
+        let mut first = OrderBook::default().with_validation();
+        for text in &log[..3] {
+            first.process(&decode_message(text)?)?;
+        }
+        let mut snapshot = Vec::new();
+        first.save(&mut snapshot)?;
+
+        let mut restored = OrderBook::restore(snapshot.as_slice())?;
+        for text in &log[3..] {
+            restored.process(&decode_message(text)?)?;
+        }
+
+        let mut expected = Vec::new();
+        whole.save(&mut expected)?;
+        let mut actual = Vec::new();
+        restored.save(&mut actual)?;
+        assert_eq!(String::from_utf8(actual).unwrap(), String::from_utf8(expected).unwrap());
+        assert_eq!(restored.orders.len(), 2);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn restore_snapshot_without_the_fields_added_since_version_1() -> Result<(), crate::error::Error>
+    {
+        let snapshot = r#"{"version":1,"order_book":{"orders":{"INITIATOR-ACCEPTOR-61":{"messages":[{"fields":[{"tag":8,"value":"FIX.4.4"},{"tag":9,"value":"149"},{"tag":35,"value":"D"},{"tag":49,"value":"INITIATOR"},{"tag":56,"value":"ACCEPTOR"},{"tag":34,"value":"2"},{"tag":52,"value":"20200114-08:13:20.041"},{"tag":11,"value":"61"},{"tag":55,"value":"BHP.AX"},{"tag":54,"value":"1"},{"tag":38,"value":"10000"},{"tag":40,"value":"2"},{"tag":44,"value":"21"},{"tag":59,"value":"0"},{"tag":10,"value":"021"}],"decode_checksum":139,"decode_checksum_valid":true},{"fields":[{"tag":8,"value":"FIX.4.4"},{"tag":9,"value":"173"},{"tag":35,"value":"8"},{"tag":49,"value":"ACCEPTOR"},{"tag":56,"value":"INITIATOR"},{"tag":34,"value":"2"},{"tag":52,"value":"20200114-08:13:20.072"},{"tag":39,"value":"0"},{"tag":11,"value":"61"},{"tag":37,"value":"1"},{"tag":17,"value":"1"},{"tag":150,"value":"0"},{"tag":151,"value":"10000"},{"tag":55,"value":"BHP.AX"},{"tag":54,"value":"1"},{"tag":38,"value":"10000"},{"tag":32,"value":"0"},{"tag":31,"value":"0"},{"tag":14,"value":"0"},{"tag":6,"value":"0"},{"tag":10,"value":"021"}],"decode_checksum":55,"decode_checksum_valid":true}],"fields":[{"tag":8,"value":"FIX.4.4"},{"tag":9,"value":"173"},{"tag":35,"value":"8"},{"tag":49,"value":"INITIATOR"},{"tag":56,"value":"ACCEPTOR"},{"tag":34,"value":"2"},{"tag":52,"value":"20200114-08:13:20.072"},{"tag":11,"value":"61"},{"tag":55,"value":"BHP.AX"},{"tag":54,"value":"1"},{"tag":38,"value":"10000"},{"tag":40,"value":"2"},{"tag":44,"value":"21"},{"tag":59,"value":"0"},{"tag":10,"value":"021"},{"tag":39,"value":"0"},{"tag":37,"value":"1"},{"tag":17,"value":"1"},{"tag":150,"value":"0"},{"tag":151,"value":"10000"},{"tag":32,"value":"0"},{"tag":31,"value":"0"},{"tag":14,"value":"0"},{"tag":6,"value":"0"}],"pending_fields":[],"key":"INITIATOR-ACCEPTOR-61","begin_string":"FIX.4.4","sender_comp_id":"INITIATOR","target_comp_id":"ACCEPTOR","cl_ord_id":{"tag":11,"value":"61"},"orig_cl_ord_id":null,"previous_ord_status":null,"new_cl_ord_id":null,"executions":[],"violations":[]}},"validate":false}}"#;
+        let mut restored = OrderBook::restore(snapshot.as_bytes())?;
+        assert!(restored.lists.is_empty());
+        assert!(restored.mass_actions.is_empty());
+        let order = &restored.orders["INITIATOR-ACCEPTOR-61"];
+        assert!(order.amendments.is_empty());
+        assert!(order.legs.is_empty());
+        assert_eq!(order.messages[0].logged_time, None);
+        restored.process(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=2\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}151=0\u{0001}38=10000\u{0001}32=10000\u{0001}31=21\u{0001}14=10000\u{0001}6=21\u{0001}10=000\u{0001}")?)?;
+        assert_eq!(restored.orders["INITIATOR-ACCEPTOR-61"].filled_quantity(), 10000.0);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn restore_rejects_unknown_snapshot_versions()
+    {
+        let snapshot = r#"{"version":0,"order_book":{"orders":{},"validate":false}}"#;
+        assert_eq!(OrderBook::restore(snapshot.as_bytes()).err(), Some(Error::InvalidSnapshot("unsupported snapshot version 0".to_string())));
+    }
+
+}
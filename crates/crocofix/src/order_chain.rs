@@ -0,0 +1,341 @@
+use crate::error::Error;
+use crate::execution::Execution;
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::order_event::{field_changes, FieldChange};
+
+// Fields that change with every message rather than because the order was amended.
+const TRANSIENT_FIELDS: [u32; 16] = [
+    crate::FIX_5_0SP2::BeginString::TAG,
+    crate::FIX_5_0SP2::BodyLength::TAG,
+    crate::FIX_5_0SP2::MsgType::TAG,
+    crate::FIX_5_0SP2::MsgSeqNum::TAG,
+    crate::FIX_5_0SP2::SendingTime::TAG,
+    crate::FIX_5_0SP2::OrigSendingTime::TAG,
+    crate::FIX_5_0SP2::PossDupFlag::TAG,
+    crate::FIX_5_0SP2::PossResend::TAG,
+    crate::FIX_5_0SP2::CheckSum::TAG,
+    crate::FIX_5_0SP2::ExecID::TAG,
+    crate::FIX_5_0SP2::ExecType::TAG,
+    crate::FIX_5_0SP2::OrdStatus::TAG,
+    crate::FIX_5_0SP2::TransactTime::TAG,
+    crate::FIX_5_0SP2::LastQty::TAG,
+    crate::FIX_5_0SP2::LastPx::TAG,
+    crate::FIX_5_0SP2::Text::TAG
+];
+
+// The changes made to an order by a cancel replace. Each version of an order carries the
+// amendments that led to it so the latest version has the audit trail for the whole chain.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Amendment {
+    pub old_key: String,
+    pub new_key: String,
+    pub changes: Vec<FieldChange>
+}
+
+impl Amendment {
+
+    pub fn new(old_key: &str, new_key: &str, before: &FieldCollection, after: &FieldCollection) -> Self
+    {
+        Amendment {
+            old_key: old_key.to_string(),
+            new_key: new_key.to_string(),
+            changes: field_changes(before, after)
+                .into_iter()
+                .filter(|change| !TRANSIENT_FIELDS.contains(&change.tag))
+                .collect()
+        }
+    }
+
+    pub fn change(&self, tag: u32) -> Option<&FieldChange>
+    {
+        self.changes.iter().find(|change| change.tag == tag)
+    }
+
+}
+
+impl OrderBook {
+
+    // The version of the order this one replaced.
+    pub fn predecessor(&self, key: &str) -> Option<&Order>
+    {
+        self.orders.get(key)?.predecessor.as_ref().and_then(|key| self.orders.get(key))
+    }
+
+    // The version of the order that replaced this one.
+    pub fn successor(&self, key: &str) -> Option<&Order>
+    {
+        self.orders.get(key)?.successor.as_ref().and_then(|key| self.orders.get(key))
+    }
+
+    // The original order in the chain this order belongs to.
+    pub fn root(&self, key: &str) -> Option<&Order>
+    {
+        let mut order = self.orders.get(key)?;
+        // Guard against a malformed chain that loops back on itself.
+        for _ in 0..self.orders.len() {
+            match self.predecessor(&order.key) {
+                Some(predecessor) => order = predecessor,
+                None => break
+            }
+        }
+        Some(order)
+    }
+
+    // The current version of the order in the chain this order belongs to.
+    pub fn latest(&self, key: &str) -> Option<&Order>
+    {
+        self.chain(key).last().copied()
+    }
+
+    // Every version of the order from the original to the current, empty if the key is unknown.
+    pub fn chain(&self, key: &str) -> Vec<&Order>
+    {
+        let mut chain = Vec::new();
+        let mut next = self.root(key);
+        while let Some(order) = next {
+            if chain.len() == self.orders.len() {
+                break;
+            }
+            chain.push(order);
+            next = self.successor(&order.key);
+        }
+        chain
+    }
+
+    // The chains that have been amended at least once, in the order they were created.
+    pub fn chains(&self) -> Vec<Vec<&Order>>
+    {
+        self.orders
+            .values()
+            .filter(|order| order.predecessor.is_none() && order.successor.is_some())
+            .map(|order| self.chain(&order.key))
+            .collect()
+    }
+
+    // What changed at each amendment of the chain this order belongs to, oldest first.
+    pub fn audit_trail(&self, key: &str) -> Vec<&Amendment>
+    {
+        self.latest(key).map(|order| order.amendments.iter().collect()).unwrap_or_default()
+    }
+
+    // This version of the order and the versions it replaced, oldest first.
+    pub fn versions(&self, key: &str) -> Vec<&Order>
+    {
+        let mut versions = Vec::new();
+        let mut next = self.orders.get(key);
+        while let Some(order) = next {
+            if versions.len() == self.orders.len() {
+                break;
+            }
+            versions.push(order);
+            next = self.predecessor(&order.key);
+        }
+        versions.reverse();
+        versions
+    }
+
+    // Each fill belongs to the version of the order it was reported against but CumQty carries
+    // over a cancel replace, so these are the executions that make up the CumQty of this version.
+    pub fn chain_executions(&self, key: &str) -> Vec<&Execution>
+    {
+        self.versions(key).into_iter().flat_map(|order| order.executions.iter()).collect()
+    }
+
+    pub fn chain_filled_quantity(&self, key: &str) -> f64
+    {
+        self.versions(key).iter().map(|order| order.filled_quantity()).sum()
+    }
+
+    pub fn chain_notional(&self, key: &str) -> f64
+    {
+        self.versions(key).iter().map(|order| order.notional()).sum()
+    }
+
+    pub fn chain_vwap(&self, key: &str) -> Option<f64>
+    {
+        let filled_quantity = self.chain_filled_quantity(key);
+        if filled_quantity > 0.0 {
+            Some(self.chain_notional(key) / filled_quantity)
+        }
+        else {
+            None
+        }
+    }
+
+    // Compare the CumQty and AvgPx of this version of the order with the fills of the chain.
+    pub fn check_fills(&self, key: &str) -> Vec<String>
+    {
+        self.orders
+            .get(key)
+            .map(|order| order.check_fills(self.chain_filled_quantity(key), self.chain_notional(key)))
+            .unwrap_or_default()
+    }
+
+    // A bust or correction of a fill reported before a cancel replace references an execution of
+    // an earlier version of the order, that execution is superseded where it was reported.
+    pub(crate) fn supersede_predecessor_execution(&mut self, key: &str, execution_report: &Message) -> Result<(), Error>
+    {
+        let Some(adjustment) = Execution::from_execution_report(execution_report)? else {
+            return Ok(());
+        };
+
+        let Some(exec_ref_id) = adjustment.exec_ref_id.as_deref() else {
+            return Ok(());
+        };
+
+        if !adjustment.is_trade_cancel() && !adjustment.is_trade_correct() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = self.versions(key).iter().rev().map(|order| order.key.clone()).collect();
+        for version in keys {
+            let Some(order) = self.orders.get_mut(&version) else {
+                continue;
+            };
+            if order.executions.iter().any(|execution| execution.is_adjusted_by(exec_ref_id)) {
+                // The order being updated adjusts its own executions.
+                if version != key {
+                    order.supersede_execution(exec_ref_id, &adjustment.exec_id);
+                }
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+    const REPORT_NEW: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}39=0\u{0001}11=61\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}151=10000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+    const REPLACE_QUANTITY: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=62\u{0001}41=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+    const PENDING_QUANTITY: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=E\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=2\u{0001}150=E\u{0001}151=10000\u{0001}38=10000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+    const REPLACED_QUANTITY: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=3\u{0001}150=5\u{0001}151=15000\u{0001}38=15000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+    const REPLACE_PRICE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=63\u{0001}41=62\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=15000\u{0001}40=2\u{0001}44=22\u{0001}10=000\u{0001}";
+    const REPLACED_PRICE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=5\u{0001}11=63\u{0001}41=62\u{0001}37=1\u{0001}17=4\u{0001}150=5\u{0001}151=15000\u{0001}38=15000\u{0001}44=22\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+
+    fn process(texts: &[&str]) -> Result<OrderBook, crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        for text in texts {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    fn assert_chain(book: &OrderBook) -> Result<(), crate::error::Error>
+    {
+        let keys = |orders: Vec<&Order>| orders.iter().map(|order| order.key.clone()).collect::<Vec<String>>();
+        let expected = vec!["INITIATOR-ACCEPTOR-61", "INITIATOR-ACCEPTOR-62", "INITIATOR-ACCEPTOR-63"];
+
+        for key in &expected {
+            assert_eq!(keys(book.chain(key)), expected);
+            assert_eq!(book.root(key).unwrap().key, "INITIATOR-ACCEPTOR-61");
+            assert_eq!(book.latest(key).unwrap().key, "INITIATOR-ACCEPTOR-63");
+        }
+        assert_eq!(book.chains().len(), 1);
+
+        assert_eq!(book.predecessor("INITIATOR-ACCEPTOR-62").unwrap().key, "INITIATOR-ACCEPTOR-61");
+        assert_eq!(book.successor("INITIATOR-ACCEPTOR-62").unwrap().key, "INITIATOR-ACCEPTOR-63");
+        assert!(book.predecessor("INITIATOR-ACCEPTOR-61").is_none());
+        assert!(book.successor("INITIATOR-ACCEPTOR-63").is_none());
+
+        for key in &expected[..2] {
+            assert_eq!(book.orders[*key].fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::Replaced());
+        }
+        assert_eq!(book.orders["INITIATOR-ACCEPTOR-63"].fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::New());
+
+        let trail = book.audit_trail("INITIATOR-ACCEPTOR-61");
+        assert_eq!(trail.len(), 2);
+        assert_eq!(trail[0].old_key, "INITIATOR-ACCEPTOR-61");
+        assert_eq!(trail[0].new_key, "INITIATOR-ACCEPTOR-62");
+        assert_eq!(trail[0].change(crate::FIX_5_0SP2::OrderQty::TAG).unwrap().to_string(), "OrderQty 10000 -> 15000");
+        assert_eq!(trail[0].change(crate::FIX_5_0SP2::Price::TAG), None);
+        assert_eq!(trail[1].change(crate::FIX_5_0SP2::Price::TAG).unwrap().to_string(), "Price 21 -> 22");
+        assert_eq!(trail[1].change(crate::FIX_5_0SP2::OrdStatus::TAG), None);
+        Ok(())
+    }
+
+    #[test]
+    pub fn chain_navigation_and_audit_trail() -> Result<(), crate::error::Error>
+    {
+        let book = process(&[ORDER_SINGLE, REPORT_NEW, REPLACE_QUANTITY, PENDING_QUANTITY, REPLACED_QUANTITY, REPLACE_PRICE, REPLACED_PRICE])?;
+        assert_chain(&book)
+    }
+
+    #[test]
+    pub fn late_pending_report_does_not_undo_the_replace() -> Result<(), crate::error::Error>
+    {
+        let book = process(&[ORDER_SINGLE, REPORT_NEW, REPLACED_QUANTITY, REPLACE_QUANTITY, PENDING_QUANTITY, REPLACE_PRICE, REPLACED_PRICE])?;
+        assert_chain(&book)
+    }
+
+    #[test]
+    pub fn replaced_report_for_a_later_version_waits_for_its_predecessor() -> Result<(), crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        for text in [ORDER_SINGLE, REPORT_NEW, REPLACED_PRICE] {
+            book.process(&decode_message(text)?)?;
+        }
+        assert_eq!(book.orders.len(), 1);
+
+        book.process(&decode_message(REPLACE_QUANTITY)?)?;
+        let events = book.process(&decode_message(REPLACED_QUANTITY)?)?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].key, "INITIATOR-ACCEPTOR-63");
+
+        assert_chain(&book)
+    }
+
+    #[test]
+    pub fn fills_belong_to_the_version_they_were_reported_against() -> Result<(), crate::error::Error>
+    {
+        const FILL_ORIGINAL: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=10\u{0001}150=F\u{0001}151=6000\u{0001}32=4000\u{0001}31=20\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}";
+        const LATE_FILL_ORIGINAL: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=11\u{0001}150=F\u{0001}151=10000\u{0001}32=1000\u{0001}31=22\u{0001}14=5000\u{0001}6=20.4\u{0001}10=000\u{0001}";
+        const FILL_REPLACEMENT: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}39=1\u{0001}11=62\u{0001}37=1\u{0001}17=12\u{0001}150=F\u{0001}151=9000\u{0001}32=1000\u{0001}31=21\u{0001}14=6000\u{0001}6=20.5\u{0001}10=000\u{0001}";
+        const BUST_ORIGINAL_FILL: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}39=1\u{0001}11=62\u{0001}37=1\u{0001}17=13\u{0001}19=10\u{0001}150=H\u{0001}151=13000\u{0001}14=2000\u{0001}6=21.5\u{0001}10=000\u{0001}";
+
+        let replaced = REPLACED_QUANTITY.replace("14=0", "14=4000").replace("6=0", "6=20");
+        let mut book = process(&[ORDER_SINGLE, REPORT_NEW, FILL_ORIGINAL, REPLACE_QUANTITY, &replaced, LATE_FILL_ORIGINAL, FILL_REPLACEMENT])?;
+
+        let exec_ids = |key: &str| book.orders[key].executions.iter().map(|execution| execution.exec_id.clone()).collect::<Vec<String>>();
+        assert_eq!(exec_ids("INITIATOR-ACCEPTOR-61"), vec!["10", "11"]);
+        assert_eq!(exec_ids("INITIATOR-ACCEPTOR-62"), vec!["12"]);
+
+        assert_eq!(book.orders["INITIATOR-ACCEPTOR-62"].filled_quantity(), 1000.0);
+        assert_eq!(book.chain_executions("INITIATOR-ACCEPTOR-62").len(), 3);
+        assert_eq!(book.chain_filled_quantity("INITIATOR-ACCEPTOR-62"), 6000.0);
+        assert_eq!(book.chain_vwap("INITIATOR-ACCEPTOR-62"), Some(20.5));
+        assert!(book.check_fills("INITIATOR-ACCEPTOR-62").is_empty());
+
+        let aggregate = &book.aggregate(&Default::default(), &[crate::FIX_5_0SP2::Symbol::TAG])[&vec!["BHP.AX".to_string()]];
+        assert_eq!(aggregate.orders, 2);
+        assert_eq!(aggregate.filled_quantity, 6000.0);
+        assert_eq!(aggregate.vwap(), Some(20.5));
+
+        book.process(&decode_message(BUST_ORIGINAL_FILL)?)?;
+        assert_eq!(book.orders["INITIATOR-ACCEPTOR-61"].executions[0].superseded_by.as_deref(), Some("13"));
+        assert_eq!(book.chain_filled_quantity("INITIATOR-ACCEPTOR-62"), 2000.0);
+        assert!(book.check_fills("INITIATOR-ACCEPTOR-62").is_empty());
+        Ok(())
+    }
+
+}
@@ -0,0 +1,98 @@
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use std::io::Write;
+
+// Prints each cancel replace chain as a tree from the original order to the current version,
+// with the OrdStatus of each version and the fields changed by the amendment that created it.
+#[derive(Default)]
+pub struct OrderChainReport {
+}
+
+impl OrderChainReport
+{
+    fn ord_status(order: &Order) -> String
+    {
+        let Some(ord_status) = order.ord_status() else {
+            return String::new();
+        };
+        crate::FIX_5_0SP2::fields()
+            .name_of_value(crate::FIX_5_0SP2::OrdStatus::TAG as usize, ord_status)
+            .unwrap_or(ord_status)
+            .to_string()
+    }
+
+    fn print_chain<W: Write>(writer: &mut W, chain: &[&Order]) -> std::io::Result<()>
+    {
+        let Some(latest) = chain.last() else {
+            return Ok(());
+        };
+
+        for (depth, order) in chain.iter().enumerate() {
+            let mut line = if depth == 0 {
+                format!("{} {}", order.key, OrderChainReport::ord_status(order))
+            }
+            else {
+                format!("{}`-- {} {}", "    ".repeat(depth - 1), order.key, OrderChainReport::ord_status(order))
+            };
+            if let Some(amendment) = latest.amendments.iter().find(|amendment| amendment.new_key == order.key) {
+                let changes: Vec<String> = amendment.changes.iter().map(|change| change.to_string()).collect();
+                line.push_str(&format!(" ({})", changes.join(", ")));
+            }
+            writer.write_all(format!("{}\n", line).as_bytes())?;
+        }
+
+        writer.write_all(b"\n")
+    }
+
+    pub fn print<W: Write>(&self, writer: &mut W, order_book: &OrderBook) -> std::io::Result<()>
+    {
+        for chain in order_book.chains() {
+            OrderChainReport::print_chain(writer, &chain)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn chain_is_printed_as_a_tree() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=71\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=100\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=5\u{0001}11=62\u{0001}41=61\u{0001}37=1\u{0001}17=3\u{0001}150=5\u{0001}38=15000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=5\u{0001}11=63\u{0001}41=62\u{0001}37=1\u{0001}17=4\u{0001}150=5\u{0001}44=22\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderChainReport::default().print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines, vec![
+            "INITIATOR-ACCEPTOR-61 Replaced",
+            "`-- INITIATOR-ACCEPTOR-62 Replaced (ClOrdID 61 -> 62, OrderQty 10000 -> 15000, OrderID  -> 1, OrigClOrdID  -> 61)",
+            "    `-- INITIATOR-ACCEPTOR-63 New (ClOrdID 62 -> 63, Price 21 -> 22, OrigClOrdID 61 -> 62)",
+            ""
+        ]);
+        Ok(())
+    }
+
+}
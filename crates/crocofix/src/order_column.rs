@@ -0,0 +1,156 @@
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::field::format_decimal;
+use crate::dictionary::OrchestrationField;
+use crate::report_writer::ValueFormatter;
+use std::cmp::Ordering;
+
+// Where the values in a column of the order report come from, a field of the order or a value
+// derived from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnSource {
+    Field(u32),
+    // OrderQty - CumQty
+    LeavesQty,
+    // The LastQty * LastPx of the fills that haven't been busted or corrected, including those of
+    // the versions the order replaced.
+    Notional
+}
+
+// A column of the order report with an optional label to use in place of the field name and an
+// optional formatter for the values. Fields that aren't in the dictionary are labelled with the
+// tag if they don't have a label.
+pub struct OrderColumn {
+    pub source: ColumnSource,
+    pub label: Option<String>,
+    pub formatter: Option<Box<dyn ValueFormatter>>
+}
+
+impl OrderColumn {
+
+    pub fn new(source: ColumnSource) -> Self
+    {
+        Self {
+            source,
+            label: None,
+            formatter: None
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self
+    {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Box<dyn ValueFormatter>) -> Self
+    {
+        self.formatter = Some(formatter);
+        self
+    }
+
+    pub fn definition(&self) -> Option<&'static dyn OrchestrationField>
+    {
+        match self.source {
+            ColumnSource::Field(tag) => crate::FIX_5_0SP2::fields().try_get(tag as usize),
+            _ => None
+        }
+    }
+
+    pub fn header(&self) -> String
+    {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+
+        match self.source {
+            ColumnSource::Field(tag) => self.definition().map(|definition| definition.name().to_string()).unwrap_or_else(|| tag.to_string()),
+            ColumnSource::LeavesQty => "LeavesQty".to_string(),
+            ColumnSource::Notional => "Notional".to_string()
+        }
+    }
+
+    // Fields that aren't in the dictionary are assumed to be numeric if they have a formatter.
+    pub fn is_numeric(&self) -> bool
+    {
+        match self.source {
+            ColumnSource::Field(_) => self.definition().map(|definition| definition.is_numeric()).unwrap_or(self.formatter.is_some()),
+            _ => true
+        }
+    }
+
+    // The tags the values are taken or derived from, a change to any of them changes the value.
+    pub fn tags(&self) -> Vec<u32>
+    {
+        match self.source {
+            ColumnSource::Field(tag) => vec![tag],
+            ColumnSource::LeavesQty => vec![crate::FIX_5_0SP2::OrderQty::TAG, crate::FIX_5_0SP2::CumQty::TAG],
+            ColumnSource::Notional => vec![crate::FIX_5_0SP2::CumQty::TAG, crate::FIX_5_0SP2::AvgPx::TAG]
+        }
+    }
+
+    // Are the values summed in subtotal and total rows.
+    pub fn is_totalled(&self) -> bool
+    {
+        matches!(
+            self.source,
+            ColumnSource::Field(crate::FIX_5_0SP2::OrderQty::TAG)
+            | ColumnSource::Field(crate::FIX_5_0SP2::CumQty::TAG)
+            | ColumnSource::LeavesQty
+            | ColumnSource::Notional
+        )
+    }
+
+    fn decimal_field(order: &Order, tag: u32) -> f64
+    {
+        order.fields.try_get(tag).and_then(|field| field.decimal_value().ok()).unwrap_or_default()
+    }
+
+    // The unformatted value, None if the order doesn't have the field.
+    pub fn value(&self, order_book: &OrderBook, order: &Order) -> Option<String>
+    {
+        match self.source {
+            ColumnSource::Field(tag) => order.fields.try_get(tag).map(|field| field.value.clone()),
+            ColumnSource::LeavesQty => {
+                let leaves_qty = OrderColumn::decimal_field(order, crate::FIX_5_0SP2::OrderQty::TAG) - OrderColumn::decimal_field(order, crate::FIX_5_0SP2::CumQty::TAG);
+                Some(format_decimal(leaves_qty))
+            },
+            ColumnSource::Notional => Some(format_decimal(order_book.chain_notional(&order.key)))
+        }
+    }
+
+    pub fn decimal_value(&self, order_book: &OrderBook, order: &Order) -> Option<f64>
+    {
+        self.value(order_book, order).and_then(|value| value.parse::<f64>().ok())
+    }
+
+    // Compare the values of two orders, numerically if the column is numeric, orders without a
+    // value sort after those with one.
+    pub fn compare(&self, order_book: &OrderBook, left: &Order, right: &Order) -> Ordering
+    {
+        match (self.value(order_book, left), self.value(order_book, right)) {
+            (Some(left), Some(right)) => match (self.is_numeric(), left.parse::<f64>(), right.parse::<f64>()) {
+                (true, Ok(left), Ok(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+                _ => left.cmp(&right)
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal
+        }
+    }
+
+    // Format the value with the formatter if there is one, otherwise use the name of enumerated
+    // values e.g. Buy for Side=1.
+    pub fn display(&self, value: &str) -> String
+    {
+        if let Some(formatter) = &self.formatter {
+            return formatter.format(value);
+        }
+
+        match self.definition().and_then(|definition| definition.name_of_value(value)) {
+            Some(name) => name.to_string(),
+            None => value.to_string()
+        }
+    }
+
+}
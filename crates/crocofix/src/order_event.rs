@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::fmt;
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderEventKind {
+    OrderCreated,
+    Acknowledged,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Replaced { old_key: String, new_key: String },
+    Rejected,
+    PendingChange,
+    CancelRejected,
+    // Any other change to the order e.g. a restatement or a trade bust.
+    Updated
+}
+
+impl OrderEventKind {
+
+    fn for_ord_status(ord_status: Option<&str>) -> Self
+    {
+        let Some(ord_status) = ord_status else {
+            return OrderEventKind::Updated;
+        };
+
+        if ord_status == crate::FIX_5_0SP2::OrdStatus::New().value {
+            OrderEventKind::Acknowledged
+        }
+        else if ord_status == crate::FIX_5_0SP2::OrdStatus::PartiallyFilled().value {
+            OrderEventKind::PartiallyFilled
+        }
+        else if ord_status == crate::FIX_5_0SP2::OrdStatus::Filled().value {
+            OrderEventKind::Filled
+        }
+        else if ord_status == crate::FIX_5_0SP2::OrdStatus::Canceled().value {
+            OrderEventKind::Canceled
+        }
+        else if ord_status == crate::FIX_5_0SP2::OrdStatus::Rejected().value {
+            OrderEventKind::Rejected
+        }
+        else if ord_status == crate::FIX_5_0SP2::OrdStatus::PendingCancel().value ||
+                ord_status == crate::FIX_5_0SP2::OrdStatus::PendingReplace().value {
+            OrderEventKind::PendingChange
+        }
+        else {
+            OrderEventKind::Updated
+        }
+    }
+
+    // Classify a change made by a message that doesn't have an ExecType, such as a ListStatus, by
+    // the resulting OrdStatus.
+    pub fn for_ord_status_change(before: &FieldCollection, after: &FieldCollection) -> Self
+    {
+        let ord_status = |fields: &FieldCollection| fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).map(|field| field.value.clone());
+        if ord_status(before) == ord_status(after) {
+            return OrderEventKind::Updated;
+        }
+        OrderEventKind::for_ord_status(ord_status(after).as_deref())
+    }
+
+    // Classify an ExecutionReport, other than a Replaced, that has been applied to an order. We
+    // prefer the ExecType and fall back to the OrdStatus for versions of FIX that don't have it.
+    pub fn for_execution_report(execution_report: &Message, before: &FieldCollection, after: &FieldCollection) -> Self
+    {
+        let ord_status = |fields: &FieldCollection| fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).map(|field| field.value.clone());
+
+        let Some(exec_type) = execution_report.fields.try_get(crate::FIX_5_0SP2::ExecType::TAG) else {
+            return OrderEventKind::for_ord_status_change(before, after);
+        };
+
+        let exec_type = exec_type.value.as_str();
+
+        // FIX 4.2 and earlier report fills with ExecType PartialFill (1) and Fill (2).
+        if exec_type == crate::FIX_5_0SP2::ExecType::Trade().value || exec_type == "1" || exec_type == "2" {
+            if ord_status(after).as_deref() == Some(crate::FIX_5_0SP2::OrdStatus::Filled().value) {
+                return OrderEventKind::Filled;
+            }
+            return OrderEventKind::PartiallyFilled;
+        }
+
+        if exec_type == crate::FIX_5_0SP2::ExecType::New().value {
+            OrderEventKind::Acknowledged
+        }
+        else if exec_type == crate::FIX_5_0SP2::ExecType::Canceled().value {
+            OrderEventKind::Canceled
+        }
+        else if exec_type == crate::FIX_5_0SP2::ExecType::Rejected().value {
+            OrderEventKind::Rejected
+        }
+        else if exec_type == crate::FIX_5_0SP2::ExecType::PendingCancel().value ||
+                exec_type == crate::FIX_5_0SP2::ExecType::PendingReplace().value {
+            OrderEventKind::PendingChange
+        }
+        else {
+            OrderEventKind::Updated
+        }
+    }
+
+}
+
+// A field that was added, removed, or changed by a message.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    pub tag: u32,
+    pub before: Option<String>,
+    pub after: Option<String>
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = crate::FIX_5_0SP2::fields().name_of_field(self.tag as usize).map(|name| name.to_string()).unwrap_or(self.tag.to_string());
+        write!(f, "{} {} -> {}", name, self.before.as_deref().unwrap_or(""), self.after.as_deref().unwrap_or(""))
+    }
+}
+
+// Compare the first instance of each field, orders don't contain repeating groups.
+pub fn field_changes(before: &FieldCollection, after: &FieldCollection) -> Vec<FieldChange>
+{
+    let mut changes = Vec::new();
+    let mut seen = HashSet::new();
+
+    for field in after {
+        if !seen.insert(field.tag) {
+            continue;
+        }
+        let previous = before.try_get(field.tag);
+        if previous.map(|previous| previous.value.as_str()) != Some(field.value.as_str()) {
+            changes.push(FieldChange {
+                tag: field.tag,
+                before: previous.map(|previous| previous.value.clone()),
+                after: Some(field.value.clone())
+            });
+        }
+    }
+
+    for field in before {
+        if seen.insert(field.tag) {
+            changes.push(FieldChange { tag: field.tag, before: Some(field.value.clone()), after: None });
+        }
+    }
+
+    changes
+}
+
+// A change to an order reported by the OrderBook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderEvent {
+    pub key: String,
+    pub kind: OrderEventKind,
+    pub changes: Vec<FieldChange>
+}
+
+impl OrderEvent {
+
+    pub fn new(key: &str, kind: OrderEventKind, before: &FieldCollection, after: &FieldCollection) -> Self
+    {
+        OrderEvent {
+            key: key.to_string(),
+            kind,
+            changes: field_changes(before, after)
+        }
+    }
+
+    pub fn change(&self, tag: u32) -> Option<&FieldChange>
+    {
+        self.changes.iter().find(|change| change.tag == tag)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::field::Field;
+
+    #[test]
+    pub fn changed_added_and_removed_fields()
+    {
+        let mut before = FieldCollection::default();
+        before.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, "0"));
+        before.push(Field::from_str(crate::FIX_5_0SP2::Text::TAG, "hello"));
+        before.push(Field::from_str(crate::FIX_5_0SP2::Symbol::TAG, "BHP.AX"));
+
+        let mut after = FieldCollection::default();
+        after.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, "1"));
+        after.push(Field::from_str(crate::FIX_5_0SP2::Symbol::TAG, "BHP.AX"));
+        after.push(Field::from_str(crate::FIX_5_0SP2::CumQty::TAG, "100"));
+
+        let changes = field_changes(&before, &after);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].to_string(), "OrdStatus 0 -> 1");
+        assert_eq!(changes[1].to_string(), "CumQty  -> 100");
+        assert_eq!(changes[2].to_string(), "Text hello -> ");
+    }
+
+    #[test]
+    pub fn no_changes()
+    {
+        let mut fields = FieldCollection::default();
+        fields.push(Field::from_str(crate::FIX_5_0SP2::OrdStatus::TAG, "0"));
+        assert!(field_changes(&fields, &fields.clone()).is_empty());
+    }
+
+}
@@ -0,0 +1,338 @@
+use crate::field_collection::{FieldCollection, SetOperation};
+use crate::message::Message;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::order_event::{OrderEvent, OrderEventKind};
+use crate::order_query::OrderAggregate;
+use crate::error::Error;
+
+// The per order status fields in the NoOrders group of a ListStatus.
+const LIST_STATUS_ORDER_FIELDS: [u32; 7] = [
+    crate::FIX_5_0SP2::CumQty::TAG,
+    crate::FIX_5_0SP2::OrdStatus::TAG,
+    crate::FIX_5_0SP2::LeavesQty::TAG,
+    crate::FIX_5_0SP2::CxlQty::TAG,
+    crate::FIX_5_0SP2::AvgPx::TAG,
+    crate::FIX_5_0SP2::OrdRejReason::TAG,
+    crate::FIX_5_0SP2::Text::TAG
+];
+
+// A NewOrderList and the orders it created. Lists are identified by SenderCompID, TargetCompID,
+// and ListID regardless of the OrderKeyStrategy, the orders in the list are keyed as usual.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderList {
+    pub key: String,
+    pub list_id: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    // The NewOrderList, ListStatus, and ListCancelRequest messages for this list.
+    pub messages: Vec<Message>,
+    // The list level fields e.g. BidType, TotNoOrders, and the most recent ListStatusType and ListOrderStatus.
+    pub fields: FieldCollection,
+    // The keys of the orders in the list in ListSeqNo order, these are the original versions of
+    // any orders that have since been replaced.
+    pub order_keys: Vec<String>,
+    pub cancel_requested: bool
+}
+
+impl OrderList {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, list_id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, list_id)
+    }
+
+    pub fn key_for_message(message: &Message, reverse_comp_ids: bool) -> Result<String, Error>
+    {
+        let sender_comp_id = message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str();
+        let target_comp_id = message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str();
+        let list_id = message.fields.get(crate::FIX_5_0SP2::ListID::TAG)?.value.as_str();
+
+        if reverse_comp_ids {
+            Ok(OrderList::create_key(target_comp_id, sender_comp_id, list_id))
+        }
+        else {
+            Ok(OrderList::create_key(sender_comp_id, target_comp_id, list_id))
+        }
+    }
+
+    fn new(key: String, new_order_list: &Message) -> Result<Self, Error>
+    {
+        let mut list = OrderList {
+            key,
+            list_id: new_order_list.fields.get(crate::FIX_5_0SP2::ListID::TAG)?.value.clone(),
+            sender_comp_id: new_order_list.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone(),
+            target_comp_id: new_order_list.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.clone(),
+            ..Default::default()
+        };
+        list.update(new_order_list);
+        Ok(list)
+    }
+
+    // Record a message and apply the list level fields that precede the NoOrders group.
+    fn update(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+        for field in message.fields.iter().take_while(|field| field.tag != crate::FIX_5_0SP2::NoOrders::TAG) {
+            if !Order::is_identity_field(field.tag) {
+                self.fields.set(field, SetOperation::ReplaceFirstOrAppend);
+            }
+        }
+    }
+
+    pub fn reported_list_order_status(&self) -> Option<&str>
+    {
+        self.fields.try_get(crate::FIX_5_0SP2::ListOrderStatus::TAG).map(|field| field.value.as_str())
+    }
+
+}
+
+// Create the message for an order in the NoOrders group of a NewOrderList, this is the list
+// level fields followed by the fields of the order.
+fn list_order_message(new_order_list: &Message, order_fields: &FieldCollection) -> Message
+{
+    let mut message = Message::default();
+    for field in new_order_list.fields.iter().take_while(|field| field.tag != crate::FIX_5_0SP2::NoOrders::TAG) {
+        message.fields.push(field.clone());
+    }
+    for field in order_fields {
+        message.fields.push(field.clone());
+    }
+    message
+}
+
+impl OrderBook {
+
+    // The current version of each order in the list.
+    pub fn list_orders(&self, list_key: &str) -> Vec<&Order>
+    {
+        let Some(list) = self.lists.get(list_key) else {
+            return Vec::new();
+        };
+        list.order_keys.iter().filter_map(|key| self.latest(key)).collect()
+    }
+
+    pub fn list_for_order(&self, order_key: &str) -> Option<&OrderList>
+    {
+        let root = self.root(order_key)?;
+        self.lists.values().find(|list| list.order_keys.contains(&root.key))
+    }
+
+    pub fn list_aggregate(&self, list_key: &str) -> OrderAggregate
+    {
+        let mut aggregate = OrderAggregate::default();
+        // The list has the current version of each order so the fills of the versions it replaced are added.
+        for order in self.list_orders(list_key) {
+            aggregate.add(order, self.chain_filled_quantity(&order.key), self.chain_notional(&order.key));
+        }
+        aggregate
+    }
+
+    // The ListOrderStatus derived from the orders in the list. The list is AllDone when every
+    // order is closed, or Reject if every order was rejected, Cancelling after a
+    // ListCancelRequest, and Executing once any order has a fill. Otherwise it is the status most
+    // recently reported in a ListStatus, or ReceivedForExecution if there hasn't been one.
+    pub fn list_order_status(&self, list_key: &str) -> Option<&'static str>
+    {
+        let list = self.lists.get(list_key)?;
+        let orders = self.list_orders(list_key);
+
+        let rejected = crate::FIX_5_0SP2::OrdStatus::Rejected().value;
+
+        if !orders.is_empty() && orders.iter().all(|order| !order.is_open()) {
+            if orders.iter().all(|order| order.ord_status() == Some(rejected)) {
+                return Some(crate::FIX_5_0SP2::ListOrderStatus::Reject().value);
+            }
+            return Some(crate::FIX_5_0SP2::ListOrderStatus::AllDone().value);
+        }
+
+        if list.cancel_requested {
+            return Some(crate::FIX_5_0SP2::ListOrderStatus::Cancelling().value);
+        }
+
+        if orders.iter().any(|order| self.chain_filled_quantity(&order.key) > 0.0) {
+            return Some(crate::FIX_5_0SP2::ListOrderStatus::Executing().value);
+        }
+
+        let reported = list.reported_list_order_status();
+        crate::FIX_5_0SP2::fields()[crate::FIX_5_0SP2::ListOrderStatus::TAG as usize]
+            .values()
+            .iter()
+            .find(|value| Some(value.value) == reported)
+            .map(|value| value.value)
+            .or(Some(crate::FIX_5_0SP2::ListOrderStatus::ReceivedForExecution().value))
+    }
+
+    // Create the list and an order for each entry in its NoOrders group.
+    pub(crate) fn process_new_order_list(&mut self, new_order_list: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let key = OrderList::key_for_message(new_order_list, false)?;
+        if self.lists.contains_key(&key) {
+            return Err(Error::OrderBookAlreadyContainsOrderWithKey(key));
+        }
+
+        let mut list = OrderList::new(key.clone(), new_order_list)?;
+        let mut events = Vec::new();
+
+        for order_fields in new_order_list.fields.group(crate::FIX_5_0SP2::NoOrders::TAG, &[]) {
+            let order_events = self.process_order_single(&list_order_message(new_order_list, &order_fields))?;
+            if let Some(event) = order_events.first() {
+                list.order_keys.push(event.key.clone());
+            }
+            events.extend(order_events);
+        }
+
+        self.lists.insert(key, list);
+        Ok(events)
+    }
+
+    // Apply the list level status and the status reported for each order in the NoOrders group.
+    pub(crate) fn process_list_status(&mut self, list_status: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let key = OrderList::key_for_message(list_status, true)?;
+
+        let Some(list) = self.lists.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        list.update(list_status);
+
+        let done = [
+            crate::FIX_5_0SP2::ListOrderStatus::AllDone().value,
+            crate::FIX_5_0SP2::ListOrderStatus::Reject().value
+        ];
+        if list.reported_list_order_status().is_some_and(|status| done.contains(&status)) {
+            list.cancel_requested = false;
+        }
+
+        let order_keys: Vec<String> = self.list_orders(&key).iter().map(|order| order.key.clone()).collect();
+        let mut events = Vec::new();
+
+        for order_fields in list_status.fields.group(crate::FIX_5_0SP2::NoOrders::TAG, &[]) {
+            let Some(cl_ord_id) = order_fields.try_get(crate::FIX_5_0SP2::ClOrdID::TAG) else {
+                continue;
+            };
+            let order_key = order_keys
+                .iter()
+                .find(|key| self.orders.get(*key).is_some_and(|order| order.cl_ord_id.value == cl_ord_id.value));
+            let Some(order) = order_key.and_then(|key| self.orders.get_mut(key)) else {
+                continue;
+            };
+            let before = order.fields.clone();
+            order.record(list_status);
+            for field in &order_fields {
+                if LIST_STATUS_ORDER_FIELDS.contains(&field.tag) {
+                    order.fields.set(field, SetOperation::ReplaceFirstOrAppend);
+                }
+            }
+            let kind = OrderEventKind::for_ord_status_change(&before, &order.fields);
+            let event = OrderEvent::new(&order.key, kind, &before, &order.fields);
+            if !event.changes.is_empty() {
+                events.push(event);
+            }
+        }
+
+        Ok(events)
+    }
+
+    pub(crate) fn process_list_cancel_request(&mut self, list_cancel_request: &Message) -> Result<Vec<OrderEvent>, Error>
+    {
+        let key = OrderList::key_for_message(list_cancel_request, false)?;
+
+        let Some(list) = self.lists.get_mut(&key) else {
+            return Err(Error::OrderBookDoesNotContainOrderWithKey(key));
+        };
+
+        list.update(list_cancel_request);
+        list.cancel_requested = true;
+
+        Ok(vec![])
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const NEW_ORDER_LIST: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=E\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}66=L1\u{0001}394=3\u{0001}68=2\u{0001}73=2\u{0001}11=1\u{0001}67=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=100\u{0001}40=1\u{0001}11=2\u{0001}67=2\u{0001}55=RIO.AX\u{0001}54=2\u{0001}38=200\u{0001}40=1\u{0001}10=000\u{0001}";
+
+    fn process(texts: &[&str]) -> Result<OrderBook, Error>
+    {
+        let mut book = OrderBook::default();
+        for text in texts {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    #[test]
+    pub fn new_order_list_creates_an_order_for_each_entry() -> Result<(), Error>
+    {
+        let book = process(&[NEW_ORDER_LIST])?;
+        assert_eq!(book.lists.len(), 1);
+        assert_eq!(book.orders.len(), 2);
+
+        let list = &book.lists["INITIATOR-ACCEPTOR-L1"];
+        assert_eq!(list.order_keys, vec!["INITIATOR-ACCEPTOR-1", "INITIATOR-ACCEPTOR-2"]);
+        assert_eq!(list.fields.get(crate::FIX_5_0SP2::TotNoOrders::TAG)?.value, "2");
+
+        let order = &book.orders["INITIATOR-ACCEPTOR-2"];
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::ListID::TAG)?.value, "L1");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::Symbol::TAG)?.value, "RIO.AX");
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrderQty::TAG)?.value, "200");
+        assert_eq!(book.list_for_order("INITIATOR-ACCEPTOR-2").map(|list| list.list_id.as_str()), Some("L1"));
+        assert_eq!(book.list_order_status("INITIATOR-ACCEPTOR-L1"), Some(crate::FIX_5_0SP2::ListOrderStatus::ReceivedForExecution().value));
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_status_updates_the_orders() -> Result<(), Error>
+    {
+        let list_status = "8=FIX.4.4\u{0001}9=0\u{0001}35=N\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}66=L1\u{0001}429=2\u{0001}82=1\u{0001}83=1\u{0001}431=3\u{0001}68=2\u{0001}73=2\u{0001}11=1\u{0001}14=100\u{0001}39=2\u{0001}151=0\u{0001}84=0\u{0001}6=21\u{0001}11=2\u{0001}14=0\u{0001}39=0\u{0001}151=200\u{0001}84=0\u{0001}6=0\u{0001}10=000\u{0001}";
+        let mut book = process(&[NEW_ORDER_LIST])?;
+        let events = book.process(&decode_message(list_status)?)?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, OrderEventKind::Filled);
+
+        let order = &book.orders["INITIATOR-ACCEPTOR-1"];
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::OrdStatus::TAG)?, crate::FIX_5_0SP2::OrdStatus::Filled());
+        assert_eq!(order.fields.get(crate::FIX_5_0SP2::CumQty::TAG)?.value, "100");
+        assert_eq!(book.lists["INITIATOR-ACCEPTOR-L1"].reported_list_order_status(), Some(crate::FIX_5_0SP2::ListOrderStatus::Executing().value));
+        Ok(())
+    }
+
+    #[test]
+    pub fn list_order_status_is_derived_from_the_orders() -> Result<(), Error>
+    {
+        let fill = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}66=L1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=2\u{0001}32=100\u{0001}31=21\u{0001}151=0\u{0001}14=100\u{0001}6=21\u{0001}10=000\u{0001}";
+        let cancel_list = "8=FIX.4.4\u{0001}9=0\u{0001}35=K\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}66=L1\u{0001}60=20240115-10:00:00\u{0001}10=000\u{0001}";
+        let canceled = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=2\u{0001}66=L1\u{0001}37=2\u{0001}17=2\u{0001}150=4\u{0001}39=4\u{0001}151=0\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}";
+
+        let mut book = process(&[NEW_ORDER_LIST, fill])?;
+        assert_eq!(book.list_order_status("INITIATOR-ACCEPTOR-L1"), Some(crate::FIX_5_0SP2::ListOrderStatus::Executing().value));
+
+        book.process(&decode_message(cancel_list)?)?;
+        assert_eq!(book.list_order_status("INITIATOR-ACCEPTOR-L1"), Some(crate::FIX_5_0SP2::ListOrderStatus::Cancelling().value));
+
+        book.process(&decode_message(canceled)?)?;
+        assert_eq!(book.list_order_status("INITIATOR-ACCEPTOR-L1"), Some(crate::FIX_5_0SP2::ListOrderStatus::AllDone().value));
+
+        let aggregate = book.list_aggregate("INITIATOR-ACCEPTOR-L1");
+        assert_eq!(aggregate.orders, 2);
+        assert_eq!(aggregate.open_orders, 0);
+        assert_eq!(aggregate.filled_quantity, 100.0);
+        Ok(())
+    }
+
+}
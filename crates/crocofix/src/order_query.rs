@@ -0,0 +1,373 @@
+use indexmap::IndexMap;
+use std::str::FromStr;
+use crate::error::Error;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    // The order can still trade, see Order::is_open.
+    Open,
+    // The current value of a field e.g. Symbol, Side, OrdStatus, or Account.
+    Field { tag: u32, value: String },
+    // The SenderCompID and TargetCompID of the NewOrderSingle.
+    Counterparty { sender_comp_id: String, target_comp_id: String },
+    // UTCTimestamps compare correctly as text so the bounds are inclusive string comparisons.
+    TransactTimeFrom(String),
+    TransactTimeTo(String)
+}
+
+impl Condition {
+
+    pub fn matches(&self, order: &Order) -> bool
+    {
+        let transact_time = || order.fields.try_get(crate::FIX_5_0SP2::TransactTime::TAG).map(|field| field.value.as_str());
+
+        match self {
+            Condition::Open => order.is_open(),
+            Condition::Field { tag, value } => order.fields.try_get(*tag).is_some_and(|field| &field.value == value),
+            Condition::Counterparty { sender_comp_id, target_comp_id } => {
+                &order.sender_comp_id == sender_comp_id && &order.target_comp_id == target_comp_id
+            }
+            Condition::TransactTimeFrom(from) => transact_time().is_some_and(|time| time >= from.as_str()),
+            Condition::TransactTimeTo(to) => transact_time().is_some_and(|time| time <= to.as_str())
+        }
+    }
+
+}
+
+// Parse a condition from the command line, one of
+//  open
+//  TransactTime>=20200114-08:00:00
+//  TransactTime<=20200114-09:00:00
+//  Name=Value where Name is a field name or tag and Value is an enumerated value name or raw value e.g. OrdStatus=Filled or 39=2
+impl FromStr for Condition {
+
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err>
+    {
+        let text = text.trim();
+
+        if text.eq_ignore_ascii_case("open") {
+            return Ok(Condition::Open);
+        }
+
+        if let Some((name, value)) = text.split_once(">=") {
+            if resolve_tag(name)? == crate::FIX_5_0SP2::TransactTime::TAG {
+                return Ok(Condition::TransactTimeFrom(value.trim().to_string()));
+            }
+            return Err(Error::InvalidOrderQuery(text.to_string()));
+        }
+
+        if let Some((name, value)) = text.split_once("<=") {
+            if resolve_tag(name)? == crate::FIX_5_0SP2::TransactTime::TAG {
+                return Ok(Condition::TransactTimeTo(value.trim().to_string()));
+            }
+            return Err(Error::InvalidOrderQuery(text.to_string()));
+        }
+
+        let Some((name, value)) = text.split_once('=') else {
+            return Err(Error::InvalidOrderQuery(text.to_string()));
+        };
+
+        let tag = resolve_tag(name)?;
+        let value = value.trim();
+        let definition = &crate::FIX_5_0SP2::fields()[tag as usize];
+        let value = match definition.values().iter().find(|field_value| field_value.name == value) {
+            Some(field_value) => field_value.value.to_string(),
+            None => value.to_string()
+        };
+
+        Ok(Condition::Field { tag, value })
+    }
+
+}
+
+fn resolve_tag(name: &str) -> Result<u32, Error>
+{
+    let name = name.trim();
+
+    if let Ok(tag) = name.parse::<u32>() {
+        if crate::FIX_5_0SP2::fields().is_tag_valid(tag as usize) {
+            return Ok(tag);
+        }
+    }
+    else if let Some(field) = crate::FIX_5_0SP2::fields().field_with_name(name) {
+        return Ok(field.tag());
+    }
+
+    Err(Error::InvalidOrderQuery(format!("unknown field '{}'", name)))
+}
+
+// Orders must match every condition, an empty filter matches every order.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OrderFilter {
+    conditions: Vec<Condition>
+}
+
+impl OrderFilter {
+
+    pub fn with_condition(mut self, condition: Condition) -> Self
+    {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn open(self) -> Self
+    {
+        self.with_condition(Condition::Open)
+    }
+
+    pub fn field(self, tag: u32, value: &str) -> Self
+    {
+        self.with_condition(Condition::Field { tag, value: value.to_string() })
+    }
+
+    pub fn symbol(self, symbol: &str) -> Self
+    {
+        self.field(crate::FIX_5_0SP2::Symbol::TAG, symbol)
+    }
+
+    pub fn side(self, side: &str) -> Self
+    {
+        self.field(crate::FIX_5_0SP2::Side::TAG, side)
+    }
+
+    pub fn ord_status(self, ord_status: &str) -> Self
+    {
+        self.field(crate::FIX_5_0SP2::OrdStatus::TAG, ord_status)
+    }
+
+    pub fn account(self, account: &str) -> Self
+    {
+        self.field(crate::FIX_5_0SP2::Account::TAG, account)
+    }
+
+    pub fn counterparty(self, sender_comp_id: &str, target_comp_id: &str) -> Self
+    {
+        self.with_condition(Condition::Counterparty { sender_comp_id: sender_comp_id.to_string(), target_comp_id: target_comp_id.to_string() })
+    }
+
+    // Either bound can be omitted.
+    pub fn transact_time(self, from: Option<&str>, to: Option<&str>) -> Self
+    {
+        let filter = match from {
+            Some(from) => self.with_condition(Condition::TransactTimeFrom(from.to_string())),
+            None => self
+        };
+        match to {
+            Some(to) => filter.with_condition(Condition::TransactTimeTo(to.to_string())),
+            None => filter
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.conditions.is_empty()
+    }
+
+    pub fn matches(&self, order: &Order) -> bool
+    {
+        self.conditions.iter().all(|condition| condition.matches(order))
+    }
+
+}
+
+// Totals for a group of orders. Replaced orders are counted but their fills are added with the
+// current version of the order, which is the one that carries the CumQty. A replaced order can
+// still receive fills so it is recognised by having a successor rather than by its OrdStatus.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OrderAggregate {
+    pub orders: usize,
+    pub open_orders: usize,
+    pub open_quantity: f64,
+    pub filled_quantity: f64,
+    pub filled_notional: f64
+}
+
+impl OrderAggregate {
+
+    pub(crate) fn add(&mut self, order: &Order, filled_quantity: f64, filled_notional: f64)
+    {
+        self.orders += 1;
+
+        if order.is_open() {
+            self.open_orders += 1;
+            self.open_quantity += order.open_quantity();
+        }
+
+        self.filled_quantity += filled_quantity;
+        self.filled_notional += filled_notional;
+    }
+
+    pub fn vwap(&self) -> Option<f64>
+    {
+        if self.filled_quantity > 0.0 {
+            Some(self.filled_notional / self.filled_quantity)
+        }
+        else {
+            None
+        }
+    }
+
+}
+
+impl OrderBook {
+
+    pub fn query<'a>(&'a self, filter: &'a OrderFilter) -> impl Iterator<Item = &'a Order> + 'a
+    {
+        self.orders.values().filter(move |order| filter.matches(order))
+    }
+
+    // Group the matching orders by the values of the specified fields, orders without a field are
+    // grouped under an empty value. Groups are in the order they were first seen.
+    pub fn group_by<'a>(&'a self, filter: &OrderFilter, tags: &[u32]) -> IndexMap<Vec<String>, Vec<&'a Order>>
+    {
+        let mut groups: IndexMap<Vec<String>, Vec<&Order>> = IndexMap::new();
+        for order in self.orders.values().filter(|order| filter.matches(order)) {
+            let key = tags
+                .iter()
+                .map(|tag| order.fields.try_get(*tag).map(|field| field.value.clone()).unwrap_or_default())
+                .collect();
+            groups.entry(key).or_default().push(order);
+        }
+        groups
+    }
+
+    // e.g. open quantity and filled notional per symbol and side with
+    //  book.aggregate(&OrderFilter::default(), &[Symbol::TAG, Side::TAG])
+    pub fn aggregate(&self, filter: &OrderFilter, tags: &[u32]) -> IndexMap<Vec<String>, OrderAggregate>
+    {
+        self.group_by(filter, tags)
+            .into_iter()
+            .map(|(key, orders)| {
+                let mut aggregate = OrderAggregate::default();
+                for order in orders {
+                    if order.successor.is_some() {
+                        aggregate.add(order, 0.0, 0.0);
+                    }
+                    else {
+                        aggregate.add(order, self.chain_filled_quantity(&order.key), self.chain_notional(&order.key));
+                    }
+                }
+                (key, aggregate)
+            })
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    // BHP buy partially filled, BHP sell filled, RIO buy acknowledged and sent via another session.
+    fn order_book() -> Result<OrderBook, crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}1=A1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}60=20200114-08:13:20.000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}39=1\u{0001}11=1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}151=6000\u{0001}38=10000\u{0001}32=4000\u{0001}31=20\u{0001}14=4000\u{0001}6=20\u{0001}60=20200114-08:13:21.000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}1=A2\u{0001}55=BHP.AX\u{0001}54=2\u{0001}38=500\u{0001}40=2\u{0001}44=21\u{0001}60=20200114-09:00:00.000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=2\u{0001}11=2\u{0001}37=2\u{0001}17=2\u{0001}150=F\u{0001}151=0\u{0001}38=500\u{0001}32=500\u{0001}31=21\u{0001}14=500\u{0001}6=21\u{0001}60=20200114-09:00:01.000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=OTHER\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=3\u{0001}1=A1\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=200\u{0001}40=2\u{0001}44=100\u{0001}60=20200114-10:00:00.000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=OTHER\u{0001}34=2\u{0001}39=0\u{0001}11=3\u{0001}37=3\u{0001}17=3\u{0001}150=0\u{0001}151=200\u{0001}38=200\u{0001}14=0\u{0001}6=0\u{0001}60=20200114-10:00:01.000\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    fn cl_ord_ids(book: &OrderBook, filter: &OrderFilter) -> Vec<String>
+    {
+        book.query(filter).map(|order| order.cl_ord_id.value.clone()).collect()
+    }
+
+    #[test]
+    pub fn filters() -> Result<(), crate::error::Error>
+    {
+        let book = order_book()?;
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default()), vec!["1", "2", "3"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().open()), vec!["1", "3"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().symbol("BHP.AX").side("2")), vec!["2"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().ord_status("2")), vec!["2"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().account("A1")), vec!["1", "3"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().counterparty("OTHER", "ACCEPTOR")), vec!["3"]);
+        assert_eq!(cl_ord_ids(&book, &OrderFilter::default().transact_time(Some("20200114-09:00:00"), Some("20200114-09:30:00"))), vec!["2"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn parse_conditions() -> Result<(), crate::error::Error>
+    {
+        assert_eq!("open".parse::<Condition>()?, Condition::Open);
+        assert_eq!("OrdStatus=Filled".parse::<Condition>()?, Condition::Field { tag: 39, value: "2".to_string() });
+        assert_eq!("55=BHP.AX".parse::<Condition>()?, Condition::Field { tag: 55, value: "BHP.AX".to_string() });
+        assert_eq!("TransactTime>=20200114-09:00:00".parse::<Condition>()?, Condition::TransactTimeFrom("20200114-09:00:00".to_string()));
+        assert_eq!("TransactTime<=20200114-09:00:00".parse::<Condition>()?, Condition::TransactTimeTo("20200114-09:00:00".to_string()));
+        assert_eq!("Rubbish=1".parse::<Condition>(), Err(Error::InvalidOrderQuery("unknown field 'Rubbish'".to_string())));
+        assert_eq!("Symbol>=A".parse::<Condition>(), Err(Error::InvalidOrderQuery("Symbol>=A".to_string())));
+        assert_eq!("Symbol".parse::<Condition>(), Err(Error::InvalidOrderQuery("Symbol".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    pub fn aggregate_by_symbol_and_side() -> Result<(), crate::error::Error>
+    {
+        let book = order_book()?;
+        let aggregates = book.aggregate(&OrderFilter::default(), &[crate::FIX_5_0SP2::Symbol::TAG, crate::FIX_5_0SP2::Side::TAG]);
+        assert_eq!(aggregates.len(), 3);
+
+        let bhp_buy = &aggregates[&vec!["BHP.AX".to_string(), "1".to_string()]];
+        assert_eq!(bhp_buy.orders, 1);
+        assert_eq!(bhp_buy.open_quantity, 6000.0);
+        assert_eq!(bhp_buy.filled_notional, 80000.0);
+
+        let bhp_sell = &aggregates[&vec!["BHP.AX".to_string(), "2".to_string()]];
+        assert_eq!(bhp_sell.open_orders, 0);
+        assert_eq!(bhp_sell.open_quantity, 0.0);
+        assert_eq!(bhp_sell.filled_notional, 10500.0);
+        assert_eq!(bhp_sell.vwap(), Some(21.0));
+
+        let rio_buy = &aggregates[&vec!["RIO.AX".to_string(), "1".to_string()]];
+        assert_eq!(rio_buy.open_quantity, 200.0);
+        assert_eq!(rio_buy.vwap(), None);
+        Ok(())
+    }
+
+    #[test]
+    pub fn aggregate_includes_the_fills_of_replaced_versions() -> Result<(), crate::error::Error>
+    {
+        let mut book = order_book()?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=4\u{0001}41=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=12000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}")?)?;
+        book.process(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=1\u{0001}11=4\u{0001}41=1\u{0001}37=1\u{0001}17=4\u{0001}150=5\u{0001}151=8000\u{0001}38=12000\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}")?)?;
+
+        let bhp_buy = vec!["BHP.AX".to_string(), "1".to_string()];
+        let tags = [crate::FIX_5_0SP2::Symbol::TAG, crate::FIX_5_0SP2::Side::TAG];
+
+        let all = &book.aggregate(&OrderFilter::default(), &tags)[&bhp_buy];
+        assert_eq!(all.orders, 2);
+        assert_eq!(all.filled_quantity, 4000.0);
+        assert_eq!(all.filled_notional, 80000.0);
+
+        let open = &book.aggregate(&OrderFilter::default().open(), &tags)[&bhp_buy];
+        assert_eq!(open.orders, 1);
+        assert_eq!(open.open_quantity, 8000.0);
+        assert_eq!(open.filled_quantity, 4000.0);
+        assert_eq!(open.vwap(), Some(20.0));
+        Ok(())
+    }
+
+}
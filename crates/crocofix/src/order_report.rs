@@ -1,10 +1,14 @@
 use crate::order_book::OrderBook;
 use crate::order::Order;
-use crate::field::Field;
-use crate::dictionary::OrchestrationField;
+use crate::order_query::OrderFilter;
+use crate::mass_action::MassActionKind;
+use crate::order_event::{OrderEvent, OrderEventKind};
+use crate::execution::Execution;
+use crate::field::{format_decimal, Field};
+use crate::order_column::{ColumnSource, OrderColumn};
+use crate::report_writer::{ReportCell, ReportTable, ReportWriter, TableReportWriter};
 use std::io::Write;
-use comfy_table::*;
-use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+use std::cmp::Ordering;
 
 
 pub const DEFAULT_FIELDS: &[u32] = &[
@@ -23,22 +27,266 @@ pub const DEFAULT_FIELDS: &[u32] = &[
     crate::FIX_5_0SP2::AvgPx::TAG                                
 ];
 
-#[derive(Default)]
+const FILL_HEADERS: [&str; 9] = ["ClOrdID", "ExecID", "ExecType", "LastQty", "LastPx", "Notional", "LastMkt", "TransactTime", "Notes"];
+const NUMERIC_FILL_COLUMNS: [usize; 3] = [3, 4, 5];
+
+const LEG_HEADERS: [&str; 9] = ["ClOrdID", "LegRefID", "LegSymbol", "LegSide", "LegRatioQty", "LegQty", "CumQty", "AvgPx", "OrdStatus"];
+const NUMERIC_LEG_COLUMNS: [usize; 4] = [4, 5, 6, 7];
+
+const LIST_HEADERS: [&str; 8] = ["ListID", "ListOrderStatus", "Orders", "OpenOrders", "OpenQty", "CumQty", "AvgPx", "ListStatusText"];
+const NUMERIC_LIST_COLUMNS: [usize; 5] = [2, 3, 4, 5, 6];
+
+const MASS_ACTION_HEADERS: [&str; 8] = ["MassActionID", "Request", "Scope", "MassCancelResponse", "Expected", "Affected", "Outstanding", "OutstandingClOrdIDs"];
+const NUMERIC_MASS_ACTION_COLUMNS: [usize; 3] = [4, 5, 6];
+
 pub struct OrderReport {
 
-    fields: Vec<u32>
+    columns: Vec<OrderColumn>,
+    fills: bool,
+    filter: OrderFilter,
+    writer: Box<dyn ReportWriter>,
+    // The tag to sort by and whether the sort is descending.
+    sort: Option<(u32, bool)>,
+    group_by: Option<u32>,
+    totals: bool
+}
+
+impl Default for OrderReport {
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+            fills: false,
+            filter: OrderFilter::default(),
+            writer: Box::new(TableReportWriter),
+            sort: None,
+            group_by: None,
+            totals: false
+        }
+    }
 }
 
 impl OrderReport
 {
     pub fn with_fields(fields: Vec<u32>) -> Self
+    {
+        OrderReport::with_columns(fields.into_iter().map(|tag| OrderColumn::new(ColumnSource::Field(tag))).collect())
+    }
+
+    // Fields with labels and formatters, and derived values such as the notional.
+    pub fn with_columns(columns: Vec<OrderColumn>) -> Self
     {
         Self {
-            fields: fields
+            columns,
+            ..Default::default()
+        }
+    }
+
+    // Only report orders that match the filter.
+    pub fn with_filter(mut self, filter: OrderFilter) -> Self
+    {
+        self.filter = filter;
+        self
+    }
+
+    // Print a table of the executions for each order after the order table.
+    pub fn with_fills(mut self, fills: bool) -> Self
+    {
+        self.fills = fills;
+        self
+    }
+
+    // Render the report as CSV, JSON Lines etc. instead of ASCII tables.
+    pub fn with_writer(mut self, writer: Box<dyn ReportWriter>) -> Self
+    {
+        self.writer = writer;
+        self
+    }
+
+    // Sort the orders by the value of a field, numerically if the FIX data type is numeric, orders
+    // without the field are last.
+    pub fn with_sort(mut self, tag: u32, descending: bool) -> Self
+    {
+        self.sort = Some((tag, descending));
+        self
+    }
+
+    // Group the orders by the value of a field e.g. Symbol, TargetCompID, or OrdStatus, and print a
+    // subtotal row after each group.
+    pub fn with_group_by(mut self, tag: u32) -> Self
+    {
+        self.group_by = Some(tag);
+        self
+    }
+
+    // Print a grand total row after the orders.
+    pub fn with_totals(mut self, totals: bool) -> Self
+    {
+        self.totals = totals;
+        self
+    }
+
+    // The executions are those of every version of the order, a fill can be busted or corrected
+    // after the order is replaced.
+    fn execution_notes(executions: &[&Execution], execution: &Execution) -> String
+    {
+        let mut notes = Vec::new();
+
+        if let Some(exec_ref_id) = &execution.exec_ref_id {
+            if execution.is_trade_cancel() {
+                notes.push(format!("Busts {}", exec_ref_id));
+            }
+            else if execution.is_trade_correct() {
+                notes.push(format!("Corrects {}", exec_ref_id));
+            }
+        }
+
+        if let Some(superseded_by) = &execution.superseded_by {
+            let busted = executions.iter().any(|other| &other.exec_id == superseded_by && other.is_trade_cancel());
+            notes.push(format!("{} by {}", if busted { "Busted" } else { "Corrected" }, superseded_by));
+        }
+
+        if execution.disputed {
+            notes.push("Disputed".to_string());
+        }
+
+        notes.join(", ")
+    }
+
+    // Each order lists the fills reported against it, the total is for the fills that make up its
+    // CumQty which includes those of the versions it replaced.
+    fn fills_table(order_book: &OrderBook, orders: &[&Order]) -> ReportTable
+    {
+        let mut table = ReportTable::new("Fills", &FILL_HEADERS, &NUMERIC_FILL_COLUMNS);
+
+        for order in orders.iter().filter(|order| !order.executions.is_empty()) {
+            let chain_executions: Vec<&Execution> = order_book.chain(&order.key).into_iter().flat_map(|version| version.executions.iter()).collect();
+            for (index, execution) in order.executions.iter().enumerate() {
+                let row = vec![
+                    if index == 0 { order.cl_ord_id.value.clone() } else { String::new() },
+                    execution.exec_id.clone(),
+                    crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::ExecType::TAG as usize, &execution.exec_type).unwrap_or(&execution.exec_type).to_string(),
+                    format_decimal(execution.last_qty),
+                    format_decimal(execution.last_px),
+                    format_decimal(execution.notional()),
+                    execution.last_mkt.clone().unwrap_or_default(),
+                    execution.transact_time.clone().unwrap_or_default(),
+                    OrderReport::execution_notes(&chain_executions, execution)
+                ];
+                table.add_row(row);
+            }
+            let total = vec![
+                String::new(),
+                "Total".to_string(),
+                String::new(),
+                format_decimal(order_book.chain_filled_quantity(&order.key)),
+                order_book.chain_vwap(&order.key).map(format_decimal).unwrap_or_default(),
+                format_decimal(order_book.chain_notional(&order.key)),
+                String::new(),
+                String::new(),
+                order_book.check_fills(&order.key).join(", ")
+            ];
+            table.add_row(total);
+        }
+
+        table
+    }
+
+    fn legs_table(orders: &[&Order]) -> ReportTable
+    {
+        let mut table = ReportTable::new("Legs", &LEG_HEADERS, &NUMERIC_LEG_COLUMNS);
+
+        for order in orders.iter().filter(|order| !order.legs.is_empty()) {
+            for (index, leg) in order.legs.iter().enumerate() {
+                let row = vec![
+                    if index == 0 { order.cl_ord_id.value.clone() } else { String::new() },
+                    leg.leg_ref_id.clone().unwrap_or_default(),
+                    leg.symbol.clone().unwrap_or_default(),
+                    leg.side.as_deref().map(|side| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::Side::TAG as usize, side).unwrap_or(side).to_string()).unwrap_or_default(),
+                    format_decimal(leg.ratio_qty),
+                    format_decimal(leg.quantity),
+                    format_decimal(leg.cum_qty),
+                    leg.avg_px().map(format_decimal).unwrap_or_default(),
+                    leg.ord_status.as_deref().map(|ord_status| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::OrdStatus::TAG as usize, ord_status).unwrap_or(ord_status).to_string()).unwrap_or_default()
+                ];
+                table.add_row(row);
+            }
+        }
+
+        table
+    }
+
+    // Lists are reported if any of their orders match the filter.
+    fn lists_table(order_book: &OrderBook, filter: &OrderFilter) -> Option<ReportTable>
+    {
+        let lists: Vec<_> = order_book.lists
+            .values()
+            .filter(|list| order_book.list_orders(&list.key).iter().any(|order| filter.matches(order)))
+            .collect();
+
+        if lists.is_empty() {
+            return None;
+        }
+
+        let mut table = ReportTable::new("Lists", &LIST_HEADERS, &NUMERIC_LIST_COLUMNS);
+
+        for list in lists {
+            let aggregate = order_book.list_aggregate(&list.key);
+            let row = vec![
+                list.list_id.clone(),
+                order_book.list_order_status(&list.key).map(|status| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::ListOrderStatus::TAG as usize, status).unwrap_or(status).to_string()).unwrap_or_default(),
+                aggregate.orders.to_string(),
+                aggregate.open_orders.to_string(),
+                format_decimal(aggregate.open_quantity),
+                format_decimal(aggregate.filled_quantity),
+                aggregate.vwap().map(format_decimal).unwrap_or_default(),
+                list.fields.try_get(crate::FIX_5_0SP2::ListStatusText::TAG).map(|field| field.value.clone()).unwrap_or_default()
+            ];
+            table.add_row(row);
+        }
+
+        Some(table)
+    }
+
+    // Mass actions are reported if any of the orders they were expected to, or did, apply to match the filter.
+    fn mass_actions_table(order_book: &OrderBook, filter: &OrderFilter) -> Option<ReportTable>
+    {
+        let mass_actions: Vec<_> = order_book.mass_actions
+            .values()
+            .filter(|mass_action| {
+                filter.is_empty() ||
+                order_book.mass_action_orders(&mass_action.key).iter().any(|order| filter.matches(order)) ||
+                order_book.unexpected_orders(&mass_action.key).iter().any(|order| filter.matches(order))
+            })
+            .collect();
+
+        if mass_actions.is_empty() {
+            return None;
         }
+
+        let mut table = ReportTable::new("MassActions", &MASS_ACTION_HEADERS, &NUMERIC_MASS_ACTION_COLUMNS);
+
+        for mass_action in mass_actions {
+            let outstanding = order_book.outstanding_orders(&mass_action.key);
+            let row = vec![
+                mass_action.id.clone(),
+                match mass_action.kind {
+                    MassActionKind::Cancel => "OrderMassCancelRequest".to_string(),
+                    MassActionKind::Status => "OrderMassStatusRequest".to_string()
+                },
+                mass_action.scope.to_string(),
+                mass_action.mass_cancel_response.as_deref().map(|response| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::MassCancelResponse::TAG as usize, response).unwrap_or(response).to_string()).unwrap_or_default(),
+                mass_action.expected.len().to_string(),
+                mass_action.affected.len().to_string(),
+                outstanding.len().to_string(),
+                outstanding.iter().map(|order| order.cl_ord_id.value.as_str()).collect::<Vec<_>>().join(" ")
+            ];
+            table.add_row(row);
+        }
+
+        Some(table)
     }
 
-    fn pending_value_for_field(order: &Order, field: &Field, definition: &Box<dyn OrchestrationField>) -> Option<String>
+    fn pending_value_for_field(order: &Order, field: &Field, column: &OrderColumn) -> Option<String>
     {
         if Order::is_identity_field(field.tag) {
             return None;
@@ -52,77 +300,480 @@ impl OrderReport
             return None;
         }
 
-        let text = match definition.name_of_value(pending.value.as_str()) {
-            Some(name) => name.to_string(),
-            None => pending.value.clone()
+        Some(column.display(&pending.value))
+    }
+
+    fn compare_values(tag: u32, left: Option<&str>, right: Option<&str>, descending: bool) -> Ordering
+    {
+        let (left, right) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal
         };
 
-        Some(format!("({})", text))
+        let is_numeric = crate::FIX_5_0SP2::fields().try_get(tag as usize).is_some_and(|definition| definition.is_numeric());
+
+        let ordering = match (is_numeric, left.parse::<f64>(), right.parse::<f64>()) {
+            (true, Ok(left), Ok(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+            _ => left.cmp(right)
+        };
+
+        if descending { ordering.reverse() } else { ordering }
     }
 
-    pub fn print<W: Write>(&mut self, writer: &mut W, order_book: &OrderBook) -> std::io::Result<()>
+    fn field_value(order: &Order, tag: u32) -> Option<&str>
     {
-        let mut table = Table::default();
+        order.fields.try_get(tag).map(|field| field.value.as_str())
+    }
 
-        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
-            .set_style(TableComponent::HeaderLines, '-')
-            .set_style(TableComponent::MiddleHeaderIntersections, '-')
-        ;
+    // Orders in the same group are adjacent and sorted within the group, the sort is stable so
+    // orders that compare equal stay in the order they were created.
+    fn sort_orders(&self, orders: &mut [&Order])
+    {
+        orders.sort_by(|left, right| {
+            let group = match self.group_by {
+                Some(tag) => OrderReport::compare_values(tag, OrderReport::field_value(left, tag), OrderReport::field_value(right, tag), false),
+                None => Ordering::Equal
+            };
+            group.then_with(|| match self.sort {
+                Some((tag, descending)) => OrderReport::compare_values(tag, OrderReport::field_value(left, tag), OrderReport::field_value(right, tag), descending),
+                None => Ordering::Equal
+            })
+        });
+    }
 
-        let headers: Vec<Cell> = self.fields.iter().map(|tag| {
-            // TODO - field lookup needs to return an option 
-            let field = &crate::FIX_5_0SP2::fields()[*tag as usize];
-            Cell::new(field.name().to_string()).set_alignment(
-                if field.is_numeric() {
-                    CellAlignment::Right
-                }
-                else {
-                    CellAlignment::Left
+    fn has_totals(&self) -> bool
+    {
+        self.group_by.is_some() || self.totals
+    }
+
+    // Sum the columns that are totalled, the label goes in the first column that isn't. Replaced
+    // orders are excluded because the replacement carries their fills.
+    fn total_row(order_book: &OrderBook, columns: &[&OrderColumn], label: String, orders: &[&Order]) -> Vec<ReportCell>
+    {
+        let orders: Vec<&&Order> = orders.iter().filter(|order| order.successor.is_none()).collect();
+        let label_column = columns.iter().position(|column| !column.is_totalled());
+
+        columns.iter().enumerate().map(|(index, column)| {
+            if column.is_totalled() {
+                let total = orders.iter().filter_map(|order| column.decimal_value(order_book, order)).sum();
+                ReportCell::new(column.display(&format_decimal(total)))
+            }
+            else if label_column == Some(index) {
+                ReportCell::new(label.clone())
+            }
+            else {
+                ReportCell::default()
+            }
+        }).collect()
+    }
+
+    // e.g. BHP.AX Total, Filled Total, or No OrdStatus Total for the orders without the field.
+    fn group_label(tag: u32, value: Option<&str>) -> String
+    {
+        let definition = crate::FIX_5_0SP2::fields().try_get(tag as usize);
+        match value {
+            Some(value) => format!("{} Total", definition.and_then(|definition| definition.name_of_value(value)).unwrap_or(value)),
+            None => format!("No {} Total", definition.map(|definition| definition.name().to_string()).unwrap_or_else(|| tag.to_string()))
+        }
+    }
+
+    // Was a value of the order changed by one of the events, a pending value is a change made by
+    // the request that is pending.
+    fn is_changed(events: &[OrderEvent], order: &Order, tags: &[u32], cell: &ReportCell) -> bool
+    {
+        events.iter().any(|event| {
+            if let OrderEventKind::Replaced { old_key, .. } = &event.kind {
+                if old_key == &order.key {
+                    return tags.contains(&crate::FIX_5_0SP2::OrdStatus::TAG);
                 }
-            )
-        }).collect();
+            }
+            event.key == order.key && (tags.iter().any(|tag| event.change(*tag).is_some()) || (event.kind == OrderEventKind::PendingChange && cell.pending.is_some()))
+        })
+    }
 
+    fn order_cell(order_book: &OrderBook, column: &OrderColumn, order: &Order, events: &[OrderEvent]) -> ReportCell
+    {
+        let cell = match column.source {
+            ColumnSource::Field(tag) => match order.fields.try_get(tag) {
+                Some(field) => ReportCell::new(column.display(&field.value)).with_pending(OrderReport::pending_value_for_field(order, field, column)),
+                // A field that was removed is still a change.
+                None => ReportCell::default()
+            },
+            _ => ReportCell::new(column.value(order_book, order).map(|value| column.display(&value)).unwrap_or_default())
+        };
 
-        table.set_header(headers);
-        
-        order_book.orders
-            .values()
-            .for_each(|order| {
-                let row: Vec<Cell> = self.fields.iter().map(|tag| {
-                    if let Some(field) = order.fields.try_get(*tag) {
-
-                        let definition = &crate::FIX_5_0SP2::fields()[*tag as usize];
-                        
-                        let value = match definition.name_of_value(field.value.as_str()) {
-                            None => field.value.clone(),
-                            Some(name) => name.to_string()
-                        };
-
-                        let text = match OrderReport::pending_value_for_field(&order, &field, &definition) {
-                            Some(pending_value) => value + " " + pending_value.as_str(),
-                            None => value
-                        };
-
-                        Cell::new(text).set_alignment(
-                            if definition.is_numeric() {
-                                CellAlignment::Right
-                            }
-                            else {
-                                CellAlignment::Left
-                            }
-                        )
-                    }        
-                    else {
-                        Cell::new("".to_string())
-                    }
-                }).collect();
-                table.add_row(row);
-            });
-        
-        let report = format!("{}\n\n", table);
+        let changed = OrderReport::is_changed(events, order, &column.tags(), &cell);
+        cell.with_changed(changed)
+    }
+
+    // The totals add a Notional column if there isn't one.
+    fn orders_table(&self, order_book: &OrderBook, orders: &[&Order], events: &[OrderEvent], totals: bool) -> ReportTable
+    {
+        let notional = OrderColumn::new(ColumnSource::Notional);
+        let mut columns: Vec<&OrderColumn> = self.columns.iter().collect();
+        if totals && !columns.iter().any(|column| column.source == ColumnSource::Notional) {
+            columns.push(&notional);
+        }
 
-        writer.write(report.as_bytes())?;
+        let mut table = ReportTable {
+            name: "Orders".to_string(),
+            headers: columns.iter().map(|column| column.header()).collect(),
+            numeric_columns: columns.iter().enumerate().filter(|(_, column)| column.is_numeric()).map(|(index, _)| index).collect(),
+            rows: Vec::new()
+        };
 
+        for order in orders {
+            table.rows.push(columns.iter().map(|column| OrderReport::order_cell(order_book, column, order, events)).collect());
+        }
+
+        if totals {
+            self.add_totals(order_book, &mut table, &columns, orders);
+        }
+
+        table
+    }
+
+    // Insert a subtotal row after each group and append the grand total.
+    fn add_totals(&self, order_book: &OrderBook, table: &mut ReportTable, columns: &[&OrderColumn], orders: &[&Order])
+    {
+        if let Some(tag) = self.group_by {
+            let mut rows = Vec::new();
+            let mut order_rows = std::mem::take(&mut table.rows).into_iter();
+            for group in orders.chunk_by(|left, right| OrderReport::field_value(left, tag) == OrderReport::field_value(right, tag)) {
+                rows.extend(order_rows.by_ref().take(group.len()));
+                rows.push(OrderReport::total_row(order_book, columns, OrderReport::group_label(tag, OrderReport::field_value(group[0], tag)), group));
+            }
+            table.rows = rows;
+        }
+
+        if self.totals {
+            table.rows.push(OrderReport::total_row(order_book, columns, "Total".to_string(), orders));
+        }
+    }
+
+    pub fn print<W: Write>(&mut self, writer: &mut W, order_book: &OrderBook) -> std::io::Result<()>
+    {
+        let mut orders: Vec<&Order> = order_book.query(&self.filter).collect();
+        self.sort_orders(&mut orders);
+
+        let mut tables = vec![self.orders_table(order_book, &orders, &[], self.has_totals())];
+
+        if self.fills && orders.iter().any(|order| !order.executions.is_empty()) {
+            tables.push(OrderReport::fills_table(order_book, &orders));
+        }
+
+        if orders.iter().any(|order| !order.legs.is_empty()) {
+            tables.push(OrderReport::legs_table(&orders));
+        }
+
+        if let Some(lists) = OrderReport::lists_table(order_book, &self.filter) {
+            tables.push(lists);
+        }
+
+        if let Some(mass_actions) = OrderReport::mass_actions_table(order_book, &self.filter) {
+            tables.push(mass_actions);
+        }
+
+        self.writer.write(writer, &tables)
+    }
+
+    // Print only the orders changed by the events from processing a message, with the changed
+    // fields highlighted, followed by their fills and legs. Nothing is printed if none of the
+    // orders match the filter.
+    pub fn print_changes<W: Write>(&mut self, writer: &mut W, order_book: &OrderBook, events: &[OrderEvent]) -> std::io::Result<()>
+    {
+        let is_changed = |order: &Order| events.iter().any(|event| {
+            event.key == order.key || matches!(&event.kind, OrderEventKind::Replaced { old_key, .. } if old_key == &order.key)
+        });
+
+        let mut orders: Vec<&Order> = order_book.query(&self.filter).filter(|order| is_changed(order)).collect();
+
+        if orders.is_empty() {
+            return Ok(());
+        }
+
+        self.sort_orders(&mut orders);
+
+        let mut tables = vec![self.orders_table(order_book, &orders, events, false)];
+
+        if self.fills && orders.iter().any(|order| !order.executions.is_empty()) {
+            tables.push(OrderReport::fills_table(order_book, &orders));
+        }
+
+        if orders.iter().any(|order| !order.legs.is_empty()) {
+            tables.push(OrderReport::legs_table(&orders));
+        }
+
+        self.writer.write(writer, &tables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+    use crate::report_writer::NumberFormatter;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn fills_are_printed_under_each_order() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=2\u{0001}150=F\u{0001}32=4000\u{0001}31=20\u{0001}30=XASX\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=3\u{0001}150=F\u{0001}32=6000\u{0001}31=21\u{0001}30=XASX\u{0001}14=10000\u{0001}6=20.6\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}39=1\u{0001}11=61\u{0001}37=1\u{0001}17=4\u{0001}19=2\u{0001}150=H\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::CumQty::TAG]).with_fills(true).print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[7], "| ClOrdID   ExecID   ExecType      LastQty   LastPx   Notional   LastMkt   TransactTime   Notes       |");
+        assert_eq!(lines[9], "| 61        2        Trade            4000       20      80000   XASX                     Busted by 4 |");
+        assert_eq!(lines[10], "|           3        Trade            6000       21     126000   XASX                                 |");
+        assert_eq!(lines[11], "|           4        TradeCancel         0        0          0                            Busts 2     |");
+        assert_eq!(lines[12], "|           Total                     6000       21     126000                                        |");
+        Ok(())
+    }
+
+    #[test]
+    pub fn orders_are_written_as_csv_with_pending_values() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}41=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=20000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::Side::TAG, crate::FIX_5_0SP2::OrderQty::TAG])
+            .with_writer(Box::new(crate::report_writer::CsvReportWriter))
+            .print(&mut output, &book)?;
+
+        assert_eq!(String::from_utf8_lossy(&output), "ClOrdID,Side,OrderQty\r\n1,Buy,10000 (20000)\r\n");
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn only_changed_orders_are_printed_with_changes_highlighted() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=200\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let events = book.process(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=2\u{0001}37=1\u{0001}17=1\u{0001}150=0\u{0001}39=0\u{0001}151=200\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}")?)?;
+
+        let mut output = Vec::new();
+        let mut report = OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::Symbol::TAG, crate::FIX_5_0SP2::OrdStatus::TAG])
+            .with_writer(Box::new(crate::report_writer::MarkdownReportWriter));
+        report.print_changes(&mut output, &book, &events)?;
+
+        assert_eq!(String::from_utf8_lossy(&output), concat!(
+            "| ClOrdID | Symbol | OrdStatus |\n",
+            "| --- | --- | --- |\n",
+            "| 2 | RIO.AX | **New** |\n",
+            "\n"
+        ));
+
+        let mut output = Vec::new();
+        report.print_changes(&mut output, &book, &[])?;
+        assert!(output.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn orders_are_sorted_and_grouped_with_subtotals_and_a_total() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=200\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=900\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=3\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=3\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=1\u{0001}32=400\u{0001}31=21\u{0001}151=600\u{0001}14=400\u{0001}6=21\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::Symbol::TAG, crate::FIX_5_0SP2::OrderQty::TAG, crate::FIX_5_0SP2::CumQty::TAG])
+            .with_sort(crate::FIX_5_0SP2::OrderQty::TAG, true)
+            .with_group_by(crate::FIX_5_0SP2::Symbol::TAG)
+            .with_totals(true)
+            .with_writer(Box::new(crate::report_writer::CsvReportWriter))
+            .print(&mut output, &book)?;
+
+        assert_eq!(String::from_utf8_lossy(&output), concat!(
+            "ClOrdID,Symbol,OrderQty,CumQty,Notional\r\n",
+            "3,BHP.AX,1000,400,8400\r\n",
+            "2,BHP.AX,900,,0\r\n",
+            "BHP.AX Total,,1900,400,8400\r\n",
+            "1,RIO.AX,200,,0\r\n",
+            "RIO.AX Total,,200,0,0\r\n",
+            "Total,,2100,400,8400\r\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn replaced_orders_are_totalled_once_with_the_notional_of_the_chain() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=1\u{0001}32=4000\u{0001}31=20\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}41=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=20000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=2\u{0001}41=1\u{0001}37=1\u{0001}17=2\u{0001}150=5\u{0001}39=1\u{0001}38=20000\u{0001}151=16000\u{0001}14=4000\u{0001}6=20\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::OrderQty::TAG, crate::FIX_5_0SP2::CumQty::TAG])
+            .with_totals(true)
+            .with_writer(Box::new(crate::report_writer::CsvReportWriter))
+            .print(&mut output, &book)?;
+
+        assert_eq!(String::from_utf8_lossy(&output), concat!(
+            "ClOrdID,OrderQty,CumQty,Notional\r\n",
+            "1,10000,4000,80000\r\n",
+            "2,20000,4000,80000\r\n",
+            "Total,20000,4000,80000\r\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn user_defined_fields_labels_formatters_and_derived_columns_are_printed() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}9999=DESK1\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=1\u{0001}32=4000\u{0001}31=21.5\u{0001}14=4000\u{0001}6=21.5\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let quantity: NumberFormatter = ",".parse()?;
+        let columns = vec![
+            OrderColumn::new(ColumnSource::Field(crate::FIX_5_0SP2::ClOrdID::TAG)),
+            OrderColumn::new(ColumnSource::Field(9999)).with_label("Desk"),
+            OrderColumn::new(ColumnSource::Field(9998)),
+            OrderColumn::new(ColumnSource::Field(crate::FIX_5_0SP2::OrderQty::TAG)).with_label("Qty").with_formatter(Box::new(quantity)),
+            OrderColumn::new(ColumnSource::Field(crate::FIX_5_0SP2::Price::TAG)).with_formatter(Box::new(NumberFormatter::default().with_precision(2))),
+            OrderColumn::new(ColumnSource::LeavesQty).with_formatter(Box::new(quantity)),
+            OrderColumn::new(ColumnSource::Notional).with_formatter(Box::new(",.2".parse::<NumberFormatter>()?))
+        ];
+
+        let mut output = Vec::new();
+        OrderReport::with_columns(columns).with_writer(Box::new(crate::report_writer::CsvReportWriter)).print(&mut output, &book)?;
+
+        assert_eq!(String::from_utf8_lossy(&output), concat!(
+            "ClOrdID,Desk,9998,Qty,Price,LeavesQty,Notional\r\n",
+            "1,DESK1,,\"10,000\",21.00,\"6,000\",\"86,000.00\"\r\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn only_orders_matching_the_filter_are_printed() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=200\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        let filter = OrderFilter::default().with_condition("Symbol=RIO.AX".parse()?);
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG, crate::FIX_5_0SP2::Symbol::TAG]).with_filter(filter).print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        let rows: Vec<&&str> = lines.iter().filter(|line| line.contains(".AX")).collect();
+        assert_eq!(rows, vec![&"| 2         RIO.AX |"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn legs_and_lists_are_printed_after_the_orders() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AB\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=S1\u{0001}54=1\u{0001}555=2\u{0001}600=BHP.AX\u{0001}623=1\u{0001}624=1\u{0001}654=L1\u{0001}600=RIO.AX\u{0001}623=2\u{0001}624=2\u{0001}654=L2\u{0001}38=100\u{0001}40=2\u{0001}44=1.5\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=S1\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=1\u{0001}442=2\u{0001}55=BHP.AX\u{0001}32=100\u{0001}31=21\u{0001}151=100\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=E\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}66=B1\u{0001}394=3\u{0001}68=2\u{0001}73=2\u{0001}11=1\u{0001}67=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=100\u{0001}40=1\u{0001}11=2\u{0001}67=2\u{0001}55=RIO.AX\u{0001}54=2\u{0001}38=200\u{0001}40=1\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=1\u{0001}66=B1\u{0001}37=2\u{0001}17=2\u{0001}150=F\u{0001}39=2\u{0001}32=100\u{0001}31=21\u{0001}151=0\u{0001}14=100\u{0001}6=21\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG]).print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[9], "| ClOrdID   LegRefID   LegSymbol   LegSide   LegRatioQty   LegQty   CumQty   AvgPx   OrdStatus |");
+        assert_eq!(lines[11], "| S1        L1         BHP.AX      Buy                 1      100      100      21   Filled    |");
+        assert_eq!(lines[12], "|           L2         RIO.AX      Sell                2      200        0                     |");
+        assert_eq!(lines[16], "| ListID   ListOrderStatus   Orders   OpenOrders   OpenQty   CumQty   AvgPx   ListStatusText |");
+        assert_eq!(lines[18], "| B1       Executing              2            1       200      100      21                  |");
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_actions_are_printed_after_the_orders() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=100\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=BHP.AX\u{0001}54=2\u{0001}38=200\u{0001}40=2\u{0001}44=22\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=q\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=4\u{0001}11=M1\u{0001}530=1\u{0001}55=BHP.AX\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=r\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=M1\u{0001}37=MC1\u{0001}530=1\u{0001}531=1\u{0001}533=1\u{0001}534=1\u{0001}41=1\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=1\u{0001}37=1\u{0001}17=1\u{0001}150=4\u{0001}39=4\u{0001}151=0\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}"
+        ];
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        OrderReport::with_fields(vec![crate::FIX_5_0SP2::ClOrdID::TAG]).print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[8], "| MassActionID   Request                  Scope           MassCancelResponse         Expected   Affected   Outstanding   OutstandingClOrdIDs |");
+        assert_eq!(lines[10], "| M1             OrderMassCancelRequest   Symbol=BHP.AX   CancelOrdersForASecurity          2          1             1   2                   |");
+        Ok(())
+    }
+
+}
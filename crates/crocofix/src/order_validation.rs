@@ -0,0 +1,250 @@
+use crate::message::Message;
+use crate::order::Order;
+use crate::execution::Execution;
+use crate::field::QUANTITY_TOLERANCE;
+use std::fmt;
+
+// A breach of the FIX order state change matrices or quantity invariants by an ExecutionReport.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Violation {
+    pub msg_seq_num: Option<String>,
+    pub description: String
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.msg_seq_num {
+            Some(msg_seq_num) => write!(f, "MsgSeqNum={} {}", msg_seq_num, self.description),
+            None => write!(f, "{}", self.description)
+        }
+    }
+}
+
+fn ord_status_name(value: &str) -> String
+{
+    crate::FIX_5_0SP2::fields()
+        .name_of_value(crate::FIX_5_0SP2::OrdStatus::TAG as usize, value)
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn decimal(message: &Message, tag: u32) -> Option<f64>
+{
+    message.fields.try_get(tag).and_then(|field| field.decimal_value().ok())
+}
+
+// The OrdStatus values that can follow each OrdStatus as described by the order state change
+// matrices in the FIX specification. Statuses not listed here are not checked.
+fn permitted_transitions(from: &str) -> Option<&'static [&'static str]>
+{
+    const NEW: &str = "0";
+    const PARTIALLY_FILLED: &str = "1";
+    const FILLED: &str = "2";
+    const DONE_FOR_DAY: &str = "3";
+    const CANCELED: &str = "4";
+    const REPLACED: &str = "5";
+    const PENDING_CANCEL: &str = "6";
+    const REJECTED: &str = "8";
+    const PENDING_NEW: &str = "A";
+    const EXPIRED: &str = "C";
+    const PENDING_REPLACE: &str = "E";
+
+    match from {
+        PENDING_NEW => Some(&[PENDING_NEW, NEW, PARTIALLY_FILLED, FILLED, CANCELED, REJECTED, EXPIRED, DONE_FOR_DAY, PENDING_CANCEL, PENDING_REPLACE]),
+        // FIX 4.2 reports the replacement with OrdStatus Replaced so it is treated like New.
+        NEW | REPLACED => Some(&[NEW, PARTIALLY_FILLED, FILLED, CANCELED, REPLACED, PENDING_CANCEL, PENDING_REPLACE, DONE_FOR_DAY, EXPIRED]),
+        PARTIALLY_FILLED => Some(&[PARTIALLY_FILLED, FILLED, CANCELED, REPLACED, PENDING_CANCEL, PENDING_REPLACE, DONE_FOR_DAY, EXPIRED]),
+        FILLED => Some(&[FILLED, DONE_FOR_DAY]),
+        DONE_FOR_DAY => Some(&[DONE_FOR_DAY, CANCELED, EXPIRED]),
+        PENDING_CANCEL => Some(&[PENDING_CANCEL, NEW, PARTIALLY_FILLED, FILLED, CANCELED, PENDING_REPLACE, DONE_FOR_DAY, EXPIRED]),
+        PENDING_REPLACE => Some(&[PENDING_REPLACE, NEW, PARTIALLY_FILLED, FILLED, CANCELED, REPLACED, PENDING_CANCEL, DONE_FOR_DAY, EXPIRED]),
+        CANCELED => Some(&[CANCELED]),
+        REJECTED => Some(&[REJECTED]),
+        EXPIRED => Some(&[EXPIRED]),
+        _ => None
+    }
+}
+
+// Orders in these states can't be filled and must have LeavesQty=0.
+pub(crate) fn is_closed(ord_status: &str) -> bool
+{
+    ord_status == crate::FIX_5_0SP2::OrdStatus::Filled().value ||
+    ord_status == crate::FIX_5_0SP2::OrdStatus::DoneForDay().value ||
+    ord_status == crate::FIX_5_0SP2::OrdStatus::Canceled().value ||
+    ord_status == crate::FIX_5_0SP2::OrdStatus::Rejected().value ||
+    ord_status == crate::FIX_5_0SP2::OrdStatus::Expired().value
+}
+
+// The OrdStatus most recently reported by the counterparty. We can't use the OrdStatus in the
+// order fields because it is set to PendingCancel or PendingReplace when a request is sent.
+fn reported_ord_status(order: &Order) -> Option<&str>
+{
+    order.messages
+        .iter()
+        .rev()
+        .filter(|message| message.msg_type() == Some(crate::FIX_5_0SP2::MsgType::ExecutionReport().value))
+        .find_map(|message| message.fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG))
+        .map(|field| field.value.as_str())
+}
+
+// Check an ExecutionReport against the current state of the order, this must be called before the order is updated.
+pub fn validate_execution_report(order: &Order, execution_report: &Message) -> Vec<Violation>
+{
+    // The status and quantities of a report for an individual leg are those of the leg.
+    if !order.legs.is_empty() && crate::multileg::is_individual_leg_report(execution_report) {
+        return Vec::new();
+    }
+
+    let mut descriptions = Vec::new();
+
+    let exec_type = execution_report.fields.try_get(crate::FIX_5_0SP2::ExecType::TAG).map(|field| field.value.as_str());
+    let ord_status = execution_report.fields.try_get(crate::FIX_5_0SP2::OrdStatus::TAG).map(|field| field.value.as_str());
+    let previous_ord_status = reported_ord_status(order);
+
+    // Status reports, restatements, busts, and corrections can legitimately move an order backwards.
+    let is_adjustment = exec_type.is_some_and(|exec_type| {
+        exec_type == crate::FIX_5_0SP2::ExecType::OrderStatus().value ||
+        exec_type == crate::FIX_5_0SP2::ExecType::Restated().value ||
+        exec_type == crate::FIX_5_0SP2::ExecType::TradeCancel().value ||
+        exec_type == crate::FIX_5_0SP2::ExecType::TradeCorrect().value
+    });
+
+    let execution = Execution::from_execution_report(execution_report).ok().flatten();
+    let is_fill = execution.as_ref().is_some_and(|execution| !execution.is_trade_cancel() && !execution.is_trade_correct());
+
+    if let Some(from) = previous_ord_status {
+        if is_fill && is_closed(from) {
+            descriptions.push(format!("fill received for an order with OrdStatus {}", ord_status_name(from)));
+        }
+        else if let (Some(to), Some(permitted), false) = (ord_status, permitted_transitions(from), is_adjustment) {
+            if !permitted.contains(&to) {
+                descriptions.push(format!("OrdStatus {} is not valid after {}", ord_status_name(to), ord_status_name(from)));
+            }
+        }
+    }
+
+    let order_qty = decimal(execution_report, crate::FIX_5_0SP2::OrderQty::TAG)
+        .or_else(|| order.fields.try_get(crate::FIX_5_0SP2::OrderQty::TAG).and_then(|field| field.decimal_value().ok()));
+    let cum_qty = decimal(execution_report, crate::FIX_5_0SP2::CumQty::TAG);
+    let leaves_qty = decimal(execution_report, crate::FIX_5_0SP2::LeavesQty::TAG);
+    let previous_cum_qty = order.fields.try_get(crate::FIX_5_0SP2::CumQty::TAG).and_then(|field| field.decimal_value().ok()).unwrap_or_default();
+
+    if let (Some(cum_qty), Some(leaves_qty), Some(order_qty), Some(ord_status)) = (cum_qty, leaves_qty, order_qty, ord_status) {
+        if is_closed(ord_status) {
+            if leaves_qty.abs() > QUANTITY_TOLERANCE {
+                descriptions.push(format!("LeavesQty {} should be 0 when OrdStatus is {}", leaves_qty, ord_status_name(ord_status)));
+            }
+        }
+        else if (cum_qty + leaves_qty - order_qty).abs() > QUANTITY_TOLERANCE {
+            descriptions.push(format!("CumQty {} + LeavesQty {} does not equal OrderQty {}", cum_qty, leaves_qty, order_qty));
+        }
+    }
+
+    if let Some(cum_qty) = cum_qty {
+        if !is_adjustment && cum_qty < previous_cum_qty - QUANTITY_TOLERANCE {
+            descriptions.push(format!("CumQty decreased from {} to {}", previous_cum_qty, cum_qty));
+        }
+        if let (true, Some(execution)) = (is_fill, &execution) {
+            if (cum_qty - previous_cum_qty - execution.last_qty).abs() > QUANTITY_TOLERANCE {
+                descriptions.push(format!("LastQty {} does not match the change in CumQty from {} to {}", execution.last_qty, previous_cum_qty, cum_qty));
+            }
+        }
+    }
+
+    let msg_seq_num = execution_report.fields.try_get(crate::FIX_5_0SP2::MsgSeqNum::TAG).map(|field| field.value.clone());
+
+    descriptions
+        .into_iter()
+        .map(|description| Violation { msg_seq_num: msg_seq_num.clone(), description })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=61\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=10000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}";
+
+    fn order(reports: &[&str]) -> Result<Order, crate::error::Error>
+    {
+        let mut order = Order::new(&decode_message(ORDER_SINGLE)?)?;
+        for report in reports {
+            order.update(&decode_message(report)?)?;
+        }
+        Ok(order)
+    }
+
+    fn report(msg_seq_num: u32, exec_type: &str, ord_status: &str, last_qty: u32, cum_qty: u32, leaves_qty: u32) -> String
+    {
+        format!("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34={}\u{0001}11=61\u{0001}37=1\u{0001}17={}\u{0001}150={}\u{0001}39={}\u{0001}38=10000\u{0001}32={}\u{0001}31=20\u{0001}14={}\u{0001}151={}\u{0001}10=000\u{0001}",
+            msg_seq_num, msg_seq_num, exec_type, ord_status, last_qty, cum_qty, leaves_qty)
+    }
+
+    fn descriptions(order: &Order, text: &str) -> Result<Vec<String>, crate::error::Error>
+    {
+        Ok(validate_execution_report(order, &decode_message(text)?).into_iter().map(|violation| violation.description).collect())
+    }
+
+    #[test]
+    pub fn valid_lifecycle_has_no_violations() -> Result<(), crate::error::Error>
+    {
+        let reports = [report(2, "0", "0", 0, 0, 10000), report(3, "F", "1", 4000, 4000, 6000), report(4, "F", "2", 6000, 10000, 0)];
+        let mut order = Order::new(&decode_message(ORDER_SINGLE)?)?;
+        for report in &reports {
+            let message = decode_message(report)?;
+            assert!(validate_execution_report(&order, &message).is_empty());
+            order.update(&message)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn fill_after_canceled() -> Result<(), crate::error::Error>
+    {
+        let order = order(&[&report(2, "0", "0", 0, 0, 10000), &report(3, "4", "4", 0, 0, 0)])?;
+        assert_eq!(descriptions(&order, &report(4, "F", "1", 100, 100, 0))?, vec![
+            "fill received for an order with OrdStatus Canceled",
+            "CumQty 100 + LeavesQty 0 does not equal OrderQty 10000"
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn pending_replace_after_filled() -> Result<(), crate::error::Error>
+    {
+        let order = order(&[&report(2, "F", "2", 10000, 10000, 0)])?;
+        assert_eq!(descriptions(&order, &report(3, "E", "E", 0, 10000, 0))?, vec!["OrdStatus PendingReplace is not valid after Filled"]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn status_requests_and_busts_may_move_backwards() -> Result<(), crate::error::Error>
+    {
+        let order = order(&[&report(2, "F", "2", 10000, 10000, 0)])?;
+        assert!(descriptions(&order, &report(3, "H", "0", 0, 0, 10000))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn quantity_invariants() -> Result<(), crate::error::Error>
+    {
+        let order = order(&[&report(2, "F", "1", 4000, 4000, 6000)])?;
+        assert_eq!(descriptions(&order, &report(3, "F", "1", 1000, 3000, 7000))?, vec![
+            "CumQty decreased from 4000 to 3000",
+            "LastQty 1000 does not match the change in CumQty from 4000 to 3000"
+        ]);
+        assert_eq!(descriptions(&order, &report(3, "F", "1", 1000, 5000, 4000))?, vec!["CumQty 5000 + LeavesQty 4000 does not equal OrderQty 10000"]);
+        Ok(())
+    }
+
+}
@@ -0,0 +1,324 @@
+use indexmap::IndexMap;
+use crate::error::Error;
+use crate::execution::Execution;
+use crate::field::QUANTITY_TOLERANCE;
+use crate::message::Message;
+use crate::order::Order;
+
+// A fill that contributes to a position. Corrections amend the fill in place so it keeps its
+// place in the sequence, exec_ids holds the ExecID of the fill and of each correction to it.
+#[derive(Debug, Clone, PartialEq)]
+struct PositionFill {
+    exec_ids: Vec<String>,
+    // Positive for buys and negative for sells.
+    quantity: f64,
+    price: f64
+}
+
+// The position in a Symbol for an Account using the average cost method. Reducing a position
+// realises the difference between the fill price and the average cost, the average cost of the
+// remaining position is unchanged.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Position {
+    pub account: String,
+    pub symbol: String,
+    pub bought_quantity: f64,
+    pub sold_quantity: f64,
+    // Positive when long and negative when short.
+    pub net_quantity: f64,
+    pub average_cost: f64,
+    pub realised_pnl: f64,
+    // The price of the most recent fill, this is used to value the position.
+    pub last_price: f64
+}
+
+impl Position {
+
+    fn new(account: &str, symbol: &str) -> Self
+    {
+        Position {
+            account: account.to_string(),
+            symbol: symbol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn apply(&mut self, quantity: f64, price: f64)
+    {
+        // A fill with no quantity, such as one corrected to zero, doesn't change the position.
+        if quantity.abs() < QUANTITY_TOLERANCE {
+            return;
+        }
+
+        if quantity > 0.0 {
+            self.bought_quantity += quantity;
+        }
+        else {
+            self.sold_quantity -= quantity;
+        }
+
+        self.last_price = price;
+
+        let net_quantity = self.net_quantity + quantity;
+
+        if self.net_quantity.abs() < QUANTITY_TOLERANCE || self.net_quantity.signum() == quantity.signum() {
+            // Opening or increasing the position.
+            self.average_cost = (self.net_quantity.abs() * self.average_cost + quantity.abs() * price) / net_quantity.abs();
+        }
+        else {
+            // Reducing, closing, or reversing the position.
+            let closed = quantity.abs().min(self.net_quantity.abs());
+            self.realised_pnl += closed * (price - self.average_cost) * self.net_quantity.signum();
+            if net_quantity.abs() < QUANTITY_TOLERANCE {
+                self.average_cost = 0.0;
+            }
+            else if net_quantity.signum() != self.net_quantity.signum() {
+                self.average_cost = price;
+            }
+        }
+
+        self.net_quantity = if net_quantity.abs() < QUANTITY_TOLERANCE { 0.0 } else { net_quantity };
+    }
+
+    // The value of the position at the last fill price, negative when short.
+    pub fn net_exposure(&self) -> f64
+    {
+        self.net_quantity * self.last_price
+    }
+
+    pub fn gross_exposure(&self) -> f64
+    {
+        self.net_exposure().abs()
+    }
+
+}
+
+// Maintains positions per Account and Symbol from the fills in ExecutionReports. Busts and
+// corrections can refer to any earlier fill so the affected position is rebuilt from its fills.
+#[derive(Default)]
+pub struct PositionKeeper {
+    positions: IndexMap<(String, String), Position>,
+    fills: IndexMap<(String, String), Vec<PositionFill>>
+}
+
+impl PositionKeeper {
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position>
+    {
+        self.positions.values()
+    }
+
+    pub fn position(&self, account: &str, symbol: &str) -> Option<&Position>
+    {
+        self.positions.get(&(account.to_string(), symbol.to_string()))
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.positions.clear();
+        self.fills.clear();
+    }
+
+    // Apply any fill, bust, or correction in an ExecutionReport. ExecutionReports don't always
+    // carry the Account, Symbol, and Side so they are taken from the order if it is supplied.
+    pub fn process(&mut self, execution_report: &Message, order: Option<&Order>) -> Result<(), Error>
+    {
+        let Some(execution) = Execution::from_execution_report(execution_report)? else {
+            return Ok(());
+        };
+
+        let value = |tag: u32| {
+            execution_report.fields
+                .try_get(tag)
+                .or_else(|| order.and_then(|order| order.fields.try_get(tag)))
+                .map(|field| field.value.as_str())
+        };
+
+        let account = value(crate::FIX_5_0SP2::Account::TAG).unwrap_or_default().to_string();
+        let Some(symbol) = value(crate::FIX_5_0SP2::Symbol::TAG) else {
+            return Err(Error::MessageDoesNotContainFieldWithTag(crate::FIX_5_0SP2::Symbol::TAG));
+        };
+        let key = (account, symbol.to_string());
+
+        let fills = self.fills.entry(key.clone()).or_default();
+
+        // Busts and corrections refer to the fill they adjust by ExecRefID.
+        if execution.is_trade_cancel() || execution.is_trade_correct() {
+            let Some(exec_ref_id) = &execution.exec_ref_id else {
+                return Err(Error::MessageDoesNotContainFieldWithTag(crate::FIX_5_0SP2::ExecRefID::TAG));
+            };
+            let Some(index) = fills.iter().position(|fill| fill.exec_ids.contains(exec_ref_id)) else {
+                return Err(Error::OrderBookDoesNotContainExecution(exec_ref_id.clone()));
+            };
+            if execution.is_trade_cancel() {
+                fills.remove(index);
+            }
+            else {
+                let fill = &mut fills[index];
+                fill.exec_ids.push(execution.exec_id.clone());
+                fill.quantity = execution.last_qty * fill.quantity.signum();
+                fill.price = execution.last_px;
+            }
+            self.rebuild(&key);
+            return Ok(());
+        }
+
+        // Ignore fills we've already seen e.g. resent with PossDupFlag.
+        if fills.iter().any(|fill| fill.exec_ids.contains(&execution.exec_id)) {
+            return Ok(());
+        }
+
+        let Some(side) = value(crate::FIX_5_0SP2::Side::TAG) else {
+            return Err(Error::MessageDoesNotContainFieldWithTag(crate::FIX_5_0SP2::Side::TAG));
+        };
+
+        let quantity = if is_buy(side) { execution.last_qty } else { -execution.last_qty };
+
+        fills.push(PositionFill {
+            exec_ids: vec![execution.exec_id.clone()],
+            quantity,
+            price: execution.last_px
+        });
+
+        self.positions
+            .entry(key.clone())
+            .or_insert_with(|| Position::new(&key.0, &key.1))
+            .apply(quantity, execution.last_px);
+
+        Ok(())
+    }
+
+    fn rebuild(&mut self, key: &(String, String))
+    {
+        let mut position = Position::new(&key.0, &key.1);
+        if let Some(fills) = self.fills.get(key) {
+            for fill in fills {
+                position.apply(fill.quantity, fill.price);
+            }
+        }
+        self.positions.insert(key.clone(), position);
+    }
+
+}
+
+fn is_buy(side: &str) -> bool
+{
+    side == crate::FIX_5_0SP2::Side::Buy().value ||
+    side == crate::FIX_5_0SP2::Side::BuyMinus().value
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    fn fill(exec_id: &str, side: &str, quantity: &str, price: &str) -> Result<Message, crate::error::Error>
+    {
+        decode_message(&format!("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}1=A1\u{0001}55=BHP.AX\u{0001}54={}\u{0001}17={}\u{0001}150=F\u{0001}32={}\u{0001}31={}\u{0001}10=000\u{0001}", side, exec_id, quantity, price))
+    }
+
+    #[test]
+    pub fn average_cost_and_realised_pnl() -> Result<(), crate::error::Error>
+    {
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&fill("1", "1", "100", "10")?, None)?;
+        keeper.process(&fill("2", "1", "100", "12")?, None)?;
+        keeper.process(&fill("3", "2", "150", "13")?, None)?;
+
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.bought_quantity, 200.0);
+        assert_eq!(position.sold_quantity, 150.0);
+        assert_eq!(position.net_quantity, 50.0);
+        assert_eq!(position.average_cost, 11.0);
+        assert_eq!(position.realised_pnl, 300.0);
+        assert_eq!(position.net_exposure(), 650.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn reversing_a_position() -> Result<(), crate::error::Error>
+    {
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&fill("1", "1", "100", "10")?, None)?;
+        keeper.process(&fill("2", "5", "300", "9")?, None)?;
+
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.net_quantity, -200.0);
+        assert_eq!(position.average_cost, 9.0);
+        assert_eq!(position.realised_pnl, -100.0);
+        assert_eq!(position.net_exposure(), -1800.0);
+        assert_eq!(position.gross_exposure(), 1800.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn busts_and_corrections_rebuild_the_position() -> Result<(), crate::error::Error>
+    {
+        let bust = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}1=A1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}17=4\u{0001}19=1\u{0001}150=H\u{0001}10=000\u{0001}")?;
+        let correction = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}1=A1\u{0001}55=BHP.AX\u{0001}54=2\u{0001}17=5\u{0001}19=3\u{0001}150=G\u{0001}32=50\u{0001}31=14\u{0001}10=000\u{0001}")?;
+
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&fill("1", "1", "100", "10")?, None)?;
+        keeper.process(&fill("2", "1", "100", "12")?, None)?;
+        keeper.process(&fill("3", "2", "150", "13")?, None)?;
+        keeper.process(&bust, None)?;
+
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.net_quantity, -50.0);
+        assert_eq!(position.realised_pnl, 100.0);
+
+        keeper.process(&correction, None)?;
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.net_quantity, 50.0);
+        assert_eq!(position.average_cost, 12.0);
+        assert_eq!(position.realised_pnl, 100.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn duplicate_fills_are_ignored() -> Result<(), crate::error::Error>
+    {
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&fill("1", "1", "100", "10")?, None)?;
+        keeper.process(&fill("1", "1", "100", "10")?, None)?;
+        assert_eq!(keeper.position("A1", "BHP.AX").unwrap().net_quantity, 100.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn zero_quantity_fills_do_not_change_the_position() -> Result<(), crate::error::Error>
+    {
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&fill("1", "1", "0", "10")?, None)?;
+
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.net_quantity, 0.0);
+        assert_eq!(position.average_cost, 0.0);
+
+        keeper.process(&fill("2", "1", "100", "12")?, None)?;
+        let position = keeper.position("A1", "BHP.AX").unwrap();
+        assert_eq!(position.net_quantity, 100.0);
+        assert_eq!(position.average_cost, 12.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn side_is_taken_from_the_order() -> Result<(), crate::error::Error>
+    {
+        let order = Order::new(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}11=1\u{0001}1=A2\u{0001}55=RIO.AX\u{0001}54=2\u{0001}38=100\u{0001}10=000\u{0001}")?)?;
+        let report = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}11=1\u{0001}17=1\u{0001}150=F\u{0001}32=100\u{0001}31=100\u{0001}10=000\u{0001}")?;
+        let mut keeper = PositionKeeper::default();
+        keeper.process(&report, Some(&order))?;
+        assert_eq!(keeper.position("A2", "RIO.AX").unwrap().net_quantity, -100.0);
+        Ok(())
+    }
+
+}
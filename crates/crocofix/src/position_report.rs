@@ -0,0 +1,130 @@
+use crate::position::{Position, PositionKeeper};
+use crate::field::format_decimal;
+use std::io::Write;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+const HEADERS: [&str; 10] = ["Account", "Symbol", "Bought", "Sold", "Net", "AvgCost", "RealisedPnL", "LastPx", "NetExposure", "GrossExposure"];
+const NUMERIC_COLUMNS: [usize; 8] = [2, 3, 4, 5, 6, 7, 8, 9];
+
+// Prints a table of positions with a total row for each account.
+#[derive(Default)]
+pub struct PositionReport {
+}
+
+impl PositionReport
+{
+    fn row(values: [String; 10]) -> Vec<Cell>
+    {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                Cell::new(value).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+            })
+            .collect()
+    }
+
+    fn position_row(position: &Position, first: bool) -> Vec<Cell>
+    {
+        PositionReport::row([
+            if first { position.account.clone() } else { String::new() },
+            position.symbol.clone(),
+            format_decimal(position.bought_quantity),
+            format_decimal(position.sold_quantity),
+            format_decimal(position.net_quantity),
+            format_decimal(position.average_cost),
+            format_decimal(position.realised_pnl),
+            format_decimal(position.last_price),
+            format_decimal(position.net_exposure()),
+            format_decimal(position.gross_exposure())
+        ])
+    }
+
+    fn total_row(positions: &[&Position]) -> Vec<Cell>
+    {
+        PositionReport::row([
+            String::new(),
+            "Total".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            format_decimal(positions.iter().map(|position| position.realised_pnl).sum()),
+            String::new(),
+            format_decimal(positions.iter().map(|position| position.net_exposure()).sum()),
+            format_decimal(positions.iter().map(|position| position.gross_exposure()).sum())
+        ])
+    }
+
+    pub fn print<W: Write>(&self, writer: &mut W, position_keeper: &PositionKeeper) -> std::io::Result<()>
+    {
+        let mut table = Table::default();
+
+        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+            .set_style(TableComponent::HeaderLines, '-')
+            .set_style(TableComponent::MiddleHeaderIntersections, '-')
+        ;
+
+        table.set_header(HEADERS.iter().enumerate().map(|(index, header)| {
+            Cell::new(header).set_alignment(if NUMERIC_COLUMNS.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+        }));
+
+        let mut accounts: Vec<&str> = Vec::new();
+        for position in position_keeper.positions() {
+            if !accounts.contains(&position.account.as_str()) {
+                accounts.push(position.account.as_str());
+            }
+        }
+
+        for account in accounts {
+            let positions: Vec<&Position> = position_keeper.positions().filter(|position| position.account == account).collect();
+            for (index, position) in positions.iter().enumerate() {
+                table.add_row(PositionReport::position_row(position, index == 0));
+            }
+            table.add_row(PositionReport::total_row(&positions));
+        }
+
+        writer.write_all(format!("{}\n\n", table).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn positions_are_totalled_by_account() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}1=A1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}17=1\u{0001}150=F\u{0001}32=100\u{0001}31=10\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}1=A1\u{0001}55=RIO.AX\u{0001}54=2\u{0001}17=2\u{0001}150=F\u{0001}32=10\u{0001}31=100\u{0001}10=000\u{0001}"
+        ];
+        let mut keeper = PositionKeeper::default();
+        for text in messages {
+            keeper.process(&decode_message(text)?, None)?;
+        }
+
+        let mut output = Vec::new();
+        PositionReport::default().print(&mut output, &keeper)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[1], "| Account   Symbol   Bought   Sold   Net   AvgCost   RealisedPnL   LastPx   NetExposure   GrossExposure |");
+        assert_eq!(lines[3], "| A1        BHP.AX      100      0   100        10             0       10          1000            1000 |");
+        assert_eq!(lines[4], "|           RIO.AX        0     10   -10       100             0      100         -1000            1000 |");
+        assert_eq!(lines[5], "|           Total                                              0                      0            2000 |");
+        Ok(())
+    }
+
+}
@@ -0,0 +1,282 @@
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+
+// The members of the NoOrders group in an AllocationInstruction or AllocationReport, it is
+// followed by other groups so it can't be read to the end of the message.
+const ALLOCATION_ORDER_FIELDS: [u32; 8] = [
+    crate::FIX_5_0SP2::ClOrdID::TAG,
+    crate::FIX_5_0SP2::OrderID::TAG,
+    crate::FIX_5_0SP2::SecondaryOrderID::TAG,
+    crate::FIX_5_0SP2::SecondaryClOrdID::TAG,
+    crate::FIX_5_0SP2::ListID::TAG,
+    crate::FIX_5_0SP2::OrderQty::TAG,
+    crate::FIX_5_0SP2::OrderAvgPx::TAG,
+    crate::FIX_5_0SP2::OrderBookingQty::TAG
+];
+
+fn decimal(fields: &FieldCollection, tag: u32) -> Option<f64>
+{
+    fields.try_get(tag).and_then(|field| field.decimal_value().ok())
+}
+
+fn value(fields: &FieldCollection, tag: u32) -> Option<String>
+{
+    fields.try_get(tag).map(|field| field.value.clone())
+}
+
+// An order referenced by a post-trade message, it is matched with an order in the OrderBook by
+// ClOrdID or OrderID.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderReference {
+    pub cl_ord_id: Option<String>,
+    pub order_id: Option<String>,
+    pub side: Option<String>
+}
+
+impl OrderReference {
+
+    pub fn from_fields(fields: &FieldCollection) -> Self
+    {
+        Self {
+            cl_ord_id: value(fields, crate::FIX_5_0SP2::ClOrdID::TAG),
+            order_id: value(fields, crate::FIX_5_0SP2::OrderID::TAG),
+            side: value(fields, crate::FIX_5_0SP2::Side::TAG)
+        }
+    }
+
+    // e.g. ClOrdID=1 or OrderID=A1
+    pub fn describe(&self) -> String
+    {
+        match (&self.cl_ord_id, &self.order_id) {
+            (Some(cl_ord_id), _) => format!("ClOrdID={}", cl_ord_id),
+            (None, Some(order_id)) => format!("OrderID={}", order_id),
+            (None, None) => String::new()
+        }
+    }
+
+}
+
+// An instance of the NoAllocs group.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllocationAccount {
+    pub account: String,
+    pub individual_alloc_id: Option<String>,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>
+}
+
+impl AllocationAccount {
+
+    pub fn from_fields(fields: &FieldCollection) -> Self
+    {
+        Self {
+            account: value(fields, crate::FIX_5_0SP2::AllocAccount::TAG).unwrap_or_default(),
+            individual_alloc_id: value(fields, crate::FIX_5_0SP2::IndividualAllocID::TAG),
+            quantity: decimal(fields, crate::FIX_5_0SP2::AllocQty::TAG),
+            price: decimal(fields, crate::FIX_5_0SP2::AllocPrice::TAG)
+        }
+    }
+
+}
+
+// An AllocationInstruction, or an AllocationReport for an allocation we haven't seen the
+// instruction for, with the acknowledgements and reports that followed it. Allocations are
+// identified by the SenderCompID and TargetCompID of the first message and the AllocID.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Allocation {
+    pub key: String,
+    // Every AllocID the allocation has had, a replace with a new AllocID adds one.
+    pub alloc_ids: Vec<String>,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub messages: Vec<Message>,
+    pub symbol: Option<String>,
+    pub side: Option<String>,
+    pub quantity: Option<f64>,
+    pub avg_px: Option<f64>,
+    pub orders: Vec<OrderReference>,
+    pub accounts: Vec<AllocationAccount>,
+    // The AllocStatus of the most recent AllocationInstructionAck or AllocationReport.
+    pub alloc_status: Option<String>,
+    pub alloc_rej_code: Option<String>,
+    pub canceled: bool
+}
+
+impl Allocation {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, alloc_id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, alloc_id)
+    }
+
+    pub fn new(message: &Message, sender_comp_id: &str, target_comp_id: &str, alloc_id: &str) -> Self
+    {
+        let mut allocation = Self {
+            key: Allocation::create_key(sender_comp_id, target_comp_id, alloc_id),
+            alloc_ids: vec![alloc_id.to_string()],
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            ..Default::default()
+        };
+        allocation.update(message);
+        allocation
+    }
+
+    pub fn alloc_id(&self) -> &str
+    {
+        self.alloc_ids.last().map(|alloc_id| alloc_id.as_str()).unwrap_or_default()
+    }
+
+    // Was this allocation exchanged between these comp ids in either direction.
+    pub fn is_between(&self, sender_comp_id: &str, target_comp_id: &str) -> bool
+    {
+        (self.sender_comp_id == sender_comp_id && self.target_comp_id == target_comp_id) ||
+        (self.sender_comp_id == target_comp_id && self.target_comp_id == sender_comp_id)
+    }
+
+    // Replace the details with those of a new, replacement, or report message, an AllocationReport
+    // may omit the groups if they haven't changed.
+    pub fn update(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+
+        let fields = &message.fields;
+        self.symbol = value(fields, crate::FIX_5_0SP2::Symbol::TAG).or(self.symbol.take());
+        self.side = value(fields, crate::FIX_5_0SP2::Side::TAG).or(self.side.take());
+        self.quantity = decimal(fields, crate::FIX_5_0SP2::Quantity::TAG).or(self.quantity);
+        self.avg_px = decimal(fields, crate::FIX_5_0SP2::AvgPx::TAG).or(self.avg_px);
+
+        let orders: Vec<OrderReference> = fields.group(crate::FIX_5_0SP2::NoOrders::TAG, &ALLOCATION_ORDER_FIELDS).iter().map(OrderReference::from_fields).collect();
+        if !orders.is_empty() {
+            self.orders = orders;
+        }
+
+        let accounts: Vec<AllocationAccount> = fields.group(crate::FIX_5_0SP2::NoAllocs::TAG, &[]).iter().map(AllocationAccount::from_fields).collect();
+        if !accounts.is_empty() {
+            self.accounts = accounts;
+        }
+
+        self.set_status(message);
+    }
+
+    // Record an AllocationInstructionAck or the status of an AllocationReport.
+    pub fn set_status(&mut self, message: &Message)
+    {
+        if let Some(alloc_status) = value(&message.fields, crate::FIX_5_0SP2::AllocStatus::TAG) {
+            self.alloc_status = Some(alloc_status);
+            self.alloc_rej_code = value(&message.fields, crate::FIX_5_0SP2::AllocRejCode::TAG);
+        }
+    }
+
+    pub fn is_rejected(&self) -> bool
+    {
+        [
+            crate::FIX_5_0SP2::AllocStatus::BlockLevelReject().value,
+            crate::FIX_5_0SP2::AllocStatus::AccountLevelReject().value,
+            crate::FIX_5_0SP2::AllocStatus::RejectedByIntermediary().value
+        ]
+        .contains(&self.alloc_status.as_deref().unwrap_or_default())
+    }
+
+    // The total AllocQty of the accounts.
+    pub fn allocated_quantity(&self) -> f64
+    {
+        self.accounts.iter().filter_map(|account| account.quantity).sum()
+    }
+
+}
+
+// A Confirmation of the allocation to one account, identified by the SenderCompID, TargetCompID,
+// and ConfirmID.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Confirmation {
+    pub key: String,
+    pub confirm_id: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub messages: Vec<Message>,
+    pub alloc_id: Option<String>,
+    pub account: Option<String>,
+    pub quantity: Option<f64>,
+    pub avg_px: Option<f64>,
+    pub confirm_status: Option<String>,
+    pub canceled: bool
+}
+
+impl Confirmation {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, confirm_id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, confirm_id)
+    }
+
+    pub fn new(sender_comp_id: &str, target_comp_id: &str, confirm_id: &str) -> Self
+    {
+        Self {
+            key: Confirmation::create_key(sender_comp_id, target_comp_id, confirm_id),
+            confirm_id: confirm_id.to_string(),
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+
+        let fields = &message.fields;
+        self.alloc_id = value(fields, crate::FIX_5_0SP2::AllocID::TAG).or(self.alloc_id.take());
+        self.account = value(fields, crate::FIX_5_0SP2::AllocAccount::TAG).or(self.account.take());
+        self.quantity = decimal(fields, crate::FIX_5_0SP2::AllocQty::TAG).or(self.quantity);
+        self.avg_px = decimal(fields, crate::FIX_5_0SP2::AvgPx::TAG).or(self.avg_px);
+        self.confirm_status = value(fields, crate::FIX_5_0SP2::ConfirmStatus::TAG).or(self.confirm_status.take());
+        self.canceled = fields.try_get(crate::FIX_5_0SP2::ConfirmTransType::TAG).is_some_and(|field| field.value == crate::FIX_5_0SP2::ConfirmTransType::Cancel().value);
+    }
+
+}
+
+// A TradeCaptureReport, each side may reference an order.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeCapture {
+    pub trade_report_id: String,
+    pub messages: Vec<Message>,
+    pub exec_id: Option<String>,
+    pub symbol: Option<String>,
+    pub last_qty: Option<f64>,
+    pub last_px: Option<f64>,
+    pub sides: Vec<OrderReference>
+}
+
+impl TradeCapture {
+
+    pub fn new(trade_report_id: &str) -> Self
+    {
+        Self {
+            trade_report_id: trade_report_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+
+        let fields = &message.fields;
+        self.exec_id = value(fields, crate::FIX_5_0SP2::ExecID::TAG).or(self.exec_id.take());
+        self.symbol = value(fields, crate::FIX_5_0SP2::Symbol::TAG).or(self.symbol.take());
+        self.last_qty = decimal(fields, crate::FIX_5_0SP2::LastQty::TAG).or(self.last_qty);
+        self.last_px = decimal(fields, crate::FIX_5_0SP2::LastPx::TAG).or(self.last_px);
+
+        let sides: Vec<OrderReference> = fields.group(crate::FIX_5_0SP2::NoSides::TAG, &[]).iter().map(OrderReference::from_fields).collect();
+        if !sides.is_empty() {
+            self.sides = sides;
+        }
+    }
+
+}
@@ -0,0 +1,523 @@
+use indexmap::IndexMap;
+use crate::field::{format_decimal, AVG_PX_TOLERANCE, QUANTITY_TOLERANCE};
+use crate::message::Message;
+use crate::order::Order;
+use crate::order_book::OrderBook;
+use crate::post_trade::{Allocation, Confirmation, OrderReference, TradeCapture};
+use crate::error::Error;
+
+
+// A post-trade message that disagrees with the orders or allocation it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    // The name of the message type e.g. AllocationInstruction.
+    pub source: String,
+    // The AllocID, ConfirmID, or TradeReportID.
+    pub id: String,
+    // The order, account, or allocation the message was compared with.
+    pub reference: String,
+    pub description: String
+}
+
+impl Mismatch {
+
+    fn new(source: &str, id: &str, reference: &str, description: String) -> Self
+    {
+        Self {
+            source: source.to_string(),
+            id: id.to_string(),
+            reference: reference.to_string(),
+            description
+        }
+    }
+
+}
+
+// Tracks allocations, confirmations, and trade capture reports so they can be reconciled with the
+// fills in an OrderBook.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PostTradeBook {
+    pub allocations: IndexMap<String, Allocation>,
+    pub confirmations: IndexMap<String, Confirmation>,
+    pub trade_captures: IndexMap<String, TradeCapture>
+}
+
+impl PostTradeBook {
+
+    pub fn process(&mut self, message: &Message) -> Result<(), Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::AllocationInstruction().value {
+            return self.process_allocation_instruction(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::AllocationInstructionAck().value {
+            return self.process_allocation_instruction_ack(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::AllocationReport().value {
+            return self.process_allocation_report(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::Confirmation().value {
+            return self.process_confirmation(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::TradeCaptureReport().value {
+            return self.process_trade_capture_report(message);
+        }
+
+        Err(Error::UnsupportedMsgType(msg_type.to_string()))
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.allocations.clear();
+        self.confirmations.clear();
+        self.trade_captures.clear();
+    }
+
+    fn comp_ids(message: &Message) -> Result<(&str, &str), Error>
+    {
+        Ok((
+            message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str(),
+            message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str()
+        ))
+    }
+
+    // Find an allocation exchanged between the comp ids in either direction that has had this AllocID.
+    fn find_allocation_key(&self, sender_comp_id: &str, target_comp_id: &str, alloc_id: &str) -> Option<String>
+    {
+        self.allocations
+            .values()
+            .find(|allocation| allocation.is_between(sender_comp_id, target_comp_id) && allocation.alloc_ids.iter().any(|id| id == alloc_id))
+            .map(|allocation| allocation.key.clone())
+    }
+
+    fn is_trans_type(message: &Message, trans_type: &str) -> bool
+    {
+        message.fields.try_get(crate::FIX_5_0SP2::AllocTransType::TAG).is_some_and(|field| field.value == trans_type)
+    }
+
+    fn process_allocation_instruction(&mut self, instruction: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = PostTradeBook::comp_ids(instruction)?;
+        let alloc_id = instruction.fields.get(crate::FIX_5_0SP2::AllocID::TAG)?.value.as_str();
+
+        let is_replace = PostTradeBook::is_trans_type(instruction, crate::FIX_5_0SP2::AllocTransType::Replace().value);
+        let is_cancel = PostTradeBook::is_trans_type(instruction, crate::FIX_5_0SP2::AllocTransType::Cancel().value);
+
+        if !is_replace && !is_cancel {
+            let key = Allocation::create_key(sender_comp_id, target_comp_id, alloc_id);
+            if self.allocations.contains_key(&key) {
+                return Err(Error::PostTradeBookAlreadyContainsAllocationWithKey(key));
+            }
+            self.allocations.insert(key, Allocation::new(instruction, sender_comp_id, target_comp_id, alloc_id));
+            return Ok(());
+        }
+
+        // A replace or cancel references the allocation it applies to with RefAllocID, it may have
+        // a new AllocID or reuse the original.
+        let ref_alloc_id = instruction.fields.try_get(crate::FIX_5_0SP2::RefAllocID::TAG).map(|field| field.value.as_str()).unwrap_or(alloc_id);
+        let Some(key) = self.find_allocation_key(sender_comp_id, target_comp_id, ref_alloc_id) else {
+            return Err(Error::PostTradeBookDoesNotContainAllocationWithID(ref_alloc_id.to_string()));
+        };
+        let Some(allocation) = self.allocations.get_mut(&key) else {
+            return Err(Error::PostTradeBookDoesNotContainAllocationWithID(ref_alloc_id.to_string()));
+        };
+
+        if is_cancel {
+            allocation.messages.push(instruction.clone());
+            allocation.canceled = true;
+            return Ok(());
+        }
+
+        allocation.update(instruction);
+        if allocation.alloc_id() != alloc_id {
+            allocation.alloc_ids.push(alloc_id.to_string());
+        }
+
+        Ok(())
+    }
+
+    fn process_allocation_instruction_ack(&mut self, ack: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = PostTradeBook::comp_ids(ack)?;
+        let alloc_id = ack.fields.get(crate::FIX_5_0SP2::AllocID::TAG)?.value.as_str();
+
+        let Some(allocation) = self.find_allocation_key(sender_comp_id, target_comp_id, alloc_id).and_then(|key| self.allocations.get_mut(&key)) else {
+            return Err(Error::PostTradeBookDoesNotContainAllocationWithID(alloc_id.to_string()));
+        };
+
+        allocation.messages.push(ack.clone());
+        allocation.set_status(ack);
+
+        Ok(())
+    }
+
+    // An AllocationReport updates the allocation it reports on, or creates one if the instruction
+    // wasn't seen e.g. a sell side calculated allocation.
+    fn process_allocation_report(&mut self, report: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = PostTradeBook::comp_ids(report)?;
+        let alloc_id = match report.fields.try_get(crate::FIX_5_0SP2::AllocID::TAG) {
+            Some(field) => field.value.as_str(),
+            None => report.fields.get(crate::FIX_5_0SP2::AllocReportID::TAG)?.value.as_str()
+        };
+
+        let Some(allocation) = self.find_allocation_key(sender_comp_id, target_comp_id, alloc_id).and_then(|key| self.allocations.get_mut(&key)) else {
+            let allocation = Allocation::new(report, sender_comp_id, target_comp_id, alloc_id);
+            self.allocations.insert(allocation.key.clone(), allocation);
+            return Ok(());
+        };
+
+        if PostTradeBook::is_trans_type(report, crate::FIX_5_0SP2::AllocTransType::Cancel().value) {
+            allocation.messages.push(report.clone());
+            allocation.canceled = true;
+            return Ok(());
+        }
+
+        allocation.update(report);
+
+        Ok(())
+    }
+
+    fn process_confirmation(&mut self, confirmation: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = PostTradeBook::comp_ids(confirmation)?;
+        let confirm_id = confirmation.fields.get(crate::FIX_5_0SP2::ConfirmID::TAG)?.value.as_str();
+
+        let reversed = Confirmation::create_key(target_comp_id, sender_comp_id, confirm_id);
+        let key = if self.confirmations.contains_key(&reversed) { reversed } else { Confirmation::create_key(sender_comp_id, target_comp_id, confirm_id) };
+
+        self.confirmations
+            .entry(key)
+            .or_insert_with(|| Confirmation::new(sender_comp_id, target_comp_id, confirm_id))
+            .update(confirmation);
+
+        Ok(())
+    }
+
+    fn process_trade_capture_report(&mut self, report: &Message) -> Result<(), Error>
+    {
+        let trade_report_id = report.fields.get(crate::FIX_5_0SP2::TradeReportID::TAG)?.value.as_str();
+
+        self.trade_captures
+            .entry(trade_report_id.to_string())
+            .or_insert_with(|| TradeCapture::new(trade_report_id))
+            .update(report);
+
+        Ok(())
+    }
+
+    // The current version of the order a post-trade message refers to, matched by ClOrdID if
+    // present otherwise OrderID.
+    pub fn find_order<'a>(order_book: &'a OrderBook, reference: &OrderReference) -> Option<&'a Order>
+    {
+        let order = match (&reference.cl_ord_id, &reference.order_id) {
+            (Some(cl_ord_id), _) => order_book.orders.values().find(|order| &order.cl_ord_id.value == cl_ord_id),
+            (None, Some(order_id)) => order_book.orders.values().find(|order| order.fields.try_get(crate::FIX_5_0SP2::OrderID::TAG).is_some_and(|field| &field.value == order_id)),
+            (None, None) => None
+        }?;
+        order_book.latest(&order.key)
+    }
+
+    // Compare the allocations with the fills of the orders they allocate, the confirmations with
+    // the allocations, and the trade capture reports with the fills.
+    pub fn mismatches(&self, order_book: &OrderBook) -> Vec<Mismatch>
+    {
+        let mut mismatches = Vec::new();
+
+        for allocation in self.allocations.values().filter(|allocation| !allocation.canceled) {
+            mismatches.extend(PostTradeBook::allocation_mismatches(allocation, order_book));
+        }
+
+        for confirmation in self.confirmations.values().filter(|confirmation| !confirmation.canceled) {
+            mismatches.extend(self.confirmation_mismatches(confirmation));
+        }
+
+        for trade_capture in self.trade_captures.values() {
+            mismatches.extend(PostTradeBook::trade_capture_mismatches(trade_capture, order_book));
+        }
+
+        mismatches
+    }
+
+    fn allocation_mismatches(allocation: &Allocation, order_book: &OrderBook) -> Vec<Mismatch>
+    {
+        // An allocation we only saw AllocationReports for is reported as one.
+        let source = if allocation.messages.first().and_then(|message| message.msg_type()) == Some(crate::FIX_5_0SP2::MsgType::AllocationReport().value) {
+            "AllocationReport"
+        }
+        else {
+            "AllocationInstruction"
+        };
+
+        let mut mismatches = Vec::new();
+        let alloc_id = allocation.alloc_id();
+        let mismatch = |reference: &str, description: String| Mismatch::new(source, alloc_id, reference, description);
+
+        if allocation.is_rejected() {
+            let alloc_status = allocation.alloc_status.as_deref().unwrap_or_default();
+            let mut description = format!("AllocStatus is {}", crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::AllocStatus::TAG as usize, alloc_status).unwrap_or(alloc_status));
+            if let Some(alloc_rej_code) = &allocation.alloc_rej_code {
+                description += &format!(" ({})", crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::AllocRejCode::TAG as usize, alloc_rej_code).unwrap_or(alloc_rej_code));
+            }
+            mismatches.push(mismatch("", description));
+        }
+
+        let mut orders: Vec<&Order> = Vec::new();
+        for reference in &allocation.orders {
+            match PostTradeBook::find_order(order_book, reference) {
+                Some(order) => {
+                    if !orders.iter().any(|existing| existing.key == order.key) {
+                        orders.push(order);
+                    }
+                    let order_side = order.fields.try_get(crate::FIX_5_0SP2::Side::TAG).map(|field| field.value.as_str());
+                    if let (Some(side), Some(order_side)) = (allocation.side.as_deref(), order_side) {
+                        if side != order_side {
+                            mismatches.push(mismatch(&reference.describe(), format!(
+                                "Side {} does not match order Side {}",
+                                crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::Side::TAG as usize, side).unwrap_or(side),
+                                crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::Side::TAG as usize, order_side).unwrap_or(order_side)
+                            )));
+                        }
+                    }
+                }
+                None => mismatches.push(mismatch(&reference.describe(), "Order not found".to_string()))
+            }
+        }
+
+        let reference = allocation.orders.iter().map(OrderReference::describe).collect::<Vec<_>>().join(" ");
+
+        if !orders.is_empty() {
+            let filled_quantity: f64 = orders.iter().map(|order| order_book.chain_filled_quantity(&order.key)).sum();
+            let notional: f64 = orders.iter().map(|order| order_book.chain_notional(&order.key)).sum();
+
+            if let Some(quantity) = allocation.quantity {
+                if (quantity - filled_quantity).abs() > QUANTITY_TOLERANCE {
+                    mismatches.push(mismatch(&reference, format!("Quantity {} does not match filled quantity {}", format_decimal(quantity), format_decimal(filled_quantity))));
+                }
+            }
+
+            if let Some(avg_px) = allocation.avg_px {
+                if filled_quantity > 0.0 && (avg_px - notional / filled_quantity).abs() > AVG_PX_TOLERANCE {
+                    mismatches.push(mismatch(&reference, format!("AvgPx {} does not match VWAP {}", format_decimal(avg_px), format_decimal(notional / filled_quantity))));
+                }
+            }
+        }
+
+        if let Some(quantity) = allocation.quantity {
+            let allocated_quantity = allocation.allocated_quantity();
+            if !allocation.accounts.is_empty() && (quantity - allocated_quantity).abs() > QUANTITY_TOLERANCE {
+                mismatches.push(mismatch(&reference, format!("AllocQty total {} does not match Quantity {}", format_decimal(allocated_quantity), format_decimal(quantity))));
+            }
+        }
+
+        mismatches
+    }
+
+    fn confirmation_mismatches(&self, confirmation: &Confirmation) -> Vec<Mismatch>
+    {
+        const SOURCE: &str = "Confirmation";
+
+        let mut mismatches = Vec::new();
+        let account = confirmation.account.as_deref().unwrap_or_default();
+        let mismatch = |description: String| Mismatch::new(SOURCE, &confirmation.confirm_id, account, description);
+
+        if let Some(confirm_status) = confirmation.confirm_status.as_deref() {
+            if [
+                crate::FIX_5_0SP2::ConfirmStatus::MismatchedAccount().value,
+                crate::FIX_5_0SP2::ConfirmStatus::MissingSettlementInstructions().value,
+                crate::FIX_5_0SP2::ConfirmStatus::RequestRejected().value
+            ].contains(&confirm_status) {
+                mismatches.push(mismatch(format!("ConfirmStatus is {}", crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::ConfirmStatus::TAG as usize, confirm_status).unwrap_or(confirm_status))));
+            }
+        }
+
+        let alloc_id = confirmation.alloc_id.as_deref().unwrap_or_default();
+        let Some(allocation) = self.find_allocation_key(&confirmation.sender_comp_id, &confirmation.target_comp_id, alloc_id).and_then(|key| self.allocations.get(&key)) else {
+            mismatches.push(mismatch(format!("AllocID {} not found", alloc_id)));
+            return mismatches;
+        };
+
+        let Some(allocation_account) = allocation.accounts.iter().find(|allocation_account| allocation_account.account == account) else {
+            mismatches.push(mismatch(format!("AllocAccount {} is not in AllocID {}", account, alloc_id)));
+            return mismatches;
+        };
+
+        if let (Some(quantity), Some(allocated_quantity)) = (confirmation.quantity, allocation_account.quantity) {
+            if (quantity - allocated_quantity).abs() > QUANTITY_TOLERANCE {
+                mismatches.push(mismatch(format!("AllocQty {} does not match allocated quantity {}", format_decimal(quantity), format_decimal(allocated_quantity))));
+            }
+        }
+
+        if let (Some(avg_px), Some(allocated_px)) = (confirmation.avg_px, allocation_account.price.or(allocation.avg_px)) {
+            if (avg_px - allocated_px).abs() > AVG_PX_TOLERANCE {
+                mismatches.push(mismatch(format!("AvgPx {} does not match allocated price {}", format_decimal(avg_px), format_decimal(allocated_px))));
+            }
+        }
+
+        mismatches
+    }
+
+    fn trade_capture_mismatches(trade_capture: &TradeCapture, order_book: &OrderBook) -> Vec<Mismatch>
+    {
+        const SOURCE: &str = "TradeCaptureReport";
+
+        let mut mismatches = Vec::new();
+
+        // Sides without a ClOrdID or OrderID are for the counterparty's orders.
+        for reference in trade_capture.sides.iter().filter(|side| side.cl_ord_id.is_some() || side.order_id.is_some()) {
+            let mismatch = |description: String| Mismatch::new(SOURCE, &trade_capture.trade_report_id, &reference.describe(), description);
+
+            let Some(order) = PostTradeBook::find_order(order_book, reference) else {
+                mismatches.push(mismatch("Order not found".to_string()));
+                continue;
+            };
+
+            let fills: Vec<_> = order_book.chain_executions(&order.key).into_iter().filter(|execution| execution.is_active()).collect();
+            let last_qty = trade_capture.last_qty.unwrap_or_default();
+            let last_px = trade_capture.last_px.unwrap_or_default();
+
+            match &trade_capture.exec_id {
+                Some(exec_id) => {
+                    let Some(fill) = fills.iter().find(|execution| &execution.exec_id == exec_id) else {
+                        mismatches.push(mismatch(format!("ExecID {} not found in the fills", exec_id)));
+                        continue;
+                    };
+                    if (fill.last_qty - last_qty).abs() > QUANTITY_TOLERANCE {
+                        mismatches.push(mismatch(format!("LastQty {} does not match fill LastQty {}", format_decimal(last_qty), format_decimal(fill.last_qty))));
+                    }
+                    if (fill.last_px - last_px).abs() > AVG_PX_TOLERANCE {
+                        mismatches.push(mismatch(format!("LastPx {} does not match fill LastPx {}", format_decimal(last_px), format_decimal(fill.last_px))));
+                    }
+                }
+                None => {
+                    let matched = fills.iter().any(|execution| (execution.last_qty - last_qty).abs() <= QUANTITY_TOLERANCE && (execution.last_px - last_px).abs() <= AVG_PX_TOLERANCE);
+                    if !matched {
+                        mismatches.push(mismatch(format!("No fill for {}@{}", format_decimal(last_qty), format_decimal(last_px))));
+                    }
+                }
+            }
+        }
+
+        mismatches
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    fn order_book(messages: &[&str]) -> Result<OrderBook, crate::error::Error>
+    {
+        let mut book = OrderBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    const ORDERS: [&str; 4] = [
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}37=A1\u{0001}17=E1\u{0001}150=0\u{0001}39=0\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}151=1000\u{0001}14=0\u{0001}6=0\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=1\u{0001}37=A1\u{0001}17=E2\u{0001}150=F\u{0001}39=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}32=600\u{0001}31=21\u{0001}151=400\u{0001}14=600\u{0001}6=21\u{0001}10=000\u{0001}",
+        "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=4\u{0001}11=1\u{0001}37=A1\u{0001}17=E3\u{0001}150=F\u{0001}39=2\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}32=400\u{0001}31=21.1\u{0001}151=0\u{0001}14=1000\u{0001}6=21.04\u{0001}10=000\u{0001}"
+    ];
+
+    #[test]
+    pub fn matching_allocation_and_confirmations_have_no_mismatches() -> Result<(), crate::error::Error>
+    {
+        let order_book = order_book(&ORDERS)?;
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=5\u{0001}70=AL1\u{0001}71=0\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=1000\u{0001}6=21.04\u{0001}73=1\u{0001}11=1\u{0001}37=A1\u{0001}78=2\u{0001}79=FUND1\u{0001}80=700\u{0001}79=FUND2\u{0001}80=300\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=P\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}70=AL1\u{0001}87=0\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AK\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}664=C1\u{0001}666=0\u{0001}665=4\u{0001}70=AL1\u{0001}79=FUND1\u{0001}80=700\u{0001}6=21.04\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AE\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}571=T1\u{0001}17=E3\u{0001}55=BHP.AX\u{0001}32=400\u{0001}31=21.1\u{0001}552=1\u{0001}54=1\u{0001}37=A1\u{0001}11=1\u{0001}10=000\u{0001}"
+        ];
+        let mut book = PostTradeBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let allocation = &book.allocations["INITIATOR-ACCEPTOR-AL1"];
+        assert_eq!(allocation.alloc_status.as_deref(), Some("0"));
+        assert_eq!(allocation.orders.len(), 1);
+        assert_eq!(allocation.accounts.len(), 2);
+        assert_eq!(allocation.allocated_quantity(), 1000.0);
+        assert_eq!(book.mismatches(&order_book), vec![]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn mismatched_quantities_and_prices_are_reported() -> Result<(), crate::error::Error>
+    {
+        let order_book = order_book(&ORDERS)?;
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=5\u{0001}70=AL1\u{0001}71=0\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=1200\u{0001}6=21.05\u{0001}73=2\u{0001}11=1\u{0001}11=9\u{0001}78=1\u{0001}79=FUND1\u{0001}80=1000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AK\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=6\u{0001}664=C1\u{0001}666=0\u{0001}665=4\u{0001}70=AL1\u{0001}79=FUND2\u{0001}80=200\u{0001}6=21.05\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=AE\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=7\u{0001}571=T1\u{0001}17=E2\u{0001}55=BHP.AX\u{0001}32=500\u{0001}31=21\u{0001}552=1\u{0001}54=1\u{0001}11=1\u{0001}10=000\u{0001}"
+        ];
+        let mut book = PostTradeBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let descriptions: Vec<String> = book.mismatches(&order_book).into_iter().map(|mismatch| format!("{} {} {} {}", mismatch.source, mismatch.id, mismatch.reference, mismatch.description)).collect();
+        assert_eq!(descriptions, vec![
+            "AllocationInstruction AL1 ClOrdID=9 Order not found",
+            "AllocationInstruction AL1 ClOrdID=1 ClOrdID=9 Quantity 1200 does not match filled quantity 1000",
+            "AllocationInstruction AL1 ClOrdID=1 ClOrdID=9 AvgPx 21.05 does not match VWAP 21.04",
+            "AllocationInstruction AL1 ClOrdID=1 ClOrdID=9 AllocQty total 1000 does not match Quantity 1200",
+            "Confirmation C1 FUND2 AllocAccount FUND2 is not in AllocID AL1",
+            "TradeCaptureReport T1 ClOrdID=1 LastQty 500 does not match fill LastQty 600"
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn replaced_and_canceled_allocations() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=5\u{0001}70=AL1\u{0001}71=0\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=1000\u{0001}78=1\u{0001}79=FUND1\u{0001}80=1000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=6\u{0001}70=AL2\u{0001}71=1\u{0001}72=AL1\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=1000\u{0001}78=2\u{0001}79=FUND1\u{0001}80=600\u{0001}79=FUND2\u{0001}80=400\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=P\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=5\u{0001}70=AL2\u{0001}87=1\u{0001}88=1\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=7\u{0001}70=AL3\u{0001}71=2\u{0001}72=AL2\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=1000\u{0001}10=000\u{0001}"
+        ];
+        let mut book = PostTradeBook::default();
+
+        book.process(&decode_message(messages[0])?)?;
+        assert_eq!(book.process(&decode_message(messages[0])?), Err(Error::PostTradeBookAlreadyContainsAllocationWithKey("INITIATOR-ACCEPTOR-AL1".to_string())));
+
+        book.process(&decode_message(messages[1])?)?;
+        book.process(&decode_message(messages[2])?)?;
+        let allocation = &book.allocations["INITIATOR-ACCEPTOR-AL1"];
+        assert_eq!(allocation.alloc_id(), "AL2");
+        assert_eq!(allocation.accounts.len(), 2);
+        assert!(allocation.is_rejected());
+        let mismatches = book.mismatches(&OrderBook::default());
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].description, "AllocStatus is BlockLevelReject (IncorrectQuantity)");
+
+        book.process(&decode_message(messages[3])?)?;
+        assert!(book.allocations["INITIATOR-ACCEPTOR-AL1"].canceled);
+        assert_eq!(book.mismatches(&OrderBook::default()), vec![]);
+        Ok(())
+    }
+
+}
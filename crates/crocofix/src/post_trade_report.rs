@@ -0,0 +1,75 @@
+use crate::order_book::OrderBook;
+use crate::post_trade_book::PostTradeBook;
+use std::io::Write;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+const HEADERS: [&str; 4] = ["Source", "ID", "Reference", "Mismatch"];
+
+// Prints a table of the allocations, confirmations, and trade capture reports that don't agree
+// with the fills in the OrderBook.
+#[derive(Default)]
+pub struct PostTradeReport {
+}
+
+impl PostTradeReport
+{
+    pub fn print<W: Write>(&self, writer: &mut W, post_trade_book: &PostTradeBook, order_book: &OrderBook) -> std::io::Result<()>
+    {
+        let mut table = Table::default();
+
+        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+            .set_style(TableComponent::HeaderLines, '-')
+            .set_style(TableComponent::MiddleHeaderIntersections, '-')
+        ;
+
+        table.set_header(HEADERS);
+
+        for mismatch in post_trade_book.mismatches(order_book) {
+            table.add_row(vec![mismatch.source, mismatch.id, mismatch.reference, mismatch.description]);
+        }
+
+        writer.write_all(format!("{}\n\n", table).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn mismatches_are_printed() -> Result<(), crate::error::Error>
+    {
+        let orders = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}11=1\u{0001}37=A1\u{0001}17=E1\u{0001}150=F\u{0001}39=2\u{0001}55=BHP.AX\u{0001}54=1\u{0001}38=1000\u{0001}32=1000\u{0001}31=21\u{0001}151=0\u{0001}14=1000\u{0001}6=21\u{0001}10=000\u{0001}"
+        ];
+        let mut order_book = OrderBook::default();
+        for text in orders {
+            order_book.process(&decode_message(text)?)?;
+        }
+
+        let mut post_trade_book = PostTradeBook::default();
+        post_trade_book.process(&decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=J\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}70=AL1\u{0001}71=0\u{0001}54=1\u{0001}55=BHP.AX\u{0001}53=900\u{0001}6=21\u{0001}73=1\u{0001}11=1\u{0001}78=1\u{0001}79=FUND1\u{0001}80=900\u{0001}10=000\u{0001}")?)?;
+
+        let mut output = Vec::new();
+        PostTradeReport::default().print(&mut output, &post_trade_book, &order_book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[1], "| Source                  ID    Reference   Mismatch                                         |");
+        assert_eq!(lines[3], "| AllocationInstruction   AL1   ClOrdID=1   Quantity 900 does not match filled quantity 1000 |");
+        Ok(())
+    }
+
+}
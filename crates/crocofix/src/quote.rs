@@ -0,0 +1,315 @@
+use crate::field_collection::{FieldCollection, RemoveOperation, SetOperation};
+use crate::message::Message;
+use crate::timestamp::parse_utc_timestamp;
+
+// Session level fields that aren't part of the state of a quote.
+const SESSION_FIELDS: [u32; 10] = [
+    crate::FIX_5_0SP2::BeginString::TAG,
+    crate::FIX_5_0SP2::BodyLength::TAG,
+    crate::FIX_5_0SP2::MsgType::TAG,
+    crate::FIX_5_0SP2::SenderCompID::TAG,
+    crate::FIX_5_0SP2::TargetCompID::TAG,
+    crate::FIX_5_0SP2::MsgSeqNum::TAG,
+    crate::FIX_5_0SP2::SendingTime::TAG,
+    crate::FIX_5_0SP2::PossDupFlag::TAG,
+    crate::FIX_5_0SP2::PossResend::TAG,
+    crate::FIX_5_0SP2::CheckSum::TAG
+];
+
+// The time a message was sent, TransactTime if present otherwise SendingTime.
+pub(crate) fn message_time(message: &Message) -> Option<&str>
+{
+    message.fields.try_get(crate::FIX_5_0SP2::TransactTime::TAG)
+        .or_else(|| message.fields.try_get(crate::FIX_5_0SP2::SendingTime::TAG))
+        .map(|field| field.value.as_str())
+}
+
+fn decimal(fields: &FieldCollection, tag: u32) -> Option<f64>
+{
+    fields.try_get(tag).and_then(|field| field.decimal_value().ok())
+}
+
+// A QuoteResponse or previously quoted NewOrderSingle that hit the bid or lifted the offer.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuoteHit {
+    pub msg_type: String,
+    pub cl_ord_id: Option<String>,
+    pub side: Option<String>,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    pub time: Option<String>,
+    // The hit was sent after the ValidUntilTime of the quote.
+    pub late: bool
+}
+
+impl QuoteHit {
+
+    // Buying lifts the offer, selling hits the bid.
+    pub fn is_lift(&self) -> bool
+    {
+        self.side.as_deref() == Some(crate::FIX_5_0SP2::Side::Buy().value)
+    }
+
+}
+
+// A Quote or an entry in a MassQuote. Quotes are identified by the SenderCompID and TargetCompID of
+// the quoter and the QuoteID, or the QuoteSetID and QuoteEntryID for a MassQuote entry.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quote {
+    pub key: String,
+    // The QuoteID of a Quote or the QuoteEntryID of a MassQuote entry.
+    pub quote_id: String,
+    pub quote_set_id: Option<String>,
+    pub quote_req_id: Option<String>,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub messages: Vec<Message>,
+    // The prices, sizes, ValidUntilTime, and the most recent QuoteStatus. A MassQuote entry also
+    // has the fields of the MassQuote and quote set it was last sent in.
+    pub fields: FieldCollection,
+    pub hits: Vec<QuoteHit>
+}
+
+impl Quote {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, quote_id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, quote_id)
+    }
+
+    pub fn create_entry_key(sender_comp_id: &str, target_comp_id: &str, quote_set_id: &str, quote_entry_id: &str) -> String
+    {
+        format!("{}-{}-{}-{}", sender_comp_id, target_comp_id, quote_set_id, quote_entry_id)
+    }
+
+    pub(crate) fn new(key: String, quote_id: &str, message: &Message) -> Self
+    {
+        let value = |tag: u32| message.fields.try_get(tag).map(|field| field.value.clone()).unwrap_or_default();
+        Quote {
+            key,
+            quote_id: quote_id.to_string(),
+            sender_comp_id: value(crate::FIX_5_0SP2::SenderCompID::TAG),
+            target_comp_id: value(crate::FIX_5_0SP2::TargetCompID::TAG),
+            ..Default::default()
+        }
+    }
+
+    // Record a message and apply the fields to the quote, for a MassQuote the fields are those of
+    // the message, quote set, and entry rather than the whole message.
+    pub(crate) fn update(&mut self, message: &Message, fields: &FieldCollection)
+    {
+        self.messages.push(message.clone());
+        for field in fields {
+            if !SESSION_FIELDS.contains(&field.tag) {
+                self.fields.set(field, SetOperation::ReplaceFirstOrAppend);
+            }
+        }
+        if let Some(quote_req_id) = fields.try_get(crate::FIX_5_0SP2::QuoteReqID::TAG) {
+            self.quote_req_id = Some(quote_req_id.value.clone());
+        }
+    }
+
+    // Record a message that is part of the history of the quote but doesn't change it e.g. a counter quote response.
+    pub(crate) fn record(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+    }
+
+    // A quote sent again replaces the previous one so an earlier QuoteStatus no longer applies.
+    pub(crate) fn refresh(&mut self, message: &Message, fields: &FieldCollection)
+    {
+        self.fields.remove(crate::FIX_5_0SP2::QuoteStatus::TAG, RemoveOperation::RemoveAll);
+        self.update(message, fields);
+    }
+
+    pub(crate) fn set_quote_status(&mut self, message: &Message, quote_status: &str)
+    {
+        self.messages.push(message.clone());
+        self.fields.set(&crate::field::Field::from_str(crate::FIX_5_0SP2::QuoteStatus::TAG, quote_status), SetOperation::ReplaceFirstOrAppend);
+    }
+
+    // Record a hit from a QuoteResponse or NewOrderSingle, a NewOrderSingle for a hit we have
+    // already seen in a QuoteResponse only adds the message.
+    pub(crate) fn hit(&mut self, message: &Message)
+    {
+        self.messages.push(message.clone());
+
+        let value = |tag: u32| message.fields.try_get(tag).map(|field| field.value.clone());
+        let cl_ord_id = value(crate::FIX_5_0SP2::ClOrdID::TAG);
+
+        if cl_ord_id.is_some() && self.hits.iter().any(|hit| hit.cl_ord_id == cl_ord_id) {
+            return;
+        }
+
+        let time = message_time(message).map(|time| time.to_string());
+        let late = time.as_deref().is_some_and(|time| self.is_expired_at(time));
+
+        self.hits.push(QuoteHit {
+            msg_type: message.msg_type().unwrap_or_default().to_string(),
+            cl_ord_id,
+            side: value(crate::FIX_5_0SP2::Side::TAG),
+            quantity: decimal(&message.fields, crate::FIX_5_0SP2::OrderQty::TAG),
+            price: decimal(&message.fields, crate::FIX_5_0SP2::Price::TAG),
+            time,
+            late
+        });
+    }
+
+    pub fn symbol(&self) -> Option<&str>
+    {
+        self.fields.try_get(crate::FIX_5_0SP2::Symbol::TAG).map(|field| field.value.as_str())
+    }
+
+    pub fn quote_status(&self) -> Option<&str>
+    {
+        self.fields.try_get(crate::FIX_5_0SP2::QuoteStatus::TAG).map(|field| field.value.as_str())
+    }
+
+    pub fn valid_until_time(&self) -> Option<&str>
+    {
+        self.fields.try_get(crate::FIX_5_0SP2::ValidUntilTime::TAG).map(|field| field.value.as_str())
+    }
+
+    pub fn bid_px(&self) -> Option<f64>
+    {
+        decimal(&self.fields, crate::FIX_5_0SP2::BidPx::TAG)
+    }
+
+    pub fn offer_px(&self) -> Option<f64>
+    {
+        decimal(&self.fields, crate::FIX_5_0SP2::OfferPx::TAG)
+    }
+
+    pub fn bid_size(&self) -> Option<f64>
+    {
+        decimal(&self.fields, crate::FIX_5_0SP2::BidSize::TAG)
+    }
+
+    pub fn offer_size(&self) -> Option<f64>
+    {
+        decimal(&self.fields, crate::FIX_5_0SP2::OfferSize::TAG)
+    }
+
+    // The quote hasn't been canceled, rejected, expired, or passed on by the counterparty. It may
+    // still be past its ValidUntilTime, see is_expired_at.
+    pub fn is_live(&self) -> bool
+    {
+        match self.quote_status() {
+            None => true,
+            Some(quote_status) => {
+                quote_status == crate::FIX_5_0SP2::QuoteStatus::Accepted().value ||
+                quote_status == crate::FIX_5_0SP2::QuoteStatus::Pending().value ||
+                quote_status == crate::FIX_5_0SP2::QuoteStatus::Query().value ||
+                quote_status == crate::FIX_5_0SP2::QuoteStatus::LockedMarketWarning().value ||
+                quote_status == crate::FIX_5_0SP2::QuoteStatus::CrossMarketWarning().value
+            }
+        }
+    }
+
+    // The quote has a ValidUntilTime earlier than time, quotes without one don't expire.
+    pub fn is_expired_at(&self, time: &str) -> bool
+    {
+        let Some(valid_until_time) = self.valid_until_time().and_then(parse_utc_timestamp) else {
+            return false;
+        };
+        parse_utc_timestamp(time).is_some_and(|time| time > valid_until_time)
+    }
+
+    pub fn is_hit(&self) -> bool
+    {
+        !self.hits.is_empty()
+    }
+
+}
+
+// A QuoteRequest and the quotes sent in response. Requests are identified by the SenderCompID and
+// TargetCompID of the requester and the QuoteReqID.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuoteRequest {
+    pub key: String,
+    pub quote_req_id: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    // The QuoteRequest and any QuoteRequestReject.
+    pub messages: Vec<Message>,
+    // The instances of the NoRelatedSym group e.g. Symbol, Side, OrderQty, and ValidUntilTime.
+    pub related_symbols: Vec<FieldCollection>,
+    pub quote_keys: Vec<String>,
+    pub rejected: bool
+}
+
+impl QuoteRequest {
+
+    pub fn create_key(sender_comp_id: &str, target_comp_id: &str, quote_req_id: &str) -> String
+    {
+        format!("{}-{}-{}", sender_comp_id, target_comp_id, quote_req_id)
+    }
+
+    pub(crate) fn new(key: String, quote_request: &Message) -> Result<Self, crate::error::Error>
+    {
+        Ok(QuoteRequest {
+            key,
+            quote_req_id: quote_request.fields.get(crate::FIX_5_0SP2::QuoteReqID::TAG)?.value.clone(),
+            sender_comp_id: quote_request.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone(),
+            target_comp_id: quote_request.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.clone(),
+            messages: vec![quote_request.clone()],
+            related_symbols: quote_request.fields.group(crate::FIX_5_0SP2::NoRelatedSym::TAG, &[]),
+            quote_keys: Vec::new(),
+            rejected: false
+        })
+    }
+
+    // The values of a field across the NoRelatedSym group e.g. the symbols quotes were requested for.
+    pub fn values(&self, tag: u32) -> Vec<&str>
+    {
+        self.related_symbols
+            .iter()
+            .filter_map(|fields| fields.try_get(tag))
+            .map(|field| field.value.as_str())
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn hits_after_the_valid_until_time_are_late() -> Result<(), crate::error::Error>
+    {
+        let message = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=S\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=2\u{0001}117=Q1\u{0001}55=EUR/USD\u{0001}132=1.085\u{0001}133=1.0852\u{0001}62=20240115-10:00:30\u{0001}10=000\u{0001}")?;
+        let in_time = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=2\u{0001}11=1\u{0001}117=Q1\u{0001}54=1\u{0001}38=1000000\u{0001}44=1.0852\u{0001}60=20240115-10:00:29.999\u{0001}10=000\u{0001}")?;
+        let late = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=3\u{0001}11=2\u{0001}117=Q1\u{0001}54=2\u{0001}38=1000000\u{0001}44=1.085\u{0001}60=20240115-10:00:31\u{0001}10=000\u{0001}")?;
+
+        let mut quote = Quote::new("DEALER-CLIENT-Q1".to_string(), "Q1", &message);
+        quote.update(&message, &message.fields);
+        assert_eq!(quote.fields.try_get(crate::FIX_5_0SP2::MsgSeqNum::TAG), None);
+        assert_eq!(quote.offer_px(), Some(1.0852));
+        assert!(quote.is_live());
+
+        quote.hit(&in_time);
+        quote.hit(&late);
+        quote.hit(&late);
+        assert_eq!(quote.hits.len(), 2);
+        assert!(quote.hits[0].is_lift());
+        assert!(!quote.hits[0].late);
+        assert!(!quote.hits[1].is_lift());
+        assert!(quote.hits[1].late);
+        assert!(quote.is_expired_at("20240115-10:01:00"));
+        Ok(())
+    }
+
+}
@@ -0,0 +1,530 @@
+use indexmap::IndexMap;
+use crate::field_collection::FieldCollection;
+use crate::message::Message;
+use crate::quote::{message_time, Quote, QuoteRequest};
+use crate::error::Error;
+
+// Tracks the RFQ and quote lifecycle alongside an OrderBook. Quotes are keyed by the quoter so
+// messages from the counterparty e.g. QuoteResponse, QuoteStatusReport, or a previously quoted
+// NewOrderSingle, are matched with the comp ids reversed.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuoteBook {
+    pub requests: IndexMap<String, QuoteRequest>,
+    pub quotes: IndexMap<String, Quote>,
+    // The TransactTime or SendingTime of the most recent message, quotes with an earlier
+    // ValidUntilTime have expired.
+    pub time: Option<String>
+}
+
+impl QuoteBook {
+
+    pub fn process(&mut self, message: &Message) -> Result<(), Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        // The time advances with every message, including those the book doesn't handle, so quotes
+        // expire even when the session is quiet apart from heartbeats.
+        if let Some(time) = message_time(message) {
+            self.time = Some(time.to_string());
+        }
+
+        self.process_message(msg_type, message)
+    }
+
+    fn process_message(&mut self, msg_type: &str, message: &Message) -> Result<(), Error>
+    {
+        if msg_type == crate::FIX_5_0SP2::MsgType::QuoteRequest().value {
+            return self.process_quote_request(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::QuoteRequestReject().value {
+            return self.process_quote_request_reject(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::Quote().value {
+            return self.process_quote(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::QuoteCancel().value {
+            return self.process_quote_cancel(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::QuoteStatusReport().value {
+            return self.process_quote_status_report(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::QuoteResponse().value {
+            return self.process_quote_response(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::MassQuote().value {
+            return self.process_mass_quote(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::MassQuoteAcknowledgement().value {
+            return self.process_mass_quote_acknowledgement(message);
+        }
+
+        if msg_type == crate::FIX_5_0SP2::MsgType::NewOrderSingle().value {
+            return self.process_order_single(message);
+        }
+
+        Err(Error::UnsupportedMsgType(msg_type.to_string()))
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.requests.clear();
+        self.quotes.clear();
+        self.time = None;
+    }
+
+    pub fn quotes_for_request(&self, request_key: &str) -> Vec<&Quote>
+    {
+        let Some(request) = self.requests.get(request_key) else {
+            return Vec::new();
+        };
+        request.quote_keys.iter().filter_map(|key| self.quotes.get(key)).collect()
+    }
+
+    // The quote is still live but its ValidUntilTime has passed.
+    pub fn is_expired(&self, quote: &Quote) -> bool
+    {
+        quote.is_live() && self.time.as_deref().is_some_and(|time| quote.is_expired_at(time))
+    }
+
+    // Quotes that can still be hit.
+    pub fn live_quotes(&self) -> impl Iterator<Item = &Quote>
+    {
+        self.quotes.values().filter(|quote| quote.is_live() && !self.is_expired(quote))
+    }
+
+    fn comp_ids(message: &Message) -> Result<(&str, &str), Error>
+    {
+        Ok((
+            message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.as_str(),
+            message.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value.as_str()
+        ))
+    }
+
+    // Find the quote a message refers to, the message may be from the counterparty or the quoter.
+    fn find_quote_key(&self, message: &Message, quote_id: &str) -> Result<String, Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(message)?;
+        let reversed = Quote::create_key(target_comp_id, sender_comp_id, quote_id);
+        if self.quotes.contains_key(&reversed) {
+            return Ok(reversed);
+        }
+        let direct = Quote::create_key(sender_comp_id, target_comp_id, quote_id);
+        if self.quotes.contains_key(&direct) {
+            return Ok(direct);
+        }
+        Err(Error::QuoteBookDoesNotContainQuoteWithKey(reversed))
+    }
+
+    fn process_quote_request(&mut self, quote_request: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(quote_request)?;
+        let quote_req_id = quote_request.fields.get(crate::FIX_5_0SP2::QuoteReqID::TAG)?.value.as_str();
+        let key = QuoteRequest::create_key(sender_comp_id, target_comp_id, quote_req_id);
+
+        if self.requests.contains_key(&key) {
+            return Err(Error::QuoteBookAlreadyContainsQuoteRequestWithKey(key));
+        }
+
+        let request = QuoteRequest::new(key.clone(), quote_request)?;
+        self.requests.insert(key, request);
+        Ok(())
+    }
+
+    fn process_quote_request_reject(&mut self, quote_request_reject: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(quote_request_reject)?;
+        let quote_req_id = quote_request_reject.fields.get(crate::FIX_5_0SP2::QuoteReqID::TAG)?.value.as_str();
+        let key = QuoteRequest::create_key(target_comp_id, sender_comp_id, quote_req_id);
+
+        let Some(request) = self.requests.get_mut(&key) else {
+            return Err(Error::QuoteBookDoesNotContainQuoteRequestWithKey(key));
+        };
+
+        request.messages.push(quote_request_reject.clone());
+        request.rejected = true;
+        Ok(())
+    }
+
+    // Link a quote to the request it responds to, quotes can also be sent without a request.
+    fn link_request(&mut self, quote_key: &str, quote: &Message)
+    {
+        let Ok((sender_comp_id, target_comp_id)) = QuoteBook::comp_ids(quote) else {
+            return;
+        };
+        let Some(quote_req_id) = quote.fields.try_get(crate::FIX_5_0SP2::QuoteReqID::TAG) else {
+            return;
+        };
+        let request_key = QuoteRequest::create_key(target_comp_id, sender_comp_id, &quote_req_id.value);
+        if let Some(request) = self.requests.get_mut(&request_key) {
+            if !request.quote_keys.iter().any(|key| key == quote_key) {
+                request.quote_keys.push(quote_key.to_string());
+            }
+        }
+    }
+
+    fn process_quote(&mut self, quote: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(quote)?;
+        let quote_id = quote.fields.get(crate::FIX_5_0SP2::QuoteID::TAG)?.value.as_str();
+        let key = Quote::create_key(sender_comp_id, target_comp_id, quote_id);
+
+        self.quotes
+            .entry(key.clone())
+            .or_insert_with(|| Quote::new(key.clone(), quote_id, quote))
+            .refresh(quote, &quote.fields);
+
+        self.link_request(&key, quote);
+        Ok(())
+    }
+
+    // The QuoteStatus of a quote canceled by a QuoteCancel, the scoped cancel types share values
+    // with the corresponding QuoteStatus.
+    fn canceled_quote_status(quote_cancel_type: Option<&str>) -> &'static str
+    {
+        let statuses = [
+            (crate::FIX_5_0SP2::QuoteCancelType::CancelForOneOrMoreSecurities(), crate::FIX_5_0SP2::QuoteStatus::CancelForSymbol()),
+            (crate::FIX_5_0SP2::QuoteCancelType::CancelForSecurityType(), crate::FIX_5_0SP2::QuoteStatus::CanceledForSecurityType()),
+            (crate::FIX_5_0SP2::QuoteCancelType::CancelForUnderlyingSecurity(), crate::FIX_5_0SP2::QuoteStatus::CanceledForUnderlying()),
+            (crate::FIX_5_0SP2::QuoteCancelType::CancelAllQuotes(), crate::FIX_5_0SP2::QuoteStatus::CanceledAll())
+        ];
+        statuses
+            .iter()
+            .find(|(cancel_type, _)| Some(cancel_type.value) == quote_cancel_type)
+            .map(|(_, quote_status)| quote_status.value)
+            .unwrap_or(crate::FIX_5_0SP2::QuoteStatus::RemovedFromMarket().value)
+    }
+
+    // A QuoteCancel for a QuoteID cancels that quote, otherwise it cancels the live quotes from
+    // the sender for the symbols, security types, or underlyings it lists, or all of them.
+    fn process_quote_cancel(&mut self, quote_cancel: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(quote_cancel)?;
+        let quote_cancel_type = quote_cancel.fields.try_get(crate::FIX_5_0SP2::QuoteCancelType::TAG).map(|field| field.value.as_str());
+        let quote_id = quote_cancel.fields.try_get(crate::FIX_5_0SP2::QuoteID::TAG).map(|field| field.value.as_str());
+
+        let specified = quote_id
+            .map(|quote_id| Quote::create_key(sender_comp_id, target_comp_id, quote_id))
+            .filter(|key| self.quotes.contains_key(key));
+
+        let keys: Vec<String> = match specified {
+            Some(key) => vec![key],
+            None => {
+                let scope_tag = if quote_cancel_type == Some(crate::FIX_5_0SP2::QuoteCancelType::CancelForOneOrMoreSecurities().value) {
+                    Some(crate::FIX_5_0SP2::Symbol::TAG)
+                }
+                else if quote_cancel_type == Some(crate::FIX_5_0SP2::QuoteCancelType::CancelForSecurityType().value) {
+                    Some(crate::FIX_5_0SP2::SecurityType::TAG)
+                }
+                else if quote_cancel_type == Some(crate::FIX_5_0SP2::QuoteCancelType::CancelForUnderlyingSecurity().value) {
+                    Some(crate::FIX_5_0SP2::UnderlyingSymbol::TAG)
+                }
+                else if quote_cancel_type == Some(crate::FIX_5_0SP2::QuoteCancelType::CancelAllQuotes().value) {
+                    None
+                }
+                else {
+                    return Err(Error::QuoteBookDoesNotContainQuoteWithKey(Quote::create_key(sender_comp_id, target_comp_id, quote_id.unwrap_or_default())));
+                };
+
+                let values: Vec<&str> = match scope_tag {
+                    Some(tag) => quote_cancel.fields.iter().filter(|field| field.tag == tag).map(|field| field.value.as_str()).collect(),
+                    None => Vec::new()
+                };
+
+                self.quotes
+                    .values()
+                    .filter(|quote| quote.sender_comp_id == sender_comp_id && quote.target_comp_id == target_comp_id && quote.is_live())
+                    .filter(|quote| match scope_tag {
+                        Some(tag) => quote.fields.try_get(tag).is_some_and(|field| values.contains(&field.value.as_str())),
+                        None => true
+                    })
+                    .map(|quote| quote.key.clone())
+                    .collect()
+            }
+        };
+
+        let quote_status = QuoteBook::canceled_quote_status(quote_cancel_type);
+        for key in keys {
+            if let Some(quote) = self.quotes.get_mut(&key) {
+                quote.set_quote_status(quote_cancel, quote_status);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_quote_status_report(&mut self, quote_status_report: &Message) -> Result<(), Error>
+    {
+        let quote_id = quote_status_report.fields.get(crate::FIX_5_0SP2::QuoteID::TAG)?.value.as_str();
+        let key = self.find_quote_key(quote_status_report, quote_id)?;
+
+        let Some(quote) = self.quotes.get_mut(&key) else {
+            return Err(Error::QuoteBookDoesNotContainQuoteWithKey(key));
+        };
+
+        match quote_status_report.fields.try_get(crate::FIX_5_0SP2::QuoteStatus::TAG) {
+            Some(quote_status) => quote.set_quote_status(quote_status_report, &quote_status.value),
+            None => quote.record(quote_status_report)
+        }
+
+        Ok(())
+    }
+
+    // A hit becomes an order, the response is recorded as a hit on the quote. An expired or
+    // passed response ends the quote and any other response e.g. a counter is recorded.
+    fn process_quote_response(&mut self, quote_response: &Message) -> Result<(), Error>
+    {
+        let quote_id = quote_response.fields.get(crate::FIX_5_0SP2::QuoteID::TAG)?.value.as_str();
+        let key = self.find_quote_key(quote_response, quote_id)?;
+
+        let Some(quote) = self.quotes.get_mut(&key) else {
+            return Err(Error::QuoteBookDoesNotContainQuoteWithKey(key));
+        };
+
+        let quote_resp_type = quote_response.fields.get(crate::FIX_5_0SP2::QuoteRespType::TAG)?;
+
+        if quote_resp_type == crate::FIX_5_0SP2::QuoteRespType::Hit() {
+            quote.hit(quote_response);
+        }
+        else if quote_resp_type == crate::FIX_5_0SP2::QuoteRespType::Expired() {
+            quote.set_quote_status(quote_response, crate::FIX_5_0SP2::QuoteStatus::Expired().value);
+        }
+        else if quote_resp_type == crate::FIX_5_0SP2::QuoteRespType::Pass() {
+            quote.set_quote_status(quote_response, crate::FIX_5_0SP2::QuoteStatus::Pass().value);
+        }
+        else {
+            quote.record(quote_response);
+        }
+
+        Ok(())
+    }
+
+    // A NewOrderSingle with a QuoteID is a hit on that quote, other orders are not quote related.
+    fn process_order_single(&mut self, order_single: &Message) -> Result<(), Error>
+    {
+        let Some(quote_id) = order_single.fields.try_get(crate::FIX_5_0SP2::QuoteID::TAG).map(|field| field.value.as_str()) else {
+            return Ok(());
+        };
+        let key = self.find_quote_key(order_single, quote_id)?;
+
+        let Some(quote) = self.quotes.get_mut(&key) else {
+            return Err(Error::QuoteBookDoesNotContainQuoteWithKey(key));
+        };
+
+        quote.hit(order_single);
+        Ok(())
+    }
+
+    // Each entry in each quote set of a MassQuote is tracked as a separate quote, a later MassQuote
+    // with the same QuoteSetID and QuoteEntryID replaces it.
+    fn process_mass_quote(&mut self, mass_quote: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(mass_quote)?;
+
+        let header = mass_quote.fields.iter().take_while(|field| field.tag != crate::FIX_5_0SP2::NoQuoteSets::TAG);
+
+        for quote_set in mass_quote.fields.group(crate::FIX_5_0SP2::NoQuoteSets::TAG, &[]) {
+            let quote_set_id = quote_set.get(crate::FIX_5_0SP2::QuoteSetID::TAG)?.value.clone();
+            let set_fields = quote_set.iter().take_while(|field| field.tag != crate::FIX_5_0SP2::NoQuoteEntries::TAG);
+
+            for entry in quote_set.group(crate::FIX_5_0SP2::NoQuoteEntries::TAG, &[]) {
+                let quote_entry_id = entry.get(crate::FIX_5_0SP2::QuoteEntryID::TAG)?.value.clone();
+                let key = Quote::create_entry_key(sender_comp_id, target_comp_id, &quote_set_id, &quote_entry_id);
+
+                let mut fields = FieldCollection::default();
+                for field in header.clone() {
+                    fields.push(field.clone());
+                }
+                for field in set_fields.clone() {
+                    fields.push(field.clone());
+                }
+                for field in &entry {
+                    fields.push(field.clone());
+                }
+
+                let quote = self.quotes.entry(key.clone()).or_insert_with(|| {
+                    let mut quote = Quote::new(key.clone(), &quote_entry_id, mass_quote);
+                    quote.quote_set_id = Some(quote_set_id.clone());
+                    quote
+                });
+                quote.refresh(mass_quote, &fields);
+            }
+        }
+
+        Ok(())
+    }
+
+    // The QuoteStatus of the acknowledgement applies to every entry of the MassQuote with the
+    // acknowledged QuoteID, entries it lists with a QuoteEntryRejectReason were rejected.
+    fn process_mass_quote_acknowledgement(&mut self, acknowledgement: &Message) -> Result<(), Error>
+    {
+        let (sender_comp_id, target_comp_id) = QuoteBook::comp_ids(acknowledgement)?;
+        let quote_status = acknowledgement.fields.get(crate::FIX_5_0SP2::QuoteStatus::TAG)?.value.as_str();
+        let quote_id = acknowledgement.fields.try_get(crate::FIX_5_0SP2::QuoteID::TAG).map(|field| field.value.as_str());
+
+        let mut statuses: IndexMap<String, &str> = self.quotes
+            .values()
+            .filter(|quote| quote.quote_set_id.is_some() && quote.sender_comp_id == target_comp_id && quote.target_comp_id == sender_comp_id)
+            .filter(|quote| quote_id.is_some() && quote.fields.try_get(crate::FIX_5_0SP2::QuoteID::TAG).map(|field| field.value.as_str()) == quote_id)
+            .map(|quote| (quote.key.clone(), quote_status))
+            .collect();
+
+        for quote_set in acknowledgement.fields.group(crate::FIX_5_0SP2::NoQuoteSets::TAG, &[]) {
+            let quote_set_id = quote_set.get(crate::FIX_5_0SP2::QuoteSetID::TAG)?.value.as_str();
+            for entry in quote_set.group(crate::FIX_5_0SP2::NoQuoteEntries::TAG, &[]) {
+                let quote_entry_id = entry.get(crate::FIX_5_0SP2::QuoteEntryID::TAG)?.value.as_str();
+                let status = if entry.try_get(crate::FIX_5_0SP2::QuoteEntryRejectReason::TAG).is_some() {
+                    crate::FIX_5_0SP2::QuoteStatus::Rejected().value
+                }
+                else {
+                    quote_status
+                };
+                statuses.insert(Quote::create_entry_key(target_comp_id, sender_comp_id, quote_set_id, quote_entry_id), status);
+            }
+        }
+
+        for (key, status) in statuses {
+            if let Some(quote) = self.quotes.get_mut(&key) {
+                quote.set_quote_status(acknowledgement, status);
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Result<Message, Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    fn process(texts: &[&str]) -> Result<QuoteBook, Error>
+    {
+        let mut book = QuoteBook::default();
+        for text in texts {
+            book.process(&decode_message(text)?)?;
+        }
+        Ok(book)
+    }
+
+    const QUOTE_REQUEST: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=R\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=2\u{0001}52=20240115-10:00:00\u{0001}131=RFQ1\u{0001}146=1\u{0001}55=EUR/USD\u{0001}54=1\u{0001}38=1000000\u{0001}10=000\u{0001}";
+    const QUOTE: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=S\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=2\u{0001}52=20240115-10:00:01\u{0001}131=RFQ1\u{0001}117=Q1\u{0001}55=EUR/USD\u{0001}132=1.085\u{0001}133=1.0852\u{0001}134=1000000\u{0001}135=1000000\u{0001}62=20240115-10:00:30\u{0001}10=000\u{0001}";
+    const HIT: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=AJ\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=3\u{0001}52=20240115-10:00:05\u{0001}693=R1\u{0001}117=Q1\u{0001}694=1\u{0001}11=H1\u{0001}55=EUR/USD\u{0001}54=1\u{0001}38=1000000\u{0001}44=1.0852\u{0001}10=000\u{0001}";
+    const ORDER: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=4\u{0001}52=20240115-10:00:05\u{0001}11=H1\u{0001}117=Q1\u{0001}55=EUR/USD\u{0001}54=1\u{0001}38=1000000\u{0001}40=D\u{0001}44=1.0852\u{0001}10=000\u{0001}";
+
+    #[test]
+    pub fn request_for_quote_hit_and_lifted() -> Result<(), Error>
+    {
+        let book = process(&[QUOTE_REQUEST, QUOTE, HIT, ORDER])?;
+
+        let request = &book.requests["CLIENT-DEALER-RFQ1"];
+        assert_eq!(request.values(crate::FIX_5_0SP2::Symbol::TAG), vec!["EUR/USD"]);
+        assert_eq!(request.quote_keys, vec!["DEALER-CLIENT-Q1"]);
+
+        let quotes = book.quotes_for_request("CLIENT-DEALER-RFQ1");
+        assert_eq!(quotes.len(), 1);
+        let quote = quotes[0];
+        assert_eq!(quote.quote_req_id.as_deref(), Some("RFQ1"));
+        assert_eq!(quote.bid_px(), Some(1.085));
+        assert_eq!(quote.valid_until_time(), Some("20240115-10:00:30"));
+        assert_eq!(quote.messages.len(), 3);
+        assert_eq!(quote.hits.len(), 1);
+        assert_eq!(quote.hits[0].cl_ord_id.as_deref(), Some("H1"));
+        assert!(quote.hits[0].is_lift());
+        assert!(!quote.hits[0].late);
+        assert!(quote.is_live());
+        assert!(!book.is_expired(quote));
+        Ok(())
+    }
+
+    #[test]
+    pub fn quotes_expire_cancel_and_report_status() -> Result<(), Error>
+    {
+        let second = "8=FIX.4.4\u{0001}9=0\u{0001}35=S\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=3\u{0001}52=20240115-10:00:02\u{0001}117=Q2\u{0001}55=USD/JPY\u{0001}132=148.1\u{0001}133=148.2\u{0001}10=000\u{0001}";
+        let third = "8=FIX.4.4\u{0001}9=0\u{0001}35=S\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=4\u{0001}52=20240115-10:00:02\u{0001}117=Q3\u{0001}55=AUD/USD\u{0001}132=0.66\u{0001}133=0.6602\u{0001}10=000\u{0001}";
+        let cancel = "8=FIX.4.4\u{0001}9=0\u{0001}35=Z\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=5\u{0001}52=20240115-10:00:40\u{0001}298=1\u{0001}295=1\u{0001}55=USD/JPY\u{0001}10=000\u{0001}";
+        let status = "8=FIX.4.4\u{0001}9=0\u{0001}35=AI\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=3\u{0001}52=20240115-10:00:41\u{0001}117=Q3\u{0001}297=5\u{0001}10=000\u{0001}";
+
+        let book = process(&[QUOTE, second, third, cancel, status])?;
+
+        // Q1 is past its ValidUntilTime.
+        assert!(book.is_expired(&book.quotes["DEALER-CLIENT-Q1"]));
+        assert_eq!(book.quotes["DEALER-CLIENT-Q2"].quote_status(), Some(crate::FIX_5_0SP2::QuoteStatus::CancelForSymbol().value));
+        assert_eq!(book.quotes["DEALER-CLIENT-Q3"].quote_status(), Some(crate::FIX_5_0SP2::QuoteStatus::Rejected().value));
+        assert_eq!(book.live_quotes().count(), 0);
+
+        let late_order = ORDER.replace("52=20240115-10:00:05", "52=20240115-10:00:45");
+        let mut book = book;
+        book.process(&decode_message(&late_order)?)?;
+        assert!(book.quotes["DEALER-CLIENT-Q1"].hits[0].late);
+        Ok(())
+    }
+
+    #[test]
+    pub fn messages_the_book_does_not_handle_advance_the_time() -> Result<(), Error>
+    {
+        let heartbeat = "8=FIX.4.4\u{0001}9=0\u{0001}35=0\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=3\u{0001}52=20240115-10:00:31\u{0001}10=000\u{0001}";
+
+        let mut book = process(&[QUOTE])?;
+        assert!(!book.is_expired(&book.quotes["DEALER-CLIENT-Q1"]));
+
+        assert_eq!(book.process(&decode_message(heartbeat)?), Err(Error::UnsupportedMsgType("0".to_string())));
+        assert!(book.is_expired(&book.quotes["DEALER-CLIENT-Q1"]));
+        Ok(())
+    }
+
+    #[test]
+    pub fn order_without_a_quote_id_is_ignored() -> Result<(), Error>
+    {
+        let order = ORDER.replace("117=Q1\u{0001}", "");
+        let book = process(&[QUOTE, &order])?;
+        assert!(book.quotes["DEALER-CLIENT-Q1"].hits.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn mass_quote_entries_are_acknowledged() -> Result<(), Error>
+    {
+        let mass_quote = "8=FIX.4.4\u{0001}9=0\u{0001}35=i\u{0001}49=MAKER\u{0001}56=EXCHANGE\u{0001}34=2\u{0001}117=MQ1\u{0001}296=1\u{0001}302=S1\u{0001}304=2\u{0001}295=2\u{0001}299=E1\u{0001}55=BHP.AX\u{0001}132=21\u{0001}133=21.1\u{0001}299=E2\u{0001}55=RIO.AX\u{0001}132=100\u{0001}133=100.5\u{0001}10=000\u{0001}";
+        let acknowledgement = "8=FIX.4.4\u{0001}9=0\u{0001}35=b\u{0001}49=EXCHANGE\u{0001}56=MAKER\u{0001}34=2\u{0001}117=MQ1\u{0001}297=0\u{0001}296=1\u{0001}302=S1\u{0001}295=1\u{0001}299=E2\u{0001}368=1\u{0001}10=000\u{0001}";
+        let update = "8=FIX.4.4\u{0001}9=0\u{0001}35=i\u{0001}49=MAKER\u{0001}56=EXCHANGE\u{0001}34=3\u{0001}117=MQ2\u{0001}296=1\u{0001}302=S1\u{0001}295=1\u{0001}299=E2\u{0001}55=RIO.AX\u{0001}132=100.1\u{0001}133=100.4\u{0001}10=000\u{0001}";
+        let accepted = "8=FIX.4.4\u{0001}9=0\u{0001}35=b\u{0001}49=EXCHANGE\u{0001}56=MAKER\u{0001}34=3\u{0001}117=MQ2\u{0001}297=0\u{0001}10=000\u{0001}";
+
+        let mut book = process(&[mass_quote, acknowledgement])?;
+        assert_eq!(book.quotes.len(), 2);
+        let first = &book.quotes["MAKER-EXCHANGE-S1-E1"];
+        assert_eq!(first.quote_set_id.as_deref(), Some("S1"));
+        assert_eq!(first.quote_id, "E1");
+        assert_eq!(first.fields.get(crate::FIX_5_0SP2::QuoteID::TAG)?.value, "MQ1");
+        assert_eq!(first.symbol(), Some("BHP.AX"));
+        assert_eq!(first.quote_status(), Some(crate::FIX_5_0SP2::QuoteStatus::Accepted().value));
+        assert_eq!(book.quotes["MAKER-EXCHANGE-S1-E2"].quote_status(), Some(crate::FIX_5_0SP2::QuoteStatus::Rejected().value));
+
+        book.process(&decode_message(update)?)?;
+        book.process(&decode_message(accepted)?)?;
+        let second = &book.quotes["MAKER-EXCHANGE-S1-E2"];
+        assert_eq!(second.offer_px(), Some(100.4));
+        assert_eq!(second.quote_status(), Some(crate::FIX_5_0SP2::QuoteStatus::Accepted().value));
+        assert_eq!(book.live_quotes().count(), 2);
+        Ok(())
+    }
+
+}
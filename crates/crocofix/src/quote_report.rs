@@ -0,0 +1,185 @@
+use crate::quote::{Quote, QuoteHit};
+use crate::quote_book::QuoteBook;
+use crate::field::format_decimal;
+use std::io::Write;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+const REQUEST_HEADERS: [&str; 6] = ["QuoteReqID", "Symbol", "Side", "OrderQty", "Quotes", "Status"];
+const NUMERIC_REQUEST_COLUMNS: [usize; 2] = [3, 4];
+
+const QUOTE_HEADERS: [&str; 10] = ["QuoteReqID", "QuoteID", "Symbol", "BidSize", "BidPx", "OfferPx", "OfferSize", "ValidUntilTime", "QuoteStatus", "Hits"];
+const NUMERIC_QUOTE_COLUMNS: [usize; 4] = [3, 4, 5, 6];
+
+// Prints a table of quote requests, if there are any, followed by a table of quotes with the hits
+// and lifts that became orders.
+#[derive(Default)]
+pub struct QuoteReport {
+}
+
+impl QuoteReport
+{
+    fn table(headers: &[&str], numeric_columns: &[usize]) -> Table
+    {
+        let mut table = Table::default();
+
+        table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+            .set_style(TableComponent::HeaderLines, '-')
+            .set_style(TableComponent::MiddleHeaderIntersections, '-')
+        ;
+
+        table.set_header(headers.iter().enumerate().map(|(index, header)| {
+            Cell::new(header).set_alignment(if numeric_columns.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+        }));
+
+        table
+    }
+
+    fn row(values: Vec<String>, numeric_columns: &[usize]) -> Vec<Cell>
+    {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                Cell::new(value).set_alignment(if numeric_columns.contains(&index) { CellAlignment::Right } else { CellAlignment::Left })
+            })
+            .collect()
+    }
+
+    // e.g. H1 Lift 1000000@1.0852 (late)
+    fn format_hit(hit: &QuoteHit) -> String
+    {
+        let mut text = format!(
+            "{} {} {}@{}",
+            hit.cl_ord_id.as_deref().unwrap_or_default(),
+            if hit.is_lift() { "Lift" } else { "Hit" },
+            hit.quantity.map(format_decimal).unwrap_or_default(),
+            hit.price.map(format_decimal).unwrap_or_default()
+        );
+        if hit.late {
+            text.push_str(" (late)");
+        }
+        text
+    }
+
+    fn quote_status(quote_book: &QuoteBook, quote: &Quote) -> String
+    {
+        if quote_book.is_expired(quote) {
+            let expired = crate::FIX_5_0SP2::QuoteStatus::Expired().value;
+            return crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::QuoteStatus::TAG as usize, expired).unwrap_or(expired).to_string();
+        }
+        quote.quote_status().map(|quote_status| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::QuoteStatus::TAG as usize, quote_status).unwrap_or(quote_status).to_string()).unwrap_or_default()
+    }
+
+    fn requests_table(quote_book: &QuoteBook) -> Table
+    {
+        let mut table = QuoteReport::table(&REQUEST_HEADERS, &NUMERIC_REQUEST_COLUMNS);
+
+        for request in quote_book.requests.values() {
+            let quotes = quote_book.quotes_for_request(&request.key);
+            let status = if request.rejected {
+                "Rejected"
+            }
+            else if quotes.iter().any(|quote| quote.is_hit()) {
+                "Hit"
+            }
+            else if !quotes.is_empty() {
+                "Quoted"
+            }
+            else {
+                "Pending"
+            };
+            let row = vec![
+                request.quote_req_id.clone(),
+                request.values(crate::FIX_5_0SP2::Symbol::TAG).join(" "),
+                request.values(crate::FIX_5_0SP2::Side::TAG).iter().map(|side| crate::FIX_5_0SP2::fields().name_of_value(crate::FIX_5_0SP2::Side::TAG as usize, side).unwrap_or(side).to_string()).collect::<Vec<_>>().join(" "),
+                request.values(crate::FIX_5_0SP2::OrderQty::TAG).join(" "),
+                quotes.len().to_string(),
+                status.to_string()
+            ];
+            table.add_row(QuoteReport::row(row, &NUMERIC_REQUEST_COLUMNS));
+        }
+
+        table
+    }
+
+    fn quotes_table(quote_book: &QuoteBook) -> Table
+    {
+        let mut table = QuoteReport::table(&QUOTE_HEADERS, &NUMERIC_QUOTE_COLUMNS);
+
+        for quote in quote_book.quotes.values() {
+            let row = vec![
+                quote.quote_req_id.clone().unwrap_or_default(),
+                match &quote.quote_set_id {
+                    Some(quote_set_id) => format!("{}/{}", quote_set_id, quote.quote_id),
+                    None => quote.quote_id.clone()
+                },
+                quote.symbol().unwrap_or_default().to_string(),
+                quote.bid_size().map(format_decimal).unwrap_or_default(),
+                quote.bid_px().map(format_decimal).unwrap_or_default(),
+                quote.offer_px().map(format_decimal).unwrap_or_default(),
+                quote.offer_size().map(format_decimal).unwrap_or_default(),
+                quote.valid_until_time().unwrap_or_default().to_string(),
+                QuoteReport::quote_status(quote_book, quote),
+                quote.hits.iter().map(QuoteReport::format_hit).collect::<Vec<_>>().join(", ")
+            ];
+            table.add_row(QuoteReport::row(row, &NUMERIC_QUOTE_COLUMNS));
+        }
+
+        table
+    }
+
+    pub fn print<W: Write>(&self, writer: &mut W, quote_book: &QuoteBook) -> std::io::Result<()>
+    {
+        let mut report = String::new();
+
+        if !quote_book.requests.is_empty() {
+            report += &format!("{}\n\n", QuoteReport::requests_table(quote_book));
+        }
+
+        report += &format!("{}\n\n", QuoteReport::quotes_table(quote_book));
+
+        writer.write_all(report.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::Message;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        Ok(message)
+    }
+
+    #[test]
+    pub fn requests_and_quotes_are_printed() -> Result<(), crate::error::Error>
+    {
+        let messages = [
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=R\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=2\u{0001}52=20240115-10:00:00\u{0001}131=RFQ1\u{0001}146=1\u{0001}55=EUR/USD\u{0001}54=1\u{0001}38=1000000\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=S\u{0001}49=DEALER\u{0001}56=CLIENT\u{0001}34=2\u{0001}52=20240115-10:00:01\u{0001}131=RFQ1\u{0001}117=Q1\u{0001}55=EUR/USD\u{0001}132=1.085\u{0001}133=1.0852\u{0001}134=1000000\u{0001}135=1000000\u{0001}62=20240115-10:00:30\u{0001}10=000\u{0001}",
+            "8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=CLIENT\u{0001}56=DEALER\u{0001}34=3\u{0001}52=20240115-10:00:05\u{0001}11=H1\u{0001}117=Q1\u{0001}55=EUR/USD\u{0001}54=1\u{0001}38=1000000\u{0001}40=D\u{0001}44=1.0852\u{0001}10=000\u{0001}"
+        ];
+        let mut book = QuoteBook::default();
+        for text in messages {
+            book.process(&decode_message(text)?)?;
+        }
+
+        let mut output = Vec::new();
+        QuoteReport::default().print(&mut output, &book)?;
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+
+        assert_eq!(lines[1], "| QuoteReqID   Symbol    Side   OrderQty   Quotes   Status |");
+        assert_eq!(lines[3], "| RFQ1         EUR/USD   Buy     1000000        1   Hit    |");
+        assert_eq!(lines[7], "| QuoteReqID   QuoteID   Symbol    BidSize   BidPx   OfferPx   OfferSize   ValidUntilTime      QuoteStatus   Hits                   |");
+        assert_eq!(lines[9], "| RFQ1         Q1        EUR/USD   1000000   1.085    1.0852     1000000   20240115-10:00:30                 H1 Lift 1000000@1.0852 |");
+        Ok(())
+    }
+
+}
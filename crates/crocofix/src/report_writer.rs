@@ -0,0 +1,537 @@
+use crate::error::Error;
+use std::io::Write;
+use std::str::FromStr;
+use comfy_table::*;
+use comfy_table::presets::ASCII_BORDERS_ONLY_CONDENSED;
+
+// A value in a report with the value of an outstanding request e.g. the OrderQty of a pending
+// replace. Changed values are highlighted by the formats that support it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReportCell {
+    pub value: String,
+    pub pending: Option<String>,
+    pub changed: bool
+}
+
+impl ReportCell {
+
+    pub fn new(value: String) -> Self
+    {
+        Self {
+            value,
+            pending: None,
+            changed: false
+        }
+    }
+
+    pub fn with_pending(mut self, pending: Option<String>) -> Self
+    {
+        self.pending = pending;
+        self
+    }
+
+    pub fn with_changed(mut self, changed: bool) -> Self
+    {
+        self.changed = changed;
+        self
+    }
+
+    // The value followed by the pending value in parentheses e.g. 10000 (20000)
+    pub fn text(&self) -> String
+    {
+        match &self.pending {
+            Some(pending) => format!("{} ({})", self.value, pending),
+            None => self.value.clone()
+        }
+    }
+
+}
+
+// The rows of one table in a report, numeric columns are right aligned by the formats that support it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReportTable {
+    pub name: String,
+    pub headers: Vec<String>,
+    pub numeric_columns: Vec<usize>,
+    pub rows: Vec<Vec<ReportCell>>
+}
+
+impl ReportTable {
+
+    pub fn new(name: &str, headers: &[&str], numeric_columns: &[usize]) -> Self
+    {
+        Self {
+            name: name.to_string(),
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            numeric_columns: numeric_columns.to_vec(),
+            rows: Vec::new()
+        }
+    }
+
+    pub fn add_row(&mut self, values: Vec<String>)
+    {
+        self.rows.push(values.into_iter().map(ReportCell::new).collect());
+    }
+
+    fn is_numeric(&self, column: usize) -> bool
+    {
+        self.numeric_columns.contains(&column)
+    }
+
+}
+
+// Renders the tables of a report, the first table is the main table e.g. the orders and the rest
+// are details e.g. the fills.
+pub trait ReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>;
+
+}
+
+// ASCII tables for a terminal, changed values are bold when writing to a terminal.
+#[derive(Default)]
+pub struct TableReportWriter;
+
+impl ReportWriter for TableReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>
+    {
+        let mut report = String::new();
+
+        for report_table in tables {
+            let alignment = |column: usize| if report_table.is_numeric(column) { CellAlignment::Right } else { CellAlignment::Left };
+
+            let mut table = Table::default();
+
+            table.load_preset(ASCII_BORDERS_ONLY_CONDENSED)
+                .set_style(TableComponent::HeaderLines, '-')
+                .set_style(TableComponent::MiddleHeaderIntersections, '-')
+            ;
+
+            table.set_header(report_table.headers.iter().enumerate().map(|(index, header)| Cell::new(header).set_alignment(alignment(index))));
+
+            for row in &report_table.rows {
+                table.add_row(row.iter().enumerate().map(|(index, cell)| {
+                    let text = Cell::new(cell.text()).set_alignment(alignment(index));
+                    if cell.changed { text.add_attribute(Attribute::Bold).fg(Color::Yellow) } else { text }
+                }));
+            }
+
+            report += &format!("{}\n\n", table);
+        }
+
+        writer.write_all(report.as_bytes())
+    }
+
+}
+
+// RFC 4180 CSV of the main table for loading into a spreadsheet.
+#[derive(Default)]
+pub struct CsvReportWriter;
+
+impl CsvReportWriter {
+
+    // Quote values containing a delimiter, quote, or line break and double any quotes.
+    fn quote(value: &str) -> String
+    {
+        if value.contains([',', '"', '\r', '\n']) || value.starts_with(' ') || value.ends_with(' ') {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        }
+        else {
+            value.to_string()
+        }
+    }
+
+    fn line(values: impl Iterator<Item = String>) -> String
+    {
+        values.map(|value| CsvReportWriter::quote(&value)).collect::<Vec<_>>().join(",") + "\r\n"
+    }
+
+}
+
+impl ReportWriter for CsvReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>
+    {
+        let Some(table) = tables.first() else {
+            return Ok(());
+        };
+
+        let mut report = CsvReportWriter::line(table.headers.iter().cloned());
+
+        for row in &table.rows {
+            report += &CsvReportWriter::line(row.iter().map(ReportCell::text));
+        }
+
+        writer.write_all(report.as_bytes())
+    }
+
+}
+
+// One JSON object per row of the main table for loading into a notebook. Empty values are
+// omitted and pending values are in a nested Pending object.
+#[derive(Default)]
+pub struct JsonLinesReportWriter;
+
+impl JsonLinesReportWriter {
+
+    fn quote(value: &str) -> String
+    {
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c)
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    fn member(name: &str, value: &str) -> String
+    {
+        format!("{}:{}", JsonLinesReportWriter::quote(name), JsonLinesReportWriter::quote(value))
+    }
+
+}
+
+impl ReportWriter for JsonLinesReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>
+    {
+        let Some(table) = tables.first() else {
+            return Ok(());
+        };
+
+        let mut report = String::new();
+
+        for row in &table.rows {
+            let mut members: Vec<String> = table.headers
+                .iter()
+                .zip(row)
+                .filter(|(_, cell)| !cell.value.is_empty())
+                .map(|(header, cell)| JsonLinesReportWriter::member(header, &cell.value))
+                .collect();
+
+            let pending: Vec<String> = table.headers
+                .iter()
+                .zip(row)
+                .filter_map(|(header, cell)| cell.pending.as_deref().map(|pending| JsonLinesReportWriter::member(header, pending)))
+                .collect();
+
+            if !pending.is_empty() {
+                members.push(format!("\"Pending\":{{{}}}", pending.join(",")));
+            }
+
+            report += &format!("{{{}}}\n", members.join(","));
+        }
+
+        writer.write_all(report.as_bytes())
+    }
+
+}
+
+// GitHub flavoured Markdown tables, changed values are bold.
+#[derive(Default)]
+pub struct MarkdownReportWriter;
+
+impl MarkdownReportWriter {
+
+    fn escape(value: &str) -> String
+    {
+        value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+    }
+
+}
+
+impl ReportWriter for MarkdownReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>
+    {
+        let mut report = String::new();
+
+        for table in tables {
+            let line = |values: Vec<String>| format!("| {} |\n", values.join(" | "));
+
+            report += &line(table.headers.iter().map(|header| MarkdownReportWriter::escape(header)).collect());
+            report += &line((0..table.headers.len()).map(|column| if table.is_numeric(column) { "---:".to_string() } else { "---".to_string() }).collect());
+            for row in &table.rows {
+                report += &line(row.iter().map(|cell| {
+                    let text = MarkdownReportWriter::escape(&cell.text());
+                    if cell.changed && !text.is_empty() { format!("**{}**", text) } else { text }
+                }).collect());
+            }
+            report.push('\n');
+        }
+
+        writer.write_all(report.as_bytes())
+    }
+
+}
+
+// A standalone HTML document with a table for each report table.
+#[derive(Default)]
+pub struct HtmlReportWriter;
+
+impl HtmlReportWriter {
+
+    fn escape(value: &str) -> String
+    {
+        value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    fn cell(element: &str, classes: &[&str], content: &str) -> String
+    {
+        if classes.is_empty() {
+            format!("<{}>{}</{}>", element, content, element)
+        }
+        else {
+            format!("<{} class=\"{}\">{}</{}>", element, classes.join(" "), content, element)
+        }
+    }
+
+    fn classes(numeric: bool, changed: bool) -> Vec<&'static str>
+    {
+        [(numeric, "numeric"), (changed, "changed")].into_iter().filter(|(enabled, _)| *enabled).map(|(_, class)| class).collect()
+    }
+
+}
+
+impl ReportWriter for HtmlReportWriter {
+
+    fn write(&self, writer: &mut dyn Write, tables: &[ReportTable]) -> std::io::Result<()>
+    {
+        let mut report = String::from(concat!(
+            "<!DOCTYPE html>\n",
+            "<html>\n",
+            "<head>\n",
+            "<meta charset=\"utf-8\">\n",
+            "<title>Orders</title>\n",
+            "<style>\n",
+            "table { border-collapse: collapse; margin-bottom: 1em; font-family: monospace; }\n",
+            "th, td { border: 1px solid #ccc; padding: 2px 8px; text-align: left; }\n",
+            ".numeric { text-align: right; }\n",
+            ".pending { color: #888; }\n",
+            ".changed { font-weight: bold; background: #ffc; }\n",
+            "</style>\n",
+            "</head>\n",
+            "<body>\n"
+        ));
+
+        for table in tables {
+            report += &format!("<table class=\"{}\">\n<thead>\n<tr>", HtmlReportWriter::escape(&table.name));
+            for (index, header) in table.headers.iter().enumerate() {
+                report += &HtmlReportWriter::cell("th", &HtmlReportWriter::classes(table.is_numeric(index), false), &HtmlReportWriter::escape(header));
+            }
+            report += "</tr>\n</thead>\n<tbody>\n";
+            for row in &table.rows {
+                report += "<tr>";
+                for (index, cell) in row.iter().enumerate() {
+                    let mut content = HtmlReportWriter::escape(&cell.value);
+                    if let Some(pending) = &cell.pending {
+                        content += &format!(" <span class=\"pending\">({})</span>", HtmlReportWriter::escape(pending));
+                    }
+                    report += &HtmlReportWriter::cell("td", &HtmlReportWriter::classes(table.is_numeric(index), cell.changed), &content);
+                }
+                report += "</tr>\n";
+            }
+            report += "</tbody>\n</table>\n";
+        }
+
+        report += "</body>\n</html>\n";
+
+        writer.write_all(report.as_bytes())
+    }
+
+}
+
+// Formats a value for display in a report e.g. adding thousands separators to a quantity.
+pub trait ValueFormatter {
+
+    fn format(&self, value: &str) -> String;
+
+}
+
+// Formats numbers with an optional thousands separator and a fixed number of decimal places,
+// values that aren't numbers are left as they are. The text form is a comma for the separator
+// followed by a period and the precision e.g. ",.2", "," or ".4".
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NumberFormatter {
+    pub thousands_separator: bool,
+    pub precision: Option<usize>
+}
+
+impl NumberFormatter {
+
+    pub fn with_thousands_separator(mut self, thousands_separator: bool) -> Self
+    {
+        self.thousands_separator = thousands_separator;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self
+    {
+        self.precision = Some(precision);
+        self
+    }
+
+    fn separate_thousands(integer: &str) -> String
+    {
+        let mut separated = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index).is_multiple_of(3) {
+                separated.push(',');
+            }
+            separated.push(digit);
+        }
+        separated
+    }
+
+}
+
+impl ValueFormatter for NumberFormatter {
+
+    fn format(&self, value: &str) -> String
+    {
+        let Ok(number) = value.trim().parse::<f64>() else {
+            return value.to_string();
+        };
+
+        let text = match self.precision {
+            Some(precision) => format!("{:.*}", precision, number),
+            None => value.trim().to_string()
+        };
+
+        if !self.thousands_separator {
+            return text;
+        }
+
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str())
+        };
+
+        match digits.split_once('.') {
+            Some((integer, fraction)) => format!("{}{}.{}", sign, NumberFormatter::separate_thousands(integer), fraction),
+            None => format!("{}{}", sign, NumberFormatter::separate_thousands(digits))
+        }
+    }
+
+}
+
+impl FromStr for NumberFormatter {
+
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err>
+    {
+        let text = text.trim();
+
+        let (thousands_separator, rest) = match text.strip_prefix(',') {
+            Some(rest) => (true, rest),
+            None => (false, text)
+        };
+
+        let formatter = NumberFormatter::default().with_thousands_separator(thousands_separator);
+
+        if rest.is_empty() {
+            if !thousands_separator {
+                return Err(Error::InvalidNumberFormat(text.to_string()));
+            }
+            return Ok(formatter);
+        }
+
+        let Some(precision) = rest.strip_prefix('.').and_then(|precision| precision.parse::<usize>().ok()) else {
+            return Err(Error::InvalidNumberFormat(text.to_string()));
+        };
+
+        Ok(formatter.with_precision(precision))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn orders() -> Vec<ReportTable>
+    {
+        let mut table = ReportTable::new("Orders", &["ClOrdID", "Text", "OrderQty"], &[2]);
+        table.rows.push(vec![
+            ReportCell::new("1".to_string()),
+            ReportCell::new("say \"hi\", <now>".to_string()),
+            ReportCell::new("100".to_string()).with_pending(Some("200".to_string()))
+        ]);
+        table.add_row(vec!["2".to_string(), String::new(), "50".to_string()]);
+        vec![table]
+    }
+
+    fn write(report_writer: &dyn ReportWriter) -> Result<String, crate::error::Error>
+    {
+        let mut output = Vec::new();
+        report_writer.write(&mut output, &orders())?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    #[test]
+    pub fn csv() -> Result<(), crate::error::Error>
+    {
+        assert_eq!(write(&CsvReportWriter)?, "ClOrdID,Text,OrderQty\r\n1,\"say \"\"hi\"\", <now>\",100 (200)\r\n2,,50\r\n");
+        Ok(())
+    }
+
+    #[test]
+    pub fn json_lines() -> Result<(), crate::error::Error>
+    {
+        assert_eq!(write(&JsonLinesReportWriter)?, concat!(
+            "{\"ClOrdID\":\"1\",\"Text\":\"say \\\"hi\\\", <now>\",\"OrderQty\":\"100\",\"Pending\":{\"OrderQty\":\"200\"}}\n",
+            "{\"ClOrdID\":\"2\",\"OrderQty\":\"50\"}\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn markdown() -> Result<(), crate::error::Error>
+    {
+        assert_eq!(write(&MarkdownReportWriter)?, concat!(
+            "| ClOrdID | Text | OrderQty |\n",
+            "| --- | --- | ---: |\n",
+            "| 1 | say \"hi\", <now> | 100 (200) |\n",
+            "| 2 |  | 50 |\n",
+            "\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn html() -> Result<(), crate::error::Error>
+    {
+        let output = write(&HtmlReportWriter)?;
+        assert!(output.starts_with("<!DOCTYPE html>\n"));
+        assert!(output.contains("<tr><th>ClOrdID</th><th>Text</th><th class=\"numeric\">OrderQty</th></tr>"));
+        assert!(output.contains("<tr><td>1</td><td>say &quot;hi&quot;, &lt;now&gt;</td><td class=\"numeric\">100 <span class=\"pending\">(200)</span></td></tr>"));
+        assert!(output.ends_with("</body>\n</html>\n"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn numbers_are_formatted() -> Result<(), crate::error::Error>
+    {
+        let formatter: NumberFormatter = ",.2".parse()?;
+        assert_eq!(formatter.format("1234567.891"), "1,234,567.89");
+        assert_eq!(formatter.format("-1000"), "-1,000.00");
+        assert_eq!(formatter.format("BHP.AX"), "BHP.AX");
+        assert_eq!(",".parse::<NumberFormatter>()?.format("123456.5"), "123,456.5");
+        assert_eq!(".4".parse::<NumberFormatter>()?.format("21"), "21.0000");
+        assert_eq!("2".parse::<NumberFormatter>(), Err(Error::InvalidNumberFormat("2".to_string())));
+        Ok(())
+    }
+
+}
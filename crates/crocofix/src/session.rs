@@ -0,0 +1,346 @@
+use crate::message::Message;
+use crate::field::Field;
+use crate::field_collection::FieldCollection;
+use crate::error::Error;
+use crate::timestamp::utc_timestamp_now;
+use std::time::{Duration, Instant};
+
+// The fields a session writes into the standard header and trailer of every message it sends.
+const SESSION_FIELDS: [u32; 8] = [
+    crate::FIX_5_0SP2::BeginString::TAG,
+    crate::FIX_5_0SP2::BodyLength::TAG,
+    crate::FIX_5_0SP2::MsgType::TAG,
+    crate::FIX_5_0SP2::SenderCompID::TAG,
+    crate::FIX_5_0SP2::TargetCompID::TAG,
+    crate::FIX_5_0SP2::MsgSeqNum::TAG,
+    crate::FIX_5_0SP2::SendingTime::TAG,
+    crate::FIX_5_0SP2::CheckSum::TAG
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionState {
+    Connected,
+    LogonSent,
+    LoggedOn,
+    LogoutSent,
+    LoggedOut
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionOptions {
+    pub begin_string: String,
+    pub sender_comp_id: String,
+    // An acceptor can leave this empty and it will be populated from the SenderCompID of the incoming Logon.
+    pub target_comp_id: String,
+    pub heartbeat_interval: u32
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            begin_string: "FIX.4.4".to_string(),
+            sender_comp_id: String::new(),
+            target_comp_id: String::new(),
+            heartbeat_interval: 30
+        }
+    }
+}
+
+// A minimal FIX session state machine. This handles the administrative messages required to
+// establish and maintain a session and stamps the standard header on outgoing messages. It does
+// no IO so it can be driven by blocking or asynchronous transports. Resend requests are
+// answered with a SequenceReset, messages are never actually retransmitted.
+pub struct Session {
+    pub options: SessionOptions,
+    state: SessionState,
+    next_outgoing_msg_seq_num: u32,
+    next_incoming_msg_seq_num: u32,
+    last_sent: Option<Instant>
+}
+
+impl Session {
+
+    pub fn new(options: SessionOptions) -> Self
+    {
+        Self {
+            options,
+            state: SessionState::Connected,
+            next_outgoing_msg_seq_num: 1,
+            next_incoming_msg_seq_num: 1,
+            last_sent: None
+        }
+    }
+
+    pub fn state(&self) -> SessionState
+    {
+        self.state
+    }
+
+    pub fn next_outgoing_msg_seq_num(&self) -> u32
+    {
+        self.next_outgoing_msg_seq_num
+    }
+
+    pub fn next_incoming_msg_seq_num(&self) -> u32
+    {
+        self.next_incoming_msg_seq_num
+    }
+
+    // Create a message with a standard header, the supplied body, and a trailer. The BodyLength
+    // and CheckSum are placeholders that are calculated when the message is encoded. Each call
+    // consumes an outgoing MsgSeqNum so messages must be sent in the order they are created.
+    pub fn create(&mut self, msg_type: &str, body: &FieldCollection) -> Message
+    {
+        let mut message = Message::default();
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::BeginString::TAG, &self.options.begin_string));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::BodyLength::TAG, "0"));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::MsgType::TAG, msg_type));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::SenderCompID::TAG, &self.options.sender_comp_id));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::TargetCompID::TAG, &self.options.target_comp_id));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::MsgSeqNum::TAG, &self.next_outgoing_msg_seq_num.to_string()));
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::SendingTime::TAG, &utc_timestamp_now()));
+        for field in body {
+            message.fields.push(field.clone());
+        }
+        message.fields.push(Field::from_str(crate::FIX_5_0SP2::CheckSum::TAG, "000"));
+        self.next_outgoing_msg_seq_num += 1;
+        self.last_sent = Some(Instant::now());
+        message
+    }
+
+    // Create a copy of an existing message with this sessions header. This is useful for sending
+    // messages that were captured from another session.
+    pub fn prepare(&mut self, message: &Message) -> Result<Message, Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        let mut body = FieldCollection::default();
+        for field in &message.fields {
+            if !SESSION_FIELDS.contains(&field.tag) {
+                body.push(field.clone());
+            }
+        }
+
+        Ok(self.create(msg_type, &body))
+    }
+
+    pub fn logon(&mut self) -> Message
+    {
+        self.state = SessionState::LogonSent;
+        let body = self.logon_body();
+        self.create(crate::FIX_5_0SP2::message::Logon::MSG_TYPE, &body)
+    }
+
+    pub fn logout(&mut self, text: Option<&str>) -> Message
+    {
+        self.state = SessionState::LogoutSent;
+        let mut body = FieldCollection::default();
+        if let Some(text) = text {
+            body.push(Field::from_str(crate::FIX_5_0SP2::Text::TAG, text));
+        }
+        self.create(crate::FIX_5_0SP2::message::Logout::MSG_TYPE, &body)
+    }
+
+    fn logon_body(&self) -> FieldCollection
+    {
+        let mut body = FieldCollection::default();
+        body.push(Field::from_str(crate::FIX_5_0SP2::EncryptMethod::TAG, "0"));
+        body.push(Field::from_str(crate::FIX_5_0SP2::HeartBtInt::TAG, &self.options.heartbeat_interval.to_string()));
+        body
+    }
+
+    // Process an incoming message and return any administrative messages that should be sent in
+    // response. Application messages are accepted as is, the caller is responsible for passing them
+    // on to the application.
+    pub fn receive(&mut self, message: &Message) -> Result<Vec<Message>, Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        let msg_seq_num = message.fields.get(crate::FIX_5_0SP2::MsgSeqNum::TAG)?;
+        let msg_seq_num: u32 = msg_seq_num.value.parse().map_err(|_error| Error::FieldValueParseFailed(format!("{}", msg_seq_num)))?;
+        self.next_incoming_msg_seq_num = msg_seq_num + 1;
+
+        let mut responses = Vec::new();
+
+        if msg_type == crate::FIX_5_0SP2::message::Logon::MSG_TYPE {
+            if self.options.target_comp_id.is_empty() {
+                self.options.target_comp_id = message.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value.clone();
+            }
+            if let Some(heartbeat_interval) = message.fields.try_get(crate::FIX_5_0SP2::HeartBtInt::TAG) {
+                if let Ok(heartbeat_interval) = heartbeat_interval.value.parse() {
+                    self.options.heartbeat_interval = heartbeat_interval;
+                }
+            }
+            if self.state != SessionState::LogonSent {
+                let body = self.logon_body();
+                responses.push(self.create(msg_type, &body));
+            }
+            self.state = SessionState::LoggedOn;
+        }
+        else if msg_type == crate::FIX_5_0SP2::message::TestRequest::MSG_TYPE {
+            let mut body = FieldCollection::default();
+            if let Some(test_req_id) = message.fields.try_get(crate::FIX_5_0SP2::TestReqID::TAG) {
+                body.push(test_req_id.clone());
+            }
+            responses.push(self.create(crate::FIX_5_0SP2::message::Heartbeat::MSG_TYPE, &body));
+        }
+        else if msg_type == crate::FIX_5_0SP2::message::ResendRequest::MSG_TYPE {
+            // The SequenceReset consumes the current outgoing MsgSeqNum so the counterparty should expect the one after it.
+            let mut body = FieldCollection::default();
+            body.push(Field::from_str(crate::FIX_5_0SP2::NewSeqNo::TAG, &(self.next_outgoing_msg_seq_num + 1).to_string()));
+            responses.push(self.create(crate::FIX_5_0SP2::message::SequenceReset::MSG_TYPE, &body));
+        }
+        else if msg_type == crate::FIX_5_0SP2::message::SequenceReset::MSG_TYPE {
+            let new_seq_no = message.fields.get(crate::FIX_5_0SP2::NewSeqNo::TAG)?;
+            self.next_incoming_msg_seq_num = new_seq_no.value.parse().map_err(|_error| Error::FieldValueParseFailed(format!("{}", new_seq_no)))?;
+        }
+        else if msg_type == crate::FIX_5_0SP2::message::Logout::MSG_TYPE {
+            if self.state != SessionState::LogoutSent {
+                responses.push(self.create(msg_type, &FieldCollection::default()));
+            }
+            self.state = SessionState::LoggedOut;
+        }
+
+        Ok(responses)
+    }
+
+    // Returns a Heartbeat if nothing has been sent for the heartbeat interval.
+    pub fn poll(&mut self, now: Instant) -> Option<Message>
+    {
+        if self.state != SessionState::LoggedOn || self.options.heartbeat_interval == 0 {
+            return None;
+        }
+
+        let interval = Duration::from_secs(self.options.heartbeat_interval as u64);
+
+        match self.last_sent {
+            Some(last_sent) if now.saturating_duration_since(last_sent) < interval => None,
+            _ => Some(self.create(crate::FIX_5_0SP2::message::Heartbeat::MSG_TYPE, &FieldCollection::default()))
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::message::EncodeOptions;
+
+    fn decode_message(text: &str) -> Result<Message, crate::error::Error>
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes())?;
+        assert!(result.complete);
+        assert_eq!(result.consumed, text.len());
+        Ok(message)
+    }
+
+    fn acceptor() -> Session
+    {
+        Session::new(SessionOptions { sender_comp_id: "ACCEPTOR".to_string(), ..Default::default() })
+    }
+
+    const LOGON: &str = "8=FIX.4.4\u{0001}9=72\u{0001}35=A\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=1\u{0001}52=20190816-10:34:27.752\u{0001}98=0\u{0001}108=10\u{0001}10=013\u{0001}";
+
+    #[test]
+    pub fn acceptor_responds_to_logon() -> Result<(), crate::error::Error>
+    {
+        let mut session = acceptor();
+        let responses = session.receive(&decode_message(LOGON)?)?;
+        assert_eq!(responses.len(), 1);
+        let logon = &responses[0];
+        assert_eq!(logon.msg_type(), Some("A"));
+        assert_eq!(logon.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value, "INITIATOR");
+        assert_eq!(logon.fields.get(crate::FIX_5_0SP2::HeartBtInt::TAG)?.value, "10");
+        assert_eq!(logon.fields.get(crate::FIX_5_0SP2::MsgSeqNum::TAG)?.value, "1");
+        assert_eq!(session.state(), SessionState::LoggedOn);
+        assert_eq!(session.next_incoming_msg_seq_num(), 2);
+        Ok(())
+    }
+
+    #[test]
+    pub fn initiator_does_not_respond_to_logon() -> Result<(), crate::error::Error>
+    {
+        let mut session = Session::new(SessionOptions { sender_comp_id: "ACCEPTOR".to_string(), target_comp_id: "INITIATOR".to_string(), ..Default::default() });
+        let logon = session.logon();
+        assert_eq!(logon.msg_type(), Some("A"));
+        assert_eq!(session.state(), SessionState::LogonSent);
+        assert!(session.receive(&decode_message(LOGON)?)?.is_empty());
+        assert_eq!(session.state(), SessionState::LoggedOn);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_request_is_answered_with_heartbeat() -> Result<(), crate::error::Error>
+    {
+        let mut session = acceptor();
+        session.receive(&decode_message(LOGON)?)?;
+        let test_request = "8=FIX.4.4\u{0001}9=61\u{0001}35=1\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20190816-10:34:57.752\u{0001}112=TEST\u{0001}10=000\u{0001}";
+        let responses = session.receive(&decode_message(test_request)?)?;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].msg_type(), Some("0"));
+        assert_eq!(responses[0].fields.get(crate::FIX_5_0SP2::TestReqID::TAG)?.value, "TEST");
+        assert_eq!(responses[0].fields.get(crate::FIX_5_0SP2::MsgSeqNum::TAG)?.value, "2");
+        Ok(())
+    }
+
+    #[test]
+    pub fn logout_is_acknowledged() -> Result<(), crate::error::Error>
+    {
+        let mut session = acceptor();
+        session.receive(&decode_message(LOGON)?)?;
+        let logout = "8=FIX.4.4\u{0001}9=52\u{0001}35=5\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20190816-10:34:57.752\u{0001}10=000\u{0001}";
+        let responses = session.receive(&decode_message(logout)?)?;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].msg_type(), Some("5"));
+        assert_eq!(session.state(), SessionState::LoggedOut);
+        Ok(())
+    }
+
+    #[test]
+    pub fn prepare_replaces_the_header() -> Result<(), crate::error::Error>
+    {
+        let mut session = Session::new(SessionOptions { sender_comp_id: "SENDER".to_string(), target_comp_id: "TARGET".to_string(), ..Default::default() });
+        let order_single = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
+        let prepared = session.prepare(&decode_message(order_single)?)?;
+        assert_eq!(prepared.fields.get(crate::FIX_5_0SP2::SenderCompID::TAG)?.value, "SENDER");
+        assert_eq!(prepared.fields.get(crate::FIX_5_0SP2::TargetCompID::TAG)?.value, "TARGET");
+        assert_eq!(prepared.fields.get(crate::FIX_5_0SP2::MsgSeqNum::TAG)?.value, "1");
+        assert_eq!(prepared.fields.get(crate::FIX_5_0SP2::ClOrdID::TAG)?.value, "61");
+        assert_eq!(prepared.fields.len(), 18);
+        Ok(())
+    }
+
+    #[test]
+    pub fn created_messages_encode_and_decode() -> Result<(), crate::error::Error>
+    {
+        let mut session = Session::new(SessionOptions { sender_comp_id: "SENDER".to_string(), target_comp_id: "TARGET".to_string(), ..Default::default() });
+        let logon = session.logon();
+        let mut buffer = Vec::new();
+        logon.encode(&mut buffer, EncodeOptions::Standard)?;
+        let mut decoded = Message::default();
+        let result = decoded.decode(&buffer)?;
+        assert!(result.complete);
+        assert_eq!(result.consumed, buffer.len());
+        assert_eq!(decoded.fields.len(), logon.fields.len());
+        Ok(())
+    }
+
+    #[test]
+    pub fn heartbeat_is_sent_when_idle() -> Result<(), crate::error::Error>
+    {
+        let mut session = acceptor();
+        assert!(session.poll(Instant::now()).is_none());
+        session.receive(&decode_message(LOGON)?)?;
+        assert!(session.poll(Instant::now()).is_none());
+        let heartbeat = session.poll(Instant::now() + Duration::from_secs(11));
+        assert_eq!(heartbeat.and_then(|message| message.msg_type().map(str::to_string)), Some("0".to_string()));
+        Ok(())
+    }
+
+}
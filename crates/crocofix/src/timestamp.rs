@@ -0,0 +1,68 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+// The FIX UTCTimestamp format with millisecond precision e.g. 20200114-08:13:20.041
+pub const UTC_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H:%M:%S%.3f";
+
+// Parsing accepts any number of fractional digits including none.
+const UTC_TIMESTAMP_PARSE_FORMAT: &str = "%Y%m%d-%H:%M:%S%.f";
+
+pub fn format_utc_timestamp(timestamp: &DateTime<Utc>) -> String
+{
+    timestamp.format(UTC_TIMESTAMP_FORMAT).to_string()
+}
+
+pub fn utc_timestamp_now() -> String
+{
+    format_utc_timestamp(&Utc::now())
+}
+
+// The timestamp in the text preceding a message on a log line, if it has one.
+pub fn parse_log_prefix(prefix: &str) -> Option<DateTime<Utc>>
+{
+    prefix.split_whitespace().find_map(parse_utc_timestamp)
+}
+
+pub fn parse_utc_timestamp(text: &str) -> Option<DateTime<Utc>>
+{
+    NaiveDateTime::parse_from_str(text.trim(), UTC_TIMESTAMP_PARSE_FORMAT)
+        .ok()
+        .map(|timestamp| timestamp.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    pub fn format_timestamp()
+    {
+        let timestamp = Utc.with_ymd_and_hms(2020, 1, 14, 8, 13, 20).unwrap() + chrono::Duration::milliseconds(41);
+        assert_eq!(format_utc_timestamp(&timestamp), "20200114-08:13:20.041");
+    }
+
+    #[test]
+    pub fn parse_timestamp_with_and_without_fractional_seconds()
+    {
+        let seconds = parse_utc_timestamp("20200114-08:13:20").unwrap();
+        let millis = parse_utc_timestamp("20200114-08:13:20.041").unwrap();
+        let micros = parse_utc_timestamp("20200114-08:13:20.041123").unwrap();
+        assert_eq!((millis - seconds).num_milliseconds(), 41);
+        assert_eq!((micros - seconds).num_microseconds(), Some(41123));
+    }
+
+    #[test]
+    pub fn parse_invalid_timestamp()
+    {
+        assert_eq!(parse_utc_timestamp("not a timestamp"), None);
+    }
+
+    #[test]
+    pub fn format_round_trip()
+    {
+        let text = utc_timestamp_now();
+        assert_eq!(format_utc_timestamp(&parse_utc_timestamp(&text).unwrap()), text);
+    }
+
+}
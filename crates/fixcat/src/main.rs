@@ -1,8 +1,24 @@
 use crocofix::message::Message;
 use crocofix::order_book::OrderBook;
+use crocofix::order::{OrderKeyStrategy, DirectKeyStrategy, HubKeyStrategy, OrderIdKeyStrategy, SecondaryClOrdIdKeyStrategy};
 use crocofix::error::Error;
 use crocofix::order_report::{OrderReport, DEFAULT_FIELDS};
-use clap::Parser;
+use crocofix::order_column::{ColumnSource, OrderColumn};
+use crocofix::order_query::{Condition, OrderFilter};
+use crocofix::position::PositionKeeper;
+use crocofix::position_report::PositionReport;
+use crocofix::latency::measure_order_book;
+use crocofix::latency_report::LatencyReport;
+use crocofix::timestamp::parse_log_prefix;
+use crocofix::order_chain_report::OrderChainReport;
+use crocofix::quote_book::QuoteBook;
+use crocofix::quote_report::QuoteReport;
+use crocofix::market_data_book::MarketDataBook;
+use crocofix::market_data_report::MarketDataReport;
+use crocofix::post_trade_book::PostTradeBook;
+use crocofix::post_trade_report::PostTradeReport;
+use crocofix::report_writer::{ReportWriter, TableReportWriter, CsvReportWriter, JsonLinesReportWriter, MarkdownReportWriter, HtmlReportWriter, NumberFormatter};
+use clap::{Parser, ValueEnum};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read, stdout};
 use std::env;
@@ -37,13 +53,126 @@ fn validate_field(tag_or_name: &str) -> Result<u32, String>
         }
     };
 
-    if crocofix::FIX_5_0SP2::fields().is_tag_valid(tag as usize) {
+    // User defined tags that aren't in the dictionary are reported by tag.
+    if tag > 0 {
         return Ok(tag);
     }
 
     Err(format!("Unable to find a FIX field with tag = '{}'", tag))
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DerivedColumn {
+    /// OrderQty minus CumQty
+    LeavesQty,
+    /// The LastQty times LastPx of the fills
+    Notional
+}
+
+impl DerivedColumn {
+
+    fn source(&self) -> ColumnSource
+    {
+        match self {
+            DerivedColumn::LeavesQty => ColumnSource::LeavesQty,
+            DerivedColumn::Notional => ColumnSource::Notional
+        }
+    }
+
+}
+
+// COLUMN=TEXT where COLUMN is a field name, a tag, or a derived column.
+fn parse_column_setting(text: &str) -> Result<(ColumnSource, String), String>
+{
+    let Some((column, setting)) = text.split_once('=') else {
+        return Err(format!("Invalid setting '{}', expected COLUMN=VALUE", text));
+    };
+
+    let source = match DerivedColumn::from_str(column.trim(), false) {
+        Ok(derived) => derived.source(),
+        Err(_) => ColumnSource::Field(validate_field(column)?)
+    };
+
+    Ok((source, setting.to_string()))
+}
+
+fn parse_number_format(text: &str) -> Result<(ColumnSource, NumberFormatter), String>
+{
+    let (source, format) = parse_column_setting(text)?;
+    let formatter = format.parse::<NumberFormatter>().map_err(|_| format!("Invalid number format '{}', expected a comma for a thousands separator and/or a period followed by the number of decimal places e.g. ,.2", format))?;
+    Ok((source, formatter))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum KeyStrategy {
+    /// SenderCompID, TargetCompID, and ClOrdID for orders sent directly to the counterparty
+    Direct,
+    /// OnBehalfOfCompID and DeliverToCompID in place of SenderCompID and TargetCompID for orders routed through a hub
+    Hub,
+    /// OrderID for drop copies, orders are created from ExecutionReports
+    OrderId,
+    /// SecondaryClOrdID for orders that keep the same SecondaryClOrdID when replaced
+    SecondaryClOrdId
+}
+
+impl KeyStrategy {
+
+    fn create(&self) -> Box<dyn OrderKeyStrategy> 
+    {
+        match self {
+            KeyStrategy::Direct => Box::new(DirectKeyStrategy),
+            KeyStrategy::Hub => Box::new(HubKeyStrategy),
+            KeyStrategy::OrderId => Box::new(OrderIdKeyStrategy),
+            KeyStrategy::SecondaryClOrdId => Box::new(SecondaryClOrdIdKeyStrategy)
+        }
+    }
+
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    /// ASCII tables printed after each message
+    Table,
+    /// CSV with a row per order
+    Csv,
+    /// JSON Lines with an object per order
+    Jsonl,
+    /// Markdown tables
+    Markdown,
+    /// A standalone HTML document
+    Html
+}
+
+impl Format {
+
+    fn create(&self) -> Box<dyn ReportWriter>
+    {
+        match self {
+            Format::Table => Box::new(TableReportWriter),
+            Format::Csv => Box::new(CsvReportWriter),
+            Format::Jsonl => Box::new(JsonLinesReportWriter),
+            Format::Markdown => Box::new(MarkdownReportWriter),
+            Format::Html => Box::new(HtmlReportWriter)
+        }
+    }
+
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Report {
+    /// Every order after each message
+    All,
+    /// Only the orders changed by each message with the changed fields highlighted
+    Changes,
+    /// Every order once at the end of the input
+    Summary
+}
+
+fn parse_condition(text: &str) -> Result<Condition, String>
+{
+    text.parse::<Condition>().map_err(|_| format!("Invalid condition '{}', expected open, Field=Value, TransactTime>=Time, or TransactTime<=Time", text))
+}
+
 /// Pretty print FIX protocol messages
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -65,6 +194,82 @@ struct Options {
     #[arg(long, value_delimiter = ',', value_parser = validate_field)]
     fields: Option<Vec<u32>>,
 
+    /// Comma separated list of derived columns to display after the fields when tracking order state
+    #[arg(long, value_enum, value_delimiter = ',', requires = "orders")]
+    derived: Vec<DerivedColumn>,
+
+    /// Use this label for a field or derived column e.g. OrderQty=Qty. Can be repeated
+    #[arg(long, value_name = "COLUMN=LABEL", value_parser = parse_column_setting, requires = "orders")]
+    label: Vec<(ColumnSource, String)>,
+
+    /// Format the numbers in a field or derived column, a comma adds thousands separators and a period followed by a number sets the decimal places e.g. Price=.2 or notional=,.2. Can be repeated
+    #[arg(long, value_name = "COLUMN=FORMAT", value_parser = parse_number_format, requires = "orders")]
+    number_format: Vec<(ColumnSource, NumberFormatter)>,
+
+    /// Show the fills for each order when tracking order state
+    #[arg(long, requires = "orders")]
+    fills: bool,
+
+    /// Check order state transitions and quantities against the FIX specification and print any violations at the end of the input
+    #[arg(long, requires = "orders")]
+    validate: bool,
+
+    /// How messages are matched to orders when tracking order state
+    #[arg(long, value_enum, default_value_t = KeyStrategy::Direct, requires = "orders")]
+    key: KeyStrategy,
+
+    /// How order state is reported, formats other than table are printed once at the end of the input in place of the messages
+    #[arg(long, value_enum, default_value_t = Format::Table, requires = "orders")]
+    format: Format,
+
+    /// When order state is reported in table format
+    #[arg(long, value_enum, default_value_t = Report::All, requires = "orders")]
+    report: Report,
+
+    /// Sort the orders by this field name or tag, numerically if the field is numeric
+    #[arg(long, value_name = "FIELD", value_parser = validate_field, requires = "orders")]
+    sort: Option<u32>,
+
+    /// Sort the orders in descending order
+    #[arg(long, requires = "sort")]
+    descending: bool,
+
+    /// Group the orders by this field name or tag e.g. Symbol, TargetCompID, or OrdStatus, with a subtotal of the OrderQty, CumQty, and notional for each group
+    #[arg(long, value_name = "FIELD", value_parser = validate_field, requires = "orders")]
+    group_by: Option<u32>,
+
+    /// Print a total of the OrderQty, CumQty, and notional of the orders
+    #[arg(long, requires = "orders")]
+    totals: bool,
+
+    /// Only report orders matching this condition, one of open, Field=Value, TransactTime>=Time, or TransactTime<=Time. Can be repeated
+    #[arg(long = "where", value_name = "CONDITION", value_parser = parse_condition, requires = "orders")]
+    conditions: Vec<Condition>,
+
+    /// Print the position, average cost, realised P&L, and exposure for each Account and Symbol at the end of the input
+    #[arg(long, requires = "orders")]
+    positions: bool,
+
+    /// Print latency percentiles per counterparty for acknowledgements, first fills, cancels, and replaces at the end of the input
+    #[arg(long, requires = "orders")]
+    latency: bool,
+
+    /// Print each cancel replace chain as a tree showing what changed at each amendment at the end of the input
+    #[arg(long, requires = "orders")]
+    chains: bool,
+
+    /// Reconcile allocations, confirmations, and trade capture reports with the fills and print any mismatches at the end of the input
+    #[arg(long = "post-trade", requires = "orders")]
+    post_trade: bool,
+
+    /// Track quote requests and quotes, including hits and lifts that became orders
+    #[arg(long)]
+    quotes: bool,
+
+    /// Rebuild the market data book from snapshots and incremental refreshes, printing this many levels of each updated symbol after each message
+    #[arg(long = "market-data", value_name = "DEPTH")]
+    market_data: Option<usize>,
+
     /// Optional input files, if not specifed input will be read from STDIN
     files: Vec<String>
 }
@@ -98,12 +303,67 @@ impl Options {
         
         Ok(DEFAULT_FIELDS.to_vec())
     } 
+
+    // The fields followed by the derived columns with any labels and number formats.
+    pub fn resolve_columns(&self) -> Result<Vec<OrderColumn>, Error>
+    {
+        let sources = self.resolve_fields()?.into_iter().map(ColumnSource::Field).chain(self.derived.iter().map(DerivedColumn::source));
+
+        Ok(sources.map(|source| {
+            let mut column = OrderColumn::new(source);
+            if let Some((_, label)) = self.label.iter().rev().find(|(labelled, _)| *labelled == source) {
+                column = column.with_label(label);
+            }
+            if let Some((_, formatter)) = self.number_format.iter().rev().find(|(formatted, _)| *formatted == source) {
+                column = column.with_formatter(Box::new(*formatter));
+            }
+            column
+        }).collect())
+    }
+}
+
+// The books and reports built up from the messages in one input.
+struct State {
+    order_book: OrderBook,
+    order_report: OrderReport,
+    position_keeper: PositionKeeper,
+    quote_book: QuoteBook,
+    market_data_book: MarketDataBook,
+    post_trade_book: PostTradeBook
+}
+
+impl State {
+
+    pub fn new(options: &Options) -> Result<Self, Error>
+    {
+        let mut order_book = OrderBook::default().with_key_strategy(options.key.create());
+        if options.validate {
+            order_book = order_book.with_validation();
+        }
+        let filter = options.conditions.iter().cloned().fold(OrderFilter::default(), OrderFilter::with_condition);
+        let mut order_report = OrderReport::with_columns(options.resolve_columns()?).with_fills(options.fills).with_filter(filter).with_writer(options.format.create()).with_totals(options.totals);
+        if let Some(sort) = options.sort {
+            order_report = order_report.with_sort(sort, options.descending);
+        }
+        if let Some(group_by) = options.group_by {
+            order_report = order_report.with_group_by(group_by);
+        }
+        Ok(Self {
+            order_book,
+            order_report,
+            position_keeper: PositionKeeper::default(),
+            quote_book: QuoteBook::default(),
+            market_data_book: MarketDataBook::default(),
+            post_trade_book: PostTradeBook::default()
+        })
+    }
+
 }
 
-fn decode_and_print_line(line: &String, options: &Options, order_book: &mut OrderBook, order_report: &mut OrderReport)
+fn decode_and_print_line(line: &String, options: &Options, state: &mut State)
 {
     if let Some(start_of_message) = line.find(FIX_MESSAGE_PREFIX) {
-        let (_prefix, suffix) = line.split_at(start_of_message);
+        let (prefix, suffix) = line.split_at(start_of_message);
         let mut message = Message::default();
         let result = match message.decode(suffix.as_bytes()) {
             Ok(result) => result,
@@ -116,18 +376,42 @@ fn decode_and_print_line(line: &String, options: &Options, order_book: &mut Orde
             if !options.admin && message.is_admin() {
                 return;
             }
-            println!("{}\n", message);
-            if options.orders {
-                match order_book.process(&message) {
-                    Ok(()) => {
-                        let _ = match order_report.print(&mut stdout(), order_book) {
-                            Ok(bytes) => bytes,
-                            Err(error) => eprintln!("{:?}", error)
-                        };
-                    },
-                    Err(_) => ()
+            if options.format == Format::Table {
+                println!("{}\n", message);
+            }
+            if options.latency {
+                message.logged_time = parse_log_prefix(prefix);
+            }
+            if options.orders && let Ok(events) = state.order_book.process(&message) {
+                if options.positions && message.msg_type() == Some(crocofix::FIX_5_0SP2::MsgType::ExecutionReport().value) {
+                    let order = events.first().and_then(|event| state.order_book.orders.get(&event.key));
+                    if let Err(error) = state.position_keeper.process(&message, order) {
+                        eprintln!("{:?}", error);
+                    }
+                }
+                let result = match (options.format, options.report) {
+                    (Format::Table, Report::All) => state.order_report.print(&mut stdout(), &state.order_book),
+                    (Format::Table, Report::Changes) => state.order_report.print_changes(&mut stdout(), &state.order_book, &events),
+                    _ => Ok(())
+                };
+                if let Err(error) = result {
+                    eprintln!("{:?}", error);
                 }
             }
+            // Every message is offered to the post trade book so only report errors for the messages it handles.
+            if options.post_trade
+                && let Err(error) = state.post_trade_book.process(&message)
+                && !matches!(error, Error::UnsupportedMsgType(_)) {
+                eprintln!("{:?}", error);
+            }
+            if options.quotes && state.quote_book.process(&message).is_ok() && let Err(error) = QuoteReport::default().print(&mut stdout(), &state.quote_book) {
+                eprintln!("{:?}", error);
+            }
+            if let Some(depth) = options.market_data
+                && let Ok(symbols) = state.market_data_book.process(&message)
+                && let Err(error) = MarketDataReport::with_depth(depth).print_symbols(&mut stdout(), &state.market_data_book, &symbols) {
+                eprintln!("{:?}", error);
+            }
         }
     }
     else {
@@ -137,15 +421,40 @@ fn decode_and_print_line(line: &String, options: &Options, order_book: &mut Orde
     }
 }
 
+fn print_violations(order_book: &OrderBook)
+{
+    let violations = order_book.violations();
+    println!("{} order state violations", violations.len());
+    for (order, violation) in violations {
+        println!("{} {}", order.key, violation);
+    }
+}
+
 fn main() -> Result<(), crocofix::error::Error>
 {
     let options = Options::parse();
-    let fields = options.resolve_fields()?;
     for reader in options.input_readers()? {
-        let mut order_book = OrderBook::default();
-        let mut order_report = OrderReport::with_fields(fields.clone());
+        let mut state = State::new(&options)?;
         for line in reader.lines() {
-            decode_and_print_line(&line?, &options, &mut order_book, &mut order_report);
+            decode_and_print_line(&line?, &options, &mut state);
+        }
+        if options.orders && (options.format != Format::Table || options.report == Report::Summary) {
+            state.order_report.print(&mut stdout(), &state.order_book)?;
+        }
+        if options.positions {
+            PositionReport::default().print(&mut stdout(), &state.position_keeper)?;
+        }
+        if options.post_trade {
+            PostTradeReport::default().print(&mut stdout(), &state.post_trade_book, &state.order_book)?;
+        }
+        if options.chains {
+            OrderChainReport::default().print(&mut stdout(), &state.order_book)?;
+        }
+        if options.latency {
+            LatencyReport::default().print(&mut stdout(), &measure_order_book(&state.order_book))?;
+        }
+        if options.validate {
+            print_violations(&state.order_book);
         }
     };
 
@@ -0,0 +1,76 @@
+mod proxy;
+mod rules;
+
+use crocofix::error::Error;
+use clap::Parser;
+use rules::Rules;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Log and optionally rewrite the FIX messages exchanged with a counterparty
+#[derive(Parser, Debug, Clone)]
+#[command(version, about, long_about = None)]
+struct Options {
+
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    address: String,
+
+    /// The port to listen on
+    #[arg(long, default_value_t = 5002)]
+    port: u16,
+
+    /// The address of the upstream FIX endpoint e.g. fix.example.com:5001
+    #[arg(long)]
+    upstream: String,
+
+    /// A YAML file describing how messages are rewritten or dropped, if not specified messages are forwarded unchanged
+    #[arg(long)]
+    rules: Option<String>,
+
+    /// Do not log messages to STDOUT
+    #[arg(long)]
+    quiet: bool
+}
+
+fn main() -> Result<(), Error>
+{
+    let options = Options::parse();
+
+    let rules = match &options.rules {
+        Some(path) => match Rules::load(path) {
+            Ok(rules) => rules,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        None => Rules::default()
+    };
+
+    // Rules are shared by all connections so skip and count apply across reconnects.
+    let rules = Arc::new(Mutex::new(rules));
+
+    let listener = TcpListener::bind((options.address.as_str(), options.port))?;
+
+    for client in listener.incoming() {
+        let client = client?;
+        let upstream = match TcpStream::connect(options.upstream.as_str()) {
+            Ok(upstream) => upstream,
+            Err(error) => {
+                eprintln!("Unable to connect to {}: {}", options.upstream, error);
+                continue;
+            }
+        };
+        let rules = rules.clone();
+        let quiet = options.quiet;
+        thread::spawn(move || {
+            if let Err(error) = proxy::serve(client, upstream, rules, quiet) {
+                eprintln!("{:?}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
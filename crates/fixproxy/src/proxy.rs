@@ -0,0 +1,187 @@
+use crate::rules::{Direction, Outcome, Rules};
+use crocofix::error::Error;
+use crocofix::frame_decoder::FrameReader;
+use crocofix::message::EncodeOptions;
+use crocofix::timestamp::utc_timestamp_now;
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Read messages from the source, apply the rules, and write what remains to the destination. Each
+// message is logged as it is forwarded with a timestamp and direction prefix so the log can be read
+// by fixcat. When the source closes the destination is shut down for writing which propagates the
+// close through the proxy.
+fn relay(source: TcpStream, mut destination: TcpStream, direction: Direction, rules: &Mutex<Rules>, quiet: bool) -> Result<(), Error>
+{
+    let mut reader = FrameReader::new(source);
+
+    loop {
+        let mut message = match reader.read_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            // Only a failure of the connection ends the relay, a message that can't be decoded is skipped.
+            Err(error) if matches!(error, Error::IoError | Error::Timeout) => return Err(error),
+            Err(error) => {
+                eprintln!("{} {} failed to decode a message {:?}", utc_timestamp_now(), direction.label(), error);
+                continue;
+            }
+        };
+
+        let outcome = rules.lock().expect("rules lock poisoned").apply(direction, &mut message);
+
+        // Unchanged messages are forwarded byte for byte, modified messages need a new BodyLength and CheckSum.
+        let options = match outcome {
+            Outcome::Unchanged => EncodeOptions::empty(),
+            Outcome::Modified => EncodeOptions::SetBodyLength | EncodeOptions::SetChecksum,
+            Outcome::Drop => {
+                let msg_seq_num = message.fields.try_get(crocofix::FIX_5_0SP2::MsgSeqNum::TAG).map(|field| field.value.as_str()).unwrap_or("");
+                eprintln!("{} {} dropped MsgType={} MsgSeqNum={}", utc_timestamp_now(), direction.label(), message.msg_type().unwrap_or(""), msg_seq_num);
+                continue;
+            }
+        };
+
+        let mut buffer = Vec::new();
+        message.encode(&mut buffer, options)?;
+
+        if !quiet {
+            println!("{} {} {}", utc_timestamp_now(), direction.label(), String::from_utf8_lossy(&buffer));
+        }
+
+        destination.write_all(&buffer)?;
+    }
+
+    let _ = destination.shutdown(Shutdown::Write);
+
+    Ok(())
+}
+
+// Relay messages in both directions between a client and the upstream endpoint until both sides have closed.
+pub fn serve(client: TcpStream, upstream: TcpStream, rules: Arc<Mutex<Rules>>, quiet: bool) -> Result<(), Error>
+{
+    client.set_nodelay(true)?;
+    upstream.set_nodelay(true)?;
+
+    let inbound = {
+        let source = upstream.try_clone()?;
+        let destination = client.try_clone()?;
+        let rules = rules.clone();
+        thread::spawn(move || relay(source, destination, Direction::Inbound, &rules, quiet))
+    };
+
+    let outbound = relay(client.try_clone()?, upstream.try_clone()?, Direction::Outbound, &rules, quiet);
+
+    if outbound.is_err() {
+        // Make sure the other direction doesn't block forever waiting for data.
+        let _ = client.shutdown(Shutdown::Both);
+        let _ = upstream.shutdown(Shutdown::Both);
+    }
+
+    let inbound = inbound.join().unwrap_or(Err(Error::IoError));
+
+    outbound.and(inbound)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crocofix::message::Message;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    const LOGON: &str = "8=FIX.4.4\u{0001}9=72\u{0001}35=A\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=1\u{0001}52=20190816-10:34:27.752\u{0001}98=0\u{0001}108=30\u{0001}10=013\u{0001}";
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}100=AUTO\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}59=1\u{0001}10=062\u{0001}";
+    const HEARTBEAT: &str = "8=FIX.4.4\u{0001}9=58\u{0001}35=0\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20190816-10:34:57.752\u{0001}10=168\u{0001}";
+
+    // Start a proxy between a client and an upstream listener on loopback and return the connected client
+    // and upstream ends along with the proxy thread.
+    fn start(rules: &str) -> (TcpStream, TcpStream, JoinHandle<Result<(), Error>>)
+    {
+        let upstream_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream_address = upstream_listener.local_addr().unwrap();
+        let rules = Arc::new(Mutex::new(Rules::from_yaml(rules).unwrap()));
+
+        let client = TcpStream::connect(proxy_listener.local_addr().unwrap()).unwrap();
+        let proxy = thread::spawn(move || {
+            let (accepted, _) = proxy_listener.accept()?;
+            let upstream = TcpStream::connect(upstream_address)?;
+            serve(accepted, upstream, rules, true)
+        });
+        let (upstream, _) = upstream_listener.accept().unwrap();
+
+        (client, upstream, proxy)
+    }
+
+    fn read_all(stream: &mut TcpStream) -> String
+    {
+        let mut text = String::new();
+        stream.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    fn read_message(stream: &TcpStream) -> Message
+    {
+        FrameReader::new(stream.try_clone().unwrap()).read_message().unwrap().unwrap()
+    }
+
+    #[test]
+    fn messages_are_forwarded_unchanged_in_both_directions()
+    {
+        let (mut client, mut upstream, proxy) = start("{}");
+
+        client.write_all(LOGON.as_bytes()).unwrap();
+        let logon = read_message(&upstream);
+        assert_eq!(logon.msg_type(), Some("A"));
+
+        upstream.write_all(HEARTBEAT.as_bytes()).unwrap();
+        upstream.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(read_all(&mut client), HEARTBEAT);
+
+        client.write_all(ORDER_SINGLE.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(read_all(&mut upstream), ORDER_SINGLE);
+
+        proxy.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn relaying_continues_after_a_message_that_fails_to_decode()
+    {
+        let (mut client, mut upstream, proxy) = start("{}");
+
+        let corrupt = "8=FIX.4.4\u{0001}9=20\u{0001}35=D\u{0001}A=B\u{0001}38=100\u{0001}10=000\u{0001}";
+        client.write_all(corrupt.as_bytes()).unwrap();
+        client.write_all(ORDER_SINGLE.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(read_all(&mut upstream), ORDER_SINGLE);
+
+        upstream.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(read_all(&mut client), "");
+
+        proxy.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn rules_rewrite_and_drop_messages()
+    {
+        let rules = "rules:\n  - direction: outbound\n    match: { MsgType: D }\n    set: { ExDestination: XASX }\n  - direction: outbound\n    match: { MsgType: A }\n    drop: true\n";
+        let (mut client, mut upstream, proxy) = start(rules);
+
+        client.write_all(LOGON.as_bytes()).unwrap();
+        client.write_all(ORDER_SINGLE.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        let forwarded = read_all(&mut upstream);
+        let expected = "8=FIX.4.4\u{0001}9=147\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}100=XASX\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}59=1\u{0001}10=202\u{0001}";
+        assert_eq!(forwarded, expected);
+
+        upstream.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(read_all(&mut client), "");
+
+        proxy.join().unwrap().unwrap();
+    }
+
+}
@@ -0,0 +1,281 @@
+use crocofix::field::Field;
+use crocofix::field_collection::{FieldCollection, RemoveOperation, SetOperation};
+use crocofix::message::Message;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    // From the client connected to the proxy to the upstream endpoint.
+    Outbound,
+    // From the upstream endpoint to the client.
+    Inbound
+}
+
+impl Direction {
+
+    // The label written to the log, these are the same width so the messages line up.
+    pub fn label(&self) -> &'static str
+    {
+        match self {
+            Direction::Outbound => "OUT",
+            Direction::Inbound => "IN "
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct RuleDefinition {
+    direction: Option<Direction>,
+    #[serde(rename = "match", default)]
+    criteria: HashMap<String, String>,
+    #[serde(default)]
+    set: HashMap<String, String>,
+    #[serde(default)]
+    remove: Vec<String>,
+    #[serde(default)]
+    drop: bool,
+    #[serde(default)]
+    skip: u32,
+    count: Option<u32>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct RulesDefinition {
+    #[serde(default)]
+    rules: Vec<RuleDefinition>
+}
+
+// A rule applies to messages travelling in its direction, or both directions if none is specified,
+// that contain all the fields in its match criteria. Fields are specified by name or tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub direction: Option<Direction>,
+    pub criteria: Vec<(u32, String)>,
+    // Replace the value of these fields, fields that aren't present are added to the end of the body.
+    pub set: Vec<(u32, String)>,
+    pub remove: Vec<u32>,
+    pub drop: bool,
+    // Let this many matching messages through unchanged before applying the rule.
+    pub skip: u32,
+    // Stop applying the rule after this many messages, the rule always applies if this isn't specified.
+    pub count: Option<u32>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Unchanged,
+    Modified,
+    Drop
+}
+
+#[derive(Debug, Default)]
+pub struct Rules {
+    rules: Vec<Rule>,
+    // The number of messages each rule has matched so far.
+    matched: Vec<u32>
+}
+
+fn resolve_tag(tag_or_name: &str) -> Result<u32, String>
+{
+    let trimmed = tag_or_name.trim();
+    match trimmed.parse::<u32>() {
+        Ok(tag) => Ok(tag),
+        Err(_) => crocofix::FIX_5_0SP2::fields()
+            .field_with_name(trimmed)
+            .map(|field| field.tag())
+            .ok_or_else(|| format!("Unable to find a FIX field with name or tag = '{}'", tag_or_name))
+    }
+}
+
+// Resolve the names and sort by tag so rules are applied in a repeatable order.
+fn resolve_fields(fields: &HashMap<String, String>) -> Result<Vec<(u32, String)>, String>
+{
+    let mut resolved = fields
+        .iter()
+        .map(|(tag_or_name, value)| Ok((resolve_tag(tag_or_name)?, value.clone())))
+        .collect::<Result<Vec<_>, String>>()?;
+    resolved.sort();
+    Ok(resolved)
+}
+
+impl Rule {
+
+    fn matches(&self, direction: Direction, message: &Message) -> bool
+    {
+        if self.direction.is_some_and(|rule_direction| rule_direction != direction) {
+            return false;
+        }
+
+        self.criteria
+            .iter()
+            .all(|(tag, value)| message.fields.try_get(*tag).is_some_and(|field| field.value == *value))
+    }
+
+    fn rewrite(&self, message: &mut Message)
+    {
+        for tag in &self.remove {
+            message.fields.remove(*tag, RemoveOperation::RemoveAll);
+        }
+
+        // Fields we add have to go before the CheckSum or they won't be encoded.
+        let mut appended = Vec::new();
+        for (tag, value) in &self.set {
+            let field = Field::from_str(*tag, value);
+            if !message.fields.set(&field, SetOperation::ReplaceFirst) {
+                appended.push(field);
+            }
+        }
+
+        if appended.is_empty() {
+            return;
+        }
+
+        let mut fields = FieldCollection::default();
+        for field in &message.fields {
+            if field.tag == crocofix::FIX_5_0SP2::CheckSum::TAG {
+                for field in appended.drain(..) {
+                    fields.push(field);
+                }
+            }
+            fields.push(field.clone());
+        }
+        for field in appended {
+            fields.push(field);
+        }
+        message.fields = fields;
+    }
+
+}
+
+impl Rules {
+
+    pub fn from_yaml(text: &str) -> Result<Self, String>
+    {
+        let definition: RulesDefinition = serde_yaml::from_str(text).map_err(|error| error.to_string())?;
+
+        let rules = definition.rules
+            .into_iter()
+            .map(|rule| {
+                Ok(Rule {
+                    direction: rule.direction,
+                    criteria: resolve_fields(&rule.criteria)?,
+                    set: resolve_fields(&rule.set)?,
+                    remove: rule.remove.iter().map(|tag_or_name| resolve_tag(tag_or_name)).collect::<Result<Vec<_>, String>>()?,
+                    drop: rule.drop,
+                    skip: rule.skip,
+                    count: rule.count
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Rules { matched: vec![0; rules.len()], rules })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String>
+    {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Rules::from_yaml(&text)
+    }
+
+    // Apply every rule that matches the message in order, a rule that drops the message stops any further rules being applied.
+    pub fn apply(&mut self, direction: Direction, message: &mut Message) -> Outcome
+    {
+        let mut outcome = Outcome::Unchanged;
+
+        for (rule, matched) in self.rules.iter().zip(self.matched.iter_mut()) {
+            if !rule.matches(direction, message) {
+                continue;
+            }
+
+            *matched += 1;
+
+            if *matched <= rule.skip || rule.count.is_some_and(|count| *matched > rule.skip + count) {
+                continue;
+            }
+
+            if rule.drop {
+                return Outcome::Drop;
+            }
+
+            rule.rewrite(message);
+            outcome = Outcome::Modified;
+        }
+
+        outcome
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crocofix::message::EncodeOptions;
+
+    fn decode_message(text: &str) -> Message
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes()).unwrap();
+        assert!(result.complete);
+        message
+    }
+
+    fn encode(message: &Message) -> String
+    {
+        let mut buffer = Vec::new();
+        message.encode(&mut buffer, EncodeOptions::Standard).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}100=AUTO\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}59=1\u{0001}10=062\u{0001}";
+
+    #[test]
+    fn example_rules_parse()
+    {
+        let rules = Rules::from_yaml(include_str!("../rules/example.yaml")).unwrap();
+        assert_eq!(rules.rules.len(), 2);
+        assert_eq!(rules.rules[0].set, vec![(100, "XASX".to_string()), (207, "XASX".to_string())]);
+        assert_eq!(rules.rules[1].criteria, vec![(35, "8".to_string())]);
+    }
+
+    #[test]
+    fn unknown_field_is_an_error()
+    {
+        let result = Rules::from_yaml("rules:\n  - remove: [MadeUp]\n");
+        assert_eq!(result.unwrap_err(), "Unable to find a FIX field with name or tag = 'MadeUp'");
+    }
+
+    #[test]
+    fn set_replaces_and_adds_fields()
+    {
+        let mut rules = Rules::from_yaml("rules:\n  - match: { MsgType: D }\n    set: { Symbol: BHP.AX, SecurityExchange: XASX }\n    remove: [ExDestination]\n").unwrap();
+        let mut message = decode_message(ORDER_SINGLE);
+        assert_eq!(rules.apply(Direction::Outbound, &mut message), Outcome::Modified);
+        assert_eq!(
+            encode(&message),
+            "8=FIX.4.4\u{0001}9=147\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}59=1\u{0001}207=XASX\u{0001}10=187\u{0001}"
+        );
+    }
+
+    #[test]
+    fn direction_is_respected()
+    {
+        let mut rules = Rules::from_yaml("rules:\n  - direction: inbound\n    drop: true\n").unwrap();
+        let mut message = decode_message(ORDER_SINGLE);
+        assert_eq!(rules.apply(Direction::Outbound, &mut message), Outcome::Unchanged);
+        assert_eq!(rules.apply(Direction::Inbound, &mut message), Outcome::Drop);
+    }
+
+    #[test]
+    fn skip_and_count_limit_the_messages_affected()
+    {
+        let mut rules = Rules::from_yaml("rules:\n  - match: { 35: D }\n    drop: true\n    skip: 1\n    count: 2\n").unwrap();
+        let outcomes: Vec<Outcome> = (0..5).map(|_| rules.apply(Direction::Outbound, &mut decode_message(ORDER_SINGLE))).collect();
+        assert_eq!(outcomes, vec![Outcome::Unchanged, Outcome::Drop, Outcome::Drop, Outcome::Unchanged, Outcome::Unchanged]);
+    }
+
+}
@@ -0,0 +1,132 @@
+use crate::replay::ClOrdIdMap;
+use crocofix::message::Message;
+use std::collections::BTreeMap;
+
+// Fields such as OrderID, ExecID, and TransactTime are expected to differ on every run so they aren't compared by default.
+pub const DEFAULT_FIELDS: [u32; 8] = [
+    crocofix::FIX_5_0SP2::MsgType::TAG,
+    crocofix::FIX_5_0SP2::ExecType::TAG,
+    crocofix::FIX_5_0SP2::OrdStatus::TAG,
+    crocofix::FIX_5_0SP2::OrderQty::TAG,
+    crocofix::FIX_5_0SP2::LastQty::TAG,
+    crocofix::FIX_5_0SP2::LastPx::TAG,
+    crocofix::FIX_5_0SP2::CumQty::TAG,
+    crocofix::FIX_5_0SP2::LeavesQty::TAG
+];
+
+const NO_VALUE: &str = "<none>";
+
+fn field_name(tag: u32) -> String
+{
+    crocofix::FIX_5_0SP2::fields().name_of_field(tag as usize).map(|name| name.to_string()).unwrap_or_else(|| tag.to_string())
+}
+
+fn value(message: &Message, tag: u32) -> &str
+{
+    message.fields.try_get(tag).map(|field| field.value.as_str()).unwrap_or(NO_VALUE)
+}
+
+// Group responses by the ClOrdID from the log, responses without a ClOrdID are grouped together.
+fn group<'a>(messages: &'a [Message], cl_ord_id: impl Fn(&'a str) -> &'a str) -> BTreeMap<&'a str, Vec<&'a Message>>
+{
+    let mut groups: BTreeMap<&str, Vec<&Message>> = BTreeMap::new();
+    for message in messages {
+        let key = message.fields.try_get(crocofix::FIX_5_0SP2::ClOrdID::TAG).map(|field| cl_ord_id(field.value.as_str())).unwrap_or("");
+        groups.entry(key).or_default().push(message);
+    }
+    groups
+}
+
+// Compare the responses received with the responses in the log for each order and return a
+// description of each difference. Responses for an order are compared in the order they were received.
+pub fn compare(expected: &[Message], actual: &[Message], cl_ord_ids: &ClOrdIdMap, fields: &[u32]) -> Vec<String>
+{
+    let expected = group(expected, |cl_ord_id| cl_ord_id);
+    let actual = group(actual, |cl_ord_id| cl_ord_ids.original(cl_ord_id));
+
+    let mut keys: Vec<&str> = expected.keys().chain(actual.keys()).copied().collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut differences = Vec::new();
+
+    for key in keys {
+        let expected = expected.get(key).map(|messages| messages.as_slice()).unwrap_or_default();
+        let actual = actual.get(key).map(|messages| messages.as_slice()).unwrap_or_default();
+
+        for (index, (expected, actual)) in expected.iter().zip(actual.iter()).enumerate() {
+            for &tag in fields {
+                let (expected_value, actual_value) = (value(expected, tag), value(actual, tag));
+                if expected_value != actual_value {
+                    differences.push(format!("ClOrdID={} response {} {} expected {} received {}", key, index + 1, field_name(tag), expected_value, actual_value));
+                }
+            }
+        }
+
+        if expected.len() != actual.len() {
+            differences.push(format!("ClOrdID={} expected {} responses received {}", key, expected.len(), actual.len()));
+        }
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_message(text: &str) -> Message
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes()).unwrap();
+        assert!(result.complete);
+        message
+    }
+
+    const NEW: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}37=1\u{0001}11=1\u{0001}17=1\u{0001}150=0\u{0001}39=0\u{0001}38=100\u{0001}14=0\u{0001}151=100\u{0001}10=000\u{0001}";
+    const FILLED: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}37=1\u{0001}11=1\u{0001}17=2\u{0001}150=F\u{0001}39=2\u{0001}38=100\u{0001}32=100\u{0001}31=11.56\u{0001}14=100\u{0001}151=0\u{0001}10=000\u{0001}";
+    const NEW_RUN: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}37=7\u{0001}11=1-R1\u{0001}17=9\u{0001}150=0\u{0001}39=0\u{0001}38=100\u{0001}14=0\u{0001}151=100\u{0001}10=000\u{0001}";
+    const PARTIAL_RUN: &str = "8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}37=7\u{0001}11=1-R1\u{0001}17=10\u{0001}150=F\u{0001}39=1\u{0001}38=100\u{0001}32=40\u{0001}31=11.56\u{0001}14=40\u{0001}151=60\u{0001}10=000\u{0001}";
+
+    fn cl_ord_ids() -> ClOrdIdMap
+    {
+        let mut cl_ord_ids = ClOrdIdMap::new("-R1");
+        let mut order_single = decode_message("8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}11=1\u{0001}10=000\u{0001}");
+        cl_ord_ids.refresh(&mut order_single);
+        cl_ord_ids
+    }
+
+    #[test]
+    fn identical_responses_have_no_differences()
+    {
+        let expected = vec![decode_message(NEW), decode_message(FILLED)];
+        let actual = vec![decode_message(NEW_RUN), decode_message(FILLED.replace("11=1", "11=1-R1").as_str())];
+        assert!(compare(&expected, &actual, &cl_ord_ids(), &DEFAULT_FIELDS).is_empty());
+    }
+
+    #[test]
+    fn differences_are_described()
+    {
+        let expected = vec![decode_message(NEW), decode_message(FILLED)];
+        let actual = vec![decode_message(NEW_RUN), decode_message(PARTIAL_RUN)];
+        assert_eq!(
+            compare(&expected, &actual, &cl_ord_ids(), &DEFAULT_FIELDS),
+            vec![
+                "ClOrdID=1 response 2 OrdStatus expected 2 received 1",
+                "ClOrdID=1 response 2 LastQty expected 100 received 40",
+                "ClOrdID=1 response 2 CumQty expected 100 received 40",
+                "ClOrdID=1 response 2 LeavesQty expected 0 received 60"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_responses_are_reported()
+    {
+        let expected = vec![decode_message(NEW), decode_message(FILLED)];
+        let actual = vec![decode_message(NEW_RUN)];
+        assert_eq!(compare(&expected, &actual, &cl_ord_ids(), &DEFAULT_FIELDS), vec!["ClOrdID=1 expected 2 responses received 1"]);
+    }
+
+}
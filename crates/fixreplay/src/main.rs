@@ -0,0 +1,269 @@
+mod comparison;
+mod replay;
+
+use crocofix::error::Error;
+use crocofix::frame_decoder::FrameReader;
+use crocofix::message::{EncodeOptions, Message};
+use crocofix::session::{Session, SessionOptions, SessionState};
+use crocofix::timestamp::utc_timestamp_now;
+use clap::Parser;
+use replay::{ClOrdIdMap, Replay};
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+// How long to wait for incoming data before checking whether the next message is due.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn validate_field(tag_or_name: &str) -> Result<u32, String>
+{
+    let trimmed = tag_or_name.trim();
+
+    let tag = match trimmed.parse::<u32>() {
+        Ok(tag) => tag,
+        Err(_) => {
+            let Some(field) = crocofix::FIX_5_0SP2::fields().field_with_name(trimmed) else {
+                return Err(format!("Unable to find a FIX field with name or tag = '{}'", tag_or_name));
+            };
+            return Ok(field.tag())
+        }
+    };
+
+    if crocofix::FIX_5_0SP2::fields().is_tag_valid(tag as usize) {
+        return Ok(tag);
+    }
+
+    Err(format!("Unable to find a FIX field with tag = '{}'", tag))
+}
+
+/// Replay one direction of a FIX session from a log
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Options {
+
+    /// Connect to an acceptor at this address e.g. 127.0.0.1:5001
+    #[arg(long, conflicts_with = "listen", required_unless_present = "listen")]
+    connect: Option<String>,
+
+    /// Listen for an initiator on this address e.g. 127.0.0.1:5001
+    #[arg(long)]
+    listen: Option<String>,
+
+    /// The SenderCompID of the messages to replay, if not specified it is taken from the first application message
+    #[arg(long)]
+    sender: Option<String>,
+
+    /// The TargetCompID of the messages to replay, if not specified it is taken from the first application message
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Send messages with the same spacing as the original SendingTimes, if not specified messages are sent as fast as possible
+    #[arg(long)]
+    pace: bool,
+
+    /// Appended to every ClOrdID so orders are unique across runs, if not specified the current time is used
+    #[arg(long)]
+    suffix: Option<String>,
+
+    /// The number of seconds to wait for the logon and for responses after the last message is sent
+    #[arg(long, default_value_t = 5)]
+    wait: u64,
+
+    /// Comma separated list of field names or tags to compare in the responses
+    #[arg(long, value_delimiter = ',', value_parser = validate_field)]
+    compare: Option<Vec<u32>>,
+
+    /// Do not log messages to STDOUT
+    #[arg(long)]
+    quiet: bool,
+
+    /// Optional input files, if not specifed input will be read from STDIN
+    files: Vec<String>
+}
+
+struct Connection {
+    reader: FrameReader<TcpStream>,
+    writer: TcpStream,
+    session: Session,
+    quiet: bool,
+    // The application messages received from the counterparty.
+    received: Vec<Message>
+}
+
+impl Connection {
+
+    fn new(stream: TcpStream, session: Session, quiet: bool) -> Result<Self, Error>
+    {
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            writer: stream.try_clone()?,
+            reader: FrameReader::new(stream),
+            session,
+            quiet,
+            received: Vec::new()
+        })
+    }
+
+    fn log(&self, direction: &str, buffer: &[u8])
+    {
+        if !self.quiet {
+            println!("{} {} {}", utc_timestamp_now(), direction, String::from_utf8_lossy(buffer));
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), Error>
+    {
+        let mut buffer = Vec::new();
+        message.encode(&mut buffer, EncodeOptions::Standard)?;
+        self.writer.write_all(&buffer)?;
+        self.log("OUT", &buffer);
+        Ok(())
+    }
+
+    // Process any incoming messages until the deadline, returns false if the connection was closed.
+    fn pump(&mut self, deadline: Instant) -> Result<bool, Error>
+    {
+        loop {
+            match self.reader.read_message() {
+                Ok(Some(message)) => {
+                    let mut buffer = Vec::new();
+                    message.encode(&mut buffer, EncodeOptions::empty())?;
+                    self.log("IN ", &buffer);
+                    for response in self.session.receive(&message)? {
+                        self.send(&response)?;
+                    }
+                    if !message.is_admin() {
+                        self.received.push(message);
+                    }
+                }
+                Ok(None) => return Ok(false),
+                Err(Error::Timeout) => {}
+                Err(error) => return Err(error)
+            }
+
+            if let Some(heartbeat) = self.session.poll(Instant::now()) {
+                self.send(&heartbeat)?;
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(true);
+            }
+        }
+    }
+
+    // Process incoming messages until the condition is met or the timeout expires.
+    fn pump_until(&mut self, timeout: Duration, condition: impl Fn(&Connection) -> bool) -> Result<bool, Error>
+    {
+        let deadline = Instant::now() + timeout;
+        while !condition(self) && Instant::now() < deadline {
+            if !self.pump(Instant::now())? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+}
+
+fn read_input(options: &Options) -> io::Result<Vec<Message>>
+{
+    if options.files.is_empty() {
+        return replay::read_messages(io::stdin().lock());
+    }
+
+    let mut messages = Vec::new();
+    for path in &options.files {
+        messages.extend(replay::read_messages(BufReader::new(File::open(path)?))?);
+    }
+    Ok(messages)
+}
+
+fn connect(options: &Options) -> io::Result<TcpStream>
+{
+    if let Some(address) = &options.connect {
+        return TcpStream::connect(address.as_str());
+    }
+
+    let address = options.listen.as_deref().unwrap_or_default();
+    let listener = TcpListener::bind(address)?;
+    let (stream, _) = listener.accept()?;
+    Ok(stream)
+}
+
+fn main() -> Result<(), Error>
+{
+    let options = Options::parse();
+
+    let replay = match Replay::new(read_input(&options)?, options.sender.as_deref(), options.target.as_deref()) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let session = Session::new(SessionOptions {
+        begin_string: replay.begin_string.clone(),
+        sender_comp_id: replay.sender_comp_id.clone(),
+        target_comp_id: replay.target_comp_id.clone(),
+        ..Default::default()
+    });
+
+    let mut connection = Connection::new(connect(&options)?, session, options.quiet)?;
+    let timeout = Duration::from_secs(options.wait);
+
+    if options.connect.is_some() {
+        let logon = connection.session.logon();
+        connection.send(&logon)?;
+    }
+
+    connection.pump_until(timeout, |connection| connection.session.state() == SessionState::LoggedOn)?;
+    if connection.session.state() != SessionState::LoggedOn {
+        eprintln!("Unable to logon");
+        std::process::exit(1);
+    }
+
+    // The suffix defaults to the current time of day e.g. -023509
+    let suffix = options.suffix.clone().unwrap_or_else(|| format!("-{}", utc_timestamp_now()[9..17].replace(':', "")));
+    let mut cl_ord_ids = ClOrdIdMap::new(&suffix);
+
+    let mut due = Instant::now();
+    for (sent, (request, delay)) in replay.requests.iter().zip(replay.delays()).enumerate() {
+        if options.pace {
+            due += delay;
+            while Instant::now() < due {
+                if !connection.pump(due)? {
+                    eprintln!("The connection was closed after sending {} of {} messages", sent, replay.requests.len());
+                    std::process::exit(1);
+                }
+            }
+        }
+        let mut message = connection.session.prepare(request)?;
+        cl_ord_ids.refresh(&mut message);
+        connection.send(&message)?;
+    }
+
+    let expected = replay.responses.len();
+    connection.pump_until(timeout, |connection| connection.received.len() >= expected)?;
+
+    let logout = connection.session.logout(None);
+    connection.send(&logout)?;
+    connection.pump_until(timeout, |connection| connection.session.state() == SessionState::LoggedOut)?;
+
+    let fields = options.compare.clone().unwrap_or_else(|| comparison::DEFAULT_FIELDS.to_vec());
+    let differences = comparison::compare(&replay.responses, &connection.received, &cl_ord_ids, &fields);
+
+    eprintln!("Sent {} messages, received {} responses, expected {}", replay.requests.len(), connection.received.len(), expected);
+    for difference in &differences {
+        eprintln!("{}", difference);
+    }
+
+    if !differences.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use crocofix::field::Field;
+use crocofix::field_collection::SetOperation;
+use crocofix::message::Message;
+use crocofix::timestamp::parse_utc_timestamp;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::time::Duration;
+
+const FIX_MESSAGE_PREFIX: &str = "8=FIX";
+
+// The fields that carry ClOrdIDs which must be unique for each run.
+const CL_ORD_ID_FIELDS: [u32; 2] = [
+    crocofix::FIX_5_0SP2::ClOrdID::TAG,
+    crocofix::FIX_5_0SP2::OrigClOrdID::TAG
+];
+
+// Read FIX messages from a log in the same format fixcat accepts, one message per line with an
+// optional prefix. Lines that don't contain a message are ignored.
+pub fn read_messages<R: BufRead>(reader: R) -> std::io::Result<Vec<Message>>
+{
+    let mut messages = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let Some(start_of_message) = line.find(FIX_MESSAGE_PREFIX) else {
+            continue;
+        };
+        let mut message = Message::default();
+        match message.decode(&line.as_bytes()[start_of_message..]) {
+            Ok(result) if result.consumed > 0 => messages.push(message),
+            Ok(_) => {}
+            Err(error) => eprintln!("{:?}", error)
+        }
+    }
+
+    Ok(messages)
+}
+
+fn comp_ids(message: &Message) -> Option<(&str, &str)>
+{
+    let sender_comp_id = message.fields.try_get(crocofix::FIX_5_0SP2::SenderCompID::TAG)?;
+    let target_comp_id = message.fields.try_get(crocofix::FIX_5_0SP2::TargetCompID::TAG)?;
+    Some((sender_comp_id.value.as_str(), target_comp_id.value.as_str()))
+}
+
+// The application messages sent in one direction of a session along with the messages the
+// counterparty sent in response. Administrative messages are not replayed, the session
+// generates its own.
+pub struct Replay {
+    pub begin_string: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub requests: Vec<Message>,
+    pub responses: Vec<Message>
+}
+
+impl Replay {
+
+    // Select the messages for the session, if the comp ids are not specified they are taken from the first application message.
+    pub fn new(messages: Vec<Message>, sender_comp_id: Option<&str>, target_comp_id: Option<&str>) -> Result<Self, String>
+    {
+        let first = messages
+            .iter()
+            .filter(|message| !message.is_admin())
+            .filter_map(comp_ids)
+            .find(|(sender, target)| sender_comp_id.is_none_or(|comp_id| comp_id == *sender) && target_comp_id.is_none_or(|comp_id| comp_id == *target));
+
+        let Some((sender_comp_id, target_comp_id)) = first.map(|(sender, target)| (sender.to_string(), target.to_string())) else {
+            return Err("The input does not contain any application messages for the session".to_string());
+        };
+
+        let mut replay = Replay {
+            begin_string: String::new(),
+            sender_comp_id,
+            target_comp_id,
+            requests: Vec::new(),
+            responses: Vec::new()
+        };
+
+        for message in messages {
+            if message.is_admin() {
+                continue;
+            }
+            match comp_ids(&message) {
+                Some((sender, target)) if sender == replay.sender_comp_id && target == replay.target_comp_id => {
+                    if replay.begin_string.is_empty() {
+                        replay.begin_string = message.fields.try_get(crocofix::FIX_5_0SP2::BeginString::TAG).map(|field| field.value.clone()).unwrap_or_default();
+                    }
+                    replay.requests.push(message);
+                }
+                Some((sender, target)) if sender == replay.target_comp_id && target == replay.sender_comp_id => {
+                    replay.responses.push(message);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(replay)
+    }
+
+    // The time to wait before sending each request so the original spacing is preserved.
+    // Messages without a valid SendingTime are sent immediately.
+    pub fn delays(&self) -> Vec<Duration>
+    {
+        let mut previous: Option<DateTime<Utc>> = None;
+
+        self.requests
+            .iter()
+            .map(|message| {
+                let sending_time = message.fields
+                    .try_get(crocofix::FIX_5_0SP2::SendingTime::TAG)
+                    .and_then(|field| parse_utc_timestamp(&field.value));
+                let delay = match (previous, sending_time) {
+                    (Some(previous), Some(sending_time)) => (sending_time - previous).to_std().unwrap_or_default(),
+                    _ => Duration::ZERO
+                };
+                if sending_time.is_some() {
+                    previous = sending_time;
+                }
+                delay
+            })
+            .collect()
+    }
+
+}
+
+// Rewrites ClOrdIDs so orders from a previous run don't collide, the original values are
+// remembered so responses can be matched with the originals.
+pub struct ClOrdIdMap {
+    suffix: String,
+    originals: HashMap<String, String>
+}
+
+impl ClOrdIdMap {
+
+    pub fn new(suffix: &str) -> Self
+    {
+        Self {
+            suffix: suffix.to_string(),
+            originals: HashMap::new()
+        }
+    }
+
+    pub fn refresh(&mut self, message: &mut Message)
+    {
+        for tag in CL_ORD_ID_FIELDS {
+            let Some(field) = message.fields.try_get(tag) else {
+                continue;
+            };
+            let refreshed = format!("{}{}", field.value, self.suffix);
+            self.originals.insert(refreshed.clone(), field.value.clone());
+            message.fields.set(&Field::from_str(tag, &refreshed), SetOperation::ReplaceFirst);
+        }
+    }
+
+    // Returns the ClOrdID from the log for a refreshed ClOrdID, values we didn't generate are returned as is.
+    pub fn original<'a>(&'a self, cl_ord_id: &'a str) -> &'a str
+    {
+        self.originals.get(cl_ord_id).map(|original| original.as_str()).unwrap_or(cl_ord_id)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const LOG: &str = "\
+2020-01-19 02:35:00.000 8=FIX.4.4\u{0001}9=72\u{0001}35=A\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=1\u{0001}52=20200119-02:35:00.000\u{0001}98=0\u{0001}108=30\u{0001}10=013\u{0001}
+not a FIX message
+2020-01-19 02:35:09.990 8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}55=WTF.AX\u{0001}54=1\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}10=061\u{0001}
+2020-01-19 02:35:10.010 8=FIX.4.4\u{0001}9=148\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}52=20200119-02:35:10.010\u{0001}37=1\u{0001}11=1\u{0001}17=1\u{0001}150=0\u{0001}39=0\u{0001}10=061\u{0001}
+2020-01-19 02:35:11.000 8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=OTHER\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:11.000\u{0001}11=7\u{0001}10=061\u{0001}
+2020-01-19 02:35:12.490 8=FIX.4.4\u{0001}9=153\u{0001}35=F\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200119-02:35:12.490\u{0001}41=1\u{0001}11=2\u{0001}55=WTF.AX\u{0001}54=1\u{0001}10=056\u{0001}
+";
+
+    #[test]
+    fn read_messages_skips_other_lines()
+    {
+        let messages = read_messages(LOG.as_bytes()).unwrap();
+        assert_eq!(messages.len(), 5);
+    }
+
+    #[test]
+    fn select_session_from_first_application_message()
+    {
+        let replay = Replay::new(read_messages(LOG.as_bytes()).unwrap(), None, None).unwrap();
+        assert_eq!(replay.sender_comp_id, "INITIATOR");
+        assert_eq!(replay.target_comp_id, "ACCEPTOR");
+        assert_eq!(replay.begin_string, "FIX.4.4");
+        assert_eq!(replay.requests.iter().map(|message| message.msg_type().unwrap()).collect::<Vec<_>>(), vec!["D", "F"]);
+        assert_eq!(replay.responses.len(), 1);
+    }
+
+    #[test]
+    fn select_session_by_comp_id()
+    {
+        let replay = Replay::new(read_messages(LOG.as_bytes()).unwrap(), Some("OTHER"), None).unwrap();
+        assert_eq!(replay.requests.len(), 1);
+        assert!(replay.responses.is_empty());
+        assert!(Replay::new(read_messages(LOG.as_bytes()).unwrap(), Some("NOBODY"), None).is_err());
+    }
+
+    #[test]
+    fn delays_follow_sending_time()
+    {
+        let replay = Replay::new(read_messages(LOG.as_bytes()).unwrap(), None, None).unwrap();
+        assert_eq!(replay.delays(), vec![Duration::ZERO, Duration::from_millis(2500)]);
+    }
+
+    #[test]
+    fn cl_ord_ids_are_refreshed_and_mapped_back()
+    {
+        let mut replay = Replay::new(read_messages(LOG.as_bytes()).unwrap(), None, None).unwrap();
+        let mut cl_ord_ids = ClOrdIdMap::new("-R1");
+        let cancel = &mut replay.requests[1];
+        cl_ord_ids.refresh(cancel);
+        assert_eq!(cancel.fields.get(crocofix::FIX_5_0SP2::ClOrdID::TAG).unwrap().value, "2-R1");
+        assert_eq!(cancel.fields.get(crocofix::FIX_5_0SP2::OrigClOrdID::TAG).unwrap().value, "1-R1");
+        assert_eq!(cl_ord_ids.original("1-R1"), "1");
+        assert_eq!(cl_ord_ids.original("99"), "99");
+    }
+
+}
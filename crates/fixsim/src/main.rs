@@ -0,0 +1,134 @@
+mod scenario;
+mod simulator;
+
+use crocofix::error::Error;
+use crocofix::frame_decoder::FrameReader;
+use crocofix::message::{EncodeOptions, Message};
+use crocofix::session::{Session, SessionOptions, SessionState};
+use crocofix::timestamp::utc_timestamp_now;
+use clap::Parser;
+use scenario::Scenario;
+use simulator::Simulator;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often scheduled steps and heartbeats are checked when there is no incoming data.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Simulate a FIX exchange or broker for integration testing
+#[derive(Parser, Debug, Clone)]
+#[command(version, about, long_about = None)]
+struct Options {
+
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    address: String,
+
+    /// The port to listen on
+    #[arg(long, default_value_t = 5001)]
+    port: u16,
+
+    /// The SenderCompID of the simulator, the TargetCompID is taken from the incoming Logon
+    #[arg(long, default_value = "ACCEPTOR")]
+    comp_id: String,
+
+    /// A YAML file describing how orders are handled, if not specified orders are acknowledged and filled
+    #[arg(long)]
+    scenario: Option<String>,
+
+    /// Do not log messages to STDOUT
+    #[arg(long)]
+    quiet: bool
+}
+
+fn log(options: &Options, direction: &str, buffer: &[u8])
+{
+    if !options.quiet {
+        println!("{} {} {}", utc_timestamp_now(), direction, String::from_utf8_lossy(buffer));
+    }
+}
+
+fn send(stream: &mut TcpStream, options: &Options, messages: Vec<Message>) -> Result<(), Error>
+{
+    for message in messages {
+        let mut buffer = Vec::new();
+        message.encode(&mut buffer, EncodeOptions::Standard)?;
+        log(options, "OUT", &buffer);
+        stream.write_all(&buffer)?;
+    }
+    Ok(())
+}
+
+fn serve(stream: TcpStream, options: &Options, scenario: Scenario) -> Result<(), Error>
+{
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut writer = stream.try_clone()?;
+    let mut reader = FrameReader::new(stream);
+    let mut session = Session::new(SessionOptions { sender_comp_id: options.comp_id.clone(), ..Default::default() });
+    let mut simulator = Simulator::new(scenario);
+
+    while session.state() != SessionState::LoggedOut {
+
+        match reader.read_message() {
+            Ok(Some(message)) => {
+                let mut buffer = Vec::new();
+                message.encode(&mut buffer, EncodeOptions::empty())?;
+                log(options, "IN ", &buffer);
+                let responses = session.receive(&message)?;
+                send(&mut writer, options, responses)?;
+                if !message.is_admin() {
+                    match simulator.handle(&mut session, &message, Instant::now()) {
+                        Ok(responses) => send(&mut writer, options, responses)?,
+                        Err(error) => eprintln!("{:?}", error)
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(Error::Timeout) => {}
+            Err(error) => return Err(error)
+        }
+
+        let responses = simulator.poll(&mut session, Instant::now())?;
+        send(&mut writer, options, responses)?;
+
+        if let Some(heartbeat) = session.poll(Instant::now()) {
+            send(&mut writer, options, vec![heartbeat])?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Error>
+{
+    let options = Options::parse();
+
+    let scenario = match &options.scenario {
+        Some(path) => match Scenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        None => Scenario::default()
+    };
+
+    let listener = TcpListener::bind((options.address.as_str(), options.port))?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let options = options.clone();
+        let scenario = scenario.clone();
+        thread::spawn(move || {
+            if let Err(error) = serve(stream, &options, scenario) {
+                eprintln!("{:?}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
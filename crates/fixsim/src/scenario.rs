@@ -0,0 +1,253 @@
+use crocofix::message::Message;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+// A single action taken against an order. Steps are executed in order, Wait delays the steps that follow it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Ack,
+    // Fill a fixed quantity or a ratio of the OrderQty, if neither is specified the remaining quantity is filled.
+    // The price defaults to the order Price or the scenario price for the Symbol.
+    Fill {
+        quantity: Option<f64>,
+        ratio: Option<f64>,
+        price: Option<f64>
+    },
+    Reject {
+        reason: Option<String>
+    },
+    // An unsolicited cancel e.g. the remainder of an IOC order.
+    Cancel {
+        reason: Option<String>
+    },
+    Wait {
+        milliseconds: u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RandomBehaviour {
+    #[serde(default)]
+    pub reject_probability: f64,
+    #[serde(default = "default_min_fills")]
+    pub min_fills: u32,
+    #[serde(default = "default_max_fills")]
+    pub max_fills: u32,
+    #[serde(default)]
+    pub min_delay_ms: u64,
+    #[serde(default)]
+    pub max_delay_ms: u64
+}
+
+fn default_min_fills() -> u32 { 1 }
+fn default_max_fills() -> u32 { 4 }
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Behaviour {
+    Scripted {
+        steps: Vec<Step>
+    },
+    Random(RandomBehaviour)
+}
+
+impl Default for Behaviour {
+    fn default() -> Self {
+        Behaviour::Scripted {
+            steps: vec![
+                Step::Ack,
+                Step::Fill { quantity: None, ratio: None, price: None }
+            ]
+        }
+    }
+}
+
+// How to respond to an OrderCancelRequest or OrderCancelReplaceRequest.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    #[default]
+    Accept,
+    Reject {
+        reason: Option<String>
+    }
+}
+
+// A rule applies to orders whose NewOrderSingle contains all the fields in the match criteria.
+// Fields are specified by name or tag. Anything not specified falls back to the scenario defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rule {
+    #[serde(rename = "match", default)]
+    pub criteria: HashMap<String, String>,
+    pub behaviour: Option<Behaviour>,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub cancel: Option<Response>,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub replace: Option<Response>
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct Scenario {
+    // Seed the random number generator so random behaviours are repeatable.
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub behaviour: Behaviour,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub cancel: Response,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub replace: Response,
+    // Prices to fill orders without a Price, such as market orders, keyed by Symbol.
+    #[serde(default)]
+    pub prices: HashMap<String, f64>,
+    #[serde(default)]
+    pub rules: Vec<Rule>
+}
+
+fn resolve_tag(tag_or_name: &str) -> Option<u32>
+{
+    let trimmed = tag_or_name.trim();
+    match trimmed.parse::<u32>() {
+        Ok(tag) => Some(tag),
+        Err(_) => crocofix::FIX_5_0SP2::fields().field_with_name(trimmed).map(|field| field.tag())
+    }
+}
+
+fn validate_behaviour(behaviour: &Behaviour) -> Result<(), String>
+{
+    if let Behaviour::Random(random) = behaviour && !(0.0..=1.0).contains(&random.reject_probability) {
+        return Err(format!("reject_probability must be between 0 and 1, found {}", random.reject_probability));
+    }
+    Ok(())
+}
+
+impl Rule {
+
+    pub fn matches(&self, message: &Message) -> bool
+    {
+        self.criteria.iter().all(|(tag_or_name, value)| {
+            resolve_tag(tag_or_name)
+                .and_then(|tag| message.fields.try_get(tag))
+                .is_some_and(|field| field.value == *value)
+        })
+    }
+
+}
+
+impl Scenario {
+
+    pub fn from_yaml(text: &str) -> Result<Self, String>
+    {
+        let scenario: Scenario = serde_yaml::from_str(text).map_err(|error| error.to_string())?;
+
+        validate_behaviour(&scenario.behaviour)?;
+
+        for rule in &scenario.rules {
+            if let Some(behaviour) = &rule.behaviour {
+                validate_behaviour(behaviour)?;
+            }
+            for tag_or_name in rule.criteria.keys() {
+                if resolve_tag(tag_or_name).is_none() {
+                    return Err(format!("Unable to find a FIX field with name or tag = '{}'", tag_or_name));
+                }
+            }
+        }
+
+        Ok(scenario)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String>
+    {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Scenario::from_yaml(&text)
+    }
+
+    // Returns the index of the first rule that matches a NewOrderSingle.
+    pub fn rule_for(&self, order_single: &Message) -> Option<usize>
+    {
+        self.rules.iter().position(|rule| rule.matches(order_single))
+    }
+
+    pub fn behaviour(&self, rule: Option<usize>) -> &Behaviour
+    {
+        rule.and_then(|index| self.rules[index].behaviour.as_ref()).unwrap_or(&self.behaviour)
+    }
+
+    pub fn cancel(&self, rule: Option<usize>) -> &Response
+    {
+        rule.and_then(|index| self.rules[index].cancel.as_ref()).unwrap_or(&self.cancel)
+    }
+
+    pub fn replace(&self, rule: Option<usize>) -> &Response
+    {
+        rule.and_then(|index| self.rules[index].replace.as_ref()).unwrap_or(&self.replace)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default_scenario_acks_and_fills()
+    {
+        let scenario = Scenario::from_yaml("{}").unwrap();
+        assert_eq!(scenario.behaviour, Behaviour::default());
+        assert_eq!(scenario.cancel, Response::Accept);
+        assert!(scenario.rules.is_empty());
+    }
+
+    #[test]
+    fn parse_example_scenario()
+    {
+        let scenario = Scenario::from_yaml(include_str!("../scenarios/example.yaml")).unwrap();
+        assert_eq!(scenario.seed, Some(42));
+        assert_eq!(scenario.prices.get("WTF.AX"), Some(&11.56));
+        assert_eq!(scenario.rules.len(), 3);
+        assert_eq!(
+            scenario.rules[0].behaviour,
+            Some(Behaviour::Scripted { steps: vec![Step::Reject { reason: Some("Unknown symbol".to_string()) }] })
+        );
+        assert_eq!(
+            scenario.rules[1].behaviour,
+            Some(Behaviour::Scripted { steps: vec![
+                Step::Ack,
+                Step::Wait { milliseconds: 100 },
+                Step::Fill { quantity: None, ratio: Some(0.5), price: None },
+                Step::Fill { quantity: None, ratio: None, price: Some(11.57) }
+            ]})
+        );
+        assert_eq!(scenario.rules[2].cancel, Some(Response::Reject { reason: Some("Too late to cancel".to_string()) }));
+        assert!(matches!(scenario.behaviour, Behaviour::Random(_)));
+    }
+
+    #[test]
+    fn unknown_match_field_is_an_error()
+    {
+        let result = Scenario::from_yaml("rules:\n  - match: { MadeUp: X }\n");
+        assert_eq!(result, Err("Unable to find a FIX field with name or tag = 'MadeUp'".to_string()));
+    }
+
+    #[test]
+    fn reject_probability_out_of_range_is_an_error()
+    {
+        let result = Scenario::from_yaml("behaviour: { type: random, reject_probability: .nan }\n");
+        assert_eq!(result, Err("reject_probability must be between 0 and 1, found NaN".to_string()));
+        let result = Scenario::from_yaml("rules:\n  - match: { Symbol: WTF.AX }\n    behaviour: { type: random, reject_probability: 1.5 }\n");
+        assert_eq!(result, Err("reject_probability must be between 0 and 1, found 1.5".to_string()));
+    }
+
+    #[test]
+    fn rule_matches_by_name_and_tag()
+    {
+        let text = "8=FIX.4.4\u{0001}9=149\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2752\u{0001}52=20200114-08:13:20.041\u{0001}11=61\u{0001}70=60\u{0001}100=AUTO\u{0001}55=BHP.AX\u{0001}54=1\u{0001}60=20200114-08:12:59.397\u{0001}38=10000\u{0001}40=2\u{0001}44=20\u{0001}59=1\u{0001}10=021\u{0001}";
+        let mut message = Message::default();
+        message.decode(text.as_bytes()).unwrap();
+        let scenario = Scenario::from_yaml("rules:\n  - match: { Symbol: WTF.AX }\n  - match: { Symbol: BHP.AX, 54: '1' }\n").unwrap();
+        assert_eq!(scenario.rule_for(&message), Some(1));
+    }
+
+}
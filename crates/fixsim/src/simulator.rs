@@ -0,0 +1,659 @@
+use crate::scenario::{Behaviour, RandomBehaviour, Response, Scenario, Step};
+use crocofix::dictionary::FieldValue;
+use crocofix::error::Error;
+use crocofix::field::Field;
+use crocofix::field_collection::FieldCollection;
+use crocofix::message::Message;
+use crocofix::order::Order;
+use crocofix::order_book::OrderBook;
+use crocofix::session::Session;
+use crocofix::FIX_5_0SP2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+struct ScheduledStep {
+    due: Instant,
+    key: String,
+    step: Step
+}
+
+// CxlRejResponseTo values
+const RESPONSE_TO_CANCEL: &str = "1";
+const RESPONSE_TO_REPLACE: &str = "2";
+// CxlRejReason values
+const TOO_LATE_TO_CANCEL: &str = "0";
+const UNKNOWN_ORDER: &str = "1";
+// OrdRejReason values
+const DUPLICATE_ORDER: &str = "6";
+
+// The simulator acts as the sell side of a session. It tracks the state of every order in an
+// OrderBook by processing both the incoming requests and the execution reports it generates so
+// the book contains exactly what a log of the session would produce.
+pub struct Simulator {
+    scenario: Scenario,
+    pub order_book: OrderBook,
+    // The scenario rule that matched each order, keyed by the order book key.
+    rules: HashMap<String, Option<usize>>,
+    scheduled: Vec<ScheduledStep>,
+    next_order_id: u64,
+    next_exec_id: u64,
+    rng: StdRng
+}
+
+fn is_terminal(order: &Order) -> bool
+{
+    let Some(ord_status) = order.fields.try_get(FIX_5_0SP2::OrdStatus::TAG) else {
+        return false;
+    };
+
+    ord_status == FIX_5_0SP2::OrdStatus::Filled() ||
+    ord_status == FIX_5_0SP2::OrdStatus::Canceled() ||
+    ord_status == FIX_5_0SP2::OrdStatus::Replaced() ||
+    ord_status == FIX_5_0SP2::OrdStatus::Rejected() ||
+    ord_status == FIX_5_0SP2::OrdStatus::Expired() ||
+    ord_status == FIX_5_0SP2::OrdStatus::DoneForDay()
+}
+
+fn decimal(order: &Order, tag: u32) -> Result<f64, Error>
+{
+    match order.fields.try_get(tag) {
+        Some(field) => field.decimal_value(),
+        None => Ok(0.0)
+    }
+}
+
+impl Simulator {
+
+    pub fn new(scenario: Scenario) -> Self
+    {
+        let rng = match scenario.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng()
+        };
+
+        Self {
+            scenario,
+            order_book: OrderBook::default(),
+            rules: HashMap::new(),
+            scheduled: Vec::new(),
+            next_order_id: 1,
+            next_exec_id: 1,
+            rng
+        }
+    }
+
+    // Handle an application message received from the initiator and return the messages to send in response.
+    pub fn handle(&mut self, session: &mut Session, message: &Message, now: Instant) -> Result<Vec<Message>, Error>
+    {
+        let Some(msg_type) = message.msg_type() else {
+            return Err(Error::MessageDoesNotContainMsgType);
+        };
+
+        let mut responses = if msg_type == FIX_5_0SP2::MsgType::NewOrderSingle().value {
+            self.new_order_single(session, message, now)?
+        }
+        else if msg_type == FIX_5_0SP2::MsgType::OrderCancelRequest().value {
+            self.order_cancel_request(session, message)?
+        }
+        else if msg_type == FIX_5_0SP2::MsgType::OrderCancelReplaceRequest().value {
+            self.order_cancel_replace_request(session, message)?
+        }
+        else {
+            return Err(Error::UnsupportedMsgType(msg_type.to_string()));
+        };
+
+        responses.extend(self.poll(session, now)?);
+
+        Ok(responses)
+    }
+
+    // Execute any scheduled steps that are due.
+    pub fn poll(&mut self, session: &mut Session, now: Instant) -> Result<Vec<Message>, Error>
+    {
+        let mut responses = Vec::new();
+
+        while let Some(index) = self.next_due(now) {
+            let scheduled = self.scheduled.remove(index);
+            if let Some(response) = self.execute(session, &scheduled.key, &scheduled.step)? {
+                responses.push(response);
+            }
+        }
+
+        Ok(responses)
+    }
+
+    // Scheduled steps are stored in the order they were scheduled so the earliest due step wins
+    // ties, this keeps the steps for each order in sequence.
+    fn next_due(&self, now: Instant) -> Option<usize>
+    {
+        self.scheduled
+            .iter()
+            .enumerate()
+            .filter(|(_, scheduled)| scheduled.due <= now)
+            .min_by_key(|(index, scheduled)| (scheduled.due, *index))
+            .map(|(index, _)| index)
+    }
+
+    fn new_order_single(&mut self, session: &mut Session, order_single: &Message, now: Instant) -> Result<Vec<Message>, Error>
+    {
+        let key = Order::key_for_message(order_single, false)?;
+
+        if self.order_book.orders.contains_key(&key) {
+            return Ok(vec![self.duplicate_order_reject(session, order_single)?]);
+        }
+
+        self.order_book.process(order_single)?;
+
+        let rule = self.scenario.rule_for(order_single);
+        self.rules.insert(key.clone(), rule);
+
+        let symbol = order_single.fields.try_get(FIX_5_0SP2::Symbol::TAG).map(|field| field.value.clone()).unwrap_or_default();
+
+        if order_single.fields.try_get(FIX_5_0SP2::Price::TAG).is_none() && !self.scenario.prices.contains_key(&symbol) {
+            let reason = Some("No price available".to_string());
+            return Ok(self.execute(session, &key, &Step::Reject { reason })?.into_iter().collect());
+        }
+
+        let steps = match self.scenario.behaviour(rule).clone() {
+            Behaviour::Scripted { steps } => steps,
+            Behaviour::Random(behaviour) => self.random_steps(&behaviour)
+        };
+
+        let mut due = now;
+        for step in steps {
+            if let Step::Wait { milliseconds } = step {
+                due += Duration::from_millis(milliseconds);
+                continue;
+            }
+            self.scheduled.push(ScheduledStep { due, key: key.clone(), step });
+        }
+
+        Ok(vec![])
+    }
+
+    // Reject a NewOrderSingle that reuses the ClOrdID of an existing order. The reject is not
+    // processed by the order book as it would be applied to the existing order.
+    fn duplicate_order_reject(&mut self, session: &mut Session, order_single: &Message) -> Result<Message, Error>
+    {
+        let order = Order::new(order_single)?;
+        let mut body = self.execution_report(&order, &order.cl_ord_id.value, None, FIX_5_0SP2::ExecType::Rejected(), FIX_5_0SP2::OrdStatus::Rejected(), None, Some("Duplicate ClOrdID"))?;
+        body.push(Field::from_str(FIX_5_0SP2::OrdRejReason::TAG, DUPLICATE_ORDER));
+        Ok(session.create(FIX_5_0SP2::MsgType::ExecutionReport().value, &body))
+    }
+
+    fn random_steps(&mut self, behaviour: &RandomBehaviour) -> Vec<Step>
+    {
+        if self.rng.random_bool(behaviour.reject_probability) {
+            return vec![Step::Reject { reason: Some("Random reject".to_string()) }];
+        }
+
+        let mut steps = vec![Step::Ack];
+
+        let min_fills = behaviour.min_fills.max(1);
+        let fills = self.rng.random_range(min_fills..=behaviour.max_fills.max(min_fills));
+
+        // Split the order at random points, the last fill takes whatever remains.
+        let mut ratios: Vec<f64> = (1..fills).map(|_| self.rng.random_range(0.0..1.0)).collect();
+        ratios.sort_by(|a, b| a.total_cmp(b));
+        let mut previous = 0.0;
+
+        for index in 0..fills as usize {
+            let delay = self.rng.random_range(behaviour.min_delay_ms..=behaviour.max_delay_ms.max(behaviour.min_delay_ms));
+            if delay > 0 {
+                steps.push(Step::Wait { milliseconds: delay });
+            }
+            match ratios.get(index) {
+                Some(&ratio) => {
+                    steps.push(Step::Fill { quantity: None, ratio: Some(ratio - previous), price: None });
+                    previous = ratio;
+                }
+                None => steps.push(Step::Fill { quantity: None, ratio: None, price: None })
+            }
+        }
+
+        steps
+    }
+
+    fn order_cancel_request(&mut self, session: &mut Session, order_cancel_request: &Message) -> Result<Vec<Message>, Error>
+    {
+        let key = Order::key_for_message(order_cancel_request, false)?;
+
+        let Some(order) = self.order_book.orders.get(&key) else {
+            let reject = self.order_cancel_reject(session, order_cancel_request, None, RESPONSE_TO_CANCEL, Some(UNKNOWN_ORDER), "Unknown order")?;
+            return Ok(vec![reject]);
+        };
+
+        let ord_status = order.fields.try_get(FIX_5_0SP2::OrdStatus::TAG).cloned();
+
+        if is_terminal(order) {
+            let reject = self.order_cancel_reject(session, order_cancel_request, ord_status, RESPONSE_TO_CANCEL, Some(TOO_LATE_TO_CANCEL), "Too late to cancel")?;
+            return Ok(vec![reject]);
+        }
+
+        self.order_book.process(order_cancel_request)?;
+
+        let rule = self.rules.get(&key).copied().flatten();
+
+        if let Response::Reject { reason } = self.scenario.cancel(rule).clone() {
+            let reason = reason.unwrap_or_else(|| "Cancel rejected".to_string());
+            let reject = self.order_cancel_reject(session, order_cancel_request, ord_status, RESPONSE_TO_CANCEL, None, &reason)?;
+            return Ok(vec![reject]);
+        }
+
+        self.scheduled.retain(|scheduled| scheduled.key != key);
+
+        let order = self.order(&key)?;
+        let cl_ord_id = order_cancel_request.fields.get(FIX_5_0SP2::ClOrdID::TAG)?.value.clone();
+        let body = self.execution_report(&order, &cl_ord_id, Some(&order.cl_ord_id.value), FIX_5_0SP2::ExecType::Canceled(), FIX_5_0SP2::OrdStatus::Canceled(), None, None)?;
+
+        Ok(vec![self.send(session, FIX_5_0SP2::MsgType::ExecutionReport().value, &body)?])
+    }
+
+    fn order_cancel_replace_request(&mut self, session: &mut Session, order_cancel_replace_request: &Message) -> Result<Vec<Message>, Error>
+    {
+        let key = Order::key_for_message(order_cancel_replace_request, false)?;
+
+        let Some(order) = self.order_book.orders.get(&key) else {
+            let reject = self.order_cancel_reject(session, order_cancel_replace_request, None, RESPONSE_TO_REPLACE, Some(UNKNOWN_ORDER), "Unknown order")?;
+            return Ok(vec![reject]);
+        };
+
+        let ord_status = order.fields.try_get(FIX_5_0SP2::OrdStatus::TAG).cloned();
+
+        if is_terminal(order) {
+            let reject = self.order_cancel_reject(session, order_cancel_replace_request, ord_status, RESPONSE_TO_REPLACE, Some(TOO_LATE_TO_CANCEL), "Too late to replace")?;
+            return Ok(vec![reject]);
+        }
+
+        let cum_qty = decimal(order, FIX_5_0SP2::CumQty::TAG)?;
+        let order_qty = order_cancel_replace_request.fields.get(FIX_5_0SP2::OrderQty::TAG)?.decimal_value()?;
+
+        self.order_book.process(order_cancel_replace_request)?;
+
+        let rule = self.rules.get(&key).copied().flatten();
+
+        let response = if order_qty <= cum_qty {
+            Response::Reject { reason: Some("OrderQty must be greater than CumQty".to_string()) }
+        }
+        else {
+            self.scenario.replace(rule).clone()
+        };
+
+        if let Response::Reject { reason } = response {
+            let reason = reason.unwrap_or_else(|| "Replace rejected".to_string());
+            let reject = self.order_cancel_reject(session, order_cancel_replace_request, ord_status, RESPONSE_TO_REPLACE, None, &reason)?;
+            return Ok(vec![reject]);
+        }
+
+        // Report the order as it will be once the replacement is applied.
+        let mut replacement = self.order(&key)?;
+        for field in &order_cancel_replace_request.fields {
+            if !Order::is_identity_field(field.tag) && field.tag != FIX_5_0SP2::MsgType::TAG {
+                replacement.fields.set(field, crocofix::field_collection::SetOperation::ReplaceFirstOrAppend);
+            }
+        }
+
+        let ord_status = if cum_qty > 0.0 { FIX_5_0SP2::OrdStatus::PartiallyFilled() } else { FIX_5_0SP2::OrdStatus::New() };
+        let cl_ord_id = order_cancel_replace_request.fields.get(FIX_5_0SP2::ClOrdID::TAG)?.value.clone();
+        let body = self.execution_report(&replacement, &cl_ord_id, Some(&replacement.cl_ord_id.value), FIX_5_0SP2::ExecType::Replaced(), ord_status, None, None)?;
+        let response = self.send(session, FIX_5_0SP2::MsgType::ExecutionReport().value, &body)?;
+
+        // Steps that have not been executed yet now apply to the replacement order, strategies that keep the
+        // same key across a replace leave the original without a successor.
+        let replacement_key = self.order_book.orders.get(&key).and_then(|order| order.successor.clone()).unwrap_or_else(|| key.clone());
+        self.rules.insert(replacement_key.clone(), rule);
+        for scheduled in self.scheduled.iter_mut().filter(|scheduled| scheduled.key == key) {
+            scheduled.key = replacement_key.clone();
+        }
+
+        Ok(vec![response])
+    }
+
+    fn order(&self, key: &str) -> Result<Order, Error>
+    {
+        self.order_book.orders.get(key).cloned().ok_or_else(|| Error::OrderBookDoesNotContainOrderWithKey(key.to_string()))
+    }
+
+    fn execute(&mut self, session: &mut Session, key: &str, step: &Step) -> Result<Option<Message>, Error>
+    {
+        let order = self.order(key)?;
+
+        if is_terminal(&order) {
+            return Ok(None);
+        }
+
+        let cl_ord_id = order.cl_ord_id.value.clone();
+
+        let body = match step {
+            Step::Ack => {
+                self.execution_report(&order, &cl_ord_id, None, FIX_5_0SP2::ExecType::New(), FIX_5_0SP2::OrdStatus::New(), None, None)?
+            }
+            Step::Fill { quantity, ratio, price } => {
+                let order_qty = decimal(&order, FIX_5_0SP2::OrderQty::TAG)?;
+                let leaves_qty = order_qty - decimal(&order, FIX_5_0SP2::CumQty::TAG)?;
+                let last_qty = match (quantity, ratio) {
+                    (Some(quantity), _) => *quantity,
+                    (None, Some(ratio)) => (order_qty * ratio).round(),
+                    (None, None) => leaves_qty
+                }.min(leaves_qty);
+                if last_qty <= 0.0 {
+                    return Ok(None);
+                }
+                let last_px = match price {
+                    Some(price) => *price,
+                    None => self.price(&order)?
+                };
+                let ord_status = if last_qty >= leaves_qty { FIX_5_0SP2::OrdStatus::Filled() } else { FIX_5_0SP2::OrdStatus::PartiallyFilled() };
+                self.execution_report(&order, &cl_ord_id, None, FIX_5_0SP2::ExecType::Trade(), ord_status, Some((last_qty, last_px)), None)?
+            }
+            Step::Reject { reason } => {
+                self.execution_report(&order, &cl_ord_id, None, FIX_5_0SP2::ExecType::Rejected(), FIX_5_0SP2::OrdStatus::Rejected(), None, reason.as_deref())?
+            }
+            Step::Cancel { reason } => {
+                self.execution_report(&order, &cl_ord_id, None, FIX_5_0SP2::ExecType::Canceled(), FIX_5_0SP2::OrdStatus::Canceled(), None, reason.as_deref())?
+            }
+            Step::Wait { .. } => return Ok(None)
+        };
+
+        Ok(Some(self.send(session, FIX_5_0SP2::MsgType::ExecutionReport().value, &body)?))
+    }
+
+    fn price(&self, order: &Order) -> Result<f64, Error>
+    {
+        if let Some(price) = order.fields.try_get(FIX_5_0SP2::Price::TAG) {
+            return price.decimal_value();
+        }
+
+        let symbol = order.fields.get(FIX_5_0SP2::Symbol::TAG)?;
+
+        self.scenario.prices.get(&symbol.value).copied().ok_or(Error::MessageDoesNotContainFieldWithTag(FIX_5_0SP2::Price::TAG))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execution_report(&mut self,
+                        order: &Order,
+                        cl_ord_id: &str,
+                        orig_cl_ord_id: Option<&str>,
+                        exec_type: &FieldValue,
+                        ord_status: &FieldValue,
+                        fill: Option<(f64, f64)>,
+                        text: Option<&str>) -> Result<FieldCollection, Error>
+    {
+        let order_id = match order.fields.try_get(FIX_5_0SP2::OrderID::TAG) {
+            Some(order_id) => order_id.value.clone(),
+            None => {
+                self.next_order_id += 1;
+                (self.next_order_id - 1).to_string()
+            }
+        };
+
+        let exec_id = self.next_exec_id.to_string();
+        self.next_exec_id += 1;
+
+        let order_qty = decimal(order, FIX_5_0SP2::OrderQty::TAG)?;
+        let mut cum_qty = decimal(order, FIX_5_0SP2::CumQty::TAG)?;
+        let mut avg_px = decimal(order, FIX_5_0SP2::AvgPx::TAG)?;
+        let (last_qty, last_px) = fill.unwrap_or((0.0, 0.0));
+
+        if last_qty > 0.0 {
+            avg_px = (avg_px * cum_qty + last_qty * last_px) / (cum_qty + last_qty);
+            cum_qty += last_qty;
+        }
+
+        let terminal = ord_status.value == FIX_5_0SP2::OrdStatus::Canceled().value || ord_status.value == FIX_5_0SP2::OrdStatus::Rejected().value;
+        let leaves_qty = if terminal { 0.0 } else { order_qty - cum_qty };
+
+        let mut body = FieldCollection::default();
+        body.push(Field::from_str(FIX_5_0SP2::OrderID::TAG, &order_id));
+        body.push(Field::from_str(FIX_5_0SP2::ClOrdID::TAG, cl_ord_id));
+        if let Some(orig_cl_ord_id) = orig_cl_ord_id {
+            body.push(Field::from_str(FIX_5_0SP2::OrigClOrdID::TAG, orig_cl_ord_id));
+        }
+        body.push(Field::from_str(FIX_5_0SP2::ExecID::TAG, &exec_id));
+        body.push(Field::from_field_value(exec_type));
+        body.push(Field::from_field_value(ord_status));
+        for tag in [FIX_5_0SP2::Symbol::TAG, FIX_5_0SP2::Side::TAG, FIX_5_0SP2::OrderQty::TAG, FIX_5_0SP2::OrdType::TAG, FIX_5_0SP2::Price::TAG, FIX_5_0SP2::TimeInForce::TAG] {
+            if let Some(field) = order.fields.try_get(tag) {
+                body.push(field.clone());
+            }
+        }
+        body.push(Field::from_decimal(FIX_5_0SP2::LastQty::TAG, last_qty));
+        body.push(Field::from_decimal(FIX_5_0SP2::LastPx::TAG, last_px));
+        body.push(Field::from_decimal(FIX_5_0SP2::LeavesQty::TAG, leaves_qty));
+        body.push(Field::from_decimal(FIX_5_0SP2::CumQty::TAG, cum_qty));
+        body.push(Field::from_decimal(FIX_5_0SP2::AvgPx::TAG, avg_px));
+        if let Some(text) = text {
+            body.push(Field::from_str(FIX_5_0SP2::Text::TAG, text));
+        }
+
+        Ok(body)
+    }
+
+    fn order_cancel_reject(&mut self,
+                           session: &mut Session,
+                           request: &Message,
+                           ord_status: Option<Field>,
+                           response_to: &str,
+                           reason: Option<&str>,
+                           text: &str) -> Result<Message, Error>
+    {
+        let mut body = FieldCollection::default();
+        let order_id = request.fields.try_get(FIX_5_0SP2::OrderID::TAG).map(|field| field.value.as_str()).unwrap_or("NONE");
+        body.push(Field::from_str(FIX_5_0SP2::OrderID::TAG, order_id));
+        body.push(request.fields.get(FIX_5_0SP2::ClOrdID::TAG)?.clone());
+        body.push(request.fields.get(FIX_5_0SP2::OrigClOrdID::TAG)?.clone());
+        body.push(ord_status.unwrap_or_else(|| Field::from_field_value(FIX_5_0SP2::OrdStatus::Rejected())));
+        body.push(Field::from_str(FIX_5_0SP2::CxlRejResponseTo::TAG, response_to));
+        if let Some(reason) = reason {
+            body.push(Field::from_str(FIX_5_0SP2::CxlRejReason::TAG, reason));
+        }
+        body.push(Field::from_str(FIX_5_0SP2::Text::TAG, text));
+
+        let message = session.create(FIX_5_0SP2::MsgType::OrderCancelReject().value, &body);
+        // Rejects for unknown orders won't be in the book.
+        let _ = self.order_book.process(&message);
+        Ok(message)
+    }
+
+    fn send(&mut self, session: &mut Session, msg_type: &str, body: &FieldCollection) -> Result<Message, Error>
+    {
+        let message = session.create(msg_type, body);
+        self.order_book.process(&message)?;
+        Ok(message)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crocofix::session::SessionOptions;
+
+    fn decode_message(text: &str) -> Message
+    {
+        let mut message = Message::default();
+        let result = message.decode(text.as_bytes()).unwrap();
+        assert!(result.complete);
+        message
+    }
+
+    fn session() -> Session
+    {
+        Session::new(SessionOptions { sender_comp_id: "ACCEPTOR".to_string(), target_comp_id: "INITIATOR".to_string(), ..Default::default() })
+    }
+
+    fn field(message: &Message, tag: u32) -> String
+    {
+        message.fields.get(tag).map(|field| field.value.clone()).unwrap_or_default()
+    }
+
+    const ORDER_SINGLE: &str = "8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}100=AUTO\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=2\u{0001}44=11.56\u{0001}59=1\u{0001}10=061\u{0001}";
+    const MARKET_ORDER: &str = "8=FIX.4.4\u{0001}9=148\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}52=20200119-02:35:09.990\u{0001}11=1\u{0001}70=1\u{0001}100=AUTO\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:30:33.801\u{0001}38=20000\u{0001}40=1\u{0001}59=1\u{0001}10=061\u{0001}";
+    const CANCEL: &str = "8=FIX.4.4\u{0001}9=153\u{0001}35=F\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200119-04:43:43.562\u{0001}41=1\u{0001}11=2\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-04:43:42.213\u{0001}38=20000\u{0001}10=056\u{0001}";
+    const REPLACE: &str = "8=FIX.4.4\u{0001}9=178\u{0001}35=G\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}52=20200119-02:35:32.416\u{0001}41=1\u{0001}11=2\u{0001}70=1\u{0001}100=AUTO\u{0001}55=WTF.AX\u{0001}54=1\u{0001}60=20200119-02:35:17.910\u{0001}38=40000\u{0001}40=2\u{0001}44=11.565\u{0001}59=1\u{0001}10=132\u{0001}";
+
+    #[test]
+    fn default_scenario_acks_and_fills_immediately()
+    {
+        let mut simulator = Simulator::new(Scenario::default());
+        let mut session = session();
+        let responses = simulator.handle(&mut session, &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ExecType::TAG), "0");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LeavesQty::TAG), "20000");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::ExecType::TAG), "F");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::OrdStatus::TAG), "2");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::LastQty::TAG), "20000");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::LastPx::TAG), "11.56");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::CumQty::TAG), "20000");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::LeavesQty::TAG), "0");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::OrderID::TAG), field(&responses[0], FIX_5_0SP2::OrderID::TAG));
+        let order = simulator.order_book.orders.values().next().unwrap();
+        assert_eq!(order.fields.get(FIX_5_0SP2::OrdStatus::TAG).unwrap(), FIX_5_0SP2::OrdStatus::Filled());
+    }
+
+    #[test]
+    fn duplicate_cl_ord_id_is_rejected()
+    {
+        let mut simulator = Simulator::new(Scenario::default());
+        let mut session = session();
+        simulator.handle(&mut session, &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        let responses = simulator.handle(&mut session, &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ClOrdID::TAG), "1");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ExecType::TAG), "8");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdStatus::TAG), "8");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdRejReason::TAG), "6");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LeavesQty::TAG), "0");
+        // The existing order is unaffected.
+        let order = simulator.order_book.orders.values().next().unwrap();
+        assert_eq!(order.fields.get(FIX_5_0SP2::OrdStatus::TAG).unwrap(), FIX_5_0SP2::OrdStatus::Filled());
+    }
+
+    #[test]
+    fn scripted_steps_wait()
+    {
+        let scenario = Scenario::from_yaml("behaviour:\n  type: scripted\n  steps: [ack, wait: { milliseconds: 100 }, fill: { ratio: 0.25, price: 11.5 }, fill: {}]\n").unwrap();
+        let mut simulator = Simulator::new(scenario);
+        let mut session = session();
+        let now = Instant::now();
+        let responses = simulator.handle(&mut session, &decode_message(ORDER_SINGLE), now).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert!(simulator.poll(&mut session, now + Duration::from_millis(50)).unwrap().is_empty());
+        let responses = simulator.poll(&mut session, now + Duration::from_millis(100)).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LastQty::TAG), "5000");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdStatus::TAG), "1");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::LastQty::TAG), "15000");
+        assert_eq!(field(&responses[1], FIX_5_0SP2::AvgPx::TAG), "11.545");
+    }
+
+    #[test]
+    fn rule_injects_reject()
+    {
+        let scenario = Scenario::from_yaml("rules:\n  - match: { Symbol: WTF.AX }\n    behaviour: { type: scripted, steps: [reject: { reason: Nope }] }\n").unwrap();
+        let mut simulator = Simulator::new(scenario);
+        let responses = simulator.handle(&mut session(), &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdStatus::TAG), "8");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::Text::TAG), "Nope");
+    }
+
+    #[test]
+    fn market_order_without_price_is_rejected()
+    {
+        let mut simulator = Simulator::new(Scenario::default());
+        let responses = simulator.handle(&mut session(), &decode_message(MARKET_ORDER), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdStatus::TAG), "8");
+    }
+
+    #[test]
+    fn market_order_filled_at_scenario_price()
+    {
+        let mut simulator = Simulator::new(Scenario::from_yaml("prices: { WTF.AX: 11.6 }").unwrap());
+        let responses = simulator.handle(&mut session(), &decode_message(MARKET_ORDER), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(field(&responses[1], FIX_5_0SP2::LastPx::TAG), "11.6");
+    }
+
+    #[test]
+    fn cancel_accepted()
+    {
+        let mut simulator = Simulator::new(Scenario::from_yaml("behaviour: { type: scripted, steps: [ack] }").unwrap());
+        let mut session = session();
+        simulator.handle(&mut session, &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        let responses = simulator.handle(&mut session, &decode_message(CANCEL), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ExecType::TAG), "4");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ClOrdID::TAG), "2");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrigClOrdID::TAG), "1");
+        let order = simulator.order_book.orders.values().next().unwrap();
+        assert_eq!(order.fields.get(FIX_5_0SP2::OrdStatus::TAG).unwrap(), FIX_5_0SP2::OrdStatus::Canceled());
+    }
+
+    #[test]
+    fn cancel_rejected()
+    {
+        let mut simulator = Simulator::new(Scenario::from_yaml("behaviour: { type: scripted, steps: [ack] }\ncancel: { reject: { reason: Busy } }").unwrap());
+        let mut session = session();
+        simulator.handle(&mut session, &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+        let responses = simulator.handle(&mut session, &decode_message(CANCEL), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].msg_type(), Some("9"));
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrdStatus::TAG), "0");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::Text::TAG), "Busy");
+        let order = simulator.order_book.orders.values().next().unwrap();
+        assert_eq!(order.fields.get(FIX_5_0SP2::OrdStatus::TAG).unwrap(), FIX_5_0SP2::OrdStatus::New());
+    }
+
+    #[test]
+    fn cancel_unknown_order()
+    {
+        let mut simulator = Simulator::new(Scenario::default());
+        let responses = simulator.handle(&mut session(), &decode_message(CANCEL), Instant::now()).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].msg_type(), Some("9"));
+        assert_eq!(field(&responses[0], FIX_5_0SP2::CxlRejReason::TAG), "1");
+    }
+
+    #[test]
+    fn replace_accepted_and_remaining_steps_apply_to_replacement()
+    {
+        let mut simulator = Simulator::new(Scenario::from_yaml("behaviour: { type: scripted, steps: [ack, wait: { milliseconds: 100 }, fill: {}] }").unwrap());
+        let mut session = session();
+        let now = Instant::now();
+        simulator.handle(&mut session, &decode_message(ORDER_SINGLE), now).unwrap();
+        let responses = simulator.handle(&mut session, &decode_message(REPLACE), now).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ExecType::TAG), "5");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::OrderQty::TAG), "40000");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LeavesQty::TAG), "40000");
+        assert_eq!(simulator.order_book.orders.len(), 2);
+        let responses = simulator.poll(&mut session, now + Duration::from_millis(100)).unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(field(&responses[0], FIX_5_0SP2::ClOrdID::TAG), "2");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LastQty::TAG), "40000");
+        assert_eq!(field(&responses[0], FIX_5_0SP2::LastPx::TAG), "11.565");
+    }
+
+    #[test]
+    fn random_behaviour_is_repeatable_with_a_seed()
+    {
+        let run = || {
+            let scenario = Scenario::from_yaml("seed: 7\nbehaviour: { type: random, min_fills: 2, max_fills: 5 }").unwrap();
+            let mut simulator = Simulator::new(scenario);
+            let responses = simulator.handle(&mut session(), &decode_message(ORDER_SINGLE), Instant::now()).unwrap();
+            responses.iter().map(|message| field(message, FIX_5_0SP2::LastQty::TAG)).collect::<Vec<_>>()
+        };
+        let first = run();
+        assert!(first.len() >= 3);
+        assert_eq!(first, run());
+        let total: f64 = first.iter().map(|qty| qty.parse::<f64>().unwrap()).sum();
+        assert_eq!(total, 20000.0);
+    }
+
+}
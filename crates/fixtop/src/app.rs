@@ -0,0 +1,332 @@
+use crocofix::message::Message;
+use crocofix::order::Order;
+use crocofix::order_book::OrderBook;
+use crocofix::order_column::{ColumnSource, OrderColumn};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+const FIX_MESSAGE_PREFIX: &str = "8=FIX";
+
+// The number of rows PageUp and PageDown move.
+const PAGE_SIZE: usize = 10;
+
+// Which pane the arrow keys move in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    Blotter,
+    Detail
+}
+
+// The state of the blotter, the orders are filtered and sorted each time they are displayed so
+// the selection is remembered by key rather than position.
+pub struct App {
+    pub order_book: OrderBook,
+    pub columns: Vec<OrderColumn>,
+    pub filter: String,
+    pub editing_filter: bool,
+    // The index of the column to sort by and whether the sort is descending.
+    pub sort: Option<(usize, bool)>,
+    pub selected: Option<String>,
+    pub detail: bool,
+    pub detail_scroll: u16,
+    pub focus: Focus,
+    pub quit: bool
+}
+
+impl App {
+
+    pub fn new(order_book: OrderBook, fields: &[u32]) -> Self
+    {
+        Self {
+            order_book,
+            columns: fields.iter().map(|tag| OrderColumn::new(ColumnSource::Field(*tag))).collect(),
+            filter: String::new(),
+            editing_filter: false,
+            sort: None,
+            selected: None,
+            detail: false,
+            detail_scroll: 0,
+            focus: Focus::Blotter,
+            quit: false
+        }
+    }
+
+    // Decode a line of a log and update the orders, lines without a FIX message, administrative
+    // messages, and messages that aren't for an order are ignored.
+    pub fn process_line(&mut self, line: &str)
+    {
+        let Some(start_of_message) = line.find(FIX_MESSAGE_PREFIX) else {
+            return;
+        };
+
+        let (_, suffix) = line.split_at(start_of_message);
+        let mut message = Message::default();
+        if message.decode(suffix.as_bytes()).is_err() || message.is_admin() {
+            return;
+        }
+
+        let _ = self.order_book.process(&message);
+    }
+
+    // The values of the order as they are displayed.
+    pub fn values(&self, order: &Order) -> Vec<String>
+    {
+        self.columns.iter().map(|column| column.value(&self.order_book, order).map(|value| column.display(&value)).unwrap_or_default()).collect()
+    }
+
+    fn matches_filter(&self, order: &Order) -> bool
+    {
+        if self.filter.is_empty() {
+            return true;
+        }
+
+        let filter = self.filter.to_lowercase();
+        self.values(order).iter().any(|value| value.to_lowercase().contains(&filter))
+    }
+
+    // The orders with a displayed value containing the filter text, ignoring case, in sort order.
+    pub fn orders(&self) -> Vec<&Order>
+    {
+        let mut orders: Vec<&Order> = self.order_book.orders.values().filter(|order| self.matches_filter(order)).collect();
+
+        if let Some((index, descending)) = self.sort {
+            let column = &self.columns[index];
+            orders.sort_by(|left, right| {
+                let ordering = column.compare(&self.order_book, left, right);
+                if descending { ordering.reverse() } else { ordering }
+            });
+        }
+
+        orders
+    }
+
+    // The position of the selected order in the displayed orders, the first order is selected if
+    // the selected order has been filtered out.
+    pub fn selected_index(&self, orders: &[&Order]) -> Option<usize>
+    {
+        if orders.is_empty() {
+            return None;
+        }
+
+        Some(self.selected.as_ref().and_then(|key| orders.iter().position(|order| &order.key == key)).unwrap_or(0))
+    }
+
+    pub fn selected_order(&self) -> Option<&Order>
+    {
+        let orders = self.orders();
+        self.selected_index(&orders).map(|index| orders[index])
+    }
+
+    // The messages of the selected order as they are shown in the detail pane.
+    pub fn detail_text(&self) -> String
+    {
+        match self.selected_order() {
+            Some(order) => order.messages.iter().map(|message| message.to_string()).collect::<Vec<_>>().join("\n\n"),
+            None => String::new()
+        }
+    }
+
+    fn move_selection(&mut self, offset: isize)
+    {
+        let orders = self.orders();
+        let Some(index) = self.selected_index(&orders) else {
+            return;
+        };
+
+        let index = index.saturating_add_signed(offset).min(orders.len() - 1);
+        self.selected = Some(orders[index].key.clone());
+        self.detail_scroll = 0;
+    }
+
+    // The detail pane scrolls until the last line of the messages is at the top.
+    fn scroll_detail(&mut self, offset: isize)
+    {
+        let last_line = self.detail_text().lines().count().saturating_sub(1).min(u16::MAX as usize);
+        self.detail_scroll = (self.detail_scroll as usize).saturating_add_signed(offset).min(last_line) as u16;
+    }
+
+    fn move_focus(&mut self, offset: isize)
+    {
+        match self.focus {
+            Focus::Blotter => self.move_selection(offset),
+            Focus::Detail => self.scroll_detail(offset)
+        }
+    }
+
+    // Sort by the next column, after the last column the orders are unsorted.
+    fn next_sort(&mut self)
+    {
+        self.sort = match self.sort {
+            None if !self.columns.is_empty() => Some((0, false)),
+            Some((index, descending)) if index + 1 < self.columns.len() => Some((index + 1, descending)),
+            _ => None
+        };
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent)
+    {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Char(character) => self.filter.push(character),
+                KeyCode::Backspace => { self.filter.pop(); },
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.editing_filter = false;
+                },
+                _ => ()
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Char('s') => self.next_sort(),
+            KeyCode::Char('r') => {
+                if let Some((index, descending)) = self.sort {
+                    self.sort = Some((index, !descending));
+                }
+            },
+            KeyCode::Enter => {
+                self.detail = !self.detail;
+                self.detail_scroll = 0;
+                self.focus = Focus::Blotter;
+            },
+            KeyCode::Tab if self.detail => {
+                self.focus = if self.focus == Focus::Blotter { Focus::Detail } else { Focus::Blotter };
+            },
+            KeyCode::Esc => {
+                self.detail = false;
+                self.focus = Focus::Blotter;
+            },
+            KeyCode::Up => self.move_focus(-1),
+            KeyCode::Down => self.move_focus(1),
+            KeyCode::PageUp => self.move_focus(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => self.move_focus(PAGE_SIZE as isize),
+            KeyCode::Home => self.move_focus(isize::MIN),
+            KeyCode::End => self.move_focus(isize::MAX),
+            _ => ()
+        }
+    }
+
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    const LOG: &str = "\
+2020-01-19 02:35:09.990 8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=2\u{0001}11=1\u{0001}55=RIO.AX\u{0001}54=1\u{0001}38=200\u{0001}40=2\u{0001}44=100\u{0001}10=000\u{0001}
+2020-01-19 02:35:10.010 8=FIX.4.4\u{0001}9=0\u{0001}35=0\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=2\u{0001}10=000\u{0001}
+not a FIX message
+2020-01-19 02:35:11.000 8=FIX.4.4\u{0001}9=0\u{0001}35=D\u{0001}49=INITIATOR\u{0001}56=ACCEPTOR\u{0001}34=3\u{0001}11=2\u{0001}55=BHP.AX\u{0001}54=2\u{0001}38=1000\u{0001}40=2\u{0001}44=21\u{0001}10=000\u{0001}
+2020-01-19 02:35:11.500 8=FIX.4.4\u{0001}9=0\u{0001}35=8\u{0001}49=ACCEPTOR\u{0001}56=INITIATOR\u{0001}34=3\u{0001}11=2\u{0001}37=1\u{0001}17=1\u{0001}150=F\u{0001}39=2\u{0001}32=1000\u{0001}31=21\u{0001}151=0\u{0001}14=1000\u{0001}6=21\u{0001}10=000\u{0001}
+";
+
+    pub fn app() -> App
+    {
+        let fields = [
+            crocofix::FIX_5_0SP2::ClOrdID::TAG,
+            crocofix::FIX_5_0SP2::Symbol::TAG,
+            crocofix::FIX_5_0SP2::OrdStatus::TAG,
+            crocofix::FIX_5_0SP2::OrderQty::TAG
+        ];
+        let mut app = App::new(OrderBook::default(), &fields);
+        for line in LOG.lines() {
+            app.process_line(line);
+        }
+        app
+    }
+
+    fn press(app: &mut App, codes: &[KeyCode])
+    {
+        for code in codes {
+            app.handle_key(KeyEvent::from(*code));
+        }
+    }
+
+    fn cl_ord_ids(app: &App) -> Vec<String>
+    {
+        app.orders().iter().map(|order| order.cl_ord_id.value.clone()).collect()
+    }
+
+    #[test]
+    fn orders_are_read_from_the_log()
+    {
+        let app = app();
+        assert_eq!(cl_ord_ids(&app), vec!["1", "2"]);
+        assert_eq!(app.values(app.orders()[1]), vec!["2", "BHP.AX", "Filled", "1000"]);
+    }
+
+    #[test]
+    fn filter_as_you_type()
+    {
+        let mut app = app();
+        press(&mut app, &[KeyCode::Char('/'), KeyCode::Char('b'), KeyCode::Char('h')]);
+        assert!(app.editing_filter);
+        assert_eq!(cl_ord_ids(&app), vec!["2"]);
+        press(&mut app, &[KeyCode::Backspace, KeyCode::Backspace, KeyCode::Char('f'), KeyCode::Char('i'), KeyCode::Enter]);
+        assert!(!app.editing_filter);
+        assert_eq!(cl_ord_ids(&app), vec!["2"]);
+        press(&mut app, &[KeyCode::Char('/'), KeyCode::Esc]);
+        assert_eq!(cl_ord_ids(&app), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn sort_numerically_and_reverse()
+    {
+        let mut app = app();
+        press(&mut app, &[KeyCode::Char('s'), KeyCode::Char('s')]);
+        assert_eq!(app.sort, Some((1, false)));
+        assert_eq!(cl_ord_ids(&app), vec!["2", "1"]);
+        press(&mut app, &[KeyCode::Char('s'), KeyCode::Char('s'), KeyCode::Char('r')]);
+        assert_eq!(app.sort, Some((3, true)));
+        assert_eq!(cl_ord_ids(&app), vec!["2", "1"]);
+        press(&mut app, &[KeyCode::Char('s')]);
+        assert_eq!(app.sort, None);
+    }
+
+    #[test]
+    fn selection_follows_the_order_when_sorted()
+    {
+        let mut app = app();
+        press(&mut app, &[KeyCode::Down, KeyCode::Down]);
+        assert_eq!(app.selected_order().map(|order| order.cl_ord_id.value.as_str()), Some("2"));
+        press(&mut app, &[KeyCode::Char('s'), KeyCode::Char('s')]);
+        assert_eq!(app.selected_index(&app.orders()), Some(0));
+        press(&mut app, &[KeyCode::End]);
+        assert_eq!(app.selected_order().map(|order| order.cl_ord_id.value.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn detail_pane_scrolls_when_focused()
+    {
+        let mut app = app();
+        press(&mut app, &[KeyCode::Enter, KeyCode::Tab, KeyCode::Down, KeyCode::Down]);
+        assert!(app.detail);
+        assert_eq!(app.focus, Focus::Detail);
+        assert_eq!(app.detail_scroll, 2);
+        press(&mut app, &[KeyCode::Esc, KeyCode::Char('q')]);
+        assert!(!app.detail);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn detail_pane_scrolls_no_further_than_the_last_line()
+    {
+        let mut app = app();
+        press(&mut app, &[KeyCode::Enter, KeyCode::Tab, KeyCode::End, KeyCode::End]);
+        let last_line = app.detail_text().lines().count() - 1;
+        assert!(last_line > 0);
+        assert_eq!(app.detail_scroll as usize, last_line);
+        press(&mut app, &[KeyCode::Down, KeyCode::Home]);
+        assert_eq!(app.detail_scroll, 0);
+    }
+
+}
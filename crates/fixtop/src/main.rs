@@ -0,0 +1,128 @@
+mod app;
+mod ui;
+
+use app::App;
+use crocofix::error::Error;
+use crocofix::order_book::OrderBook;
+use crocofix::order_report::DEFAULT_FIELDS;
+use clap::Parser;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+// How long to wait for a key press before redrawing with any new messages, and how long to wait
+// for a followed file to grow.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn validate_field(tag_or_name: &str) -> Result<u32, String>
+{
+    let trimmed = tag_or_name.trim();
+
+    let tag = match trimmed.parse::<u32>() {
+        Ok(tag) => tag,
+        Err(_) => {
+            let Some(field) = crocofix::FIX_5_0SP2::fields().field_with_name(trimmed) else {
+                return Err(format!("Unable to find a FIX field with name or tag = '{}'", tag_or_name));
+            };
+            return Ok(field.tag())
+        }
+    };
+
+    // User defined tags that aren't in the dictionary are displayed by tag.
+    if tag > 0 {
+        return Ok(tag);
+    }
+
+    Err(format!("Unable to find a FIX field with tag = '{}'", tag))
+}
+
+/// Live order blotter for FIX protocol logs
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Options {
+
+    /// Comma separated list of field names or tags to display
+    #[arg(long, value_delimiter = ',', value_parser = validate_field)]
+    fields: Option<Vec<u32>>,
+
+    /// Read the file once instead of following it as it grows
+    #[arg(long)]
+    no_follow: bool,
+
+    /// The log file to read, STDIN is read if not specified
+    input: Option<String>
+}
+
+// Send each complete line to the UI thread, a followed file is polled for new lines when the end
+// is reached so a partially written line is held until the rest of it arrives.
+fn read_lines<R: BufRead>(mut reader: R, follow: bool, sender: Sender<String>)
+{
+    let mut line = String::new();
+    loop {
+        match reader.read_line(&mut line) {
+            Ok(0) if follow => thread::sleep(POLL_INTERVAL),
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if follow && !line.ends_with('\n') {
+                    continue;
+                }
+                if sender.send(line.trim_end().to_string()).is_err() {
+                    return;
+                }
+                line.clear();
+            }
+        }
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App, receiver: Receiver<String>) -> io::Result<()>
+{
+    while !app.quit {
+        while let Ok(line) = receiver.try_recv() {
+            app.process_line(&line);
+        }
+
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => app.handle_key(key),
+            _ => ()
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Error>
+{
+    let options = Options::parse();
+    let fields = options.fields.clone().unwrap_or_else(|| DEFAULT_FIELDS.to_vec());
+
+    let (sender, receiver) = mpsc::channel();
+
+    match &options.input {
+        Some(path) => {
+            let reader = BufReader::new(File::open(path)?);
+            let follow = !options.no_follow;
+            thread::spawn(move || read_lines(reader, follow, sender));
+        },
+        None => {
+            // Keys are read from the terminal rather than STDIN when STDIN is redirected.
+            thread::spawn(move || read_lines(io::stdin().lock(), false, sender));
+        }
+    }
+
+    let mut app = App::new(OrderBook::default(), &fields);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, receiver);
+    ratatui::restore();
+    Ok(result?)
+}
@@ -0,0 +1,188 @@
+use crate::app::{App, Focus};
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+
+const HELP: &str = "q quit  / filter  s sort  r reverse  Enter messages  Tab focus";
+
+// The colour of an order's row, working orders stand out and finished orders fade.
+pub fn ord_status_color(ord_status: Option<&str>) -> Color
+{
+    match ord_status {
+        Some(ord_status) if ord_status == crocofix::FIX_5_0SP2::OrdStatus::New().value => Color::Cyan,
+        Some(ord_status) if ord_status == crocofix::FIX_5_0SP2::OrdStatus::PartiallyFilled().value => Color::Blue,
+        Some(ord_status) if ord_status == crocofix::FIX_5_0SP2::OrdStatus::Filled().value => Color::Green,
+        Some(ord_status) if ord_status == crocofix::FIX_5_0SP2::OrdStatus::Rejected().value => Color::Red,
+        Some(ord_status) if [
+            crocofix::FIX_5_0SP2::OrdStatus::PendingNew().value,
+            crocofix::FIX_5_0SP2::OrdStatus::PendingReplace().value,
+            crocofix::FIX_5_0SP2::OrdStatus::PendingCancel().value
+        ].contains(&ord_status) => Color::Yellow,
+        Some(ord_status) if [
+            crocofix::FIX_5_0SP2::OrdStatus::Canceled().value,
+            crocofix::FIX_5_0SP2::OrdStatus::Replaced().value,
+            crocofix::FIX_5_0SP2::OrdStatus::Expired().value,
+            crocofix::FIX_5_0SP2::OrdStatus::DoneForDay().value
+        ].contains(&ord_status) => Color::DarkGray,
+        _ => Color::Reset
+    }
+}
+
+fn draw_blotter(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
+{
+    let orders = app.orders();
+
+    let header = Row::new(app.columns.iter().enumerate().map(|(index, column)| {
+        let header = match app.sort {
+            Some((sorted, descending)) if sorted == index => format!("{}{}", column.header(), if descending { "▼" } else { "▲" }),
+            _ => column.header()
+        };
+        let alignment = if column.is_numeric() { Alignment::Right } else { Alignment::Left };
+        Cell::from(Text::from(header).alignment(alignment))
+    }))
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = orders.iter().map(|order| {
+        Row::new(app.columns.iter().zip(app.values(order)).map(|(column, value)| {
+            let alignment = if column.is_numeric() { Alignment::Right } else { Alignment::Left };
+            Cell::from(Text::from(value).alignment(alignment))
+        }))
+        .style(Style::default().fg(ord_status_color(order.ord_status())))
+    });
+
+    // Each column is as wide as its widest value.
+    let widths = app.columns.iter().enumerate().map(|(index, column)| {
+        let widest = orders.iter().map(|order| app.values(order)[index].chars().count()).max().unwrap_or_default();
+        Constraint::Length((column.header().chars().count() + 1).max(widest) as u16)
+    });
+
+    let border = if app.focus == Focus::Blotter { Color::White } else { Color::DarkGray };
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .block(Block::bordered().title(format!(" Orders {}/{} ", orders.len(), app.order_book.orders.len())).border_style(Style::default().fg(border)))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default().with_selected(app.selected_index(&orders));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_detail(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
+{
+    let text = app.detail_text();
+
+    let border = if app.focus == Focus::Detail { Color::White } else { Color::DarkGray };
+
+    let paragraph = Paragraph::new(text)
+        .block(Block::bordered().title(" Messages ").border_style(Style::default().fg(border)))
+        .scroll((app.detail_scroll, 0));
+
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
+{
+    let status = if app.editing_filter {
+        Line::from(format!("/{}█", app.filter))
+    }
+    else if app.filter.is_empty() {
+        Line::from(HELP)
+    }
+    else {
+        Line::from(format!("filter: {}  {}", app.filter, HELP))
+    };
+
+    frame.render_widget(Paragraph::new(status), area);
+}
+
+pub fn draw(frame: &mut Frame, app: &App)
+{
+    let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+    if app.detail {
+        let [blotter, detail] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(main);
+        draw_blotter(frame, app, blotter);
+        draw_detail(frame, app, detail);
+    }
+    else {
+        draw_blotter(frame, app, main);
+    }
+
+    draw_status(frame, app, status);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::app::tests::app;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+    fn render(app: &App, width: u16, height: u16) -> Terminal<TestBackend>
+    {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        terminal
+    }
+
+    fn lines(terminal: &Terminal<TestBackend>) -> Vec<String>
+    {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height).map(|y| {
+            (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string()
+        }).collect()
+    }
+
+    #[test]
+    fn blotter_shows_the_orders()
+    {
+        let terminal = render(&app(), 60, 6);
+        assert_eq!(lines(&terminal), vec![
+            "┌ Orders 2/2 ──────────────────────────────────────────────┐",
+            "│ClOrdID   Symbol   OrdStatus    OrderQty                  │",
+            "│1         RIO.AX                     200                  │",
+            "│2         BHP.AX   Filled           1000                  │",
+            "└──────────────────────────────────────────────────────────┘",
+            "q quit  / filter  s sort  r reverse  Enter messages  Tab foc"
+        ]);
+    }
+
+    #[test]
+    fn rows_are_coloured_by_ord_status()
+    {
+        let terminal = render(&app(), 60, 6);
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer[(1, 2)].fg, Color::Reset);
+        assert_eq!(buffer[(1, 3)].fg, Color::Green);
+    }
+
+    #[test]
+    fn detail_shows_the_messages_of_the_selected_order()
+    {
+        let mut app = app();
+        app.handle_key(KeyEvent::from(KeyCode::Down));
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        let lines = lines(&render(&app, 60, 16));
+        assert_eq!(lines[8], "┌ Messages ────────────────────────────────────────────────┐");
+        assert_eq!(lines[9], "│NewOrderSingle                                            │");
+        assert!(lines[3].starts_with("│2         BHP.AX"));
+    }
+
+    #[test]
+    fn filter_is_shown_while_typing()
+    {
+        let mut app = app();
+        for code in [KeyCode::Char('/'), KeyCode::Char('r'), KeyCode::Char('i')] {
+            app.handle_key(KeyEvent::from(code));
+        }
+        let lines = lines(&render(&app, 60, 6));
+        assert_eq!(lines[0], "┌ Orders 1/2 ──────────────────────────────────────────────┐");
+        assert_eq!(lines[5], "/ri█");
+    }
+
+}